@@ -20,4 +20,26 @@ pub trait OTAlarm {
     /// Returns:
     ///     (u32): The current time
     fn get_now(&mut self) -> u32;
-}
\ No newline at end of file
+}
+
+/// Microsecond alarm, used where the millisecond resolution of `OTAlarm` is too coarse (e.g. CSMA-CA backoffs)
+pub trait OTAlarmMicro {
+    /// Signal the alarm has fired
+    fn alarm_fired(&mut self) -> bool;
+
+    /// Set the alarm to fire dt microseconds after t0
+    /// 
+    /// Params:
+    ///     t0 - reference time
+    ///     dt - alarm delay
+    fn start_alarm_at(&mut self, t0: u32, dt: u32);
+
+    /// Stop the alarm
+    fn stop_alarm(&mut self);
+
+    /// Get the current time (in us)
+    /// 
+    /// Returns:
+    ///     (u32): The current time
+    fn get_now(&mut self) -> u32;
+}
@@ -4,6 +4,8 @@
 
 use core::ops::BitOr;
 
+pub mod aes;
+
 /// Key types usable in OpenThread
 pub enum OTKeyType {
     Raw,
@@ -1,3 +1,8 @@
+use core::convert::Infallible;
+use core::fmt;
+
+use embedded_hal::{digital, i2c, spi};
+
 /// OpenThread Error
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum OTError<E> {
@@ -111,4 +116,329 @@ pub enum OTError<E> {
     Generic,
 
     Platform(E),
-}
\ No newline at end of file
+}
+
+impl<E> OTError<E> {
+    /// Convert the platform error, keeping every other error as is
+    pub fn map_platform<F>(self, f: impl FnOnce(E) -> F) -> OTError<F> {
+        match self {
+            OTError::Failed => OTError::Failed,
+            OTError::Dropped => OTError::Dropped,
+            OTError::NoBuffers => OTError::NoBuffers,
+            OTError::NoRoute => OTError::NoRoute,
+            OTError::Busy => OTError::Busy,
+            OTError::Parse => OTError::Parse,
+            OTError::InvalidArgs => OTError::InvalidArgs,
+            OTError::Security => OTError::Security,
+            OTError::AddressQuery => OTError::AddressQuery,
+            OTError::NoAddress => OTError::NoAddress,
+            OTError::Abort => OTError::Abort,
+            OTError::NotImplemented => OTError::NotImplemented,
+            OTError::InvalidState => OTError::InvalidState,
+            OTError::NoAck => OTError::NoAck,
+            OTError::ChannelAccessFailure => OTError::ChannelAccessFailure,
+            OTError::Detached => OTError::Detached,
+            OTError::Fcs => OTError::Fcs,
+            OTError::NoFrameReceived => OTError::NoFrameReceived,
+            OTError::UnknownNeighbor => OTError::UnknownNeighbor,
+            OTError::InvalidSourceAddress => OTError::InvalidSourceAddress,
+            OTError::AddressFiltered => OTError::AddressFiltered,
+            OTError::DestinationAddressFiltered => OTError::DestinationAddressFiltered,
+            OTError::NotFound => OTError::NotFound,
+            OTError::Already => OTError::Already,
+            OTError::IP6AddressCreationFailure => OTError::IP6AddressCreationFailure,
+            OTError::NoCapable => OTError::NoCapable,
+            OTError::ResponseTimeout => OTError::ResponseTimeout,
+            OTError::Duplicated => OTError::Duplicated,
+            OTError::ReassemblyTimeout => OTError::ReassemblyTimeout,
+            OTError::NotTMF => OTError::NotTMF,
+            OTError::NotLowpanDataFrame => OTError::NotLowpanDataFrame,
+            OTError::LinkMarginLow => OTError::LinkMarginLow,
+            OTError::InvalidCommand => OTError::InvalidCommand,
+            OTError::Pending => OTError::Pending,
+            OTError::Rejected => OTError::Rejected,
+            OTError::Generic => OTError::Generic,
+            OTError::Platform(error) => OTError::Platform(f(error)),
+        }
+    }
+
+    /// Get the otError code of the error
+    ///
+    /// Platform errors are reported as OT_ERROR_FAILED (see `to_platform_code` for errors with a known kind).
+    pub fn to_code(&self) -> u8 {
+        match self {
+            OTError::Failed => 1,
+            OTError::Dropped => 2,
+            OTError::NoBuffers => 3,
+            OTError::NoRoute => 4,
+            OTError::Busy => 5,
+            OTError::Parse => 6,
+            OTError::InvalidArgs => 7,
+            OTError::Security => 8,
+            OTError::AddressQuery => 9,
+            OTError::NoAddress => 10,
+            OTError::Abort => 11,
+            OTError::NotImplemented => 12,
+            OTError::InvalidState => 13,
+            OTError::NoAck => 14,
+            OTError::ChannelAccessFailure => 15,
+            OTError::Detached => 16,
+            OTError::Fcs => 17,
+            OTError::NoFrameReceived => 18,
+            OTError::UnknownNeighbor => 19,
+            OTError::InvalidSourceAddress => 20,
+            OTError::AddressFiltered => 21,
+            OTError::DestinationAddressFiltered => 22,
+            OTError::NotFound => 23,
+            OTError::Already => 24,
+            OTError::IP6AddressCreationFailure => 26,
+            OTError::NoCapable => 27,
+            OTError::ResponseTimeout => 28,
+            OTError::Duplicated => 29,
+            OTError::ReassemblyTimeout => 30,
+            OTError::NotTMF => 31,
+            OTError::NotLowpanDataFrame => 32,
+            OTError::LinkMarginLow => 34,
+            OTError::InvalidCommand => 35,
+            OTError::Pending => 36,
+            OTError::Rejected => 37,
+            OTError::Generic => 255,
+            OTError::Platform(_) => 1,
+        }
+    }
+
+    /// Get the error of an otError code
+    ///
+    /// Returns:
+    ///     None for OT_ERROR_NONE (0), the reserved codes and unknown codes
+    pub fn from_code(code: u8) -> Option<Self> {
+        Some(match code {
+            1 => OTError::Failed,
+            2 => OTError::Dropped,
+            3 => OTError::NoBuffers,
+            4 => OTError::NoRoute,
+            5 => OTError::Busy,
+            6 => OTError::Parse,
+            7 => OTError::InvalidArgs,
+            8 => OTError::Security,
+            9 => OTError::AddressQuery,
+            10 => OTError::NoAddress,
+            11 => OTError::Abort,
+            12 => OTError::NotImplemented,
+            13 => OTError::InvalidState,
+            14 => OTError::NoAck,
+            15 => OTError::ChannelAccessFailure,
+            16 => OTError::Detached,
+            17 => OTError::Fcs,
+            18 => OTError::NoFrameReceived,
+            19 => OTError::UnknownNeighbor,
+            20 => OTError::InvalidSourceAddress,
+            21 => OTError::AddressFiltered,
+            22 => OTError::DestinationAddressFiltered,
+            23 => OTError::NotFound,
+            24 => OTError::Already,
+            26 => OTError::IP6AddressCreationFailure,
+            27 => OTError::NoCapable,
+            28 => OTError::ResponseTimeout,
+            29 => OTError::Duplicated,
+            30 => OTError::ReassemblyTimeout,
+            31 => OTError::NotTMF,
+            32 => OTError::NotLowpanDataFrame,
+            34 => OTError::LinkMarginLow,
+            35 => OTError::InvalidCommand,
+            36 => OTError::Pending,
+            37 => OTError::Rejected,
+            255 => OTError::Generic,
+            _ => return None,
+        })
+    }
+
+    /// Get the name of the error, as given by otThreadErrorToString
+    pub fn name(&self) -> &'static str {
+        match self {
+            OTError::Failed | OTError::Platform(_) => "Failed",
+            OTError::Dropped => "Drop",
+            OTError::NoBuffers => "NoBufs",
+            OTError::NoRoute => "NoRoute",
+            OTError::Busy => "Busy",
+            OTError::Parse => "Parse",
+            OTError::InvalidArgs => "InvalidArgs",
+            OTError::Security => "Security",
+            OTError::AddressQuery => "AddressQuery",
+            OTError::NoAddress => "NoAddress",
+            OTError::Abort => "Abort",
+            OTError::NotImplemented => "NotImplemented",
+            OTError::InvalidState => "InvalidState",
+            OTError::NoAck => "NoAck",
+            OTError::ChannelAccessFailure => "ChannelAccessFailure",
+            OTError::Detached => "Detached",
+            OTError::Fcs => "FcsErr",
+            OTError::NoFrameReceived => "NoFrameReceived",
+            OTError::UnknownNeighbor => "UnknownNeighbor",
+            OTError::InvalidSourceAddress => "InvalidSourceAddress",
+            OTError::AddressFiltered => "AddressFiltered",
+            OTError::DestinationAddressFiltered => "DestinationAddressFiltered",
+            OTError::NotFound => "NotFound",
+            OTError::Already => "Already",
+            OTError::IP6AddressCreationFailure => "Ipv6AddressCreationFailure",
+            OTError::NoCapable => "NotCapable",
+            OTError::ResponseTimeout => "ResponseTimeout",
+            OTError::Duplicated => "Duplicated",
+            OTError::ReassemblyTimeout => "ReassemblyTimeout",
+            OTError::NotTMF => "NotTmf",
+            OTError::NotLowpanDataFrame => "NonLowpanDataFrame",
+            OTError::LinkMarginLow => "LinkMarginLow",
+            OTError::InvalidCommand => "InvalidCommand",
+            OTError::Pending => "Pending",
+            OTError::Rejected => "Rejected",
+            // OT_ERROR_GENERIC is outside of the table of otThreadErrorToString
+            OTError::Generic => "UnknownErrorType",
+        }
+    }
+}
+
+impl<E: PlatformError> OTError<E> {
+    /// Get the otError code of the error, describing platform errors by their kind
+    pub fn to_platform_code(&self) -> u8 {
+        match self {
+            OTError::Platform(error) => error.code(),
+            error => error.to_code(),
+        }
+    }
+}
+
+/// Prints the name of the error (see `name`), followed by the platform error if any
+impl<E: fmt::Debug> fmt::Display for OTError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OTError::Platform(error) => write!(f, "{} ({:?})", self.name(), error),
+            error => f.write_str(error.name()),
+        }
+    }
+}
+
+impl<E: fmt::Debug> core::error::Error for OTError<E> {}
+
+/// Platform error that can be described by an otError code
+pub trait PlatformError {
+    /// Get the otError code closest to the error
+    fn code(&self) -> u8;
+}
+
+impl PlatformError for Infallible {
+    fn code(&self) -> u8 {
+        match *self {}
+    }
+}
+
+impl PlatformError for spi::ErrorKind {
+    fn code(&self) -> u8 {
+        let error: OTError<()> = match self {
+            spi::ErrorKind::Overrun => OTError::NoBuffers,
+            spi::ErrorKind::ModeFault => OTError::Busy,
+            spi::ErrorKind::FrameFormat => OTError::Parse,
+            _ => OTError::Failed,
+        };
+        error.to_code()
+    }
+}
+
+impl PlatformError for i2c::ErrorKind {
+    fn code(&self) -> u8 {
+        let error: OTError<()> = match self {
+            i2c::ErrorKind::ArbitrationLoss => OTError::Busy,
+            i2c::ErrorKind::NoAcknowledge(_) => OTError::NoAck,
+            i2c::ErrorKind::Overrun => OTError::NoBuffers,
+            _ => OTError::Failed,
+        };
+        error.to_code()
+    }
+}
+
+impl PlatformError for digital::ErrorKind {
+    fn code(&self) -> u8 {
+        OTError::<()>::Failed.to_code()
+    }
+}
+
+/// SPI error described by its kind (e.g. the error type of an SPI radio driver)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SpiError<E>(pub E);
+
+impl<E: spi::Error> PlatformError for SpiError<E> {
+    fn code(&self) -> u8 {
+        self.0.kind().code()
+    }
+}
+
+/// I2C error described by its kind
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct I2cError<E>(pub E);
+
+impl<E: i2c::Error> PlatformError for I2cError<E> {
+    fn code(&self) -> u8 {
+        self.0.kind().code()
+    }
+}
+
+/// GPIO error described by its kind
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DigitalError<E>(pub E);
+
+impl<E: digital::Error> PlatformError for DigitalError<E> {
+    fn code(&self) -> u8 {
+        self.0.kind().code()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Bus error of a driver, described by an embedded-hal error kind
+    #[derive(Debug)]
+    struct BusError<K>(K);
+
+    impl spi::Error for BusError<spi::ErrorKind> {
+        fn kind(&self) -> spi::ErrorKind {
+            self.0
+        }
+    }
+
+    impl i2c::Error for BusError<i2c::ErrorKind> {
+        fn kind(&self) -> i2c::ErrorKind {
+            self.0
+        }
+    }
+
+    impl digital::Error for BusError<digital::ErrorKind> {
+        fn kind(&self) -> digital::ErrorKind {
+            self.0
+        }
+    }
+
+    #[test]
+    fn platform_errors_are_described_by_their_kind() {
+        let cases = [
+            ("spi overrun", SpiError(BusError(spi::ErrorKind::Overrun)).code(), OTError::<()>::NoBuffers),
+            ("spi mode fault", SpiError(BusError(spi::ErrorKind::ModeFault)).code(), OTError::Busy),
+            ("spi other", SpiError(BusError(spi::ErrorKind::Other)).code(), OTError::Failed),
+            ("i2c arbitration loss", I2cError(BusError(i2c::ErrorKind::ArbitrationLoss)).code(), OTError::Busy),
+            (
+                "i2c no acknowledge",
+                I2cError(BusError(i2c::ErrorKind::NoAcknowledge(i2c::NoAcknowledgeSource::Address))).code(),
+                OTError::NoAck,
+            ),
+            ("digital other", DigitalError(BusError(digital::ErrorKind::Other)).code(), OTError::Failed),
+        ];
+
+        for (name, code, expected) in cases {
+            assert_eq!(OTError::<()>::from_code(code), Some(expected), "{}", name);
+        }
+
+        let error = OTError::Platform(SpiError(BusError(spi::ErrorKind::FrameFormat)));
+        assert_eq!(error.to_platform_code(), OTError::<()>::Parse.to_code());
+        assert_eq!(error.to_code(), OTError::<()>::Failed.to_code());
+        assert_eq!(OTError::<SpiError<BusError<spi::ErrorKind>>>::NoAck.to_platform_code(), 14);
+    }
+}
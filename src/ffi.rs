@@ -0,0 +1,1451 @@
+//!
+//! C Platform Layer
+//!
+//! Exports the `otPlat*` symbols the OpenThread C core links against and forwards them to the Rust drivers registered
+//! at startup with the `register_*` functions. The notifications going the other way (transmit done, energy scan
+//! done, received frames, alarm fired) are queued by `CoreRadioHandles` and the drivers, and delivered to the core by
+//! `process`, so a driver is never borrowed while the core runs.
+//!
+//! The OpenThread core is single threaded: the exported functions, the handles and `process` must all be called from
+//! the context running the core.
+//!
+//! The C types mirror the layout of `openthread/platform/radio.h` (OpenThread 1.4, with `mTxPower`). The safety
+//! requirements of the exported functions are the ones of the OpenThread platform API (valid instance and buffers).
+//!
+
+#![allow(non_camel_case_types, non_snake_case, clippy::missing_safety_doc)]
+
+use core::cell::UnsafeCell;
+use core::ffi::{c_char, c_int, c_void, CStr};
+use core::marker::PhantomData;
+use core::ptr::{self, NonNull};
+use core::slice;
+
+use crate::alarm::OTAlarm;
+use crate::entropy::OTEntropy;
+use crate::error::{OTError, PlatformError};
+use crate::flash::OTFlash;
+use crate::misc::{OTMiscellaneous, OTResetReason};
+use crate::radio::{
+    OTExtAddress, OTFrameInformation, OTKeyType, OTLinkMetrics, OTMacKeyMaterial, OTPanId, OTRadioCapabilities,
+    OTRadioConfiguration, OTRadioConfigurationCapTransmit, OTRadioFrame, OTRadioOperation, OTRadioOperationEnergyScan,
+    OTRadioOperationEnergyScanHandles, OTRadioOperationHandles, OTShortAddress, RadioIEInfo, ReceiveFrame,
+    OT_EXT_ADDRESS_SIZE, OT_MAC_KEY_SIZE, OT_RADIO_FRAME_MAX_SIZE, OT_RADIO_POWER_INVALID, OT_RADIO_RSSI_INVALID,
+};
+
+/// OpenThread instance (opaque)
+#[repr(C)]
+pub struct otInstance {
+    _private: [u8; 0],
+}
+
+/// OpenThread error code
+pub type otError = c_int;
+
+pub const OT_ERROR_NONE: otError = 0;
+pub const OT_ERROR_FAILED: otError = 1;
+pub const OT_ERROR_INVALID_STATE: otError = 13;
+
+// Reset reason reported when it can not be read (OT_PLAT_RESET_REASON_UNKNOWN)
+const RESET_REASON_UNKNOWN: c_int = OTResetReason::Unknown as c_int;
+
+// Values of otRadioKeyType
+const OT_KEY_TYPE_KEY_REF: c_int = 1;
+
+// Flags of otRadioTxInfo (C bit fields, first field in the lowest bit)
+const TX_INFO_HEADER_UPDATED: u8 = 1 << 0;
+const TX_INFO_RETX: u8 = 1 << 1;
+const TX_INFO_CSMA_CA_ENABLED: u8 = 1 << 2;
+const TX_INFO_CSL_PRESENT: u8 = 1 << 3;
+const TX_INFO_SECURITY_PROCESSED: u8 = 1 << 4;
+
+// Flags of otRadioRxInfo
+const RX_INFO_ACKED_WITH_FRAME_PENDING: u8 = 1 << 0;
+const RX_INFO_ACKED_WITH_SEC_ENH_ACK: u8 = 1 << 1;
+
+// Flags of otLinkMetrics
+const LINK_METRICS_PDU_COUNT: u8 = 1 << 0;
+const LINK_METRICS_LQI: u8 = 1 << 1;
+const LINK_METRICS_LINK_MARGIN: u8 = 1 << 2;
+const LINK_METRICS_RSSI: u8 = 1 << 3;
+
+// IE information of frames the core did not provide any for
+static NO_IE_INFO: RadioIEInfo = RadioIEInfo {
+    network_time_offset: 0,
+    time_ie_offset: 0,
+    time_sync_sequency: 0,
+};
+
+/// MAC key material (`otMacKeyMaterial`), a literal key or a key reference depending on the key type
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub union otMacKeyMaterial {
+    pub key: [u8; OT_MAC_KEY_SIZE],
+    pub key_ref: u32,
+}
+
+/// Link metrics to probe (`otLinkMetrics`, a bit field of the LINK_METRICS_* flags)
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct otLinkMetrics {
+    pub flags: u8,
+}
+
+/// Transmit information of a frame (`mInfo.mTxInfo`)
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct otRadioTxInfo {
+    pub aes_key: *const otMacKeyMaterial,
+    pub ie_info: *mut RadioIEInfo,
+    pub tx_delay_base_time: u32,
+    pub tx_delay: u32,
+    pub max_csma_backoffs: u8,
+    pub max_frame_retries: u8,
+    pub rx_channel_after_tx_done: u8,
+    pub tx_power: i8,
+    // Bit field of the TX_INFO_* flags
+    pub flags: u8,
+}
+
+/// Receive information of a frame (`mInfo.mRxInfo`)
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct otRadioRxInfo {
+    pub timestamp: u64,
+    pub ack_frame_counter: u32,
+    pub ack_key_id: u8,
+    pub rssi: i8,
+    pub lqi: u8,
+    // Bit field of the RX_INFO_* flags
+    pub flags: u8,
+}
+
+/// Transmit or receive information of a frame (`mInfo`)
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub union otRadioFrameInfo {
+    pub tx_info: otRadioTxInfo,
+    pub rx_info: otRadioRxInfo,
+}
+
+/// IEEE 802.15.4 radio frame as seen by the OpenThread core (`otRadioFrame`)
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct otRadioFrame {
+    pub psdu: *mut u8,
+    pub length: u16,
+    pub channel: u8,
+    pub radio_type: u8,
+    pub info: otRadioFrameInfo,
+}
+
+impl otRadioFrame {
+    /// Create a frame without PSDU
+    const fn empty() -> Self {
+        Self {
+            psdu: ptr::null_mut(),
+            length: 0,
+            channel: 0,
+            radio_type: 0,
+            info: otRadioFrameInfo {
+                tx_info: otRadioTxInfo {
+                    aes_key: ptr::null(),
+                    ie_info: ptr::null_mut(),
+                    tx_delay_base_time: 0,
+                    tx_delay: 0,
+                    max_csma_backoffs: 0,
+                    max_frame_retries: 0,
+                    rx_channel_after_tx_done: 0,
+                    tx_power: OT_RADIO_POWER_INVALID as i8,
+                    flags: 0,
+                },
+            },
+        }
+    }
+
+    /// Build the C view of a received frame, pointing to its PSDU
+    pub fn from_receive_frame(frame: &mut ReceiveFrame) -> Self {
+        let radio_frame = frame.as_radio_frame();
+        let (channel, radio_type) = (radio_frame.channel, radio_frame.radio_type);
+        let rx_info = match radio_frame.frame_information {
+            OTFrameInformation::RxInfo {
+                timestamp,
+                ack_frame_counter,
+                ack_key_id,
+                rssi,
+                lqi,
+                acked_with_frame_pending,
+                acked_with_sec_enh_ack,
+            } => {
+                let mut flags = 0;
+                if acked_with_frame_pending {
+                    flags |= RX_INFO_ACKED_WITH_FRAME_PENDING;
+                }
+                if acked_with_sec_enh_ack {
+                    flags |= RX_INFO_ACKED_WITH_SEC_ENH_ACK;
+                }
+                otRadioRxInfo {
+                    timestamp,
+                    ack_frame_counter,
+                    ack_key_id,
+                    rssi,
+                    lqi,
+                    flags,
+                }
+            },
+            // A ReceiveFrame always holds receive information
+            OTFrameInformation::TxInfo { .. } => otRadioRxInfo {
+                timestamp: 0,
+                ack_frame_counter: 0,
+                ack_key_id: 0,
+                rssi: OT_RADIO_RSSI_INVALID as i8,
+                lqi: 0,
+                flags: 0,
+            },
+        };
+
+        let psdu = frame.psdu_mut();
+        Self {
+            length: psdu.len() as u16,
+            psdu: psdu.as_mut_ptr(),
+            channel,
+            radio_type,
+            info: otRadioFrameInfo { rx_info },
+        }
+    }
+
+    /// Build the Rust view of a frame to transmit
+    ///
+    /// Params:
+    ///     key_type - how to read the AES key of the frame (a literal key is used when the frame has none)
+    ///
+    /// Safety:
+    ///     The frame must hold transmit information, its PSDU must be valid for `length` bytes and its AES key and IE
+    ///     information must be null or valid.
+    pub unsafe fn as_tx_frame(&self, key_type: OTKeyType) -> OTRadioFrame<'_> {
+        let tx_info = self.info.tx_info;
+        OTRadioFrame {
+            psdu: psdu(self.psdu, self.length as usize),
+            channel: self.channel,
+            radio_type: self.radio_type,
+            frame_information: OTFrameInformation::TxInfo {
+                aes_key: key_material(tx_info.aes_key, key_type),
+                io_info: tx_info.ie_info.as_ref().unwrap_or(&NO_IE_INFO),
+                tx_delay_base_time: tx_info.tx_delay_base_time,
+                tx_delay: tx_info.tx_delay,
+                max_csma_backoffs: tx_info.max_csma_backoffs,
+                max_frame_retries: tx_info.max_frame_retries,
+                rx_channel_after_tx_done: tx_info.rx_channel_after_tx_done,
+                is_header_updated: tx_info.flags & TX_INFO_HEADER_UPDATED != 0,
+                is_a_retx: tx_info.flags & TX_INFO_RETX != 0,
+                csma_ca_enabled: tx_info.flags & TX_INFO_CSMA_CA_ENABLED != 0,
+                csl_present: tx_info.flags & TX_INFO_CSL_PRESENT != 0,
+                is_security_processed: tx_info.flags & TX_INFO_SECURITY_PROCESSED != 0,
+            },
+        }
+    }
+}
+
+// Layout of the C types on 32 and 64 bit targets
+const _: () = {
+    use core::mem::{offset_of, size_of};
+
+    const POINTER_SIZE: usize = size_of::<*const u8>();
+
+    assert!(size_of::<otMacKeyMaterial>() == OT_MAC_KEY_SIZE);
+
+    assert!(offset_of!(otRadioTxInfo, aes_key) == 0);
+    assert!(offset_of!(otRadioTxInfo, ie_info) == POINTER_SIZE);
+    assert!(offset_of!(otRadioTxInfo, tx_delay_base_time) == 2 * POINTER_SIZE);
+    assert!(offset_of!(otRadioTxInfo, tx_delay) == 2 * POINTER_SIZE + 4);
+    assert!(offset_of!(otRadioTxInfo, max_csma_backoffs) == 2 * POINTER_SIZE + 8);
+    assert!(offset_of!(otRadioTxInfo, max_frame_retries) == 2 * POINTER_SIZE + 9);
+    assert!(offset_of!(otRadioTxInfo, rx_channel_after_tx_done) == 2 * POINTER_SIZE + 10);
+    assert!(offset_of!(otRadioTxInfo, tx_power) == 2 * POINTER_SIZE + 11);
+    assert!(offset_of!(otRadioTxInfo, flags) == 2 * POINTER_SIZE + 12);
+    assert!(size_of::<otRadioTxInfo>() == 2 * POINTER_SIZE + 16);
+
+    assert!(offset_of!(otRadioRxInfo, timestamp) == 0);
+    assert!(offset_of!(otRadioRxInfo, ack_frame_counter) == 8);
+    assert!(offset_of!(otRadioRxInfo, ack_key_id) == 12);
+    assert!(offset_of!(otRadioRxInfo, rssi) == 13);
+    assert!(offset_of!(otRadioRxInfo, lqi) == 14);
+    assert!(offset_of!(otRadioRxInfo, flags) == 15);
+    assert!(size_of::<otRadioRxInfo>() == 16);
+
+    assert!(offset_of!(otRadioFrame, psdu) == 0);
+    assert!(offset_of!(otRadioFrame, length) == POINTER_SIZE);
+    assert!(offset_of!(otRadioFrame, channel) == POINTER_SIZE + 2);
+    assert!(offset_of!(otRadioFrame, radio_type) == POINTER_SIZE + 3);
+    assert!(offset_of!(otRadioFrame, info) == 2 * POINTER_SIZE);
+    assert!(size_of::<otRadioFrame>() == 4 * POINTER_SIZE + 16);
+};
+
+/// View a C buffer as a slice (empty if null)
+unsafe fn psdu<'a>(data: *const u8, length: usize) -> &'a [u8] {
+    if data.is_null() {
+        &[]
+    } else {
+        slice::from_raw_parts(data, length)
+    }
+}
+
+/// Read C key material (a zero literal key if null)
+unsafe fn key_material(key: *const otMacKeyMaterial, key_type: OTKeyType) -> OTMacKeyMaterial {
+    match (key.as_ref(), key_type) {
+        (None, _) => OTMacKeyMaterial::Key([0; OT_MAC_KEY_SIZE]),
+        (Some(key), OTKeyType::LiteralKey) => OTMacKeyMaterial::Key(key.key),
+        (Some(key), OTKeyType::ReferenceKey) => OTMacKeyMaterial::Ref(key.key_ref),
+    }
+}
+
+/// Read an extended address passed by the core, which uses the little endian byte order (the drivers use the big
+/// endian one)
+unsafe fn core_ext_address(address: *const OTExtAddress) -> OTExtAddress {
+    let mut address = *address;
+    address.reverse();
+    address
+}
+
+/// Get the otError code of a result
+fn result_code<E: PlatformError>(result: Result<(), OTError<E>>) -> otError {
+    result.err().map_or(OT_ERROR_NONE, |error| error.to_platform_code() as otError)
+}
+
+/// Get the otError code of a result failing with a platform error
+fn platform_code<E>(result: Result<(), E>) -> otError {
+    result.map_or(OT_ERROR_FAILED, |()| OT_ERROR_NONE)
+}
+
+/// Radio driver, object safe view of the radio traits
+trait CoreRadio {
+    fn capabilities(&mut self) -> OTRadioCapabilities;
+    fn receive_sensitivity(&mut self) -> i8;
+    fn ieee_eui_64(&mut self) -> Option<[u8; 8]>;
+    fn set_pan_id(&mut self, pan_id: OTPanId);
+    fn set_extended_address(&mut self, address: OTExtAddress);
+    fn set_short_address(&mut self, address: OTShortAddress);
+    fn get_transmit_power(&mut self) -> Result<i8, otError>;
+    fn set_transmit_power(&mut self, power: i8) -> otError;
+    fn get_cca_energy_detect_threshold(&mut self) -> Result<i8, otError>;
+    fn set_cca_energy_detect_threshold(&mut self, threshold: i8) -> otError;
+    fn get_fem_lna_gain(&mut self) -> Result<i8, otError>;
+    fn set_fem_lna_gain(&mut self, gain: i8) -> otError;
+    fn get_promiscuous(&mut self) -> bool;
+    fn set_promiscuous(&mut self, enabled: bool);
+    fn set_rx_on_when_idle(&mut self, enabled: bool);
+    fn get_now(&mut self) -> u64;
+    fn get_bus_speed(&mut self) -> u32;
+    fn set_mac_key(
+        &mut self,
+        key_id_mode: u8,
+        key_id: u8,
+        keys: [OTMacKeyMaterial; 3],
+        key_type: OTKeyType,
+    );
+    fn set_mac_frame_counter(&mut self, frame_counter: u32, if_larger: bool);
+    fn enable(&mut self) -> otError;
+    fn disable(&mut self) -> otError;
+    fn is_enabled(&mut self) -> bool;
+    fn sleep(&mut self) -> otError;
+    fn receive(&mut self, channel: u8) -> otError;
+    fn receive_at(&mut self, channel: u8, start: u32, duration: u32) -> otError;
+    fn copy_received_frame(&mut self, buffer: &mut ReceiveFrame) -> bool;
+    fn transmit(&mut self, frame: OTRadioFrame) -> otError;
+    fn get_rssi(&mut self) -> i8;
+    fn enable_src_match(&mut self, enabled: bool);
+    fn add_src_match_short_entry(&mut self, address: OTShortAddress) -> otError;
+    fn add_src_match_ext_entry(&mut self, address: OTExtAddress) -> otError;
+    fn clear_src_match_short_entry(&mut self, address: OTShortAddress) -> otError;
+    fn clear_src_match_ext_entry(&mut self, address: OTExtAddress) -> otError;
+    fn clear_src_match_short_entries(&mut self);
+    fn clear_src_match_ext_entries(&mut self);
+    fn get_supported_channel_mask(&mut self) -> u32;
+    fn get_preferred_channel_mask(&mut self) -> u32;
+    fn set_channel_max_transmit_power(&mut self, channel: u8, max_power: i8) -> otError;
+    fn set_region(&mut self, region_code: u16) -> otError;
+    fn get_region(&mut self) -> Result<u16, otError>;
+    fn configure_enh_ack_probing(
+        &mut self,
+        link_metrics: OTLinkMetrics,
+        short_address: OTShortAddress,
+        ext_address: OTExtAddress,
+    ) -> otError;
+    fn energy_scan(&mut self, channel: u8, duration: u16) -> otError;
+}
+
+impl<R, E> CoreRadio for R
+where
+    R: OTRadioConfiguration<Error = E>
+        + OTRadioConfigurationCapTransmit<Error = E>
+        + OTRadioOperation<Error = E>
+        + OTRadioOperationEnergyScan<Error = E>,
+    E: PlatformError,
+{
+    fn capabilities(&mut self) -> OTRadioCapabilities {
+        self.radio_capabilities().unwrap_or(0)
+    }
+
+    fn receive_sensitivity(&mut self) -> i8 {
+        self.radio_receive_sensitivity().map_or(OT_RADIO_RSSI_INVALID as i8, |sensitivity| sensitivity as i8)
+    }
+
+    fn ieee_eui_64(&mut self) -> Option<[u8; 8]> {
+        self.radio_ieee_eui_64().ok()
+    }
+
+    fn set_pan_id(&mut self, pan_id: OTPanId) {
+        let _ = OTRadioConfiguration::set_pan_id(self, pan_id);
+    }
+
+    fn set_extended_address(&mut self, address: OTExtAddress) {
+        let _ = OTRadioConfiguration::set_extended_address(self, address);
+    }
+
+    fn set_short_address(&mut self, address: OTShortAddress) {
+        let _ = OTRadioConfiguration::set_short_address(self, address);
+    }
+
+    fn get_transmit_power(&mut self) -> Result<i8, otError> {
+        OTRadioConfiguration::get_transmit_power(self).map_err(|error| error.to_platform_code() as otError)
+    }
+
+    fn set_transmit_power(&mut self, power: i8) -> otError {
+        result_code(OTRadioConfiguration::set_transmit_power(self, power))
+    }
+
+    fn get_cca_energy_detect_threshold(&mut self) -> Result<i8, otError> {
+        OTRadioConfiguration::get_cca_energy_detect_threshold(self).map_err(|error| error.to_platform_code() as otError)
+    }
+
+    fn set_cca_energy_detect_threshold(&mut self, threshold: i8) -> otError {
+        result_code(OTRadioConfiguration::set_cca_energy_detect_threshold(self, threshold))
+    }
+
+    fn get_fem_lna_gain(&mut self) -> Result<i8, otError> {
+        OTRadioConfiguration::get_fem_lna_gain(self).map_err(|error| error.to_platform_code() as otError)
+    }
+
+    fn set_fem_lna_gain(&mut self, gain: i8) -> otError {
+        result_code(OTRadioConfiguration::set_fem_lna_gain(self, gain))
+    }
+
+    fn get_promiscuous(&mut self) -> bool {
+        OTRadioConfiguration::get_promiscuous(self).unwrap_or(false)
+    }
+
+    fn set_promiscuous(&mut self, enabled: bool) {
+        let _ = OTRadioConfiguration::set_promiscuous(self, enabled);
+    }
+
+    fn set_rx_on_when_idle(&mut self, enabled: bool) {
+        let _ = OTRadioConfiguration::set_rx_on_when_idle(self, enabled);
+    }
+
+    fn get_now(&mut self) -> u64 {
+        OTRadioConfiguration::get_now(self)
+    }
+
+    fn get_bus_speed(&mut self) -> u32 {
+        OTRadioConfiguration::get_bus_speed(self)
+    }
+
+    fn set_mac_key(
+        &mut self,
+        key_id_mode: u8,
+        key_id: u8,
+        [previous_key, current_key, next_key]: [OTMacKeyMaterial; 3],
+        key_type: OTKeyType,
+    ) {
+        let _ = OTRadioConfigurationCapTransmit::set_mac_key(
+            self,
+            key_id_mode,
+            key_id,
+            previous_key,
+            current_key,
+            next_key,
+            key_type,
+        );
+    }
+
+    fn set_mac_frame_counter(&mut self, frame_counter: u32, if_larger: bool) {
+        let _ = if if_larger {
+            self.set_mac_frame_counter_if_larger(frame_counter)
+        } else {
+            OTRadioConfigurationCapTransmit::set_mac_frame_counter(self, frame_counter)
+        };
+    }
+
+    fn enable(&mut self) -> otError {
+        result_code(OTRadioOperation::enable(self))
+    }
+
+    fn disable(&mut self) -> otError {
+        result_code(OTRadioOperation::disable(self))
+    }
+
+    fn is_enabled(&mut self) -> bool {
+        OTRadioOperation::is_enabled(self).unwrap_or(false)
+    }
+
+    fn sleep(&mut self) -> otError {
+        result_code(OTRadioOperation::sleep(self))
+    }
+
+    fn receive(&mut self, channel: u8) -> otError {
+        result_code(OTRadioOperation::receive(self, channel))
+    }
+
+    fn receive_at(&mut self, channel: u8, start: u32, duration: u32) -> otError {
+        result_code(OTRadioOperation::receive_at(self, channel, start, duration))
+    }
+
+    fn copy_received_frame(&mut self, buffer: &mut ReceiveFrame) -> bool {
+        match self.receive_frame() {
+            Ok(frame) => buffer.copy_from::<E>(&frame).is_ok(),
+            Err(_) => false,
+        }
+    }
+
+    fn transmit(&mut self, frame: OTRadioFrame) -> otError {
+        result_code(OTRadioOperation::transmit(self, frame))
+    }
+
+    fn get_rssi(&mut self) -> i8 {
+        OTRadioOperation::get_rssi(self).unwrap_or(OT_RADIO_RSSI_INVALID as i8)
+    }
+
+    fn enable_src_match(&mut self, enabled: bool) {
+        let _ = OTRadioOperation::enable_src_match(self, enabled);
+    }
+
+    fn add_src_match_short_entry(&mut self, address: OTShortAddress) -> otError {
+        result_code(OTRadioOperation::add_src_match_short_entry(self, address))
+    }
+
+    fn add_src_match_ext_entry(&mut self, address: OTExtAddress) -> otError {
+        result_code(OTRadioOperation::add_src_match_ext_entry(self, address))
+    }
+
+    fn clear_src_match_short_entry(&mut self, address: OTShortAddress) -> otError {
+        result_code(OTRadioOperation::clear_src_match_short_entry(self, address))
+    }
+
+    fn clear_src_match_ext_entry(&mut self, address: OTExtAddress) -> otError {
+        result_code(OTRadioOperation::clear_src_match_ext_entry(self, address))
+    }
+
+    fn clear_src_match_short_entries(&mut self) {
+        let _ = OTRadioOperation::clear_src_match_short_entries(self);
+    }
+
+    fn clear_src_match_ext_entries(&mut self) {
+        let _ = OTRadioOperation::clear_src_match_ext_entries(self);
+    }
+
+    fn get_supported_channel_mask(&mut self) -> u32 {
+        OTRadioOperation::get_supported_channel_mask(self).unwrap_or(0)
+    }
+
+    fn get_preferred_channel_mask(&mut self) -> u32 {
+        OTRadioOperation::get_preferred_channel_mask(self).unwrap_or(0)
+    }
+
+    fn set_channel_max_transmit_power(&mut self, channel: u8, max_power: i8) -> otError {
+        let result = OTRadioOperation::set_channel_max_transmit_power(self, channel, max_power);
+        result_code(result.map_err(OTError::Platform))
+    }
+
+    fn set_region(&mut self, region_code: u16) -> otError {
+        result_code(OTRadioOperation::set_region(self, region_code).map_err(OTError::Platform))
+    }
+
+    fn get_region(&mut self) -> Result<u16, otError> {
+        OTRadioOperation::get_region(self).map_err(|_| OT_ERROR_FAILED)
+    }
+
+    fn configure_enh_ack_probing(
+        &mut self,
+        link_metrics: OTLinkMetrics,
+        short_address: OTShortAddress,
+        ext_address: OTExtAddress,
+    ) -> otError {
+        result_code(OTRadioOperation::configure_enh_ack_probing(self, link_metrics, short_address, ext_address))
+    }
+
+    fn energy_scan(&mut self, channel: u8, duration: u16) -> otError {
+        result_code(OTRadioOperationEnergyScan::energy_scan(self, channel, duration as i16))
+    }
+}
+
+/// Flash driver, object safe view of OTFlash
+trait CoreFlash {
+    fn init(&mut self);
+    fn swap_size(&mut self) -> u32;
+    fn erase(&mut self, swap_index: u8);
+    fn read(&mut self, swap_index: u8, offset: u32, buffer: &mut [u8]);
+    fn write(&mut self, swap_index: u8, offset: u32, buffer: &[u8]);
+}
+
+impl<F: OTFlash> CoreFlash for F {
+    fn init(&mut self) {
+        let _ = OTFlash::init(self);
+    }
+
+    fn swap_size(&mut self) -> u32 {
+        self.get_swap_size().unwrap_or(0)
+    }
+
+    fn erase(&mut self, swap_index: u8) {
+        let _ = self.flash_erase(swap_index);
+    }
+
+    fn read(&mut self, swap_index: u8, offset: u32, buffer: &mut [u8]) {
+        let _ = self.flash_read(swap_index, offset, buffer);
+    }
+
+    fn write(&mut self, swap_index: u8, offset: u32, buffer: &[u8]) {
+        let _ = self.flash_write(swap_index, offset, buffer);
+    }
+}
+
+/// Entropy source, object safe view of OTEntropy
+trait CoreEntropy {
+    fn fill(&mut self, buffer: &mut [u8]) -> otError;
+}
+
+impl<T: OTEntropy> CoreEntropy for T {
+    fn fill(&mut self, buffer: &mut [u8]) -> otError {
+        platform_code(self.get_entropy(buffer))
+    }
+}
+
+/// Miscellaneous platform functions, object safe view of OTMiscellaneous
+trait CoreMiscellaneous {
+    fn reset(&mut self);
+    fn reset_reason(&mut self) -> c_int;
+    fn assert_fail(&mut self, filename: &'static str, line: isize);
+    fn wake_host(&mut self);
+}
+
+impl<M: OTMiscellaneous> CoreMiscellaneous for M {
+    fn reset(&mut self) {
+        let _ = OTMiscellaneous::reset(self);
+    }
+
+    fn reset_reason(&mut self) -> c_int {
+        self.get_reset_reason().map_or(RESET_REASON_UNKNOWN, |reason| reason as c_int)
+    }
+
+    fn assert_fail(&mut self, filename: &'static str, line: isize) {
+        OTMiscellaneous::assert_fail(self, filename, line);
+    }
+
+    fn wake_host(&mut self) {
+        let _ = M::wake_host();
+    }
+}
+
+/// Cell shared with the OpenThread core, only accessed from the context running the core
+struct CoreCell<T>(UnsafeCell<T>);
+
+// The core is single threaded (see the module documentation)
+unsafe impl<T> Sync for CoreCell<T> {}
+
+impl<T> CoreCell<T> {
+    const fn new(value: T) -> Self {
+        Self(UnsafeCell::new(value))
+    }
+
+    fn get(&self) -> *mut T {
+        self.0.get()
+    }
+}
+
+/// Registered drivers
+struct Drivers {
+    radio: Option<NonNull<dyn CoreRadio>>,
+    alarm: Option<NonNull<dyn OTAlarm>>,
+    flash: Option<NonNull<dyn CoreFlash>>,
+    entropy: Option<NonNull<dyn CoreEntropy>>,
+    miscellaneous: Option<NonNull<dyn CoreMiscellaneous>>,
+    // Whether the MAC keys are key references (see otPlatRadioSetMacKey)
+    key_ref: bool,
+    // Instance last passed to `process`
+    instance: *mut otInstance,
+}
+
+/// Transmit buffer handed to the core
+struct TxBuffer {
+    psdu: [u8; OT_RADIO_FRAME_MAX_SIZE],
+    ie_info: RadioIEInfo,
+    frame: otRadioFrame,
+}
+
+/// Notifications waiting to be delivered to the core
+struct Events {
+    tx_started: bool,
+    tx_done: Option<(otError, Option<ReceiveFrame>)>,
+    diag_tx_done: Option<otError>,
+    energy_scan_done: Option<i8>,
+}
+
+static DRIVERS: CoreCell<Drivers> = CoreCell::new(Drivers {
+    radio: None,
+    alarm: None,
+    flash: None,
+    entropy: None,
+    miscellaneous: None,
+    key_ref: false,
+    instance: ptr::null_mut(),
+});
+
+static TX_BUFFER: CoreCell<TxBuffer> = CoreCell::new(TxBuffer {
+    psdu: [0; OT_RADIO_FRAME_MAX_SIZE],
+    ie_info: RadioIEInfo {
+        network_time_offset: 0,
+        time_ie_offset: 0,
+        time_sync_sequency: 0,
+    },
+    frame: otRadioFrame::empty(),
+});
+
+static RX_BUFFER: CoreCell<ReceiveFrame> = CoreCell::new(ReceiveFrame::new());
+
+static EVENTS: CoreCell<Events> = CoreCell::new(Events {
+    tx_started: false,
+    tx_done: None,
+    diag_tx_done: None,
+    energy_scan_done: None,
+});
+
+/// Register the radio driver
+///
+/// Its platform errors are reported to the core by their `PlatformError` code (wrap bus errors in `SpiError` or
+/// `I2cError` to describe them by their kind).
+pub fn register_radio<R, E>(radio: &'static mut R)
+where
+    R: OTRadioConfiguration<Error = E>
+        + OTRadioConfigurationCapTransmit<Error = E>
+        + OTRadioOperation<Error = E>
+        + OTRadioOperationEnergyScan<Error = E>,
+    E: PlatformError,
+{
+    let radio: &mut dyn CoreRadio = radio;
+    unsafe { (*DRIVERS.get()).radio = Some(NonNull::from(radio)) };
+}
+
+/// Register the millisecond alarm
+pub fn register_alarm<A: OTAlarm>(alarm: &'static mut A) {
+    let alarm: &mut dyn OTAlarm = alarm;
+    unsafe { (*DRIVERS.get()).alarm = Some(NonNull::from(alarm)) };
+}
+
+/// Register the flash driver backing the settings
+pub fn register_flash<F: OTFlash>(flash: &'static mut F) {
+    let flash: &mut dyn CoreFlash = flash;
+    unsafe { (*DRIVERS.get()).flash = Some(NonNull::from(flash)) };
+}
+
+/// Register the entropy source
+pub fn register_entropy<T: OTEntropy>(entropy: &'static mut T) {
+    let entropy: &mut dyn CoreEntropy = entropy;
+    unsafe { (*DRIVERS.get()).entropy = Some(NonNull::from(entropy)) };
+}
+
+/// Register the miscellaneous platform functions
+pub fn register_miscellaneous<M: OTMiscellaneous>(miscellaneous: &'static mut M) {
+    let miscellaneous: &mut dyn CoreMiscellaneous = miscellaneous;
+    unsafe { (*DRIVERS.get()).miscellaneous = Some(NonNull::from(miscellaneous)) };
+}
+
+/// Run a closure on the registered radio
+///
+/// Returns:
+///     None if no radio is registered
+fn with_radio<T>(f: impl FnOnce(&mut dyn CoreRadio) -> T) -> Option<T> {
+    let radio = unsafe { (*DRIVERS.get()).radio }?;
+    Some(f(unsafe { &mut *radio.as_ptr() }))
+}
+
+fn with_alarm<T>(f: impl FnOnce(&mut dyn OTAlarm) -> T) -> Option<T> {
+    let alarm = unsafe { (*DRIVERS.get()).alarm }?;
+    Some(f(unsafe { &mut *alarm.as_ptr() }))
+}
+
+fn with_flash<T>(f: impl FnOnce(&mut dyn CoreFlash) -> T) -> Option<T> {
+    let flash = unsafe { (*DRIVERS.get()).flash }?;
+    Some(f(unsafe { &mut *flash.as_ptr() }))
+}
+
+fn with_entropy<T>(f: impl FnOnce(&mut dyn CoreEntropy) -> T) -> Option<T> {
+    let entropy = unsafe { (*DRIVERS.get()).entropy }?;
+    Some(f(unsafe { &mut *entropy.as_ptr() }))
+}
+
+fn with_miscellaneous<T>(f: impl FnOnce(&mut dyn CoreMiscellaneous) -> T) -> Option<T> {
+    let miscellaneous = unsafe { (*DRIVERS.get()).miscellaneous }?;
+    Some(f(unsafe { &mut *miscellaneous.as_ptr() }))
+}
+
+/// Key type of the MAC keys last set by the core
+fn key_type() -> OTKeyType {
+    if unsafe { (*DRIVERS.get()).key_ref } {
+        OTKeyType::ReferenceKey
+    } else {
+        OTKeyType::LiteralKey
+    }
+}
+
+/// Handles of the radio driver, queueing its notifications for the core (see `process`)
+pub struct CoreRadioHandles<E> {
+    _error: PhantomData<E>,
+}
+
+impl<E> CoreRadioHandles<E> {
+    pub const fn new() -> Self {
+        Self { _error: PhantomData }
+    }
+}
+
+impl<E> Default for CoreRadioHandles<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E: PlatformError> OTRadioOperationHandles for CoreRadioHandles<E> {
+    type Error = E;
+
+    fn tx_started(&mut self, _frame: OTRadioFrame) -> Result<(), Self::Error> {
+        unsafe { (*EVENTS.get()).tx_started = true };
+        Ok(())
+    }
+
+    /// The transmitted frame is always the transmit buffer of the core
+    fn tx_done(
+        &mut self,
+        _frame: OTRadioFrame,
+        ack_frame: Option<OTRadioFrame>,
+        result: Result<(), OTError<Self::Error>>,
+    ) -> Result<(), Self::Error> {
+        let ack_frame = ack_frame.and_then(|ack_frame| {
+            let mut copy = ReceiveFrame::new();
+            copy.copy_from::<E>(&ack_frame).ok().map(|()| copy)
+        });
+        unsafe { (*EVENTS.get()).tx_done = Some((result_code(result), ack_frame)) };
+        Ok(())
+    }
+
+    fn diag_tx_done(&mut self, _frame: OTRadioFrame, result: Result<(), OTError<Self::Error>>) -> Result<(), Self::Error> {
+        unsafe { (*EVENTS.get()).diag_tx_done = Some(result_code(result)) };
+        Ok(())
+    }
+
+    /// Asks the core, which owns the power calibration table
+    ///
+    /// Returns:
+    ///     OTError::NotFound if the core has no raw power setting for the channel
+    ///     OTError::InvalidState before the first call to `process`
+    fn get_raw_power_setting(
+        &mut self,
+        channel: u8,
+        raw_power_setting_buffer: &mut [u8],
+    ) -> Result<(), OTError<Self::Error>> {
+        let instance = unsafe { (*DRIVERS.get()).instance };
+        if instance.is_null() {
+            return Err(OTError::InvalidState);
+        }
+        let mut length = u16::try_from(raw_power_setting_buffer.len()).unwrap_or(u16::MAX);
+        let error = unsafe {
+            otPlatRadioGetRawPowerSetting(instance, channel, raw_power_setting_buffer.as_mut_ptr(), &mut length)
+        };
+        match error {
+            OT_ERROR_NONE => Ok(()),
+            _ => Err(OTError::NotFound),
+        }
+    }
+}
+
+impl<E> OTRadioOperationEnergyScanHandles for CoreRadioHandles<E> {
+    type Error = E;
+
+    fn energy_scan_done(&mut self, max_rssi: i8) -> Result<(), Self::Error> {
+        unsafe { (*EVENTS.get()).energy_scan_done = Some(max_rssi) };
+        Ok(())
+    }
+}
+
+/// Deliver the pending notifications of the registered drivers to the core
+///
+/// To be called from the main loop (e.g. from `otSysProcessDrivers`).
+///
+/// Safety:
+///     `instance` must be the initialized OpenThread instance.
+pub unsafe fn process(instance: *mut otInstance) {
+    (*DRIVERS.get()).instance = instance;
+
+    if with_alarm(|alarm| alarm.alarm_fired()).unwrap_or(false) {
+        otPlatAlarmMilliFired(instance);
+    }
+    if with_alarm(|alarm| alarm.alarm_fired_diagnostics()).unwrap_or(false) {
+        otPlatDiagAlarmFired(instance);
+    }
+
+    // The core may call back into the driver (and record new events), so each event is taken out of EVENTS with a
+    // short-lived borrow before the core is called
+    let tx_frame = ptr::addr_of_mut!((*TX_BUFFER.get()).frame);
+    if core::mem::take(&mut (*EVENTS.get()).tx_started) {
+        otPlatRadioTxStarted(instance, tx_frame);
+    }
+    let tx_done = (*EVENTS.get()).tx_done.take();
+    if let Some((error, mut ack_frame)) = tx_done {
+        let mut ack = ack_frame.as_mut().map(otRadioFrame::from_receive_frame);
+        let ack = ack.as_mut().map_or(ptr::null_mut(), |ack| ack as *mut otRadioFrame);
+        otPlatRadioTxDone(instance, tx_frame, ack, error);
+    }
+    let diag_tx_done = (*EVENTS.get()).diag_tx_done.take();
+    if let Some(error) = diag_tx_done {
+        otPlatDiagRadioTransmitDone(instance, tx_frame, error);
+    }
+    let energy_scan_done = (*EVENTS.get()).energy_scan_done.take();
+    if let Some(max_rssi) = energy_scan_done {
+        otPlatRadioEnergyScanDone(instance, max_rssi);
+    }
+
+    // The frame is copied out of the driver since the core processes the security of received frames in place
+    let rx_buffer = &mut *RX_BUFFER.get();
+    while with_radio(|radio| radio.copy_received_frame(rx_buffer)).unwrap_or(false) {
+        let mut frame = otRadioFrame::from_receive_frame(rx_buffer);
+        otPlatRadioReceiveDone(instance, &mut frame, OT_ERROR_NONE);
+    }
+}
+
+// Callbacks implemented by the OpenThread core
+extern "C" {
+    fn otPlatAlarmMilliFired(instance: *mut otInstance);
+    fn otPlatDiagAlarmFired(instance: *mut otInstance);
+    fn otPlatRadioTxStarted(instance: *mut otInstance, frame: *mut otRadioFrame);
+    fn otPlatRadioTxDone(instance: *mut otInstance, frame: *mut otRadioFrame, ack_frame: *mut otRadioFrame, error: otError);
+    fn otPlatDiagRadioTransmitDone(instance: *mut otInstance, frame: *mut otRadioFrame, error: otError);
+    fn otPlatRadioReceiveDone(instance: *mut otInstance, frame: *mut otRadioFrame, error: otError);
+    fn otPlatRadioEnergyScanDone(instance: *mut otInstance, max_rssi: i8);
+    fn otPlatRadioGetRawPowerSetting(
+        instance: *mut otInstance,
+        channel: u8,
+        raw_power_setting: *mut u8,
+        raw_power_setting_length: *mut u16,
+    ) -> otError;
+}
+
+// Radio (openthread/platform/radio.h)
+
+#[no_mangle]
+pub extern "C" fn otPlatRadioGetCaps(_instance: *mut otInstance) -> OTRadioCapabilities {
+    with_radio(|radio| radio.capabilities()).unwrap_or(0)
+}
+
+#[no_mangle]
+pub extern "C" fn otPlatRadioGetReceiveSensitivity(_instance: *mut otInstance) -> i8 {
+    with_radio(|radio| radio.receive_sensitivity()).unwrap_or(OT_RADIO_RSSI_INVALID as i8)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn otPlatRadioGetIeeeEui64(_instance: *mut otInstance, ieee_eui_64: *mut u8) {
+    if let Some(eui) = with_radio(|radio| radio.ieee_eui_64()).flatten() {
+        ptr::copy_nonoverlapping(eui.as_ptr(), ieee_eui_64, eui.len());
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn otPlatRadioSetPanId(_instance: *mut otInstance, pan_id: OTPanId) {
+    with_radio(|radio| radio.set_pan_id(pan_id));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn otPlatRadioSetExtendedAddress(_instance: *mut otInstance, address: *const OTExtAddress) {
+    let address = core_ext_address(address);
+    with_radio(|radio| radio.set_extended_address(address));
+}
+
+#[no_mangle]
+pub extern "C" fn otPlatRadioSetShortAddress(_instance: *mut otInstance, address: OTShortAddress) {
+    with_radio(|radio| radio.set_short_address(address));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn otPlatRadioGetTransmitPower(_instance: *mut otInstance, power: *mut i8) -> otError {
+    match with_radio(|radio| radio.get_transmit_power()) {
+        Some(Ok(value)) => {
+            *power = value;
+            OT_ERROR_NONE
+        },
+        Some(Err(error)) => error,
+        None => OT_ERROR_INVALID_STATE,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn otPlatRadioSetTransmitPower(_instance: *mut otInstance, power: i8) -> otError {
+    with_radio(|radio| radio.set_transmit_power(power)).unwrap_or(OT_ERROR_INVALID_STATE)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn otPlatRadioGetCcaEnergyDetectThreshold(_instance: *mut otInstance, threshold: *mut i8) -> otError {
+    match with_radio(|radio| radio.get_cca_energy_detect_threshold()) {
+        Some(Ok(value)) => {
+            *threshold = value;
+            OT_ERROR_NONE
+        },
+        Some(Err(error)) => error,
+        None => OT_ERROR_INVALID_STATE,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn otPlatRadioSetCcaEnergyDetectThreshold(_instance: *mut otInstance, threshold: i8) -> otError {
+    with_radio(|radio| radio.set_cca_energy_detect_threshold(threshold)).unwrap_or(OT_ERROR_INVALID_STATE)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn otPlatRadioGetFemLnaGain(_instance: *mut otInstance, gain: *mut i8) -> otError {
+    match with_radio(|radio| radio.get_fem_lna_gain()) {
+        Some(Ok(value)) => {
+            *gain = value;
+            OT_ERROR_NONE
+        },
+        Some(Err(error)) => error,
+        None => OT_ERROR_INVALID_STATE,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn otPlatRadioSetFemLnaGain(_instance: *mut otInstance, gain: i8) -> otError {
+    with_radio(|radio| radio.set_fem_lna_gain(gain)).unwrap_or(OT_ERROR_INVALID_STATE)
+}
+
+#[no_mangle]
+pub extern "C" fn otPlatRadioGetPromiscuous(_instance: *mut otInstance) -> bool {
+    with_radio(|radio| radio.get_promiscuous()).unwrap_or(false)
+}
+
+#[no_mangle]
+pub extern "C" fn otPlatRadioSetPromiscuous(_instance: *mut otInstance, enabled: bool) {
+    with_radio(|radio| radio.set_promiscuous(enabled));
+}
+
+#[no_mangle]
+pub extern "C" fn otPlatRadioSetRxOnWhenIdle(_instance: *mut otInstance, enabled: bool) {
+    with_radio(|radio| radio.set_rx_on_when_idle(enabled));
+}
+
+#[no_mangle]
+pub extern "C" fn otPlatRadioGetNow(_instance: *mut otInstance) -> u64 {
+    with_radio(|radio| radio.get_now()).unwrap_or(0)
+}
+
+#[no_mangle]
+pub extern "C" fn otPlatRadioGetBusSpeed(_instance: *mut otInstance) -> u32 {
+    with_radio(|radio| radio.get_bus_speed()).unwrap_or(0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn otPlatRadioSetMacKey(
+    _instance: *mut otInstance,
+    key_id_mode: u8,
+    key_id: u8,
+    previous_key: *const otMacKeyMaterial,
+    current_key: *const otMacKeyMaterial,
+    next_key: *const otMacKeyMaterial,
+    radio_key_type: c_int,
+) {
+    (*DRIVERS.get()).key_ref = radio_key_type == OT_KEY_TYPE_KEY_REF;
+    let keys = [
+        key_material(previous_key, key_type()),
+        key_material(current_key, key_type()),
+        key_material(next_key, key_type()),
+    ];
+    with_radio(|radio| radio.set_mac_key(key_id_mode, key_id, keys, key_type()));
+}
+
+#[no_mangle]
+pub extern "C" fn otPlatRadioSetMacFrameCounter(_instance: *mut otInstance, frame_counter: u32) {
+    with_radio(|radio| radio.set_mac_frame_counter(frame_counter, false));
+}
+
+#[no_mangle]
+pub extern "C" fn otPlatRadioSetMacFrameCounterIfLarger(_instance: *mut otInstance, frame_counter: u32) {
+    with_radio(|radio| radio.set_mac_frame_counter(frame_counter, true));
+}
+
+#[no_mangle]
+pub extern "C" fn otPlatRadioEnable(_instance: *mut otInstance) -> otError {
+    with_radio(|radio| radio.enable()).unwrap_or(OT_ERROR_INVALID_STATE)
+}
+
+#[no_mangle]
+pub extern "C" fn otPlatRadioDisable(_instance: *mut otInstance) -> otError {
+    with_radio(|radio| radio.disable()).unwrap_or(OT_ERROR_INVALID_STATE)
+}
+
+#[no_mangle]
+pub extern "C" fn otPlatRadioIsEnabled(_instance: *mut otInstance) -> bool {
+    with_radio(|radio| radio.is_enabled()).unwrap_or(false)
+}
+
+#[no_mangle]
+pub extern "C" fn otPlatRadioSleep(_instance: *mut otInstance) -> otError {
+    with_radio(|radio| radio.sleep()).unwrap_or(OT_ERROR_INVALID_STATE)
+}
+
+#[no_mangle]
+pub extern "C" fn otPlatRadioReceive(_instance: *mut otInstance, channel: u8) -> otError {
+    with_radio(|radio| radio.receive(channel)).unwrap_or(OT_ERROR_INVALID_STATE)
+}
+
+#[no_mangle]
+pub extern "C" fn otPlatRadioReceiveAt(_instance: *mut otInstance, channel: u8, start: u32, duration: u32) -> otError {
+    with_radio(|radio| radio.receive_at(channel, start, duration)).unwrap_or(OT_ERROR_INVALID_STATE)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn otPlatRadioGetTransmitBuffer(_instance: *mut otInstance) -> *mut otRadioFrame {
+    let buffer = &mut *TX_BUFFER.get();
+    buffer.frame.psdu = buffer.psdu.as_mut_ptr();
+    buffer.frame.info.tx_info.ie_info = &mut buffer.ie_info;
+    &mut buffer.frame
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn otPlatRadioTransmit(_instance: *mut otInstance, frame: *mut otRadioFrame) -> otError {
+    let frame = (*frame).as_tx_frame(key_type());
+    with_radio(|radio| radio.transmit(frame)).unwrap_or(OT_ERROR_INVALID_STATE)
+}
+
+#[no_mangle]
+pub extern "C" fn otPlatRadioGetRssi(_instance: *mut otInstance) -> i8 {
+    with_radio(|radio| radio.get_rssi()).unwrap_or(OT_RADIO_RSSI_INVALID as i8)
+}
+
+#[no_mangle]
+pub extern "C" fn otPlatRadioEnableSrcMatch(_instance: *mut otInstance, enabled: bool) {
+    with_radio(|radio| radio.enable_src_match(enabled));
+}
+
+#[no_mangle]
+pub extern "C" fn otPlatRadioAddSrcMatchShortEntry(_instance: *mut otInstance, address: OTShortAddress) -> otError {
+    with_radio(|radio| radio.add_src_match_short_entry(address)).unwrap_or(OT_ERROR_INVALID_STATE)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn otPlatRadioAddSrcMatchExtEntry(_instance: *mut otInstance, address: *const OTExtAddress) -> otError {
+    let address = core_ext_address(address);
+    with_radio(|radio| radio.add_src_match_ext_entry(address)).unwrap_or(OT_ERROR_INVALID_STATE)
+}
+
+#[no_mangle]
+pub extern "C" fn otPlatRadioClearSrcMatchShortEntry(_instance: *mut otInstance, address: OTShortAddress) -> otError {
+    with_radio(|radio| radio.clear_src_match_short_entry(address)).unwrap_or(OT_ERROR_INVALID_STATE)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn otPlatRadioClearSrcMatchExtEntry(
+    _instance: *mut otInstance,
+    address: *const OTExtAddress,
+) -> otError {
+    let address = core_ext_address(address);
+    with_radio(|radio| radio.clear_src_match_ext_entry(address)).unwrap_or(OT_ERROR_INVALID_STATE)
+}
+
+#[no_mangle]
+pub extern "C" fn otPlatRadioClearSrcMatchShortEntries(_instance: *mut otInstance) {
+    with_radio(|radio| radio.clear_src_match_short_entries());
+}
+
+#[no_mangle]
+pub extern "C" fn otPlatRadioClearSrcMatchExtEntries(_instance: *mut otInstance) {
+    with_radio(|radio| radio.clear_src_match_ext_entries());
+}
+
+#[no_mangle]
+pub extern "C" fn otPlatRadioGetSupportedChannelMask(_instance: *mut otInstance) -> u32 {
+    with_radio(|radio| radio.get_supported_channel_mask()).unwrap_or(0)
+}
+
+#[no_mangle]
+pub extern "C" fn otPlatRadioGetPreferredChannelMask(_instance: *mut otInstance) -> u32 {
+    with_radio(|radio| radio.get_preferred_channel_mask()).unwrap_or(0)
+}
+
+#[no_mangle]
+pub extern "C" fn otPlatRadioSetChannelMaxTransmitPower(_instance: *mut otInstance, channel: u8, max_power: i8) -> otError {
+    with_radio(|radio| radio.set_channel_max_transmit_power(channel, max_power)).unwrap_or(OT_ERROR_INVALID_STATE)
+}
+
+#[no_mangle]
+pub extern "C" fn otPlatRadioSetRegion(_instance: *mut otInstance, region_code: u16) -> otError {
+    with_radio(|radio| radio.set_region(region_code)).unwrap_or(OT_ERROR_INVALID_STATE)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn otPlatRadioGetRegion(_instance: *mut otInstance, region_code: *mut u16) -> otError {
+    match with_radio(|radio| radio.get_region()) {
+        Some(Ok(value)) => {
+            *region_code = value;
+            OT_ERROR_NONE
+        },
+        Some(Err(error)) => error,
+        None => OT_ERROR_INVALID_STATE,
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn otPlatRadioConfigureEnhAckProbing(
+    _instance: *mut otInstance,
+    link_metrics: otLinkMetrics,
+    short_address: OTShortAddress,
+    ext_address: *const OTExtAddress,
+) -> otError {
+    let link_metrics = OTLinkMetrics {
+        pdu_count: link_metrics.flags & LINK_METRICS_PDU_COUNT != 0,
+        lqi: link_metrics.flags & LINK_METRICS_LQI != 0,
+        link_margin: link_metrics.flags & LINK_METRICS_LINK_MARGIN != 0,
+        rssi: link_metrics.flags & LINK_METRICS_RSSI != 0,
+        reserved: false,
+    };
+    let ext_address = ext_address.as_ref().copied().unwrap_or([0; OT_EXT_ADDRESS_SIZE]);
+    with_radio(|radio| radio.configure_enh_ack_probing(link_metrics, short_address, ext_address))
+        .unwrap_or(OT_ERROR_INVALID_STATE)
+}
+
+#[no_mangle]
+pub extern "C" fn otPlatRadioEnergyScan(_instance: *mut otInstance, channel: u8, duration: u16) -> otError {
+    with_radio(|radio| radio.energy_scan(channel, duration)).unwrap_or(OT_ERROR_INVALID_STATE)
+}
+
+// Alarm (openthread/platform/alarm-milli.h)
+
+#[no_mangle]
+pub extern "C" fn otPlatAlarmMilliStartAt(_instance: *mut otInstance, t0: u32, dt: u32) {
+    with_alarm(|alarm| alarm.start_alarm_at(t0, dt));
+}
+
+#[no_mangle]
+pub extern "C" fn otPlatAlarmMilliStop(_instance: *mut otInstance) {
+    with_alarm(|alarm| alarm.stop_alarm());
+}
+
+#[no_mangle]
+pub extern "C" fn otPlatAlarmMilliGetNow() -> u32 {
+    with_alarm(|alarm| alarm.get_now()).unwrap_or(0)
+}
+
+// Flash (openthread/platform/flash.h)
+
+#[no_mangle]
+pub extern "C" fn otPlatFlashInit(_instance: *mut otInstance) {
+    with_flash(|flash| flash.init());
+}
+
+#[no_mangle]
+pub extern "C" fn otPlatFlashGetSwapSize(_instance: *mut otInstance) -> u32 {
+    with_flash(|flash| flash.swap_size()).unwrap_or(0)
+}
+
+#[no_mangle]
+pub extern "C" fn otPlatFlashErase(_instance: *mut otInstance, swap_index: u8) {
+    with_flash(|flash| flash.erase(swap_index));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn otPlatFlashRead(
+    _instance: *mut otInstance,
+    swap_index: u8,
+    offset: u32,
+    data: *mut c_void,
+    size: u32,
+) {
+    let buffer = slice::from_raw_parts_mut(data as *mut u8, size as usize);
+    with_flash(|flash| flash.read(swap_index, offset, buffer));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn otPlatFlashWrite(
+    _instance: *mut otInstance,
+    swap_index: u8,
+    offset: u32,
+    data: *const c_void,
+    size: u32,
+) {
+    let buffer = slice::from_raw_parts(data as *const u8, size as usize);
+    with_flash(|flash| flash.write(swap_index, offset, buffer));
+}
+
+// Entropy (openthread/platform/entropy.h)
+
+#[no_mangle]
+pub unsafe extern "C" fn otPlatEntropyGet(output: *mut u8, output_length: u16) -> otError {
+    let buffer = slice::from_raw_parts_mut(output, output_length as usize);
+    with_entropy(|entropy| entropy.fill(buffer)).unwrap_or(OT_ERROR_FAILED)
+}
+
+// Miscellaneous (openthread/platform/misc.h)
+
+#[no_mangle]
+pub extern "C" fn otPlatReset(_instance: *mut otInstance) {
+    with_miscellaneous(|miscellaneous| miscellaneous.reset());
+}
+
+#[no_mangle]
+pub extern "C" fn otPlatGetResetReason(_instance: *mut otInstance) -> c_int {
+    with_miscellaneous(|miscellaneous| miscellaneous.reset_reason()).unwrap_or(RESET_REASON_UNKNOWN)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn otPlatAssertFail(filename: *const c_char, line: c_int) {
+    // The file name is a string literal of the core (__FILE__)
+    let filename: &'static str = if filename.is_null() {
+        ""
+    } else {
+        CStr::from_ptr(filename).to_str().unwrap_or("")
+    };
+    with_miscellaneous(|miscellaneous| miscellaneous.assert_fail(filename, line as isize));
+}
+
+#[no_mangle]
+pub extern "C" fn otPlatWakeHost() {
+    with_miscellaneous(|miscellaneous| miscellaneous.wake_host());
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::boxed::Box;
+    use alloc::vec::Vec;
+    use core::convert::Infallible;
+
+    use super::*;
+    use crate::radio::builder::FrameBuilder;
+    use crate::radio::frame::{Frame, FrameVersion, MacAddress};
+    use crate::radio::srcmatch::DATA_REQUEST_COMMAND_ID;
+    use crate::radio::TransmitFrame;
+    use crate::sim::{SimMedium, SimRadio};
+
+    const PAN_ID: OTPanId = 0x1234;
+    const CHANNEL: u8 = 11;
+    // Extended addresses in the byte order of the drivers
+    const CORE_EXT_ADDRESS: OTExtAddress = [0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17];
+    const PEER_EXT_ADDRESS: OTExtAddress = [0x20, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27];
+
+    /// Handles recording the frame pending bit of the ACKs (None if there was no ACK)
+    #[derive(Default)]
+    struct AckFramePending(Vec<Option<bool>>);
+
+    impl OTRadioOperationHandles for AckFramePending {
+        type Error = Infallible;
+
+        fn tx_started(&mut self, _frame: OTRadioFrame) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn tx_done(
+            &mut self,
+            _frame: OTRadioFrame,
+            ack_frame: Option<OTRadioFrame>,
+            _result: Result<(), OTError<Self::Error>>,
+        ) -> Result<(), Self::Error> {
+            self.0.push(ack_frame.map(|ack| Frame::parse::<Infallible>(ack.psdu).unwrap().frame_pending()));
+            Ok(())
+        }
+
+        fn diag_tx_done(&mut self, _frame: OTRadioFrame, _result: Result<(), OTError<Self::Error>>) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn get_raw_power_setting(&mut self, _channel: u8, _raw_power_setting_buffer: &mut [u8]) -> Result<(), OTError<Self::Error>> {
+            Err(OTError::NotImplemented)
+        }
+    }
+
+    impl OTRadioOperationEnergyScanHandles for AckFramePending {
+        type Error = Infallible;
+
+        fn energy_scan_done(&mut self, _max_rssi: i8) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    /// Send a data request from the peer to the node driven through the C API
+    ///
+    /// Returns:
+    ///     The frame pending bit of the ACK (None if the node did not acknowledge the frame)
+    fn data_request(medium: &SimMedium, peer: &mut SimRadio, sequence_number: u8) -> Option<bool> {
+        let mut frame = TransmitFrame::new();
+        FrameBuilder::mac_command(FrameVersion::Version2006, DATA_REQUEST_COMMAND_ID)
+            .sequence_number(Some(sequence_number))
+            .dst(Some(PAN_ID), MacAddress::Extended(CORE_EXT_ADDRESS))
+            .src(None, MacAddress::Extended(PEER_EXT_ADDRESS))
+            .ack_request(true)
+            .build::<Infallible>(&mut frame)
+            .unwrap();
+        let radio_frame = OTRadioFrame {
+            psdu: frame.psdu(),
+            channel: CHANNEL,
+            radio_type: 0,
+            frame_information: OTFrameInformation::TxInfo {
+                aes_key: OTMacKeyMaterial::Key([0; OT_MAC_KEY_SIZE]),
+                io_info: &NO_IE_INFO,
+                tx_delay_base_time: 0,
+                tx_delay: 0,
+                max_csma_backoffs: 0,
+                max_frame_retries: 0,
+                rx_channel_after_tx_done: CHANNEL,
+                is_header_updated: false,
+                is_a_retx: false,
+                csma_ca_enabled: false,
+                csl_present: false,
+                is_security_processed: false,
+            },
+        };
+        OTRadioOperation::transmit(peer, radio_frame).unwrap();
+
+        medium.advance(10_000);
+        let mut handles = AckFramePending::default();
+        peer.process(&mut handles).unwrap();
+        handles.0.pop().flatten()
+    }
+
+    #[test]
+    fn ext_addresses_from_the_core_are_byte_reversed() {
+        let medium = SimMedium::new(1);
+        let mut peer = medium.add_node(PEER_EXT_ADDRESS);
+        OTRadioConfiguration::set_pan_id(&mut peer, PAN_ID).unwrap();
+        OTRadioOperation::enable(&mut peer).unwrap();
+        OTRadioOperation::receive(&mut peer, CHANNEL).unwrap();
+        register_radio(Box::leak(Box::new(medium.add_node([0x30; 8]))));
+
+        // The core passes extended addresses in little endian byte order
+        let mut core_ext_address = CORE_EXT_ADDRESS;
+        core_ext_address.reverse();
+        let mut peer_ext_address = PEER_EXT_ADDRESS;
+        peer_ext_address.reverse();
+
+        let instance = ptr::null_mut();
+        unsafe {
+            otPlatRadioSetPanId(instance, PAN_ID);
+            otPlatRadioSetExtendedAddress(instance, &core_ext_address);
+            assert_eq!(otPlatRadioEnable(instance), OT_ERROR_NONE);
+            assert_eq!(otPlatRadioReceive(instance, CHANNEL), OT_ERROR_NONE);
+            otPlatRadioEnableSrcMatch(instance, true);
+            assert_eq!(otPlatRadioAddSrcMatchExtEntry(instance, &peer_ext_address), OT_ERROR_NONE);
+        }
+        assert_eq!(data_request(&medium, &mut peer, 1), Some(true));
+
+        unsafe {
+            assert_eq!(otPlatRadioClearSrcMatchExtEntry(instance, &peer_ext_address), OT_ERROR_NONE);
+        }
+        assert_eq!(data_request(&medium, &mut peer, 2), Some(false));
+
+        // Without a registered implementation the reset reason is unknown
+        assert_eq!(otPlatGetResetReason(instance), OTResetReason::Unknown as c_int);
+    }
+}
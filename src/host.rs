@@ -0,0 +1,249 @@
+//!
+//! Host (std) Platform Implementation
+//!
+//! Ready implementations of the platform traits for running the upper layers on a host operating system (e.g. a Linux
+//! developer box or CI), in the spirit of the OpenThread POSIX platform:
+//!     - `HostFlash` stores both swap areas in a file
+//!     - `HostAlarm` runs on the monotonic clock
+//!     - `HostEntropy` reads the OS random number generator
+//!     - `HostMiscellaneous` records the reason of the last (simulated) reset
+//!
+//! Only available with the `std` feature.
+//!
+
+use std::convert::Infallible;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::time::Instant;
+
+use crate::alarm::OTAlarm;
+use crate::entropy::OTEntropy;
+use crate::flash::OTFlash;
+use crate::misc::{OTMiscellaneous, OTResetReason};
+
+// Default size of a flash swap area (in bytes)
+pub const HOST_FLASH_DEFAULT_SWAP_SIZE: u32 = 0x1000;
+// Path of the OS random number generator
+const OS_RANDOM_PATH: &str = "/dev/urandom";
+
+/// Flash driver storing both swap areas back to back in a file
+pub struct HostFlash {
+    file: File,
+    swap_size: u32,
+}
+
+impl HostFlash {
+    /// Open (or create) a flash file with the default swap size
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::open_with_swap_size(path, HOST_FLASH_DEFAULT_SWAP_SIZE)
+    }
+
+    /// Open (or create) a flash file
+    ///
+    /// A new (or shorter) file is extended with erased (0xff) bytes so it holds both swap areas.
+    pub fn open_with_swap_size<P: AsRef<Path>>(path: P, swap_size: u32) -> io::Result<Self> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+
+        let length = file.metadata()?.len();
+        let flash_size = 2 * swap_size as u64;
+        if length < flash_size {
+            file.seek(SeekFrom::Start(length))?;
+            file.write_all(&std::vec![0xff; (flash_size - length) as usize])?;
+        }
+
+        Ok(Self { file, swap_size })
+    }
+
+    fn address(&self, swap_index: u8, offset: u32, length: usize) -> io::Result<u64> {
+        if swap_index > 1 || offset as u64 + length as u64 > self.swap_size as u64 {
+            return Err(io::Error::from(io::ErrorKind::InvalidInput));
+        }
+        Ok(swap_index as u64 * self.swap_size as u64 + offset as u64)
+    }
+}
+
+impl OTFlash for HostFlash {
+    type Error = io::Error;
+
+    fn init(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn get_swap_size(&mut self) -> Result<u32, Self::Error> {
+        Ok(self.swap_size)
+    }
+
+    fn flash_erase(&mut self, swap_index: u8) -> Result<(), Self::Error> {
+        let address = self.address(swap_index, 0, self.swap_size as usize)?;
+        self.file.seek(SeekFrom::Start(address))?;
+        self.file.write_all(&std::vec![0xff; self.swap_size as usize])?;
+        self.file.flush()
+    }
+
+    fn flash_read(&mut self, swap_index: u8, offset: u32, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        let address = self.address(swap_index, offset, buffer.len())?;
+        self.file.seek(SeekFrom::Start(address))?;
+        self.file.read_exact(buffer)
+    }
+
+    fn flash_write(&mut self, swap_index: u8, offset: u32, buffer: &[u8]) -> Result<(), Self::Error> {
+        let address = self.address(swap_index, offset, buffer.len())?;
+        let mut current = std::vec![0u8; buffer.len()];
+        self.file.seek(SeekFrom::Start(address))?;
+        self.file.read_exact(&mut current)?;
+
+        // Like real flash, writing can only clear bits
+        for (byte, value) in current.iter_mut().zip(buffer.iter()) {
+            *byte &= value;
+        }
+
+        self.file.seek(SeekFrom::Start(address))?;
+        self.file.write_all(&current)?;
+        self.file.flush()
+    }
+}
+
+/// Millisecond alarm running on the monotonic clock
+pub struct HostAlarm {
+    start: Instant,
+    // Deadline (in ms since start) of the running alarm
+    deadline: Option<u32>,
+}
+
+impl HostAlarm {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            deadline: None,
+        }
+    }
+
+    /// Get the time remaining until the alarm fires (in ms), if it is running
+    pub fn remaining(&mut self) -> Option<u32> {
+        let deadline = self.deadline?;
+        let remaining = deadline.wrapping_sub(self.get_now()) as i32;
+        Some(remaining.max(0) as u32)
+    }
+}
+
+impl Default for HostAlarm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OTAlarm for HostAlarm {
+    /// Returns true once after the alarm deadline has passed
+    fn alarm_fired(&mut self) -> bool {
+        let now = self.get_now();
+        match self.deadline {
+            Some(deadline) if now.wrapping_sub(deadline) as i32 >= 0 => {
+                self.deadline = None;
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn alarm_fired_diagnostics(&mut self) -> bool {
+        false
+    }
+
+    fn start_alarm_at(&mut self, t0: u32, dt: u32) {
+        self.deadline = Some(t0.wrapping_add(dt));
+    }
+
+    fn stop_alarm(&mut self) {
+        self.deadline = None;
+    }
+
+    fn get_now(&mut self) -> u32 {
+        // The millisecond clock wraps around like the platform alarm counters do
+        self.start.elapsed().as_millis() as u32
+    }
+}
+
+/// Entropy source reading the OS random number generator
+pub struct HostEntropy {
+    file: File,
+}
+
+impl HostEntropy {
+    pub fn new() -> io::Result<Self> {
+        Ok(Self {
+            file: File::open(OS_RANDOM_PATH)?,
+        })
+    }
+}
+
+impl OTEntropy for HostEntropy {
+    type Error = io::Error;
+
+    fn get_entropy(&mut self, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        self.file.read_exact(buffer)
+    }
+}
+
+/// Miscellaneous platform behaviors recording the reason of the last reset
+///
+/// A host process cannot reset itself, so `reset` and `assert_fail` only record the reason the next call to
+/// `get_reset_reason` reports (`assert_fail` then panics).
+pub struct HostMiscellaneous {
+    reset_reason: OTResetReason,
+    // Number of resets performed since the platform was created
+    reset_count: u32,
+}
+
+impl HostMiscellaneous {
+    pub fn new() -> Self {
+        Self {
+            reset_reason: OTResetReason::PowerOn,
+            reset_count: 0,
+        }
+    }
+
+    /// Record the reason of the last reset
+    pub fn set_reset_reason(&mut self, reason: OTResetReason) {
+        self.reset_reason = reason;
+    }
+
+    /// Get the number of resets performed since the platform was created
+    pub fn reset_count(&self) -> u32 {
+        self.reset_count
+    }
+}
+
+impl Default for HostMiscellaneous {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OTMiscellaneous for HostMiscellaneous {
+    type Error = Infallible;
+
+    fn reset(&mut self) -> Result<(), Self::Error> {
+        self.reset_reason = OTResetReason::Software;
+        self.reset_count += 1;
+        Ok(())
+    }
+
+    fn get_reset_reason(&mut self) -> Result<OTResetReason, Self::Error> {
+        Ok(self.reset_reason)
+    }
+
+    fn assert_fail(&mut self, filename: &'static str, line: isize) {
+        self.reset_reason = OTResetReason::Assert;
+        panic!("assert failed at {}:{}", filename, line);
+    }
+
+    fn wake_host() -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
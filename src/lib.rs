@@ -6,6 +6,9 @@
 
 extern crate alloc;
 
+#[cfg(feature = "std")]
+extern crate std;
+
 pub mod alarm;
 
 pub mod radio;
@@ -16,4 +19,19 @@ pub mod error;
 
 pub mod misc;
 
-pub mod entropy;
\ No newline at end of file
+pub mod entropy;
+
+#[cfg(any(test, feature = "sim"))]
+pub mod sim;
+
+pub mod flash;
+
+pub mod settings;
+
+pub mod spinel;
+
+#[cfg(feature = "std")]
+pub mod host;
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
@@ -3,6 +3,7 @@
 //! 
 
 /// The possible reset codes for the device
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum OTResetReason {
     PowerOn = 0,
     External = 1,
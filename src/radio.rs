@@ -8,6 +8,37 @@ use alloc::string::String;
 
 use crate::{crypto::OTCryptoKeyRef, error::OTError};
 
+pub mod frame;
+
+pub mod builder;
+
+pub mod ie;
+
+pub mod security;
+
+pub mod submac;
+
+pub mod pool;
+
+pub mod state;
+
+pub mod srcmatch;
+
+pub mod enhack;
+
+pub mod linkmetrics;
+
+pub mod csl;
+
+pub mod scan;
+
+pub mod power;
+
+pub mod phy;
+
+#[cfg(feature = "async")]
+pub mod asynch;
+
 // aMaxPHYPacketSize (IEEE 802.15.4-2006)
 pub const OT_RADIO_FRAME_MAX_SIZE: usize = 127;
 // Minimal size of frame FCS + CONTROL
@@ -26,7 +57,7 @@ pub const OT_RADIO_BITS_PER_OCTET: usize = 8;
 // The O-QPSK PHY symbol rate when operating in the 780MHz, 915MHz, 2380MHz, 2450Mhz
 pub const OT_RADIO_SYMBOL_RATE: usize = 62500;
 // Symbol duration time in unit of microseconds
-pub const OT_RADIO_SYMBOL_TIME: usize = 1_000_000 * 1 / OT_RADIO_SYMBOL_RATE;
+pub const OT_RADIO_SYMBOL_TIME: usize = 1_000_000 / OT_RADIO_SYMBOL_RATE;
 // Time for 10 symbols in unit of microseconds
 pub const OT_RADIO_TEN_SYMBOLS_TIME: usize = 10 * OT_RADIO_SYMBOL_TIME;
 
@@ -120,6 +151,7 @@ pub type OTMacKey = [u8; OT_MAC_KEY_SIZE];
 pub type OTMacKeyRef = OTCryptoKeyRef;
 
 /// Mak Key Representation
+#[derive(Clone, Copy)]
 pub enum OTMacKeyMaterial {
     Key(OTMacKey),
     Ref(OTMacKeyRef),
@@ -132,6 +164,10 @@ pub enum OTKeyType {
 }
 
 /// IEEE 802.15.4 Header IE (Information Element) related information of a radio frame.
+///
+/// Laid out as the C `otRadioIeInfo` so it can be shared with the OpenThread core (see the ffi module).
+#[derive(Clone, Copy, Default)]
+#[repr(C)]
 pub struct RadioIEInfo {
     // The time offset to the Thread network time.
     pub network_time_offset: i64,
@@ -141,14 +177,179 @@ pub struct RadioIEInfo {
     pub time_sync_sequency: u8
 }
 
+/// Owned buffer holding an outgoing IEEE 802.15.4 frame
 pub struct TransmitFrame {
+    // The PSDU buffer
+    psdu: [u8; OT_RADIO_FRAME_MAX_SIZE],
+    // Length of the PSDU (including the FCS)
+    length: usize,
+}
+
+impl TransmitFrame {
+    /// Create an empty transmit frame
+    pub const fn new() -> Self {
+        Self {
+            psdu: [0u8; OT_RADIO_FRAME_MAX_SIZE],
+            length: 0,
+        }
+    }
+
+    /// Create a builder for a data frame of the given version
+    pub fn builder<'a>(version: frame::FrameVersion) -> builder::FrameBuilder<'a> {
+        builder::FrameBuilder::data(version)
+    }
+
+    /// Get the PSDU (including the FCS)
+    pub fn psdu(&self) -> &[u8] {
+        &self.psdu[..self.length]
+    }
 
+    /// Get the mutable PSDU (including the FCS)
+    pub fn psdu_mut(&mut self) -> &mut [u8] {
+        &mut self.psdu[..self.length]
+    }
+
+    /// Get the length of the PSDU (including the FCS)
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    /// Set the length of the PSDU (including the FCS)
+    pub fn set_length<E>(&mut self, length: usize) -> Result<(), OTError<E>> {
+        if length > OT_RADIO_FRAME_MAX_SIZE {
+            return Err(OTError::NoBuffers);
+        }
+        self.length = length;
+        Ok(())
+    }
+
+    /// Parse the PSDU of this frame
+    pub fn parse<E>(&self) -> Result<frame::Frame<'_>, OTError<E>> {
+        frame::Frame::parse(self.psdu())
+    }
 }
 
+impl Default for TransmitFrame {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Owned buffer holding a received IEEE 802.15.4 frame
 pub struct ReceiveFrame {
+    // The PSDU buffer
+    psdu: [u8; OT_RADIO_FRAME_MAX_SIZE],
+    // Length of the PSDU (including the FCS)
+    length: usize,
+    // Channel the frame was received on
+    pub channel: u8,
+    // Radio link type
+    pub radio_type: u8,
+    // Receive information of the frame (always `OTFrameInformation::RxInfo`)
+    pub frame_information: OTFrameInformation<'static>,
+}
+
+impl ReceiveFrame {
+    /// Create an empty receive frame
+    pub const fn new() -> Self {
+        Self {
+            psdu: [0u8; OT_RADIO_FRAME_MAX_SIZE],
+            length: 0,
+            channel: 0,
+            radio_type: 0,
+            frame_information: OTFrameInformation::RxInfo {
+                timestamp: 0,
+                ack_frame_counter: 0,
+                ack_key_id: 0,
+                rssi: 0,
+                lqi: 0,
+                acked_with_frame_pending: false,
+                acked_with_sec_enh_ack: false,
+            },
+        }
+    }
+
+    /// Get the PSDU (including the FCS)
+    pub fn psdu(&self) -> &[u8] {
+        &self.psdu[..self.length]
+    }
+
+    /// Get the mutable PSDU (including the FCS)
+    pub fn psdu_mut(&mut self) -> &mut [u8] {
+        &mut self.psdu[..self.length]
+    }
+
+    /// Get the length of the PSDU (including the FCS)
+    pub fn length(&self) -> usize {
+        self.length
+    }
 
+    /// Set the length of the PSDU (including the FCS)
+    pub fn set_length<E>(&mut self, length: usize) -> Result<(), OTError<E>> {
+        if length > OT_RADIO_FRAME_MAX_SIZE {
+            return Err(OTError::NoBuffers);
+        }
+        self.length = length;
+        Ok(())
+    }
+
+    /// Parse the PSDU of this frame
+    pub fn parse<E>(&self) -> Result<frame::Frame<'_>, OTError<E>> {
+        frame::Frame::parse(self.psdu())
+    }
+
+    /// Copy a received radio frame into this buffer
+    ///
+    /// Returns:
+    ///     OTError::InvalidArgs if the frame does not carry receive information
+    ///     OTError::NoBuffers if the PSDU exceeds OT_RADIO_FRAME_MAX_SIZE
+    pub fn copy_from<E>(&mut self, frame: &OTRadioFrame) -> Result<(), OTError<E>> {
+        let OTFrameInformation::RxInfo {
+            timestamp,
+            ack_frame_counter,
+            ack_key_id,
+            rssi,
+            lqi,
+            acked_with_frame_pending,
+            acked_with_sec_enh_ack,
+        } = frame.frame_information else {
+            return Err(OTError::InvalidArgs);
+        };
+
+        self.set_length(frame.psdu.len())?;
+        self.psdu[..frame.psdu.len()].copy_from_slice(frame.psdu);
+        self.channel = frame.channel;
+        self.radio_type = frame.radio_type;
+        self.frame_information = OTFrameInformation::RxInfo {
+            timestamp,
+            ack_frame_counter,
+            ack_key_id,
+            rssi,
+            lqi,
+            acked_with_frame_pending,
+            acked_with_sec_enh_ack,
+        };
+        Ok(())
+    }
+
+    /// Borrow this frame as a radio frame
+    pub fn as_radio_frame(&self) -> OTRadioFrame<'_> {
+        OTRadioFrame {
+            psdu: self.psdu(),
+            channel: self.channel,
+            radio_type: self.radio_type,
+            frame_information: self.frame_information.clone(),
+        }
+    }
 }
 
+impl Default for ReceiveFrame {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone)]
 pub enum OTFrameInformation<'a> {
     TxInfo {
         // The key material used for AES-CCM frame security
@@ -241,6 +442,7 @@ pub enum OTFrameInformation<'a> {
 }
 
 /// IEEE 802.15.4 radio frame
+#[derive(Clone)]
 pub struct OTRadioFrame<'a> {
     // The PSDU
     pub psdu: &'a [u8],
@@ -254,6 +456,7 @@ pub struct OTRadioFrame<'a> {
 
 /// Representation of the state of a radio.
 /// Initially, a radio is in the Disabled state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum OTRadioState {
     Disabled = 0,
     Sleep = 1,
@@ -320,6 +523,7 @@ pub struct OTRadioCoexMetrics {
 }
 
 /// Metrics specified to query
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub struct OTLinkMetrics {
     pub pdu_count: bool,
     pub lqi: bool,
@@ -488,7 +692,7 @@ pub trait OTRadioOperation {
     fn receive_at(&mut self, channel: u8, start: u32, duration: u32) -> Result<(), OTError<Self::Error>>;
 
     /// Receive an OpenThread Frame from the radio
-    fn receive_frame(&mut self) -> Result<OTRadioFrame, OTError<Self::Error>>;
+    fn receive_frame(&mut self) -> Result<OTRadioFrame<'_>, OTError<Self::Error>>;
 
     /// Begin the transmit sequence on the radio
     /// 
@@ -548,8 +752,8 @@ pub trait OTRadioOperation {
     /// Get the radio preferred channel mask that the device prefers to form on
     fn get_preferred_channel_mask(&mut self) -> Result<u32, Self::Error>;
 
-    /// Set the max transmit power for a specific channel
-    fn set_channel_max_transmit_power(&mut self, channel: u8, max_power: u8) -> Result<(), Self::Error>;
+    /// Set the max transmit power (in dBm) for a specific channel
+    fn set_channel_max_transmit_power(&mut self, channel: u8, max_power: i8) -> Result<(), Self::Error>;
 
     /// Set the region code
     /// 
@@ -587,18 +791,24 @@ pub trait OTRadioOperationHandles {
 
     /// Notify OpenThread a transmission operation has completed, providing
     /// both the transmitted frame and, if applicable, the received ack frame.
+    ///
+    /// Params:
+    ///     frame - the transmitted frame
+    ///     ack_frame - the received ack frame (None if no ack was requested or received)
+    ///     result - Ok if the frame was sent (and acknowledged when requested), otherwise
+    ///         OTError::NoAck, OTError::ChannelAccessFailure or OTError::Abort
     fn tx_done(
         &mut self,
         frame: OTRadioFrame,
-        ack_frame: OTRadioFrame,
-        error: OTError<Self::Error>,
+        ack_frame: Option<OTRadioFrame>,
+        result: Result<(), OTError<Self::Error>>,
     ) -> Result<(), Self::Error>;
 
     /// Notify the OpenThread diagnostics module that the transmission was completed.
     fn diag_tx_done(
         &mut self,
         frame: OTRadioFrame,
-        error: OTError<Self::Error>,
+        result: Result<(), OTError<Self::Error>>,
     ) -> Result<(), Self::Error>;
 
     /// Get the raw power setting for the given channel.
@@ -0,0 +1,489 @@
+//!
+//! Async Radio
+//!
+//! `OTRadioAsync` is the async counterpart of the radio operations: a transmission resolves to its outcome (and ACK),
+//! a reception to the received frame and an energy scan to the maximum RSSI, instead of being reported through
+//! `OTRadioOperationHandles` callbacks.
+//!
+//! Callback style radios are adapted in two halves:
+//!     RadioEvents - holds the outcomes reported by the driver through its handles (`RadioEvents::handles`) and wakes
+//!         the waiting task. It is usually a `static` shared with the driver, which may report from an interrupt.
+//!     AsyncRadio - owns the radio, starts the operations and awaits their outcome in the `RadioEvents`.
+//!
+//! Frames are not reported through a handle: the driver calls `RadioEventHandles::receive_done` when one is
+//! available and `AsyncRadio` reads it with `OTRadioOperation::receive_frame`.
+//!
+
+use core::cell::RefCell;
+use core::future::poll_fn;
+use core::task::{Context, Poll, Waker};
+
+use critical_section::Mutex;
+
+use super::{
+    OTFrameInformation, OTRadioFrame, OTRadioOperation, OTRadioOperationEnergyScan,
+    OTRadioOperationEnergyScanHandles, OTRadioOperationHandles, ReceiveFrame,
+};
+use crate::error::OTError;
+
+/// Async radio operations
+#[allow(async_fn_in_trait)]
+pub trait OTRadioAsync {
+    type Error;
+
+    /// Transmit a frame (see `OTRadioOperation::transmit`) and wait for the end of the transmission
+    ///
+    /// Returns:
+    ///     The ACK frame (None if no ACK was requested or the radio does not report it), otherwise OTError::NoAck,
+    ///     OTError::ChannelAccessFailure or OTError::Abort
+    async fn transmit(&mut self, frame: OTRadioFrame<'_>) -> Result<Option<OTRadioFrame<'_>>, OTError<Self::Error>>;
+
+    /// Switch to Receive on the given channel and wait for a frame
+    async fn receive(&mut self, channel: u8) -> Result<OTRadioFrame<'_>, OTError<Self::Error>>;
+
+    /// Run an energy scan on the given channel for the given duration (ms)
+    ///
+    /// Returns:
+    ///     The maximum RSSI measured (dBm)
+    async fn energy_scan(&mut self, channel: u8, duration: i16) -> Result<i8, OTError<Self::Error>>;
+}
+
+/// Outcomes reported by the driver and not awaited yet
+struct Events<E> {
+    waker: Option<Waker>,
+    tx_done: Option<Result<(), OTError<E>>>,
+    ack: ReceiveFrame,
+    // Channel and receive information of the ACK in `ack` (None if the transmission was not acknowledged with a frame)
+    ack_info: Option<(u8, OTFrameInformation<'static>)>,
+    energy_scan_done: Option<i8>,
+}
+
+impl<E> Events<E> {
+    fn wake(&mut self) {
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Outcomes of the radio operations, shared between the driver and an `AsyncRadio`
+pub struct RadioEvents<E> {
+    events: Mutex<RefCell<Events<E>>>,
+}
+
+impl<E> RadioEvents<E> {
+    pub const fn new() -> Self {
+        Self {
+            events: Mutex::new(RefCell::new(Events {
+                waker: None,
+                tx_done: None,
+                ack: ReceiveFrame::new(),
+                ack_info: None,
+                energy_scan_done: None,
+            })),
+        }
+    }
+
+    /// Get the handles given to the driver
+    pub fn handles(&self) -> RadioEventHandles<'_, E> {
+        RadioEventHandles { events: self }
+    }
+
+    fn with<T>(&self, f: impl FnOnce(&mut Events<E>) -> T) -> T {
+        critical_section::with(|cs| f(&mut self.events.borrow_ref_mut(cs)))
+    }
+
+    /// Take an outcome, registering the waker of the task when there is none yet
+    fn poll<T>(&self, cx: &mut Context<'_>, f: impl FnOnce(&mut Events<E>) -> Option<T>) -> Poll<T> {
+        self.with(|events| match f(events) {
+            Some(value) => Poll::Ready(value),
+            None => {
+                events.waker = Some(cx.waker().clone());
+                Poll::Pending
+            },
+        })
+    }
+}
+
+impl<E> Default for RadioEvents<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Handles reporting the outcomes of the radio operations to a `RadioEvents`
+pub struct RadioEventHandles<'a, E> {
+    events: &'a RadioEvents<E>,
+}
+
+impl<E> RadioEventHandles<'_, E> {
+    /// Signal that a frame was received
+    pub fn receive_done(&mut self) {
+        self.events.with(Events::wake);
+    }
+}
+
+impl<E> OTRadioOperationHandles for RadioEventHandles<'_, E> {
+    type Error = E;
+
+    fn tx_started(&mut self, _frame: OTRadioFrame) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn tx_done(
+        &mut self,
+        _frame: OTRadioFrame,
+        ack_frame: Option<OTRadioFrame>,
+        result: Result<(), OTError<Self::Error>>,
+    ) -> Result<(), Self::Error> {
+        self.events.with(|events| {
+            events.ack_info = ack_frame.and_then(|ack_frame| {
+                events.ack.set_length::<E>(ack_frame.psdu.len()).ok()?;
+                events.ack.psdu_mut().copy_from_slice(ack_frame.psdu);
+                Some((ack_frame.channel, rx_info(&ack_frame.frame_information)))
+            });
+            events.tx_done = Some(result);
+            events.wake();
+        });
+        Ok(())
+    }
+
+    fn diag_tx_done(&mut self, _frame: OTRadioFrame, result: Result<(), OTError<Self::Error>>) -> Result<(), Self::Error> {
+        self.events.with(|events| {
+            events.ack_info = None;
+            events.tx_done = Some(result);
+            events.wake();
+        });
+        Ok(())
+    }
+
+    fn get_raw_power_setting(
+        &mut self,
+        _channel: u8,
+        _raw_power_setting_buffer: &mut [u8],
+    ) -> Result<(), OTError<Self::Error>> {
+        Err(OTError::NotImplemented)
+    }
+}
+
+impl<E> OTRadioOperationEnergyScanHandles for RadioEventHandles<'_, E> {
+    type Error = E;
+
+    fn energy_scan_done(&mut self, max_rssi: i8) -> Result<(), Self::Error> {
+        self.events.with(|events| {
+            events.energy_scan_done = Some(max_rssi);
+            events.wake();
+        });
+        Ok(())
+    }
+}
+
+/// Async adapter of a callback style radio
+pub struct AsyncRadio<'a, R: OTRadioOperation> {
+    radio: R,
+    events: &'a RadioEvents<R::Error>,
+    // Copies of the last ACK and received frame
+    ack: ReceiveFrame,
+    rx: ReceiveFrame,
+}
+
+impl<'a, R: OTRadioOperation> AsyncRadio<'a, R> {
+    /// Adapt a radio reporting its outcomes to the given events (through `RadioEvents::handles`)
+    pub fn new(radio: R, events: &'a RadioEvents<R::Error>) -> Self {
+        Self {
+            radio,
+            events,
+            ack: ReceiveFrame::new(),
+            rx: ReceiveFrame::new(),
+        }
+    }
+
+    pub fn radio(&mut self) -> &mut R {
+        &mut self.radio
+    }
+
+    pub fn into_inner(self) -> R {
+        self.radio
+    }
+}
+
+impl<R, E> OTRadioAsync for AsyncRadio<'_, R>
+where
+    R: OTRadioOperation<Error = E> + OTRadioOperationEnergyScan<Error = E>,
+{
+    type Error = E;
+
+    async fn transmit(&mut self, frame: OTRadioFrame<'_>) -> Result<Option<OTRadioFrame<'_>>, OTError<E>> {
+        // Drop the outcome of a transmission that was not awaited
+        self.events.with(|events| events.tx_done = None);
+        self.radio.transmit(frame)?;
+
+        let events = self.events;
+        let ack = &mut self.ack;
+        let (result, ack_info) = poll_fn(|cx| {
+            events.poll(cx, |events| {
+                let result = events.tx_done.take()?;
+                let ack_info = events.ack_info.take();
+                if ack_info.is_some() {
+                    copy_frame(ack, &events.ack);
+                }
+                Some((result, ack_info))
+            })
+        })
+        .await;
+
+        result?;
+        Ok(ack_info.map(|(channel, frame_information)| radio_frame(&self.ack, channel, frame_information)))
+    }
+
+    async fn receive(&mut self, channel: u8) -> Result<OTRadioFrame<'_>, OTError<E>> {
+        self.radio.receive(channel)?;
+
+        let events = self.events;
+        let radio = &mut self.radio;
+        let rx = &mut self.rx;
+        let (channel, frame_information) = poll_fn(|cx| {
+            // Register the waker first so a frame signalled while reading the radio is not missed
+            events.with(|events| events.waker = Some(cx.waker().clone()));
+            match radio.receive_frame() {
+                Ok(frame) => {
+                    let received = rx.set_length(frame.psdu.len()).map(|_| {
+                        rx.psdu_mut().copy_from_slice(frame.psdu);
+                        (frame.channel, rx_info(&frame.frame_information))
+                    });
+                    Poll::Ready(received)
+                },
+                Err(OTError::NoFrameReceived) => Poll::Pending,
+                Err(error) => Poll::Ready(Err(error)),
+            }
+        })
+        .await?;
+
+        Ok(radio_frame(&self.rx, channel, frame_information))
+    }
+
+    async fn energy_scan(&mut self, channel: u8, duration: i16) -> Result<i8, OTError<E>> {
+        self.events.with(|events| events.energy_scan_done = None);
+        self.radio.energy_scan(channel, duration)?;
+
+        let events = self.events;
+        Ok(poll_fn(|cx| events.poll(cx, |events| events.energy_scan_done.take())).await)
+    }
+}
+
+/// Copy the receive information of a frame (zeroed if the frame holds transmit information)
+fn rx_info(frame_information: &OTFrameInformation) -> OTFrameInformation<'static> {
+    match *frame_information {
+        OTFrameInformation::RxInfo {
+            timestamp,
+            ack_frame_counter,
+            ack_key_id,
+            rssi,
+            lqi,
+            acked_with_frame_pending,
+            acked_with_sec_enh_ack,
+        } => OTFrameInformation::RxInfo {
+            timestamp,
+            ack_frame_counter,
+            ack_key_id,
+            rssi,
+            lqi,
+            acked_with_frame_pending,
+            acked_with_sec_enh_ack,
+        },
+        OTFrameInformation::TxInfo { .. } => OTFrameInformation::RxInfo {
+            timestamp: 0,
+            ack_frame_counter: 0,
+            ack_key_id: 0,
+            rssi: 0,
+            lqi: 0,
+            acked_with_frame_pending: false,
+            acked_with_sec_enh_ack: false,
+        },
+    }
+}
+
+fn copy_frame(to: &mut ReceiveFrame, from: &ReceiveFrame) {
+    if to.set_length::<()>(from.length()).is_ok() {
+        to.psdu_mut().copy_from_slice(from.psdu());
+    }
+}
+
+fn radio_frame<'a>(buffer: &'a ReceiveFrame, channel: u8, frame_information: OTFrameInformation<'static>) -> OTRadioFrame<'a> {
+    OTRadioFrame {
+        psdu: buffer.psdu(),
+        channel,
+        radio_type: 0,
+        frame_information,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::sync::Arc;
+    use alloc::task::Wake;
+    use core::convert::Infallible;
+    use core::future::Future;
+    use core::pin::pin;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::radio::builder::FrameBuilder;
+    use crate::radio::frame::{FrameVersion, MacAddress};
+    use crate::radio::{OTMacKeyMaterial, OTRadioConfiguration, RadioIEInfo, TransmitFrame, OT_MAC_KEY_SIZE};
+    use crate::sim::{SimMedium, SimRadio};
+
+    const PAN_ID: u16 = 0x1234;
+    const CHANNEL: u8 = 11;
+
+    static NO_IE_INFO: RadioIEInfo = RadioIEInfo {
+        network_time_offset: 0,
+        time_ie_offset: 0,
+        time_sync_sequency: 0,
+    };
+
+    /// Waker counting how many times it was woken
+    #[derive(Default)]
+    struct CountingWaker(AtomicUsize);
+
+    impl CountingWaker {
+        fn wakes(&self) -> usize {
+            self.0.load(Ordering::Relaxed)
+        }
+    }
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Add a receiving node with the given short address
+    fn add_node(medium: &SimMedium, short_address: u16) -> SimRadio {
+        let mut radio = medium.add_node([short_address as u8; 8]);
+        radio.set_pan_id(PAN_ID).unwrap();
+        radio.set_short_address(short_address).unwrap();
+        OTRadioOperation::enable(&mut radio).unwrap();
+        OTRadioOperation::receive(&mut radio, CHANNEL).unwrap();
+        radio
+    }
+
+    fn data_frame(src: u16, dst: u16) -> TransmitFrame {
+        let mut frame = TransmitFrame::new();
+        FrameBuilder::data(FrameVersion::Version2006)
+            .sequence_number(Some(1))
+            .dst(Some(PAN_ID), MacAddress::Short(dst))
+            .src(None, MacAddress::Short(src))
+            .payload(b"async")
+            .build::<Infallible>(&mut frame)
+            .unwrap();
+        frame
+    }
+
+    fn tx_frame(psdu: &[u8]) -> OTRadioFrame<'_> {
+        OTRadioFrame {
+            psdu,
+            channel: CHANNEL,
+            radio_type: 0,
+            frame_information: OTFrameInformation::TxInfo {
+                aes_key: OTMacKeyMaterial::Key([0; OT_MAC_KEY_SIZE]),
+                io_info: &NO_IE_INFO,
+                tx_delay_base_time: 0,
+                tx_delay: 0,
+                max_csma_backoffs: 0,
+                max_frame_retries: 0,
+                rx_channel_after_tx_done: CHANNEL,
+                is_header_updated: false,
+                is_a_retx: false,
+                csma_ca_enabled: false,
+                csl_present: false,
+                is_security_processed: false,
+            },
+        }
+    }
+
+    #[test]
+    fn tx_done_wakes_the_transmitting_task() {
+        let medium = SimMedium::new(1);
+        let events = RadioEvents::<Infallible>::new();
+        let mut radio = AsyncRadio::new(add_node(&medium, 0x0001), &events);
+        let waker = Arc::new(CountingWaker::default());
+        let task_waker = waker.clone().into();
+        let mut cx = Context::from_waker(&task_waker);
+
+        let frame = data_frame(0x0001, 0x0002);
+        let ack = [0x02, 0x00, 0x01];
+        let ack_frame = OTRadioFrame {
+            psdu: &ack,
+            channel: CHANNEL,
+            radio_type: 0,
+            frame_information: OTFrameInformation::RxInfo {
+                timestamp: 0,
+                ack_frame_counter: 0,
+                ack_key_id: 0,
+                rssi: -40,
+                lqi: 200,
+                acked_with_frame_pending: false,
+                acked_with_sec_enh_ack: false,
+            },
+        };
+
+        let mut transmit = pin!(radio.transmit(tx_frame(frame.psdu())));
+        assert!(transmit.as_mut().poll(&mut cx).is_pending());
+        assert_eq!(waker.wakes(), 0);
+
+        // The driver reports the outcome (e.g. from an interrupt)
+        events.handles().tx_done(tx_frame(frame.psdu()), Some(ack_frame), Ok(())).unwrap();
+        assert_eq!(waker.wakes(), 1);
+        match transmit.as_mut().poll(&mut cx) {
+            Poll::Ready(Ok(Some(ack_frame))) => {
+                assert_eq!(ack_frame.psdu, &ack);
+                assert!(matches!(ack_frame.frame_information, OTFrameInformation::RxInfo { rssi: -40, .. }));
+            },
+            _ => panic!("the transmission is not over"),
+        }
+    }
+
+    #[test]
+    fn receive_done_wakes_the_receiving_task() {
+        let medium = SimMedium::new(2);
+        let events = RadioEvents::<Infallible>::new();
+        let mut radio = AsyncRadio::new(add_node(&medium, 0x0001), &events);
+        let mut sender = add_node(&medium, 0x0002);
+        let waker = Arc::new(CountingWaker::default());
+        let task_waker = waker.clone().into();
+        let mut cx = Context::from_waker(&task_waker);
+
+        let mut receive = pin!(radio.receive(CHANNEL));
+        assert!(receive.as_mut().poll(&mut cx).is_pending());
+
+        let frame = data_frame(0x0002, 0x0001);
+        OTRadioOperation::transmit(&mut sender, tx_frame(frame.psdu())).unwrap();
+        medium.advance(10_000);
+        assert_eq!(waker.wakes(), 0);
+
+        events.handles().receive_done();
+        assert_eq!(waker.wakes(), 1);
+        match receive.as_mut().poll(&mut cx) {
+            Poll::Ready(Ok(received)) => assert_eq!(received.psdu, frame.psdu()),
+            _ => panic!("no frame was received"),
+        }
+    }
+
+    #[test]
+    fn energy_scan_done_wakes_the_scanning_task() {
+        let medium = SimMedium::new(3);
+        let events = RadioEvents::<Infallible>::new();
+        let mut radio = AsyncRadio::new(add_node(&medium, 0x0001), &events);
+        let waker = Arc::new(CountingWaker::default());
+        let task_waker = waker.clone().into();
+        let mut cx = Context::from_waker(&task_waker);
+
+        let mut energy_scan = pin!(radio.energy_scan(CHANNEL, 1));
+        assert!(energy_scan.as_mut().poll(&mut cx).is_pending());
+
+        events.handles().energy_scan_done(-62).unwrap();
+        assert_eq!(waker.wakes(), 1);
+        assert_eq!(energy_scan.as_mut().poll(&mut cx), Poll::Ready(Ok(-62)));
+    }
+}
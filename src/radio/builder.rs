@@ -0,0 +1,523 @@
+//!
+//! IEEE 802.15.4 MAC Frame Building
+//!
+
+use super::frame::{
+    is_dst_pan_id_present, is_src_pan_id_present, key_identifier_size, mic_size, FrameType, FrameVersion, MacAddress,
+    FCF_ACK_REQUEST, FCF_DST_ADDR_MODE_OFFSET, FCF_FRAME_PENDING, FCF_FRAME_VERSION_OFFSET,
+    FCF_IE_PRESENT, FCF_PAN_ID_COMPRESSION, FCF_SECURITY_ENABLED, FCF_SEQUENCE_SUPPRESSION, FCF_SRC_ADDR_MODE_OFFSET,
+    FCS_SIZE, HEADER_IE_TERMINATION_1, HEADER_IE_TERMINATION_2, PAYLOAD_IE_TERMINATION, SEC_FRAME_COUNTER_SUPPRESSION,
+    SEC_KEY_ID_MODE_OFFSET, SEC_LEVEL_MASK,
+};
+use super::{OTPanId, TransmitFrame, OT_RADIO_FRAME_MAX_SIZE};
+use crate::error::OTError;
+
+/// Auxiliary security header configuration for an outgoing frame
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SecurityConfig<'a> {
+    // The security level (0 - 7)
+    pub security_level: u8,
+    // The key identifier mode (0 - 3)
+    pub key_id_mode: u8,
+    // The frame counter (None suppresses the frame counter in IEEE 802.15.4-2015 frames)
+    pub frame_counter: Option<u32>,
+    // The key source (0, 4 or 8 bytes depending on the key id mode)
+    pub key_source: &'a [u8],
+    // The key index (ignored for key id mode 0)
+    pub key_index: u8,
+}
+
+/// Builder writing an IEEE 802.15.4 MAC frame into a `TransmitFrame`
+///
+/// The builder lays out the MAC header, the auxiliary security header, the header and payload IEs and the payload,
+/// and reserves room for the MIC and the FCS. The MIC and FCS bytes are zeroed and are expected to be filled in by
+/// the security processing and the radio respectively.
+#[derive(Clone, Copy, Debug)]
+pub struct FrameBuilder<'a> {
+    frame_type: FrameType,
+    version: FrameVersion,
+    sequence_number: Option<u8>,
+    dst_pan_id: Option<OTPanId>,
+    dst_address: MacAddress,
+    src_pan_id: Option<OTPanId>,
+    src_address: MacAddress,
+    ack_request: bool,
+    frame_pending: bool,
+    security: Option<SecurityConfig<'a>>,
+    command_id: Option<u8>,
+    header_ies: &'a [u8],
+    payload_ies: &'a [u8],
+    payload: &'a [u8],
+}
+
+impl<'a> FrameBuilder<'a> {
+    /// Create a builder for the given frame type and version
+    pub fn new(frame_type: FrameType, version: FrameVersion) -> Self {
+        Self {
+            frame_type,
+            version,
+            sequence_number: Some(0),
+            dst_pan_id: None,
+            dst_address: MacAddress::None,
+            src_pan_id: None,
+            src_address: MacAddress::None,
+            ack_request: false,
+            frame_pending: false,
+            security: None,
+            command_id: None,
+            header_ies: &[],
+            payload_ies: &[],
+            payload: &[],
+        }
+    }
+
+    /// Create a builder for a data frame
+    pub fn data(version: FrameVersion) -> Self {
+        Self::new(FrameType::Data, version)
+    }
+
+    /// Create a builder for a MAC command frame
+    pub fn mac_command(version: FrameVersion, command_id: u8) -> Self {
+        let mut builder = Self::new(FrameType::MacCommand, version);
+        builder.command_id = Some(command_id);
+        builder
+    }
+
+    /// Create a builder for a beacon frame
+    pub fn beacon(version: FrameVersion) -> Self {
+        Self::new(FrameType::Beacon, version)
+    }
+
+    /// Create a builder for an immediate acknowledgment (IEEE 802.15.4-2006)
+    pub fn imm_ack(sequence_number: u8, frame_pending: bool) -> Self {
+        Self::new(FrameType::Ack, FrameVersion::Version2006)
+            .sequence_number(Some(sequence_number))
+            .frame_pending(frame_pending)
+    }
+
+    /// Create a builder for an enhanced acknowledgment (IEEE 802.15.4-2015)
+    pub fn enh_ack(sequence_number: Option<u8>, frame_pending: bool) -> Self {
+        Self::new(FrameType::Ack, FrameVersion::Version2015)
+            .sequence_number(sequence_number)
+            .frame_pending(frame_pending)
+    }
+
+    /// Set the sequence number (None suppresses it in IEEE 802.15.4-2015 frames)
+    pub fn sequence_number(mut self, sequence_number: Option<u8>) -> Self {
+        self.sequence_number = sequence_number;
+        self
+    }
+
+    /// Set the destination PAN ID and address
+    pub fn dst(mut self, pan_id: Option<OTPanId>, address: MacAddress) -> Self {
+        self.dst_pan_id = pan_id;
+        self.dst_address = address;
+        self
+    }
+
+    /// Set the source PAN ID and address
+    ///
+    /// A source PAN ID of None (or one equal to the destination PAN ID) lets the builder compress it.
+    pub fn src(mut self, pan_id: Option<OTPanId>, address: MacAddress) -> Self {
+        self.src_pan_id = pan_id;
+        self.src_address = address;
+        self
+    }
+
+    /// Set the ack request bit
+    pub fn ack_request(mut self, ack_request: bool) -> Self {
+        self.ack_request = ack_request;
+        self
+    }
+
+    /// Set the frame pending bit
+    pub fn frame_pending(mut self, frame_pending: bool) -> Self {
+        self.frame_pending = frame_pending;
+        self
+    }
+
+    /// Enable security with the given auxiliary security header
+    pub fn security(mut self, security: SecurityConfig<'a>) -> Self {
+        self.security = Some(security);
+        self
+    }
+
+    /// Set the pre-encoded header IEs (without a termination IE)
+    pub fn header_ies(mut self, header_ies: &'a [u8]) -> Self {
+        self.header_ies = header_ies;
+        self
+    }
+
+    /// Set the pre-encoded payload IEs (without a termination IE)
+    pub fn payload_ies(mut self, payload_ies: &'a [u8]) -> Self {
+        self.payload_ies = payload_ies;
+        self
+    }
+
+    /// Set the MAC payload (excluding the command frame identifier of command frames)
+    pub fn payload(mut self, payload: &'a [u8]) -> Self {
+        self.payload = payload;
+        self
+    }
+
+    /// Select the PAN ID compression bit for the configured addressing
+    ///
+    /// Compression is preferred whenever the elided PAN ID can be recovered from the one that is sent.
+    fn frame_control<E>(&self) -> Result<u16, OTError<E>> {
+        let mut fcf = self.frame_type as u16
+            | (self.version as u16) << FCF_FRAME_VERSION_OFFSET
+            | (self.dst_address.mode() as u16) << FCF_DST_ADDR_MODE_OFFSET
+            | (self.src_address.mode() as u16) << FCF_SRC_ADDR_MODE_OFFSET;
+
+        if self.security.is_some() {
+            fcf |= FCF_SECURITY_ENABLED;
+        }
+        if self.frame_pending {
+            fcf |= FCF_FRAME_PENDING;
+        }
+        if self.ack_request {
+            fcf |= FCF_ACK_REQUEST;
+        }
+        if !self.header_ies.is_empty() || !self.payload_ies.is_empty() {
+            fcf |= FCF_IE_PRESENT;
+        }
+        if self.sequence_number.is_none() {
+            fcf |= FCF_SEQUENCE_SUPPRESSION;
+        }
+
+        // Before IEEE 802.15.4-2015 compression is only defined when both addresses are present
+        let compression_allowed = self.version == FrameVersion::Version2015
+            || (self.dst_address != MacAddress::None && self.src_address != MacAddress::None);
+
+        for compression in [true, false] {
+            if compression && !compression_allowed {
+                continue;
+            }
+
+            let candidate = if compression { fcf | FCF_PAN_ID_COMPRESSION } else { fcf };
+            let dst_present = is_dst_pan_id_present(candidate);
+            let src_present = is_src_pan_id_present(candidate);
+
+            let dst_ok = if dst_present {
+                self.dst_pan_id.is_some()
+            } else {
+                self.dst_pan_id.is_none() || (src_present && self.src_pan_id == self.dst_pan_id)
+            };
+            let src_ok = if src_present {
+                self.src_pan_id.is_some()
+            } else {
+                self.src_pan_id.is_none() || (dst_present && self.dst_pan_id == self.src_pan_id)
+            };
+
+            if dst_ok && src_ok {
+                return Ok(candidate);
+            }
+        }
+
+        Err(OTError::InvalidArgs)
+    }
+
+    /// Write the frame into a transmit frame
+    ///
+    /// Returns:
+    ///     OTError::InvalidArgs if the configuration cannot be represented by the frame version
+    ///     OTError::NoBuffers if the frame would exceed OT_RADIO_FRAME_MAX_SIZE
+    pub fn build<E>(&self, frame: &mut TransmitFrame) -> Result<(), OTError<E>> {
+        if self.version < FrameVersion::Version2015 {
+            let uses_2015_features = self.sequence_number.is_none()
+                || !self.header_ies.is_empty()
+                || !self.payload_ies.is_empty()
+                || self.security.is_some_and(|security| security.frame_counter.is_none());
+            if uses_2015_features || (self.version == FrameVersion::Version2003 && self.security.is_some()) {
+                return Err(OTError::InvalidArgs);
+            }
+        }
+        if self.command_id.is_some() != (self.frame_type == FrameType::MacCommand) {
+            return Err(OTError::InvalidArgs);
+        }
+
+        let fcf = self.frame_control()?;
+        let mut writer = Writer { buffer: &mut frame.psdu, offset: 0 };
+
+        writer.write(&fcf.to_le_bytes())?;
+        if let Some(sequence_number) = self.sequence_number {
+            writer.write(&[sequence_number])?;
+        }
+
+        if is_dst_pan_id_present(fcf) {
+            writer.write(&self.dst_pan_id.unwrap_or_default().to_le_bytes())?;
+        }
+        writer.write_address(&self.dst_address)?;
+        if is_src_pan_id_present(fcf) {
+            writer.write(&self.src_pan_id.unwrap_or_default().to_le_bytes())?;
+        }
+        writer.write_address(&self.src_address)?;
+
+        let mut mic_length = 0;
+        if let Some(security) = &self.security {
+            if security.security_level & !SEC_LEVEL_MASK != 0
+                || security.key_id_mode > 3
+                || security.key_source.len() + 1 != key_identifier_size(security.key_id_mode).max(1)
+            {
+                return Err(OTError::InvalidArgs);
+            }
+
+            let mut security_control = security.security_level | security.key_id_mode << SEC_KEY_ID_MODE_OFFSET;
+            if security.frame_counter.is_none() {
+                security_control |= SEC_FRAME_COUNTER_SUPPRESSION;
+            }
+            writer.write(&[security_control])?;
+            if let Some(frame_counter) = security.frame_counter {
+                writer.write(&frame_counter.to_le_bytes())?;
+            }
+            if security.key_id_mode != 0 {
+                writer.write(security.key_source)?;
+                writer.write(&[security.key_index])?;
+            }
+            mic_length = mic_size(security.security_level);
+        }
+
+        let has_payload = !self.payload.is_empty() || (self.command_id.is_some() && self.version == FrameVersion::Version2015);
+        if !self.header_ies.is_empty() {
+            writer.write(self.header_ies)?;
+        }
+        if !self.payload_ies.is_empty() {
+            writer.write(&header_ie_descriptor(HEADER_IE_TERMINATION_1))?;
+            writer.write(self.payload_ies)?;
+            if has_payload {
+                writer.write(&payload_ie_descriptor(PAYLOAD_IE_TERMINATION))?;
+            }
+        } else if !self.header_ies.is_empty() && has_payload {
+            writer.write(&header_ie_descriptor(HEADER_IE_TERMINATION_2))?;
+        }
+
+        if let Some(command_id) = self.command_id {
+            writer.write(&[command_id])?;
+        }
+        writer.write(self.payload)?;
+        writer.reserve(mic_length + FCS_SIZE)?;
+
+        frame.length = writer.offset;
+        Ok(())
+    }
+}
+
+/// Encode an empty header IE descriptor for the given element id
+fn header_ie_descriptor(element_id: u8) -> [u8; 2] {
+    ((element_id as u16) << 7).to_le_bytes()
+}
+
+/// Encode an empty payload IE descriptor for the given group id
+fn payload_ie_descriptor(group_id: u8) -> [u8; 2] {
+    ((group_id as u16) << 11 | 1 << 15).to_le_bytes()
+}
+
+/// Bounds checked cursor over a PSDU buffer
+struct Writer<'a> {
+    buffer: &'a mut [u8; OT_RADIO_FRAME_MAX_SIZE],
+    offset: usize,
+}
+
+impl Writer<'_> {
+    fn write<E>(&mut self, bytes: &[u8]) -> Result<(), OTError<E>> {
+        let end = self.offset + bytes.len();
+        self.buffer.get_mut(self.offset..end).ok_or(OTError::NoBuffers)?.copy_from_slice(bytes);
+        self.offset = end;
+        Ok(())
+    }
+
+    fn write_address<E>(&mut self, address: &MacAddress) -> Result<(), OTError<E>> {
+        match address {
+            MacAddress::None => Ok(()),
+            MacAddress::Short(address) => self.write(&address.to_le_bytes()),
+            MacAddress::Extended(address) => {
+                let mut reversed = *address;
+                reversed.reverse();
+                self.write(&reversed)
+            },
+        }
+    }
+
+    fn reserve<E>(&mut self, length: usize) -> Result<(), OTError<E>> {
+        let end = self.offset + length;
+        self.buffer.get_mut(self.offset..end).ok_or(OTError::NoBuffers)?.fill(0);
+        self.offset = end;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::radio::frame::Frame;
+    use core::convert::Infallible;
+
+    const EXT: [u8; 8] = [0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01];
+    const CSL_IE: [u8; 6] = [0x04, 0x0d, 0x10, 0x00, 0xe8, 0x03];
+    const VENDOR_IE: [u8; 3] = [0x01, 0x90, 0x42];
+    const KEY_SOURCE: [u8; 4] = [0x11, 0x22, 0x33, 0x44];
+
+    fn security(key_id_mode: u8, frame_counter: Option<u32>, key_source: &[u8]) -> SecurityConfig<'_> {
+        SecurityConfig {
+            security_level: 5,
+            key_id_mode,
+            frame_counter,
+            key_source,
+            key_index: 3,
+        }
+    }
+
+    fn cases() -> [(&'static str, FrameBuilder<'static>, &'static [u8]); 12] {
+        let short = |pan_id, address| (Some(pan_id), MacAddress::Short(address));
+        let data_2006 = FrameBuilder::data(FrameVersion::Version2006).sequence_number(Some(7));
+        let data_2015 = FrameBuilder::data(FrameVersion::Version2015).sequence_number(Some(8));
+        let (dst_pan_id, dst) = short(0x1234, 0x0002);
+
+        [
+            ("immediate ack", FrameBuilder::imm_ack(0x2a, true), &[]),
+            (
+                "2006 data with compressed PAN ID",
+                data_2006
+                    .dst(dst_pan_id, dst)
+                    .src(dst_pan_id, MacAddress::Short(0x0001))
+                    .ack_request(true)
+                    .payload(&[0xaa]),
+                &[0xaa],
+            ),
+            (
+                "2006 data between PANs",
+                data_2006.dst(dst_pan_id, MacAddress::Extended(EXT)).src(Some(0x5678), MacAddress::Extended(EXT)),
+                &[],
+            ),
+            (
+                "2015 data ext/ext sharing a PAN",
+                data_2015.dst(dst_pan_id, MacAddress::Extended(EXT)).src(None, MacAddress::Extended(EXT)).payload(&[1]),
+                &[1],
+            ),
+            (
+                "2015 data with suppressed sequence number and no addresses",
+                data_2015.sequence_number(None).dst(dst_pan_id, MacAddress::None).payload(&[2, 3]),
+                &[2, 3],
+            ),
+            (
+                "2015 data with header IEs and payload",
+                data_2015.dst(dst_pan_id, dst).src(None, MacAddress::Short(1)).header_ies(&CSL_IE).payload(&[4]),
+                &[4],
+            ),
+            (
+                "2015 data with header and payload IEs",
+                data_2015.dst(dst_pan_id, dst).header_ies(&CSL_IE).payload_ies(&VENDOR_IE).payload(&[5, 6]),
+                &[5, 6],
+            ),
+            (
+                "2015 enhanced ack with header IEs only",
+                FrameBuilder::enh_ack(Some(9), false).dst(dst_pan_id, dst).header_ies(&CSL_IE),
+                &[],
+            ),
+            (
+                "2006 secured data with key id mode 1",
+                data_2006
+                    .dst(dst_pan_id, dst)
+                    .src(None, MacAddress::Short(1))
+                    .security(security(1, Some(10), &[]))
+                    .payload(&[7]),
+                &[7],
+            ),
+            (
+                "2015 secured data with key id mode 2 and suppressed frame counter",
+                data_2015.dst(dst_pan_id, dst).security(security(2, None, &KEY_SOURCE)).payload(&[8]),
+                &[8],
+            ),
+            (
+                "2006 data request command",
+                FrameBuilder::mac_command(FrameVersion::Version2006, 0x04)
+                    .dst(dst_pan_id, dst)
+                    .src(None, MacAddress::Short(1)),
+                &[],
+            ),
+            (
+                "2015 command with header IEs",
+                FrameBuilder::mac_command(FrameVersion::Version2015, 0x04).dst(dst_pan_id, dst).header_ies(&CSL_IE),
+                &[0x04],
+            ),
+        ]
+    }
+
+    #[test]
+    fn build_then_parse() {
+        for (name, builder, payload) in cases() {
+            let mut tx_frame = TransmitFrame::new();
+            builder.build::<Infallible>(&mut tx_frame).unwrap_or_else(|_| panic!("{}: build failed", name));
+            let frame =
+                Frame::parse::<Infallible>(tx_frame.psdu()).unwrap_or_else(|_| panic!("{}: parse failed", name));
+
+            assert_eq!(frame.frame_type(), builder.frame_type, "{}", name);
+            assert_eq!(frame.frame_version(), builder.version, "{}", name);
+            assert_eq!(frame.sequence_number(), builder.sequence_number, "{}", name);
+            assert_eq!(frame.ack_request(), builder.ack_request, "{}", name);
+            assert_eq!(frame.frame_pending(), builder.frame_pending, "{}", name);
+            assert_eq!(frame.dst_pan_id().or(frame.src_pan_id()), builder.dst_pan_id, "{}", name);
+            assert_eq!(frame.effective_src_pan_id(), builder.src_pan_id.or(builder.dst_pan_id), "{}", name);
+            assert_eq!(frame.dst_address(), builder.dst_address, "{}", name);
+            assert_eq!(frame.src_address(), builder.src_address, "{}", name);
+            assert_eq!(frame.command_id(), builder.command_id, "{}", name);
+            assert_eq!(frame.payload(), payload, "{}", name);
+            assert!(frame.header_ie_bytes().starts_with(builder.header_ies), "{}: header IEs", name);
+
+            let payload_ies = frame.payload_ies().filter(|ie| ie.group_id != PAYLOAD_IE_TERMINATION);
+            assert_eq!(payload_ies.count(), usize::from(!builder.payload_ies.is_empty()), "{}: payload IEs", name);
+
+            match (frame.security_header(), builder.security) {
+                (None, None) => {},
+                (Some(header), Some(security)) => {
+                    assert_eq!(header.security_level(), security.security_level, "{}", name);
+                    assert_eq!(header.key_id_mode(), security.key_id_mode, "{}", name);
+                    assert_eq!(header.frame_counter, security.frame_counter, "{}", name);
+                    assert_eq!(header.key_source(), security.key_source, "{}", name);
+                    assert_eq!(header.key_index(), Some(security.key_index), "{}", name);
+                    assert_eq!(frame.mic().len(), mic_size(security.security_level), "{}", name);
+                },
+                _ => panic!("{}: unexpected security header", name),
+            }
+        }
+    }
+
+    #[test]
+    fn build_rejects_unrepresentable_frames() {
+        let dst = MacAddress::Short(0x0002);
+        let cases = [
+            (
+                "suppressed sequence number before 2015",
+                FrameBuilder::data(FrameVersion::Version2006).sequence_number(None),
+            ),
+            ("header IEs before 2015", FrameBuilder::data(FrameVersion::Version2006).header_ies(&CSL_IE)),
+            (
+                "security on a 2003 frame",
+                FrameBuilder::data(FrameVersion::Version2003).security(security(1, Some(0), &[])),
+            ),
+            (
+                "suppressed frame counter before 2015",
+                FrameBuilder::data(FrameVersion::Version2006).dst(Some(1), dst).security(security(1, None, &[])),
+            ),
+            (
+                "key source not matching the key id mode",
+                FrameBuilder::data(FrameVersion::Version2015).dst(Some(1), dst).security(security(2, Some(0), &[])),
+            ),
+            ("MAC command without a command id", FrameBuilder::new(FrameType::MacCommand, FrameVersion::Version2006)),
+            (
+                "source PAN ID without a source address",
+                FrameBuilder::data(FrameVersion::Version2006).src(Some(1), MacAddress::None),
+            ),
+        ];
+
+        for (name, builder) in cases {
+            let mut tx_frame = TransmitFrame::new();
+            assert!(matches!(builder.build::<Infallible>(&mut tx_frame), Err(OTError::InvalidArgs)), "{}", name);
+        }
+
+        let mut tx_frame = TransmitFrame::new();
+        let payload = [0u8; OT_RADIO_FRAME_MAX_SIZE];
+        let builder = FrameBuilder::data(FrameVersion::Version2006).dst(Some(1), dst).payload(&payload);
+        assert!(matches!(builder.build::<Infallible>(&mut tx_frame), Err(OTError::NoBuffers)));
+    }
+}
@@ -0,0 +1,820 @@
+//!
+//! Coordinated Sampled Listening (CSL)
+//!
+//! A CSL receiver keeps its radio asleep and only listens in a short window around each sample time, one CSL period
+//! apart. The peer learns when the next sample is from the CSL IE of the frames it receives (the phase, in units of
+//! 10 symbols). `CslReceiver` schedules those windows on a radio supporting `OTRadioOperation::receive_at`:
+//!     - `enable` starts sampling with a period and a channel
+//!     - `process` must be called at (or after) the time it returned last
+//!     - `synchronized` must be called whenever a frame from the peer is received
+//!
+//! Both clocks drift apart between two frame exchanges, so each window is widened on both sides by the drift
+//! accumulated since the last synchronization (the accuracy of both radios, in ppm) and by the fixed uncertainty of
+//! both radios (in units of 10 microseconds). A window never exceeds half a period on either side of the sample time.
+//!
+//! On the other side, `CslTransmitter` sends frames to a CSL receiver (e.g. a parent to its sleepy child): the frame is
+//! scheduled with the delayed transmission of `OTFrameInformation::TxInfo` to hit the middle of the next sample
+//! window of the peer, and rescheduled one period later when it was not acknowledged.
+//!
+
+use super::frame::Frame;
+use super::ie::TypedHeaderIe;
+use super::phy::{PhyProfile, PHY_PROFILE_PAGE_0};
+use super::{
+    Capabilities, OTFrameInformation, OTRadioCapabilities, OTRadioConfiguration, OTRadioFrame, OTRadioOperation,
+    OTRadioOperationsCSL,
+};
+use crate::error::OTError;
+
+// Time the receiver is on before the sample time regardless of drift (in microseconds)
+pub const CSL_MIN_RECEIVE_ON_AHEAD: u32 = 192;
+// Time the receiver stays on after the sample time regardless of drift (in microseconds)
+pub const CSL_MIN_RECEIVE_ON_AFTER: u32 = 5504;
+// Time needed by the radio to be ready to receive (in microseconds)
+pub const CSL_RECEIVE_TIME_AHEAD: u32 = 320;
+// Unit of the CSL uncertainty (in microseconds)
+pub const CSL_UNCERTAINTY_UNIT: u32 = 10;
+// Time needed to hand a frame to the radio before its CSL transmission (in microseconds)
+pub const CSL_FRAME_REQUEST_AHEAD: u32 = 2000;
+// Maximum number of CSL transmissions of a frame (one per period)
+pub const CSL_MAX_TX_ATTEMPTS: u8 = 4;
+
+/// Get the CSL phase of a frame whose SFD ends at the given time
+///
+/// Params:
+///     profile - the PHY profile of the radio
+///     sample_time - a CSL sample time (in microseconds, local radio clock)
+///     period - the CSL period (in units of 10 symbols)
+///     time - the end of the SFD of the frame (in microseconds, local radio clock)
+///
+/// Returns:
+///     The time until the next sample in units of 10 symbols (rounded up, 0 if the period is 0)
+pub fn csl_phase(profile: &PhyProfile, sample_time: u64, period: u16, time: u64) -> u16 {
+    let ten_symbols_time = profile.ten_symbols_time() as u64;
+    let period = period as u64 * ten_symbols_time;
+    if period == 0 {
+        return 0;
+    }
+    // The sample may be before or after the frame, so both times are reduced to the period before subtracting
+    let diff = (sample_time % period + period - time % period) % period;
+    diff.div_ceil(ten_symbols_time) as u16
+}
+
+/// Receive window around a sample time
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CslWindow {
+    // Start of the window (in microseconds, local radio clock)
+    pub start: u64,
+    // Length of the window (in microseconds)
+    pub duration: u32,
+    // Sample time the window is centered on
+    pub sample_time: u64,
+}
+
+/// CSL receiver scheduling the sample windows
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CslReceiver {
+    // CSL period in units of 10 symbols (0 when CSL is disabled)
+    period: u16,
+    channel: u8,
+    // Sample time of the last window that was scheduled
+    sample_time: u64,
+    // Time of the last frame exchange with the peer
+    last_sync: u64,
+    // Accuracy (ppm) and uncertainty (10 microseconds) of the peer clock
+    peer_accuracy: u8,
+    peer_uncertainty: u8,
+    // Time at which `process` must run next
+    next_process: u64,
+    // PHY of the radio, used for the CSL period and phase
+    profile: PhyProfile,
+}
+
+impl CslReceiver {
+    /// Create a disabled CSL receiver
+    pub const fn new() -> Self {
+        Self {
+            period: 0,
+            channel: 0,
+            sample_time: 0,
+            last_sync: 0,
+            peer_accuracy: 0,
+            peer_uncertainty: 0,
+            next_process: 0,
+            profile: PHY_PROFILE_PAGE_0,
+        }
+    }
+
+    /// Set the PHY profile of the radio (page 0 by default)
+    pub fn set_phy_profile(&mut self, profile: PhyProfile) {
+        self.profile = profile;
+    }
+
+    /// Start sampling
+    ///
+    /// The first window is one period after `now`, which is also taken as the last synchronization.
+    ///
+    /// Params:
+    ///     period - the CSL period in units of 10 symbols
+    ///     channel - the channel to sample
+    ///     now - the current time (see `OTRadioConfiguration::get_now`)
+    ///
+    /// Returns:
+    ///     OTError::InvalidArgs if the period is 0
+    pub fn enable<E>(&mut self, period: u16, channel: u8, now: u64) -> Result<(), OTError<E>> {
+        if period == 0 {
+            return Err(OTError::InvalidArgs);
+        }
+        self.period = period;
+        self.channel = channel;
+        self.sample_time = now;
+        self.last_sync = now;
+        self.next_process = now;
+        Ok(())
+    }
+
+    /// Stop sampling
+    pub fn disable(&mut self) {
+        self.period = 0;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.period != 0
+    }
+
+    /// Get the CSL period in units of 10 symbols (0 when CSL is disabled)
+    pub fn period(&self) -> u16 {
+        self.period
+    }
+
+    pub fn channel(&self) -> u8 {
+        self.channel
+    }
+
+    /// Get the sample time of the last scheduled window
+    pub fn sample_time(&self) -> u64 {
+        self.sample_time
+    }
+
+    /// Set the accuracy of the peer clock (from its CSL Clock Accuracy)
+    ///
+    /// Params:
+    ///     accuracy - the peer clock accuracy (in ppm)
+    ///     uncertainty - the peer uncertainty (in units of 10 microseconds)
+    pub fn set_peer_accuracy(&mut self, accuracy: u8, uncertainty: u8) {
+        self.peer_accuracy = accuracy;
+        self.peer_uncertainty = uncertainty;
+    }
+
+    /// Record a frame exchange with the peer, which resynchronizes both clocks
+    pub fn synchronized(&mut self, time: u64) {
+        self.last_sync = self.last_sync.max(time);
+    }
+
+    /// Get the CSL phase of a frame whose SFD ends at the given time (see `csl_phase`)
+    pub fn phase(&self, time: u64) -> u16 {
+        csl_phase(&self.profile, self.sample_time, self.period, time)
+    }
+
+    /// Get the time the receiver is on before and after a sample time
+    ///
+    /// Params:
+    ///     sample_time - the sample time of the window
+    ///     accuracy - the accuracy of the local clock (in ppm, see `OTRadioOperationsCSL::get_csl_accuracy`)
+    ///     uncertainty - the local uncertainty (in units of 10 microseconds, see
+    ///         `OTRadioOperationsCSL::get_csl_uncertainty`)
+    ///
+    /// Returns:
+    ///     The time ahead of and after the sample time (in microseconds)
+    pub fn window_edges(&self, sample_time: u64, accuracy: u8, uncertainty: u8) -> (u32, u32) {
+        let semi_period = self.period as u32 * self.profile.ten_symbols_time() / 2;
+        let elapsed = sample_time.saturating_sub(self.last_sync);
+        let drift = elapsed * (accuracy as u64 + self.peer_accuracy as u64) / 1_000_000;
+        let semi_window = (drift.min(u32::MAX as u64) as u32)
+            .saturating_add((uncertainty as u32 + self.peer_uncertainty as u32) * CSL_UNCERTAINTY_UNIT);
+
+        let ahead = semi_window.saturating_add(CSL_MIN_RECEIVE_ON_AHEAD + CSL_RECEIVE_TIME_AHEAD).min(semi_period);
+        let after = semi_window.saturating_add(CSL_MIN_RECEIVE_ON_AFTER).min(semi_period);
+        (ahead, after)
+    }
+
+    /// Get the window of the next sample (the first one whose start is not before `now`)
+    pub fn next_window(&self, now: u64, accuracy: u8, uncertainty: u8) -> Option<CslWindow> {
+        if self.period == 0 {
+            return None;
+        }
+
+        let period = self.period as u64 * self.profile.ten_symbols_time() as u64;
+        let mut sample_time = self.sample_time + period;
+        loop {
+            let (ahead, after) = self.window_edges(sample_time, accuracy, uncertainty);
+            if sample_time >= now + ahead as u64 {
+                return Some(CslWindow {
+                    start: sample_time - ahead as u64,
+                    duration: ahead + after,
+                    sample_time,
+                });
+            }
+            // Skip the samples that were missed
+            sample_time += (now + ahead as u64 - sample_time).div_ceil(period).max(1) * period;
+        }
+    }
+
+    /// Schedule the next sample window when the last one is over
+    ///
+    /// The radio is told the new sample time (for the CSL IE of its Enhanced ACKs) and asked to receive during the
+    /// window.
+    ///
+    /// Returns:
+    ///     The time at which `process` must run next (None if CSL is disabled)
+    pub fn process<R, E>(&mut self, radio: &mut R) -> Result<Option<u64>, OTError<E>>
+    where
+        R: OTRadioOperation<Error = E> + OTRadioConfiguration<Error = E> + OTRadioOperationsCSL<Error = E>,
+    {
+        if self.period == 0 {
+            return Ok(None);
+        }
+
+        let now = radio.get_now();
+        if now < self.next_process {
+            return Ok(Some(self.next_process));
+        }
+
+        let accuracy = radio.get_csl_accuracy().map_err(OTError::Platform)?;
+        let uncertainty = radio.get_csl_uncertainty().map_err(OTError::Platform)?;
+        let Some(window) = self.next_window(now, accuracy, uncertainty) else {
+            return Ok(None);
+        };
+
+        radio.update_csl_sample_time(window.sample_time as u32).map_err(OTError::Platform)?;
+        radio.receive_at(self.channel, window.start as u32, window.duration)?;
+        self.sample_time = window.sample_time;
+        self.next_process = window.start + window.duration as u64;
+        Ok(Some(self.next_process))
+    }
+}
+
+/// CSL parameters of a peer, learnt from the CSL IE of its last frame
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CslPeer {
+    // CSL period in units of 10 symbols
+    pub period: u16,
+    // Time from the end of the SFD of the last frame to the next sample (in units of 10 symbols)
+    pub phase: u16,
+    // Channel sampled by the peer (0 for the PAN channel)
+    pub channel: u8,
+    // End of the SFD of the last frame (the `RxInfo` timestamp, in microseconds)
+    pub last_rx_timestamp: u64,
+}
+
+impl CslPeer {
+    /// Get the CSL parameters from a received frame
+    ///
+    /// Params:
+    ///     frame - the frame received from the peer
+    ///     channel - the channel sampled by the peer (0 for the PAN channel)
+    ///     timestamp - the `RxInfo` timestamp of the frame
+    ///
+    /// Returns:
+    ///     None if the frame does not carry a CSL IE or the period is 0
+    pub fn from_frame(frame: &Frame, channel: u8, timestamp: u64) -> Option<Self> {
+        frame.header_ies().find_map(|ie| match ie.typed() {
+            TypedHeaderIe::Csl { phase, period } if period != 0 => Some(Self {
+                period,
+                phase,
+                channel,
+                last_rx_timestamp: timestamp,
+            }),
+            _ => None,
+        })
+    }
+
+    /// Get the period in microseconds
+    pub fn period_us(&self, profile: &PhyProfile) -> u64 {
+        self.period as u64 * profile.ten_symbols_time() as u64
+    }
+
+    /// Get the first sample time of the peer after its last frame (in microseconds)
+    pub fn sample_time(&self, profile: &PhyProfile) -> u64 {
+        self.last_rx_timestamp + self.phase as u64 * profile.ten_symbols_time() as u64
+    }
+}
+
+/// Transmit opportunity to a CSL peer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CslTxWindow {
+    // Time at which the frame must be handed to the radio (in microseconds)
+    pub request_time: u64,
+    // Time at which the end of the SFD is on air (a sample time of the peer)
+    pub tx_time: u64,
+}
+
+/// CSL transmitter scheduling frames to a CSL receiver
+#[derive(Clone, Copy, Debug)]
+pub struct CslTransmitter {
+    peer: Option<CslPeer>,
+    // Channel to get back to once the transmission is done
+    pan_channel: u8,
+    // Number of transmissions of the current frame
+    tx_attempts: u8,
+    // PHY of the radio, used for the CSL period and phase
+    profile: PhyProfile,
+}
+
+impl CslTransmitter {
+    /// Create a transmitter without peer
+    ///
+    /// Params:
+    ///     radio - the radio sending the frames
+    ///     pan_channel - the PAN channel
+    ///
+    /// Returns:
+    ///     OTError::NoCapable if the radio does not advertise Capabilities::TransmitTiming
+    pub fn new<R, E>(radio: &mut R, pan_channel: u8) -> Result<Self, OTError<E>>
+    where
+        R: OTRadioConfiguration<Error = E>,
+    {
+        let capabilities = radio.radio_capabilities().map_err(OTError::Platform)?;
+        if capabilities & Capabilities::TransmitTiming as OTRadioCapabilities == 0 {
+            return Err(OTError::NoCapable);
+        }
+        Ok(Self {
+            peer: None,
+            pan_channel,
+            tx_attempts: 0,
+            profile: PHY_PROFILE_PAGE_0,
+        })
+    }
+
+    /// Set the PHY profile of the radio (page 0 by default)
+    pub fn set_phy_profile(&mut self, profile: PhyProfile) {
+        self.profile = profile;
+    }
+
+    pub fn set_pan_channel(&mut self, pan_channel: u8) {
+        self.pan_channel = pan_channel;
+    }
+
+    /// Update the CSL parameters of the peer (on every frame received from it, see `CslPeer::from_frame`)
+    pub fn synchronize(&mut self, peer: CslPeer) {
+        self.peer = Some(peer);
+    }
+
+    /// Forget the peer (e.g. when it stopped CSL)
+    pub fn clear_peer(&mut self) {
+        self.peer = None;
+        self.tx_attempts = 0;
+    }
+
+    pub fn peer(&self) -> Option<&CslPeer> {
+        self.peer.as_ref()
+    }
+
+    /// Get the next sample of the peer that leaves CSL_FRAME_REQUEST_AHEAD to prepare the frame
+    ///
+    /// Returns:
+    ///     None if there is no peer
+    pub fn next_window(&self, now: u64) -> Option<CslTxWindow> {
+        let peer = self.peer.as_ref()?;
+        let period = peer.period_us(&self.profile);
+        if period == 0 {
+            return None;
+        }
+
+        let earliest = now + CSL_FRAME_REQUEST_AHEAD as u64;
+        let mut tx_time = earliest - earliest % period + peer.sample_time(&self.profile) % period;
+        if tx_time < earliest {
+            tx_time += period;
+        }
+        Some(CslTxWindow {
+            request_time: tx_time - CSL_FRAME_REQUEST_AHEAD as u64,
+            tx_time,
+        })
+    }
+
+    /// Schedule a frame on the next sample of the peer
+    ///
+    /// The frame is sent on the CSL channel of the peer at `tx_delay_base_time` + `tx_delay` (`now` + delay), without
+    /// CSMA-CA, and the radio goes back to the PAN channel afterwards. `csl_present` is set when the frame carries a
+    /// CSL IE.
+    ///
+    /// Params:
+    ///     now - the current time (see `OTRadioConfiguration::get_now`)
+    ///     frame - the frame to send to the peer
+    ///
+    /// Returns:
+    ///     The transmit opportunity
+    ///     OTError::InvalidState if there is no peer
+    ///     OTError::InvalidArgs if the frame does not carry transmit information
+    pub fn schedule<E>(&mut self, now: u64, frame: &mut OTRadioFrame) -> Result<CslTxWindow, OTError<E>> {
+        let peer = self.peer.ok_or(OTError::InvalidState)?;
+        let window = self.next_window(now).ok_or(OTError::InvalidState)?;
+        let csl_ie = Frame::parse(frame.psdu)?.header_ies().any(|ie| matches!(ie.typed(), TypedHeaderIe::Csl { .. }));
+
+        let OTFrameInformation::TxInfo {
+            tx_delay_base_time,
+            tx_delay,
+            rx_channel_after_tx_done,
+            is_a_retx,
+            csma_ca_enabled,
+            csl_present,
+            ..
+        } = &mut frame.frame_information else {
+            return Err(OTError::InvalidArgs);
+        };
+        *tx_delay_base_time = now as u32;
+        *tx_delay = u32::try_from(window.tx_time - now).map_err(|_| OTError::InvalidArgs)?;
+        *rx_channel_after_tx_done = self.pan_channel;
+        *is_a_retx = self.tx_attempts > 0;
+        *csma_ca_enabled = false;
+        *csl_present = csl_ie;
+        frame.channel = if peer.channel == 0 { self.pan_channel } else { peer.channel };
+
+        self.tx_attempts += 1;
+        Ok(window)
+    }
+
+    /// Handle the outcome of a transmission (see `OTRadioOperationHandles::tx_done`)
+    ///
+    /// Returns:
+    ///     True if the frame was not acknowledged and must be scheduled again (see `schedule`)
+    pub fn tx_done<E>(&mut self, result: &Result<(), OTError<E>>) -> bool {
+        let retry = matches!(result, Err(OTError::NoAck)) && self.tx_attempts < CSL_MAX_TX_ATTEMPTS;
+        if !retry {
+            self.tx_attempts = 0;
+        }
+        retry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::radio::enhack::EnhAckGenerator;
+    use crate::radio::{OTExtAddress, OTLinkMetrics, OTPanId, OTShortAddress};
+
+    // CSL period used by the tests (in units of 10 symbols, 160 ms on page 0)
+    const PERIOD: u16 = 1000;
+    const PERIOD_US: u64 = 160_000;
+    const CHANNEL: u8 = 15;
+
+    /// Radio with a settable clock recording the CSL calls
+    struct MockRadio {
+        now: u64,
+        accuracy: u8,
+        uncertainty: u8,
+        // (channel, start, duration) of each `receive_at` call
+        windows: Vec<(u8, u32, u32)>,
+        sample_time: Option<u32>,
+    }
+
+    impl MockRadio {
+        fn new(accuracy: u8, uncertainty: u8) -> Self {
+            Self {
+                now: 0,
+                accuracy,
+                uncertainty,
+                windows: Vec::new(),
+                sample_time: None,
+            }
+        }
+    }
+
+    impl OTRadioConfiguration for MockRadio {
+        type Error = Infallible;
+
+        fn radio_capabilities(&mut self) -> Result<OTRadioCapabilities, Self::Error> {
+            Ok(Capabilities::ReceiveTiming as OTRadioCapabilities)
+        }
+
+        fn radio_receive_sensitivity(&mut self) -> Result<u8, Self::Error> {
+            Ok(0)
+        }
+
+        fn radio_ieee_eui_64(&mut self) -> Result<[u8; 8], Self::Error> {
+            Ok([0; 8])
+        }
+
+        fn set_pan_id(&mut self, _pan_id: OTPanId) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn set_extended_address(&mut self, _address: OTExtAddress) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn set_short_address(&mut self, _address: OTShortAddress) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn get_transmit_power(&mut self) -> Result<i8, OTError<Self::Error>> {
+            Ok(0)
+        }
+
+        fn set_transmit_power(&mut self, _power: i8) -> Result<(), OTError<Self::Error>> {
+            Ok(())
+        }
+
+        fn get_cca_energy_detect_threshold(&mut self) -> Result<i8, OTError<Self::Error>> {
+            Ok(0)
+        }
+
+        fn set_cca_energy_detect_threshold(&mut self, _threshold: i8) -> Result<(), OTError<Self::Error>> {
+            Ok(())
+        }
+
+        fn get_fem_lna_gain(&mut self) -> Result<i8, OTError<Self::Error>> {
+            Ok(0)
+        }
+
+        fn set_fem_lna_gain(&mut self, _gain: i8) -> Result<(), OTError<Self::Error>> {
+            Ok(())
+        }
+
+        fn get_promiscuous(&mut self) -> Result<bool, Self::Error> {
+            Ok(false)
+        }
+
+        fn set_promiscuous(&mut self, _enabled: bool) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn set_rx_on_when_idle(&mut self, _enabled: bool) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn get_now(&mut self) -> u64 {
+            self.now
+        }
+
+        fn get_bus_speed(&mut self) -> u32 {
+            0
+        }
+    }
+
+    impl OTRadioOperation for MockRadio {
+        type Error = Infallible;
+
+        fn enable(&mut self) -> Result<(), OTError<Self::Error>> {
+            Ok(())
+        }
+
+        fn disable(&mut self) -> Result<(), OTError<Self::Error>> {
+            Ok(())
+        }
+
+        fn is_enabled(&mut self) -> Result<bool, Self::Error> {
+            Ok(true)
+        }
+
+        fn sleep(&mut self) -> Result<(), OTError<Self::Error>> {
+            Ok(())
+        }
+
+        fn receive(&mut self, _channel: u8) -> Result<(), OTError<Self::Error>> {
+            Ok(())
+        }
+
+        fn receive_at(&mut self, channel: u8, start: u32, duration: u32) -> Result<(), OTError<Self::Error>> {
+            self.windows.push((channel, start, duration));
+            Ok(())
+        }
+
+        fn receive_frame(&mut self) -> Result<OTRadioFrame<'_>, OTError<Self::Error>> {
+            Err(OTError::NoFrameReceived)
+        }
+
+        fn transmit(&mut self, _frame: OTRadioFrame) -> Result<(), OTError<Self::Error>> {
+            Err(OTError::NotImplemented)
+        }
+
+        fn tx_started(&mut self) {}
+
+        fn tx_done(&mut self) {}
+
+        fn diag_tx_done(&mut self) {}
+
+        fn get_rssi(&mut self) -> Result<i8, Self::Error> {
+            Ok(-100)
+        }
+
+        fn enable_src_match(&mut self, _enabled: bool) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn add_src_match_short_entry(&mut self, _address: OTShortAddress) -> Result<(), OTError<Self::Error>> {
+            Ok(())
+        }
+
+        fn add_src_match_ext_entry(&mut self, _address: OTExtAddress) -> Result<(), OTError<Self::Error>> {
+            Ok(())
+        }
+
+        fn clear_src_match_short_entry(&mut self, _address: OTShortAddress) -> Result<(), OTError<Self::Error>> {
+            Ok(())
+        }
+
+        fn clear_src_match_ext_entry(&mut self, _address: OTExtAddress) -> Result<(), OTError<Self::Error>> {
+            Ok(())
+        }
+
+        fn clear_src_match_short_entries(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn clear_src_match_ext_entries(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn get_supported_channel_mask(&mut self) -> Result<u32, Self::Error> {
+            Ok(0)
+        }
+
+        fn get_preferred_channel_mask(&mut self) -> Result<u32, Self::Error> {
+            Ok(0)
+        }
+
+        fn set_channel_max_transmit_power(&mut self, _channel: u8, _max_power: i8) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn set_region(&mut self, _region_code: u16) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn get_region(&mut self) -> Result<u16, Self::Error> {
+            Ok(0)
+        }
+
+        fn configure_enh_ack_probing(
+            &mut self,
+            _link_metrics: OTLinkMetrics,
+            _short_address: OTShortAddress,
+            _ext_address: OTExtAddress,
+        ) -> Result<(), OTError<Self::Error>> {
+            Err(OTError::NotImplemented)
+        }
+    }
+
+    impl OTRadioOperationsCSL for MockRadio {
+        type Error = Infallible;
+
+        fn enable_csl(
+            &mut self,
+            _csl_period: u32,
+            _short_address: OTShortAddress,
+            _ext_address: OTExtAddress,
+        ) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn reset_csl(&mut self) -> Result<(), Self::Error> {
+            self.sample_time = None;
+            Ok(())
+        }
+
+        fn update_csl_sample_time(&mut self, sample_time: u32) -> Result<(), Self::Error> {
+            self.sample_time = Some(sample_time);
+            Ok(())
+        }
+
+        fn get_csl_accuracy(&mut self) -> Result<u8, Self::Error> {
+            Ok(self.accuracy)
+        }
+
+        fn get_csl_uncertainty(&mut self) -> Result<u8, Self::Error> {
+            Ok(self.uncertainty)
+        }
+    }
+
+    fn receiver(now: u64) -> CslReceiver {
+        let mut receiver = CslReceiver::new();
+        receiver.enable::<Infallible>(PERIOD, CHANNEL, now).unwrap();
+        receiver
+    }
+
+    #[test]
+    fn window_widens_with_drift_and_uncertainty() {
+        let mut receiver = receiver(0);
+        receiver.set_peer_accuracy(40, 10);
+
+        // Right after the synchronization only the uncertainty of both radios widens the window
+        let semi_window = (5 + 10) * CSL_UNCERTAINTY_UNIT;
+        assert_eq!(
+            receiver.window_edges(0, 20, 5),
+            (
+                semi_window + CSL_MIN_RECEIVE_ON_AHEAD + CSL_RECEIVE_TIME_AHEAD,
+                semi_window + CSL_MIN_RECEIVE_ON_AFTER
+            )
+        );
+
+        // 500 ms later both clocks may have drifted by 60 ppm
+        assert_eq!(receiver.window_edges(500_000, 20, 5), (692, 5684));
+
+        // A frame exchange resynchronizes the clocks
+        receiver.synchronized(500_000);
+        assert_eq!(receiver.window_edges(500_000, 20, 5).0, semi_window + 512);
+
+        // The window never exceeds half a period on either side of the sample
+        let semi_period = (PERIOD_US / 2) as u32;
+        assert_eq!(receiver.window_edges(1 << 40, 20, 5), (semi_period, semi_period));
+    }
+
+    #[test]
+    fn next_window_skips_missed_samples() {
+        let receiver = receiver(0);
+        let (ahead, after) = (CSL_MIN_RECEIVE_ON_AHEAD + CSL_RECEIVE_TIME_AHEAD, CSL_MIN_RECEIVE_ON_AFTER);
+
+        assert_eq!(
+            receiver.next_window(0, 0, 0),
+            Some(CslWindow {
+                start: PERIOD_US - ahead as u64,
+                duration: ahead + after,
+                sample_time: PERIOD_US,
+            })
+        );
+
+        // The samples up to 1 s are gone, the next one is the 7th
+        let window = receiver.next_window(1_000_000, 0, 0).unwrap();
+        assert_eq!(window.sample_time, 7 * PERIOD_US);
+        assert_eq!(window.start, 7 * PERIOD_US - ahead as u64);
+
+        // A sample whose window would start in the past is skipped too
+        let window = receiver.next_window(PERIOD_US - ahead as u64 + 1, 0, 0).unwrap();
+        assert_eq!(window.sample_time, 2 * PERIOD_US);
+
+        assert_eq!(CslReceiver::new().next_window(0, 0, 0), None);
+    }
+
+    #[test]
+    fn process_reschedules_after_each_window() {
+        let mut radio = MockRadio::new(20, 5);
+        let mut receiver = receiver(0);
+
+        // 160 ms at 20 ppm is 3 us of drift, plus 50 us of uncertainty
+        let (ahead, after) = (53 + 512, 53 + CSL_MIN_RECEIVE_ON_AFTER);
+        let end = PERIOD_US - ahead as u64 + (ahead + after) as u64;
+        assert_eq!(receiver.process(&mut radio), Ok(Some(end)));
+        assert_eq!(radio.windows, [(CHANNEL, (PERIOD_US - ahead as u64) as u32, ahead + after)]);
+        assert_eq!(radio.sample_time, Some(PERIOD_US as u32));
+        assert_eq!(receiver.sample_time(), PERIOD_US);
+
+        // Nothing to do before the window is over
+        radio.now = end - 1;
+        assert_eq!(receiver.process(&mut radio), Ok(Some(end)));
+        assert_eq!(radio.windows.len(), 1);
+
+        // The next window is one period later
+        radio.now = end;
+        let next = receiver.process(&mut radio).unwrap().unwrap();
+        assert_eq!(radio.windows.len(), 2);
+        assert_eq!(radio.sample_time, Some(2 * PERIOD_US as u32));
+        assert!(next > 2 * PERIOD_US);
+
+        receiver.disable();
+        assert_eq!(receiver.process(&mut radio), Ok(None));
+        assert_eq!(radio.windows.len(), 2);
+    }
+
+    #[test]
+    fn phase_on_both_sides_of_the_sample() {
+        let profile = PHY_PROFILE_PAGE_0;
+
+        // Frame before the sample: time left until it
+        assert_eq!(csl_phase(&profile, 2000, PERIOD, 1000), 7);
+        assert_eq!(csl_phase(&profile, 2000, PERIOD, 2000), 0);
+        // Frame after the sample: time left until the next one
+        assert_eq!(csl_phase(&profile, 1000, PERIOD, 2000), 994);
+        assert_eq!(csl_phase(&profile, 1000, PERIOD, 1000 + PERIOD_US), 0);
+        assert_eq!(csl_phase(&profile, 1000, PERIOD, 1000 + 3 * PERIOD_US - 160), 1);
+        assert_eq!(csl_phase(&profile, 1000, 0, 2000), 0);
+
+        let mut radio = MockRadio::new(0, 0);
+        let mut receiver = receiver(0);
+        receiver.process(&mut radio).unwrap();
+        assert_eq!(receiver.phase(PERIOD_US - 1000), 7);
+        assert_eq!(receiver.phase(PERIOD_US + 1000), 994);
+    }
+
+    #[test]
+    fn enh_ack_phase_across_clock_wrap() {
+        let mut generator = EnhAckGenerator::new();
+        generator.enable_csl::<Infallible>(PERIOD as u32, 0x1234, [0; 8]).unwrap();
+
+        // The radio only keeps the lower 32 bits of the sample time
+        generator.update_csl_sample_time(1000);
+        assert_eq!(generator.csl_phase((5 << 32) + 2000), 994);
+        assert_eq!(generator.csl_phase((5 << 32) + 1000 - 160), 1);
+
+        // Sample just before the clock wraps, frame just after
+        generator.update_csl_sample_time(u32::MAX - 159);
+        assert_eq!(generator.csl_phase(5 << 32), PERIOD - 1);
+        // Sample just after the clock wraps, frame just before
+        generator.update_csl_sample_time(160);
+        assert_eq!(generator.csl_phase((5 << 32) - 160), 2);
+    }
+}
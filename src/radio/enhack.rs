@@ -0,0 +1,382 @@
+//!
+//! Enhanced ACK Generation
+//!
+//! IEEE 802.15.4-2015 frames requesting an acknowledgment are answered with an Enhanced ACK, which has to be on air
+//! aTurnaroundTime after the end of the frame. `EnhAckGenerator` builds it from the parsed frame in a single pass:
+//!     - the destination is the source of the acknowledged frame and the sequence number is copied
+//!     - a CSL IE is added while CSL is enabled and the frame comes from the CSL peer (see `OTRadioOperationsCSL`)
+//!     - the Enhanced-ACK Link Metrics probing IE is added when data is given (see `configure_enh_ack_probing`)
+//!     - key id mode 1 frames get a secured ACK, using the frame counter and key of the `MacSecurity` context
+//!
+//! The header IEs of an ACK are limited to OT_ACK_IE_MAX_SIZE bytes.
+//!
+
+use super::builder::{FrameBuilder, SecurityConfig};
+use super::csl::csl_phase;
+use super::frame::{Frame, FrameVersion, MacAddress};
+use super::ie::{HeaderIeWriter, THREAD_IE_ENH_ACK_PROBING, VENDOR_OUI_THREAD_COMPANY_ID};
+use super::phy::{PhyProfile, PHY_PHR_SIZE, PHY_PROFILE_PAGE_0, PHY_SHR_SIZE};
+use super::security::{MacSecurity, KEY_ID_MODE_1};
+use super::{
+    OTExtAddress, OTShortAddress, TransmitFrame, OT_ACK_IE_MAX_SIZE, OT_EXT_ADDRESS_SIZE, OT_ENH_PROBING_IE_DATA_MAX_SIZE,
+};
+use crate::error::OTError;
+
+/// Get the time at which the SFD of the ACK to a received frame ends
+///
+/// Params:
+///     profile - the PHY profile of the radio
+///     rx_timestamp - the end of the SFD of the received frame (the `RxInfo` timestamp, in microseconds)
+///     rx_length - the length of the received PSDU (in bytes)
+pub fn ack_sfd_time(profile: &PhyProfile, rx_timestamp: u64, rx_length: usize) -> u64 {
+    let octet_time = profile.octet_time() as u64;
+    rx_timestamp
+        + (PHY_PHR_SIZE as u64 + rx_length as u64) * octet_time
+        + profile.turnaround_duration() as u64
+        + PHY_SHR_SIZE as u64 * octet_time
+}
+
+/// Security information of a generated Enhanced ACK (the ACK fields of `RxInfo`)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EnhAckInfo {
+    pub ack_frame_counter: u32,
+    pub ack_key_id: u8,
+    pub acked_with_sec_enh_ack: bool,
+}
+
+/// Content of an Enhanced ACK decided by the receiver
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EnhAckContent<'a> {
+    // Frame pending bit (see `SrcMatchTable::frame_pending`)
+    pub frame_pending: bool,
+    // Link Metrics probing data for the sender (empty if it is not a probing initiator)
+    pub link_metrics: &'a [u8],
+    // Time at which the SFD of the ACK ends (see `ack_sfd_time`)
+    pub ack_time: u64,
+}
+
+/// Enhanced ACK builder keeping the CSL receiver configuration
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EnhAckGenerator {
+    // CSL period in units of 10 symbols (0 when CSL is disabled)
+    csl_period: u16,
+    // CSL sample time in microseconds (local radio clock)
+    csl_sample_time: u32,
+    csl_short_address: OTShortAddress,
+    csl_ext_address: OTExtAddress,
+    // PHY of the radio, used for the CSL phase
+    profile: PhyProfile,
+}
+
+impl EnhAckGenerator {
+    /// Create a generator with CSL disabled
+    pub const fn new() -> Self {
+        Self {
+            csl_period: 0,
+            csl_sample_time: 0,
+            csl_short_address: 0,
+            csl_ext_address: [0; OT_EXT_ADDRESS_SIZE],
+            profile: PHY_PROFILE_PAGE_0,
+        }
+    }
+
+    /// Set the PHY profile of the radio (page 0 by default)
+    pub fn set_phy_profile(&mut self, profile: PhyProfile) {
+        self.profile = profile;
+    }
+
+    /// Enable (period > 0) or disable (period = 0) the CSL IE (see `OTRadioOperationsCSL::enable_csl`)
+    ///
+    /// Params:
+    ///     csl_period - the CSL period in units of 10 symbols
+    ///     short_address - the short address of the CSL peer
+    ///     ext_address - the extended address of the CSL peer
+    ///
+    /// Returns:
+    ///     OTError::InvalidArgs if the period does not fit in the CSL IE
+    pub fn enable_csl<E>(
+        &mut self,
+        csl_period: u32,
+        short_address: OTShortAddress,
+        ext_address: OTExtAddress,
+    ) -> Result<(), OTError<E>> {
+        self.csl_period = u16::try_from(csl_period).map_err(|_| OTError::InvalidArgs)?;
+        self.csl_short_address = short_address;
+        self.csl_ext_address = ext_address;
+        Ok(())
+    }
+
+    /// Disable the CSL IE (see `OTRadioOperationsCSL::reset_csl`)
+    pub fn reset_csl(&mut self) {
+        *self = Self {
+            profile: self.profile,
+            ..Self::new()
+        };
+    }
+
+    /// Set the CSL sample time (see `OTRadioOperationsCSL::update_csl_sample_time`)
+    pub fn update_csl_sample_time(&mut self, sample_time: u32) {
+        self.csl_sample_time = sample_time;
+    }
+
+    /// Get the CSL period in units of 10 symbols (0 when CSL is disabled)
+    pub fn csl_period(&self) -> u16 {
+        self.csl_period
+    }
+
+    /// Get the CSL phase of a frame whose SFD ends at the given time (see `csl::csl_phase`)
+    pub fn csl_phase(&self, time: u64) -> u16 {
+        csl_phase(&self.profile, self.csl_sample_time_near(time), self.csl_period, time)
+    }
+
+    /// Extend the 32 bit CSL sample time to the 64 bit radio clock, picking the instant closest to the given time
+    fn csl_sample_time_near(&self, time: u64) -> u64 {
+        let sample_time = (time & !0xffff_ffff) | self.csl_sample_time as u64;
+        if sample_time > time && sample_time - time > 1 << 31 {
+            sample_time.checked_sub(1 << 32).unwrap_or(sample_time)
+        } else if sample_time < time && time - sample_time > 1 << 31 {
+            sample_time + (1 << 32)
+        } else {
+            sample_time
+        }
+    }
+
+    /// Check whether the ACK to a frame carries a CSL IE
+    fn is_csl_peer(&self, frame: &Frame) -> bool {
+        if self.csl_period == 0 {
+            return false;
+        }
+        match frame.src_address() {
+            MacAddress::Short(address) => address == self.csl_short_address,
+            MacAddress::Extended(address) => address == self.csl_ext_address,
+            MacAddress::None => false,
+        }
+    }
+
+    /// Build the Enhanced ACK to a received frame
+    ///
+    /// Params:
+    ///     frame - the received IEEE 802.15.4-2015 frame
+    ///     content - the frame pending bit, probing data and timing of the ACK
+    ///     security - the MAC security context, used when the frame is secured
+    ///     ext_address - the extended address of this device
+    ///     ack - the frame to write the ACK into
+    ///
+    /// Returns:
+    ///     The security information of the ACK
+    ///     OTError::InvalidArgs if the frame is not an IEEE 802.15.4-2015 frame or the probing data is too long
+    ///     OTError::NoBuffers if the IEs exceed OT_ACK_IE_MAX_SIZE
+    ///     OTError::Security if the frame uses key id mode 1 and the ACK cannot be secured
+    pub fn generate<E>(
+        &self,
+        frame: &Frame,
+        content: EnhAckContent,
+        security: &mut MacSecurity,
+        ext_address: &OTExtAddress,
+        ack: &mut TransmitFrame,
+    ) -> Result<EnhAckInfo, OTError<E>> {
+        let link_metrics = content.link_metrics;
+        if frame.frame_version() != FrameVersion::Version2015 || link_metrics.len() > OT_ENH_PROBING_IE_DATA_MAX_SIZE {
+            return Err(OTError::InvalidArgs);
+        }
+
+        let mut ie_buffer = [0u8; OT_ACK_IE_MAX_SIZE];
+        let mut ies = HeaderIeWriter::new(&mut ie_buffer);
+        if self.is_csl_peer(frame) {
+            ies.append_csl(self.csl_phase(content.ack_time), self.csl_period)?;
+        }
+        if !link_metrics.is_empty() {
+            let mut ie_content = [THREAD_IE_ENH_ACK_PROBING; 1 + OT_ENH_PROBING_IE_DATA_MAX_SIZE];
+            ie_content[1..1 + link_metrics.len()].copy_from_slice(link_metrics);
+            ies.append_vendor_specific(VENDOR_OUI_THREAD_COMPANY_ID, &ie_content[..1 + link_metrics.len()])?;
+        }
+
+        let mut builder = FrameBuilder::enh_ack(frame.sequence_number(), content.frame_pending)
+            .dst(frame.effective_src_pan_id(), frame.src_address())
+            .header_ies(ies.bytes());
+        // Only key id mode 1 frames are acknowledged with a secured ACK, others (e.g. mode 2) get an unsecured one
+        let rx_security = frame.security_header().filter(|header| header.key_id_mode() == KEY_ID_MODE_1);
+        if let Some(header) = rx_security {
+            builder = builder.security(SecurityConfig {
+                security_level: header.security_level(),
+                key_id_mode: KEY_ID_MODE_1,
+                frame_counter: Some(0),
+                key_source: &[],
+                key_index: header.key_index().ok_or(OTError::Security)?,
+            });
+        }
+        builder.build(ack)?;
+
+        if rx_security.is_none() {
+            return Ok(EnhAckInfo::default());
+        }
+        let (ack_frame_counter, ack_key_id) = security.process_ack(ack.psdu_mut(), ext_address)?;
+        Ok(EnhAckInfo {
+            ack_frame_counter,
+            ack_key_id,
+            acked_with_sec_enh_ack: true,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use super::*;
+    use crate::radio::frame::FrameType;
+    use crate::radio::ie::TypedHeaderIe;
+    use crate::radio::{OTKeyType, OTMacKeyMaterial, OTRadioConfigurationCapTransmit};
+
+    const PAN_ID: u16 = 0x1234;
+    const PEER: OTExtAddress = [0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17];
+    const EXT_ADDRESS: OTExtAddress = [0x20, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27];
+    const KEY_ID: u8 = 1;
+
+    /// Build a 2015 data frame from the peer, optionally secured with the given key id mode
+    fn rx_frame(version: FrameVersion, key_id_mode: Option<u8>) -> TransmitFrame {
+        let key_source = [0u8; 4];
+        let mut builder = FrameBuilder::data(version)
+            .sequence_number(Some(0x42))
+            .dst(Some(PAN_ID), MacAddress::Short(0x0001))
+            .src(None, MacAddress::Extended(PEER))
+            .ack_request(true)
+            .payload(&[0xaa, 0xbb]);
+        if let Some(key_id_mode) = key_id_mode {
+            builder = builder.security(SecurityConfig {
+                security_level: 5,
+                key_id_mode,
+                frame_counter: Some(7),
+                key_source: &key_source[..key_id_mode.saturating_sub(1) as usize * 4],
+                key_index: KEY_ID,
+            });
+        }
+
+        let mut frame = TransmitFrame::new();
+        builder.build::<Infallible>(&mut frame).unwrap();
+        frame
+    }
+
+    fn mac_security() -> MacSecurity {
+        let key = OTMacKeyMaterial::Key([0x55; 16]);
+        let mut security = MacSecurity::new();
+        security.set_mac_key(KEY_ID_MODE_1, KEY_ID, key, key, key, OTKeyType::LiteralKey).unwrap();
+        security.set_mac_frame_counter(100).unwrap();
+        security
+    }
+
+    #[test]
+    fn ack_carries_csl_and_probing_ies() {
+        let mut generator = EnhAckGenerator::new();
+        generator.enable_csl::<Infallible>(1000, 0xfffe, PEER).unwrap();
+        generator.update_csl_sample_time(10_000);
+        let rx = rx_frame(FrameVersion::Version2015, None);
+        let frame = Frame::parse::<Infallible>(rx.psdu()).unwrap();
+        let content = EnhAckContent {
+            frame_pending: true,
+            link_metrics: &[0x0a, 0x0b],
+            ack_time: 9_000,
+        };
+
+        let mut ack = TransmitFrame::new();
+        let info = generator
+            .generate::<Infallible>(&frame, content, &mut mac_security(), &EXT_ADDRESS, &mut ack)
+            .unwrap();
+        assert_eq!(info, EnhAckInfo::default());
+
+        let ack = Frame::parse::<Infallible>(ack.psdu()).unwrap();
+        assert_eq!(ack.frame_type(), FrameType::Ack);
+        assert_eq!(ack.frame_version(), FrameVersion::Version2015);
+        assert_eq!(ack.sequence_number(), Some(0x42));
+        assert!(ack.frame_pending());
+        assert!(!ack.security_enabled());
+        assert_eq!(ack.dst_pan_id(), Some(PAN_ID));
+        assert_eq!(ack.dst_address(), MacAddress::Extended(PEER));
+
+        let mut ies = ack.header_ies().map(|ie| ie.typed());
+        assert_eq!(ies.next(), Some(TypedHeaderIe::Csl { phase: 7, period: 1000 }));
+        assert_eq!(
+            ies.next(),
+            Some(TypedHeaderIe::VendorSpecific {
+                oui: VENDOR_OUI_THREAD_COMPANY_ID,
+                content: &[THREAD_IE_ENH_ACK_PROBING, 0x0a, 0x0b],
+            })
+        );
+        assert_eq!(ies.next(), None);
+    }
+
+    #[test]
+    fn ack_to_another_node_has_no_csl_ie() {
+        let mut generator = EnhAckGenerator::new();
+        generator.enable_csl::<Infallible>(1000, 0xfffe, EXT_ADDRESS).unwrap();
+        let rx = rx_frame(FrameVersion::Version2015, None);
+        let frame = Frame::parse::<Infallible>(rx.psdu()).unwrap();
+
+        let mut ack = TransmitFrame::new();
+        generator
+            .generate::<Infallible>(&frame, EnhAckContent::default(), &mut mac_security(), &EXT_ADDRESS, &mut ack)
+            .unwrap();
+        assert_eq!(Frame::parse::<Infallible>(ack.psdu()).unwrap().header_ies().count(), 0);
+    }
+
+    #[test]
+    fn key_id_mode_1_frame_gets_a_secured_ack() {
+        let generator = EnhAckGenerator::new();
+        let mut security = mac_security();
+        let rx = rx_frame(FrameVersion::Version2015, Some(KEY_ID_MODE_1));
+        let frame = Frame::parse::<Infallible>(rx.psdu()).unwrap();
+
+        let mut ack = TransmitFrame::new();
+        let info = generator
+            .generate::<Infallible>(&frame, EnhAckContent::default(), &mut security, &EXT_ADDRESS, &mut ack)
+            .unwrap();
+        assert_eq!(
+            info,
+            EnhAckInfo {
+                ack_frame_counter: 100,
+                ack_key_id: KEY_ID,
+                acked_with_sec_enh_ack: true,
+            }
+        );
+        assert_eq!(security.frame_counter(), 101);
+
+        {
+            let parsed = Frame::parse::<Infallible>(ack.psdu()).unwrap();
+            let header = parsed.security_header().unwrap();
+            assert_eq!(header.security_level(), 5);
+            assert_eq!(header.key_id_mode(), KEY_ID_MODE_1);
+            assert_eq!(header.frame_counter, Some(100));
+            assert_eq!(header.key_index(), Some(KEY_ID));
+        }
+        security.process_receive::<Infallible>(ack.psdu_mut(), &EXT_ADDRESS).unwrap();
+    }
+
+    #[test]
+    fn other_key_id_modes_get_an_unsecured_ack() {
+        let generator = EnhAckGenerator::new();
+        let mut security = mac_security();
+        let rx = rx_frame(FrameVersion::Version2015, Some(2));
+        let frame = Frame::parse::<Infallible>(rx.psdu()).unwrap();
+
+        let mut ack = TransmitFrame::new();
+        let info = generator
+            .generate::<Infallible>(&frame, EnhAckContent::default(), &mut security, &EXT_ADDRESS, &mut ack)
+            .unwrap();
+        assert_eq!(info, EnhAckInfo::default());
+        assert_eq!(security.frame_counter(), 100);
+        assert!(!Frame::parse::<Infallible>(ack.psdu()).unwrap().security_enabled());
+    }
+
+    #[test]
+    fn only_2015_frames_get_an_enh_ack() {
+        let generator = EnhAckGenerator::new();
+        let rx = rx_frame(FrameVersion::Version2006, None);
+        let frame = Frame::parse::<Infallible>(rx.psdu()).unwrap();
+
+        let mut ack = TransmitFrame::new();
+        let mut security = mac_security();
+        let content = EnhAckContent::default();
+        let result = generator.generate::<Infallible>(&frame, content, &mut security, &EXT_ADDRESS, &mut ack);
+        assert!(matches!(result, Err(OTError::InvalidArgs)));
+    }
+}
@@ -0,0 +1,1072 @@
+//!
+//! IEEE 802.15.4 MAC Frame Parsing
+//!
+
+use core::ops::Range;
+
+use super::{OTExtAddress, OTPanId, OTRadioFrame, OTShortAddress, OT_EXT_ADDRESS_SIZE, OT_RADIO_FRAME_MAX_SIZE, OT_RADIO_FRAME_MIN_SIZE};
+use crate::error::OTError;
+
+// Size of the frame control field (in bytes)
+pub const FCF_SIZE: usize = 2;
+// Size of the sequence number field (in bytes)
+pub const DSN_SIZE: usize = 1;
+// Size of a PAN ID field (in bytes)
+pub const PAN_ID_SIZE: usize = 2;
+// Size of a short address field (in bytes)
+pub const SHORT_ADDRESS_SIZE: usize = 2;
+// Size of the frame check sequence (in bytes)
+pub const FCS_SIZE: usize = 2;
+// Size of the security control field (in bytes)
+pub const SECURITY_CONTROL_SIZE: usize = 1;
+// Size of the frame counter field (in bytes)
+pub const FRAME_COUNTER_SIZE: usize = 4;
+// Size of the MAC command frame identifier (in bytes)
+pub const COMMAND_ID_SIZE: usize = 1;
+// Size of a header or payload IE descriptor (in bytes)
+pub const IE_DESCRIPTOR_SIZE: usize = 2;
+
+// Frame control field bits
+pub const FCF_FRAME_TYPE_MASK: u16 = 0x07;
+pub const FCF_SECURITY_ENABLED: u16 = 1 << 3;
+pub const FCF_FRAME_PENDING: u16 = 1 << 4;
+pub const FCF_ACK_REQUEST: u16 = 1 << 5;
+pub const FCF_PAN_ID_COMPRESSION: u16 = 1 << 6;
+pub const FCF_SEQUENCE_SUPPRESSION: u16 = 1 << 8;
+pub const FCF_IE_PRESENT: u16 = 1 << 9;
+pub const FCF_DST_ADDR_MODE_OFFSET: u16 = 10;
+pub const FCF_DST_ADDR_MODE_MASK: u16 = 3 << FCF_DST_ADDR_MODE_OFFSET;
+pub const FCF_FRAME_VERSION_OFFSET: u16 = 12;
+pub const FCF_FRAME_VERSION_MASK: u16 = 3 << FCF_FRAME_VERSION_OFFSET;
+pub const FCF_SRC_ADDR_MODE_OFFSET: u16 = 14;
+pub const FCF_SRC_ADDR_MODE_MASK: u16 = 3 << FCF_SRC_ADDR_MODE_OFFSET;
+
+// Security control field bits
+pub const SEC_LEVEL_MASK: u8 = 0x07;
+pub const SEC_KEY_ID_MODE_OFFSET: u8 = 3;
+pub const SEC_KEY_ID_MODE_MASK: u8 = 3 << SEC_KEY_ID_MODE_OFFSET;
+pub const SEC_FRAME_COUNTER_SUPPRESSION: u8 = 1 << 5;
+
+// Header IE element id terminating the header IEs when payload IEs follow
+pub const HEADER_IE_TERMINATION_1: u8 = 0x7e;
+// Header IE element id terminating the header IEs when the payload follows
+pub const HEADER_IE_TERMINATION_2: u8 = 0x7f;
+// Payload IE group id terminating the payload IEs
+pub const PAYLOAD_IE_TERMINATION: u8 = 0x0f;
+
+/// IEEE 802.15.4 Frame Type
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrameType {
+    Beacon = 0,
+    Data = 1,
+    Ack = 2,
+    MacCommand = 3,
+}
+
+impl FrameType {
+    /// Decode the frame type from the frame control field
+    pub fn from_fcf<E>(fcf: u16) -> Result<Self, OTError<E>> {
+        match fcf & FCF_FRAME_TYPE_MASK {
+            0 => Ok(FrameType::Beacon),
+            1 => Ok(FrameType::Data),
+            2 => Ok(FrameType::Ack),
+            3 => Ok(FrameType::MacCommand),
+            _ => Err(OTError::Parse),
+        }
+    }
+}
+
+/// IEEE 802.15.4 Frame Version
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FrameVersion {
+    Version2003 = 0,
+    Version2006 = 1,
+    Version2015 = 2,
+}
+
+impl FrameVersion {
+    /// Decode the frame version from the frame control field
+    pub fn from_fcf<E>(fcf: u16) -> Result<Self, OTError<E>> {
+        match (fcf & FCF_FRAME_VERSION_MASK) >> FCF_FRAME_VERSION_OFFSET {
+            0 => Ok(FrameVersion::Version2003),
+            1 => Ok(FrameVersion::Version2006),
+            2 => Ok(FrameVersion::Version2015),
+            _ => Err(OTError::Parse),
+        }
+    }
+}
+
+/// IEEE 802.15.4 Addressing Mode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AddressMode {
+    None = 0,
+    Short = 2,
+    Extended = 3,
+}
+
+impl AddressMode {
+    /// Decode an addressing mode from its two bit field value
+    pub fn from_bits<E>(bits: u16) -> Result<Self, OTError<E>> {
+        match bits {
+            0 => Ok(AddressMode::None),
+            2 => Ok(AddressMode::Short),
+            3 => Ok(AddressMode::Extended),
+            _ => Err(OTError::Parse),
+        }
+    }
+
+    /// Size of an address with this addressing mode (in bytes)
+    pub fn address_size(&self) -> usize {
+        match self {
+            AddressMode::None => 0,
+            AddressMode::Short => SHORT_ADDRESS_SIZE,
+            AddressMode::Extended => OT_EXT_ADDRESS_SIZE,
+        }
+    }
+}
+
+/// IEEE 802.15.4 MAC Address
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MacAddress {
+    None,
+    Short(OTShortAddress),
+    // Extended addresses are kept in the OpenThread (big-endian) byte order
+    Extended(OTExtAddress),
+}
+
+impl MacAddress {
+    /// Get the addressing mode used to carry this address
+    pub fn mode(&self) -> AddressMode {
+        match self {
+            MacAddress::None => AddressMode::None,
+            MacAddress::Short(_) => AddressMode::Short,
+            MacAddress::Extended(_) => AddressMode::Extended,
+        }
+    }
+
+    /// Read an address from its over-the-air (little-endian) representation
+    fn read(mode: AddressMode, bytes: &[u8]) -> Self {
+        match mode {
+            AddressMode::None => MacAddress::None,
+            AddressMode::Short => MacAddress::Short(u16::from_le_bytes([bytes[0], bytes[1]])),
+            AddressMode::Extended => {
+                let mut address = [0u8; OT_EXT_ADDRESS_SIZE];
+                for (i, byte) in address.iter_mut().enumerate() {
+                    *byte = bytes[OT_EXT_ADDRESS_SIZE - 1 - i];
+                }
+                MacAddress::Extended(address)
+            },
+        }
+    }
+}
+
+/// Get the size of the message integrity code for a security level (in bytes)
+pub fn mic_size(security_level: u8) -> usize {
+    match security_level & SEC_LEVEL_MASK {
+        1 | 5 => 4,
+        2 | 6 => 8,
+        3 | 7 => 16,
+        _ => 0,
+    }
+}
+
+/// Get the size of the key identifier field for a key id mode (in bytes)
+pub fn key_identifier_size(key_id_mode: u8) -> usize {
+    match key_id_mode {
+        1 => 1,
+        2 => 5,
+        3 => 9,
+        _ => 0,
+    }
+}
+
+/// Check whether the destination PAN ID is present for a frame control field
+pub fn is_dst_pan_id_present(fcf: u16) -> bool {
+    let dst_mode = fcf & FCF_DST_ADDR_MODE_MASK;
+    let src_mode = fcf & FCF_SRC_ADDR_MODE_MASK;
+    let compression = fcf & FCF_PAN_ID_COMPRESSION != 0;
+
+    if (fcf & FCF_FRAME_VERSION_MASK) >> FCF_FRAME_VERSION_OFFSET != FrameVersion::Version2015 as u16 {
+        return dst_mode != 0;
+    }
+
+    // IEEE 802.15.4-2015 Table 7-2
+    let dst_none = dst_mode == 0;
+    let src_none = src_mode == 0;
+    let both_ext = dst_mode == FCF_DST_ADDR_MODE_MASK && src_mode == FCF_SRC_ADDR_MODE_MASK;
+    match (dst_none, src_none) {
+        (true, true) => compression,
+        (false, true) => !compression,
+        (true, false) => false,
+        (false, false) => !(both_ext && compression),
+    }
+}
+
+/// Check whether the source PAN ID is present for a frame control field
+pub fn is_src_pan_id_present(fcf: u16) -> bool {
+    let dst_mode = fcf & FCF_DST_ADDR_MODE_MASK;
+    let src_mode = fcf & FCF_SRC_ADDR_MODE_MASK;
+
+    if src_mode == 0 || fcf & FCF_PAN_ID_COMPRESSION != 0 {
+        return false;
+    }
+
+    // IEEE 802.15.4-2015 elides the source PAN ID when both addresses are extended
+    (fcf & FCF_FRAME_VERSION_MASK) >> FCF_FRAME_VERSION_OFFSET != FrameVersion::Version2015 as u16
+        || dst_mode != FCF_DST_ADDR_MODE_MASK
+        || src_mode != FCF_SRC_ADDR_MODE_MASK
+}
+
+/// IEEE 802.15.4 Auxiliary Security Header
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SecurityHeader<'a> {
+    // The security control field
+    pub security_control: u8,
+    // The frame counter (absent when frame counter suppression is set)
+    pub frame_counter: Option<u32>,
+    // The raw key identifier field (key source followed by key index)
+    pub key_identifier: &'a [u8],
+}
+
+impl<'a> SecurityHeader<'a> {
+    /// Parse an auxiliary security header from the start of a buffer
+    pub fn parse<E>(buffer: &'a [u8]) -> Result<Self, OTError<E>> {
+        let security_control = *buffer.first().ok_or(OTError::Parse)?;
+        let mut offset = SECURITY_CONTROL_SIZE;
+
+        let frame_counter = if security_control & SEC_FRAME_COUNTER_SUPPRESSION == 0 {
+            let bytes = buffer.get(offset..offset + FRAME_COUNTER_SIZE).ok_or(OTError::Parse)?;
+            offset += FRAME_COUNTER_SIZE;
+            Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        } else {
+            None
+        };
+
+        let key_id_mode = (security_control & SEC_KEY_ID_MODE_MASK) >> SEC_KEY_ID_MODE_OFFSET;
+        let key_identifier = buffer
+            .get(offset..offset + key_identifier_size(key_id_mode))
+            .ok_or(OTError::Parse)?;
+
+        Ok(Self {
+            security_control,
+            frame_counter,
+            key_identifier,
+        })
+    }
+
+    /// Get the security level
+    pub fn security_level(&self) -> u8 {
+        self.security_control & SEC_LEVEL_MASK
+    }
+
+    /// Get the key identifier mode
+    pub fn key_id_mode(&self) -> u8 {
+        (self.security_control & SEC_KEY_ID_MODE_MASK) >> SEC_KEY_ID_MODE_OFFSET
+    }
+
+    /// Get the key index (if a key identifier is present)
+    pub fn key_index(&self) -> Option<u8> {
+        self.key_identifier.last().copied()
+    }
+
+    /// Get the key source (if present for the key id mode)
+    pub fn key_source(&self) -> &'a [u8] {
+        match self.key_identifier.len() {
+            0 => &[],
+            length => &self.key_identifier[..length - 1],
+        }
+    }
+
+    /// Get the size of the message integrity code (in bytes)
+    pub fn mic_size(&self) -> usize {
+        mic_size(self.security_level())
+    }
+
+    /// Get the size of the auxiliary security header (in bytes)
+    pub fn size(&self) -> usize {
+        SECURITY_CONTROL_SIZE
+            + self.frame_counter.map_or(0, |_| FRAME_COUNTER_SIZE)
+            + self.key_identifier.len()
+    }
+}
+
+/// IEEE 802.15.4 Header Information Element
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HeaderIe<'a> {
+    // The element id
+    pub element_id: u8,
+    // The IE content
+    pub content: &'a [u8],
+}
+
+impl HeaderIe<'_> {
+    /// Check whether the IE is a header termination IE (HT1 or HT2)
+    pub fn is_termination(&self) -> bool {
+        self.element_id == HEADER_IE_TERMINATION_1 || self.element_id == HEADER_IE_TERMINATION_2
+    }
+}
+
+/// Iterator over the header IEs of a frame
+#[derive(Clone, Debug)]
+pub struct HeaderIeIterator<'a> {
+    buffer: &'a [u8],
+}
+
+impl<'a> HeaderIeIterator<'a> {
+    /// Create an iterator over a buffer of header IEs
+    pub fn new(buffer: &'a [u8]) -> Self {
+        Self { buffer }
+    }
+}
+
+impl<'a> Iterator for HeaderIeIterator<'a> {
+    type Item = HeaderIe<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buffer.len() < IE_DESCRIPTOR_SIZE {
+            return None;
+        }
+
+        let descriptor = u16::from_le_bytes([self.buffer[0], self.buffer[1]]);
+        let length = (descriptor & 0x7f) as usize;
+        let element_id = ((descriptor >> 7) & 0xff) as u8;
+        let content = self.buffer.get(IE_DESCRIPTOR_SIZE..IE_DESCRIPTOR_SIZE + length)?;
+        self.buffer = &self.buffer[IE_DESCRIPTOR_SIZE + length..];
+
+        Some(HeaderIe { element_id, content })
+    }
+}
+
+/// IEEE 802.15.4 Payload Information Element
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PayloadIe<'a> {
+    // The group id
+    pub group_id: u8,
+    // The IE content
+    pub content: &'a [u8],
+}
+
+/// Iterator over the payload IEs of a frame
+#[derive(Clone, Debug)]
+pub struct PayloadIeIterator<'a> {
+    buffer: &'a [u8],
+}
+
+impl<'a> PayloadIeIterator<'a> {
+    /// Create an iterator over a buffer of payload IEs
+    pub fn new(buffer: &'a [u8]) -> Self {
+        Self { buffer }
+    }
+}
+
+impl<'a> Iterator for PayloadIeIterator<'a> {
+    type Item = PayloadIe<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buffer.len() < IE_DESCRIPTOR_SIZE {
+            return None;
+        }
+
+        let descriptor = u16::from_le_bytes([self.buffer[0], self.buffer[1]]);
+        let length = (descriptor & 0x7ff) as usize;
+        let group_id = ((descriptor >> 11) & 0x0f) as u8;
+        let content = self.buffer.get(IE_DESCRIPTOR_SIZE..IE_DESCRIPTOR_SIZE + length)?;
+        self.buffer = &self.buffer[IE_DESCRIPTOR_SIZE + length..];
+
+        Some(PayloadIe { group_id, content })
+    }
+}
+
+/// Zero-copy view of an IEEE 802.15.4 MAC frame
+///
+/// The PSDU is validated once by `Frame::parse` and the field offsets are kept so that
+/// every accessor afterwards is a simple slice or integer read.
+#[derive(Clone, Debug)]
+pub struct Frame<'a> {
+    psdu: &'a [u8],
+    frame_control: u16,
+    sequence_number: Option<u8>,
+    dst_pan_id: Option<OTPanId>,
+    dst_address: MacAddress,
+    src_pan_id: Option<OTPanId>,
+    src_address: MacAddress,
+    security_header: Option<SecurityHeader<'a>>,
+    // Offset of the first header IE
+    header_ie_offset: usize,
+    // Offset just past the last header IE (including any termination IE)
+    header_ie_end: usize,
+    // Offset of the first payload IE
+    payload_ie_offset: usize,
+    // Offset of the MAC payload
+    payload_offset: usize,
+    // Offset of the MIC (or FCS when unsecured)
+    footer_offset: usize,
+}
+
+impl<'a> Frame<'a> {
+    /// Parse a PSDU (including the FCS) into a frame view
+    ///
+    /// Returns:
+    ///     OTError::Parse if the PSDU is truncated or uses a reserved frame control value
+    pub fn parse<E>(psdu: &'a [u8]) -> Result<Self, OTError<E>> {
+        if psdu.len() < OT_RADIO_FRAME_MIN_SIZE || psdu.len() > OT_RADIO_FRAME_MAX_SIZE {
+            return Err(OTError::Parse);
+        }
+
+        let frame_control = u16::from_le_bytes([psdu[0], psdu[1]]);
+        let frame_type = FrameType::from_fcf(frame_control)?;
+        let version = FrameVersion::from_fcf(frame_control)?;
+        let dst_mode = AddressMode::from_bits((frame_control & FCF_DST_ADDR_MODE_MASK) >> FCF_DST_ADDR_MODE_OFFSET)?;
+        let src_mode = AddressMode::from_bits((frame_control & FCF_SRC_ADDR_MODE_MASK) >> FCF_SRC_ADDR_MODE_OFFSET)?;
+        let fcs_offset = psdu.len() - FCS_SIZE;
+        let mut offset = FCF_SIZE;
+
+        // Sequence number suppression is only defined for IEEE 802.15.4-2015 frames
+        let sequence_number = if version == FrameVersion::Version2015 && frame_control & FCF_SEQUENCE_SUPPRESSION != 0 {
+            None
+        } else {
+            let sequence_number = *psdu[..fcs_offset].get(offset).ok_or(OTError::Parse)?;
+            offset += DSN_SIZE;
+            Some(sequence_number)
+        };
+
+        let read_pan_id = |offset: &mut usize| -> Result<OTPanId, OTError<E>> {
+            let bytes = psdu[..fcs_offset].get(*offset..*offset + PAN_ID_SIZE).ok_or(OTError::Parse)?;
+            *offset += PAN_ID_SIZE;
+            Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+        };
+        let read_address = |offset: &mut usize, mode: AddressMode| -> Result<MacAddress, OTError<E>> {
+            let bytes = psdu[..fcs_offset].get(*offset..*offset + mode.address_size()).ok_or(OTError::Parse)?;
+            *offset += mode.address_size();
+            Ok(MacAddress::read(mode, bytes))
+        };
+
+        let dst_pan_id = if is_dst_pan_id_present(frame_control) {
+            Some(read_pan_id(&mut offset)?)
+        } else {
+            None
+        };
+        let dst_address = read_address(&mut offset, dst_mode)?;
+        let src_pan_id = if is_src_pan_id_present(frame_control) {
+            Some(read_pan_id(&mut offset)?)
+        } else {
+            None
+        };
+        let src_address = read_address(&mut offset, src_mode)?;
+
+        let security_header = if frame_control & FCF_SECURITY_ENABLED != 0 {
+            if version == FrameVersion::Version2003 {
+                return Err(OTError::Parse);
+            }
+            let header = SecurityHeader::parse(&psdu[offset..fcs_offset])?;
+            offset += header.size();
+            Some(header)
+        } else {
+            None
+        };
+
+        let footer_offset = fcs_offset
+            .checked_sub(security_header.as_ref().map_or(0, |header| header.mic_size()))
+            .filter(|footer_offset| *footer_offset >= offset)
+            .ok_or(OTError::Parse)?;
+
+        let header_ie_offset = offset;
+        let mut header_ie_end = offset;
+        let mut payload_ie_offset = offset;
+        let mut payload_offset = offset;
+        if frame_control & FCF_IE_PRESENT != 0 {
+            let (end, termination) = Self::find_header_ie_end(&psdu[offset..footer_offset])?;
+            header_ie_end = offset + end;
+            payload_ie_offset = header_ie_end;
+            payload_offset = payload_ie_offset;
+
+            // Payload IEs are encrypted with the payload, so they can only be located in the clear
+            if termination == Some(HEADER_IE_TERMINATION_1) && security_header.is_none() {
+                payload_offset += Self::find_payload_ie_end(&psdu[payload_ie_offset..footer_offset])?;
+            }
+        }
+
+        // Before IEEE 802.15.4-2015 the command frame identifier is sent in the clear as part of the header
+        if frame_type == FrameType::MacCommand && version < FrameVersion::Version2015 {
+            if payload_offset >= footer_offset {
+                return Err(OTError::Parse);
+            }
+            payload_ie_offset += COMMAND_ID_SIZE;
+            payload_offset += COMMAND_ID_SIZE;
+        }
+
+        Ok(Self {
+            psdu,
+            frame_control,
+            sequence_number,
+            dst_pan_id,
+            dst_address,
+            src_pan_id,
+            src_address,
+            security_header,
+            header_ie_offset,
+            header_ie_end,
+            payload_ie_offset,
+            payload_offset,
+            footer_offset,
+        })
+    }
+
+    /// Find the end of the header IEs and the termination IE that ended them (if any)
+    fn find_header_ie_end<E>(buffer: &[u8]) -> Result<(usize, Option<u8>), OTError<E>> {
+        let mut offset = 0;
+        for ie in HeaderIeIterator::new(buffer) {
+            offset += IE_DESCRIPTOR_SIZE + ie.content.len();
+            if ie.is_termination() {
+                return Ok((offset, Some(ie.element_id)));
+            }
+        }
+
+        // Without a termination IE the header IEs must exactly fill the remaining buffer
+        if offset != buffer.len() {
+            return Err(OTError::Parse);
+        }
+        Ok((offset, None))
+    }
+
+    /// Find the end of the payload IEs
+    fn find_payload_ie_end<E>(buffer: &[u8]) -> Result<usize, OTError<E>> {
+        let mut offset = 0;
+        for ie in PayloadIeIterator::new(buffer) {
+            offset += IE_DESCRIPTOR_SIZE + ie.content.len();
+            if ie.group_id == PAYLOAD_IE_TERMINATION {
+                return Ok(offset);
+            }
+        }
+
+        if offset != buffer.len() {
+            return Err(OTError::Parse);
+        }
+        Ok(offset)
+    }
+
+    /// Get the underlying PSDU
+    pub fn psdu(&self) -> &'a [u8] {
+        self.psdu
+    }
+
+    /// Get the raw frame control field
+    pub fn frame_control(&self) -> u16 {
+        self.frame_control
+    }
+
+    /// Get the frame type
+    pub fn frame_type(&self) -> FrameType {
+        // Validated during parsing
+        match self.frame_control & FCF_FRAME_TYPE_MASK {
+            0 => FrameType::Beacon,
+            1 => FrameType::Data,
+            2 => FrameType::Ack,
+            _ => FrameType::MacCommand,
+        }
+    }
+
+    /// Get the frame version
+    pub fn frame_version(&self) -> FrameVersion {
+        // Validated during parsing
+        match (self.frame_control & FCF_FRAME_VERSION_MASK) >> FCF_FRAME_VERSION_OFFSET {
+            0 => FrameVersion::Version2003,
+            1 => FrameVersion::Version2006,
+            _ => FrameVersion::Version2015,
+        }
+    }
+
+    /// Check whether security is enabled
+    pub fn security_enabled(&self) -> bool {
+        self.frame_control & FCF_SECURITY_ENABLED != 0
+    }
+
+    /// Check whether the frame pending bit is set
+    pub fn frame_pending(&self) -> bool {
+        self.frame_control & FCF_FRAME_PENDING != 0
+    }
+
+    /// Check whether the ack request bit is set
+    pub fn ack_request(&self) -> bool {
+        self.frame_control & FCF_ACK_REQUEST != 0
+    }
+
+    /// Check whether the PAN ID compression bit is set
+    pub fn pan_id_compression(&self) -> bool {
+        self.frame_control & FCF_PAN_ID_COMPRESSION != 0
+    }
+
+    /// Check whether the IE present bit is set
+    pub fn ie_present(&self) -> bool {
+        self.frame_control & FCF_IE_PRESENT != 0
+    }
+
+    /// Get the sequence number (absent when suppressed)
+    pub fn sequence_number(&self) -> Option<u8> {
+        self.sequence_number
+    }
+
+    /// Get the destination PAN ID (if present in the frame)
+    pub fn dst_pan_id(&self) -> Option<OTPanId> {
+        self.dst_pan_id
+    }
+
+    /// Get the destination address
+    pub fn dst_address(&self) -> MacAddress {
+        self.dst_address
+    }
+
+    /// Get the source PAN ID (if present in the frame)
+    pub fn src_pan_id(&self) -> Option<OTPanId> {
+        self.src_pan_id
+    }
+
+    /// Get the source PAN ID, falling back to the destination PAN ID when compressed
+    pub fn effective_src_pan_id(&self) -> Option<OTPanId> {
+        self.src_pan_id.or(self.dst_pan_id)
+    }
+
+    /// Get the source address
+    pub fn src_address(&self) -> MacAddress {
+        self.src_address
+    }
+
+    /// Get the auxiliary security header (if security is enabled)
+    pub fn security_header(&self) -> Option<&SecurityHeader<'a>> {
+        self.security_header.as_ref()
+    }
+
+    /// Get the MAC command frame identifier
+    ///
+    /// Returns None for other frame types and for IEEE 802.15.4-2015 commands whose payload is still encrypted.
+    pub fn command_id(&self) -> Option<u8> {
+        if self.frame_type() != FrameType::MacCommand {
+            return None;
+        }
+
+        if self.frame_version() < FrameVersion::Version2015 {
+            Some(self.psdu[self.payload_offset - COMMAND_ID_SIZE])
+        } else if self.security_enabled() {
+            None
+        } else {
+            self.payload().first().copied()
+        }
+    }
+
+    /// Get the length of the MAC header (including the auxiliary security header and header IEs)
+    pub fn header_length(&self) -> usize {
+        self.payload_ie_offset
+    }
+
+    /// Get the range of the header IEs within the PSDU (including any termination IE)
+    pub fn header_ie_range(&self) -> Range<usize> {
+        self.header_ie_offset..self.header_ie_end
+    }
+
+    /// Get the range of the MAC payload within the PSDU
+    pub fn payload_range(&self) -> Range<usize> {
+        self.payload_offset..self.footer_offset
+    }
+
+    /// Get the raw header IE bytes (including any termination IE)
+    pub fn header_ie_bytes(&self) -> &'a [u8] {
+        &self.psdu[self.header_ie_offset..self.header_ie_end]
+    }
+
+    /// Iterate over the header IEs
+    pub fn header_ies(&self) -> HeaderIeIterator<'a> {
+        HeaderIeIterator::new(self.header_ie_bytes())
+    }
+
+    /// Iterate over the payload IEs
+    ///
+    /// Payload IEs of secured frames are encrypted and therefore part of `payload`.
+    pub fn payload_ies(&self) -> PayloadIeIterator<'a> {
+        PayloadIeIterator::new(&self.psdu[self.payload_ie_offset..self.payload_offset])
+    }
+
+    /// Get the MAC payload
+    pub fn payload(&self) -> &'a [u8] {
+        &self.psdu[self.payload_offset..self.footer_offset]
+    }
+
+    /// Get the message integrity code (empty when unsecured)
+    pub fn mic(&self) -> &'a [u8] {
+        &self.psdu[self.footer_offset..self.psdu.len() - FCS_SIZE]
+    }
+
+    /// Get the frame check sequence
+    pub fn fcs(&self) -> u16 {
+        let length = self.psdu.len();
+        u16::from_le_bytes([self.psdu[length - 2], self.psdu[length - 1]])
+    }
+}
+
+impl<'a> OTRadioFrame<'a> {
+    /// Parse the PSDU of this radio frame
+    pub fn parse<E>(&self) -> Result<Frame<'a>, OTError<E>> {
+        Frame::parse(self.psdu)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::convert::Infallible;
+
+    const EXT: OTExtAddress = [0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01];
+
+    // Expected auxiliary security header fields
+    struct Security {
+        level: u8,
+        key_id_mode: u8,
+        frame_counter: Option<u32>,
+        key_source: &'static [u8],
+        key_index: Option<u8>,
+    }
+
+    struct Case {
+        name: &'static str,
+        psdu: &'static [u8],
+        frame_type: FrameType,
+        version: FrameVersion,
+        sequence_number: Option<u8>,
+        dst_pan_id: Option<OTPanId>,
+        dst_address: MacAddress,
+        src_pan_id: Option<OTPanId>,
+        src_address: MacAddress,
+        security: Option<Security>,
+        command_id: Option<u8>,
+        header_length: usize,
+        header_ies: &'static [(u8, &'static [u8])],
+        payload_ies: &'static [(u8, &'static [u8])],
+        payload: &'static [u8],
+        mic_size: usize,
+    }
+
+    fn cases() -> [Case; 12] {
+        [
+            Case {
+                name: "2003 immediate ack",
+                psdu: &[0x02, 0x00, 0x2a, 0x00, 0x00],
+                frame_type: FrameType::Ack,
+                version: FrameVersion::Version2003,
+                sequence_number: Some(0x2a),
+                dst_pan_id: None,
+                dst_address: MacAddress::None,
+                src_pan_id: None,
+                src_address: MacAddress::None,
+                security: None,
+                command_id: None,
+                header_length: 3,
+                header_ies: &[],
+                payload_ies: &[],
+                payload: &[],
+                mic_size: 0,
+            },
+            Case {
+                name: "2006 data short/short with PAN ID compression",
+                psdu: &[0x61, 0x98, 0x10, 0x34, 0x12, 0x02, 0x00, 0x01, 0x00, 0xaa, 0xbb, 0x00, 0x00],
+                frame_type: FrameType::Data,
+                version: FrameVersion::Version2006,
+                sequence_number: Some(0x10),
+                dst_pan_id: Some(0x1234),
+                dst_address: MacAddress::Short(0x0002),
+                src_pan_id: None,
+                src_address: MacAddress::Short(0x0001),
+                security: None,
+                command_id: None,
+                header_length: 9,
+                header_ies: &[],
+                payload_ies: &[],
+                payload: &[0xaa, 0xbb],
+                mic_size: 0,
+            },
+            Case {
+                name: "2006 data ext/ext without PAN ID compression",
+                psdu: &[
+                    0x01, 0xdc, 0x11, 0x34, 0x12, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x78, 0x56, 0x01,
+                    0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0xcc, 0x00, 0x00,
+                ],
+                frame_type: FrameType::Data,
+                version: FrameVersion::Version2006,
+                sequence_number: Some(0x11),
+                dst_pan_id: Some(0x1234),
+                dst_address: MacAddress::Extended(EXT),
+                src_pan_id: Some(0x5678),
+                src_address: MacAddress::Extended(EXT),
+                security: None,
+                command_id: None,
+                header_length: 23,
+                header_ies: &[],
+                payload_ies: &[],
+                payload: &[0xcc],
+                mic_size: 0,
+            },
+            Case {
+                name: "2015 data ext/ext with PAN ID compression carries no PAN ID",
+                psdu: &[
+                    0x41, 0xec, 0x12, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x01, 0x02, 0x03, 0x04, 0x05,
+                    0x06, 0x07, 0x08, 0x00, 0x00,
+                ],
+                frame_type: FrameType::Data,
+                version: FrameVersion::Version2015,
+                sequence_number: Some(0x12),
+                dst_pan_id: None,
+                dst_address: MacAddress::Extended(EXT),
+                src_pan_id: None,
+                src_address: MacAddress::Extended(EXT),
+                security: None,
+                command_id: None,
+                header_length: 19,
+                header_ies: &[],
+                payload_ies: &[],
+                payload: &[],
+                mic_size: 0,
+            },
+            Case {
+                name: "2015 data without addresses, suppressed sequence number and PAN ID compression",
+                psdu: &[0x41, 0x21, 0xcd, 0xab, 0xdd, 0x00, 0x00],
+                frame_type: FrameType::Data,
+                version: FrameVersion::Version2015,
+                sequence_number: None,
+                dst_pan_id: Some(0xabcd),
+                dst_address: MacAddress::None,
+                src_pan_id: None,
+                src_address: MacAddress::None,
+                security: None,
+                command_id: None,
+                header_length: 4,
+                header_ies: &[],
+                payload_ies: &[],
+                payload: &[0xdd],
+                mic_size: 0,
+            },
+            Case {
+                name: "2015 enhanced ack with an unterminated CSL IE",
+                psdu: &[
+                    0x02, 0x2a, 0x05, 0x34, 0x12, 0x01, 0x00, 0x04, 0x0d, 0x10, 0x00, 0xe8, 0x03, 0x00, 0x00,
+                ],
+                frame_type: FrameType::Ack,
+                version: FrameVersion::Version2015,
+                sequence_number: Some(0x05),
+                dst_pan_id: Some(0x1234),
+                dst_address: MacAddress::Short(0x0001),
+                src_pan_id: None,
+                src_address: MacAddress::None,
+                security: None,
+                command_id: None,
+                header_length: 13,
+                header_ies: &[(0x1a, &[0x10, 0x00, 0xe8, 0x03])],
+                payload_ies: &[],
+                payload: &[],
+                mic_size: 0,
+            },
+            Case {
+                name: "2015 data with HT1 followed by payload IEs",
+                psdu: &[
+                    0x41, 0xaa, 0x01, 0x34, 0x12, 0xff, 0xff, 0x01, 0x00, 0x00, 0x3f, 0x01, 0x90, 0x42, 0x00, 0xf8,
+                    0x99, 0x00, 0x00,
+                ],
+                frame_type: FrameType::Data,
+                version: FrameVersion::Version2015,
+                sequence_number: Some(0x01),
+                dst_pan_id: Some(0x1234),
+                dst_address: MacAddress::Short(0xffff),
+                src_pan_id: None,
+                src_address: MacAddress::Short(0x0001),
+                security: None,
+                command_id: None,
+                header_length: 11,
+                header_ies: &[(HEADER_IE_TERMINATION_1, &[])],
+                payload_ies: &[(0x02, &[0x42]), (PAYLOAD_IE_TERMINATION, &[])],
+                payload: &[0x99],
+                mic_size: 0,
+            },
+            Case {
+                name: "2006 secured data with key id mode 1",
+                psdu: &[
+                    0x49, 0x98, 0x13, 0x34, 0x12, 0x02, 0x00, 0x01, 0x00, 0x0d, 0x01, 0x00, 0x00, 0x00, 0x02, 0xe0,
+                    0xe1, 0xa0, 0xa1, 0xa2, 0xa3, 0x00, 0x00,
+                ],
+                frame_type: FrameType::Data,
+                version: FrameVersion::Version2006,
+                sequence_number: Some(0x13),
+                dst_pan_id: Some(0x1234),
+                dst_address: MacAddress::Short(0x0002),
+                src_pan_id: None,
+                src_address: MacAddress::Short(0x0001),
+                security: Some(Security {
+                    level: 5,
+                    key_id_mode: 1,
+                    frame_counter: Some(1),
+                    key_source: &[],
+                    key_index: Some(0x02),
+                }),
+                command_id: None,
+                header_length: 15,
+                header_ies: &[],
+                payload_ies: &[],
+                payload: &[0xe0, 0xe1],
+                mic_size: 4,
+            },
+            Case {
+                name: "2015 secured data with key id mode 2 and suppressed frame counter",
+                psdu: &[
+                    0x49, 0xa8, 0x14, 0x34, 0x12, 0x02, 0x00, 0x01, 0x00, 0x36, 0x11, 0x22, 0x33, 0x44, 0x07, 0xe0,
+                    0xa0, 0xa1, 0xa2, 0xa3, 0xa4, 0xa5, 0xa6, 0xa7, 0x00, 0x00,
+                ],
+                frame_type: FrameType::Data,
+                version: FrameVersion::Version2015,
+                sequence_number: Some(0x14),
+                dst_pan_id: Some(0x1234),
+                dst_address: MacAddress::Short(0x0002),
+                src_pan_id: None,
+                src_address: MacAddress::Short(0x0001),
+                security: Some(Security {
+                    level: 6,
+                    key_id_mode: 2,
+                    frame_counter: None,
+                    key_source: &[0x11, 0x22, 0x33, 0x44],
+                    key_index: Some(0x07),
+                }),
+                command_id: None,
+                header_length: 15,
+                header_ies: &[],
+                payload_ies: &[],
+                payload: &[0xe0],
+                mic_size: 8,
+            },
+            Case {
+                name: "2006 data request command carries the command id in the header",
+                psdu: &[0x63, 0x98, 0x15, 0x34, 0x12, 0x00, 0x00, 0x01, 0x00, 0x04, 0x00, 0x00],
+                frame_type: FrameType::MacCommand,
+                version: FrameVersion::Version2006,
+                sequence_number: Some(0x15),
+                dst_pan_id: Some(0x1234),
+                dst_address: MacAddress::Short(0x0000),
+                src_pan_id: None,
+                src_address: MacAddress::Short(0x0001),
+                security: None,
+                command_id: Some(0x04),
+                header_length: 10,
+                header_ies: &[],
+                payload_ies: &[],
+                payload: &[],
+                mic_size: 0,
+            },
+            Case {
+                name: "2006 secured command keeps the command id in the clear",
+                psdu: &[
+                    0x6b, 0x98, 0x16, 0x34, 0x12, 0x00, 0x00, 0x01, 0x00, 0x0d, 0x02, 0x00, 0x00, 0x00, 0x01, 0x01,
+                    0x80, 0xa0, 0xa1, 0xa2, 0xa3, 0x00, 0x00,
+                ],
+                frame_type: FrameType::MacCommand,
+                version: FrameVersion::Version2006,
+                sequence_number: Some(0x16),
+                dst_pan_id: Some(0x1234),
+                dst_address: MacAddress::Short(0x0000),
+                src_pan_id: None,
+                src_address: MacAddress::Short(0x0001),
+                security: Some(Security {
+                    level: 5,
+                    key_id_mode: 1,
+                    frame_counter: Some(2),
+                    key_source: &[],
+                    key_index: Some(0x01),
+                }),
+                command_id: Some(0x01),
+                header_length: 16,
+                header_ies: &[],
+                payload_ies: &[],
+                payload: &[0x80],
+                mic_size: 4,
+            },
+            Case {
+                name: "2015 command carries the command id in the payload",
+                psdu: &[0x43, 0xa8, 0x17, 0x34, 0x12, 0x00, 0x00, 0x01, 0x00, 0x04, 0x00, 0x00],
+                frame_type: FrameType::MacCommand,
+                version: FrameVersion::Version2015,
+                sequence_number: Some(0x17),
+                dst_pan_id: Some(0x1234),
+                dst_address: MacAddress::Short(0x0000),
+                src_pan_id: None,
+                src_address: MacAddress::Short(0x0001),
+                security: None,
+                command_id: Some(0x04),
+                header_length: 9,
+                header_ies: &[],
+                payload_ies: &[],
+                payload: &[0x04],
+                mic_size: 0,
+            },
+        ]
+    }
+
+    #[test]
+    fn parse_valid_frames() {
+        for case in cases() {
+            let frame = Frame::parse::<Infallible>(case.psdu).unwrap_or_else(|_| panic!("{}: parse failed", case.name));
+
+            assert_eq!(frame.frame_type(), case.frame_type, "{}", case.name);
+            assert_eq!(frame.frame_version(), case.version, "{}", case.name);
+            assert_eq!(frame.sequence_number(), case.sequence_number, "{}", case.name);
+            assert_eq!(frame.dst_pan_id(), case.dst_pan_id, "{}", case.name);
+            assert_eq!(frame.dst_address(), case.dst_address, "{}", case.name);
+            assert_eq!(frame.src_pan_id(), case.src_pan_id, "{}", case.name);
+            assert_eq!(frame.src_address(), case.src_address, "{}", case.name);
+            assert_eq!(frame.effective_src_pan_id(), case.src_pan_id.or(case.dst_pan_id), "{}", case.name);
+            assert_eq!(frame.command_id(), case.command_id, "{}", case.name);
+            assert_eq!(frame.header_length(), case.header_length, "{}", case.name);
+            assert_eq!(frame.payload(), case.payload, "{}", case.name);
+            assert_eq!(frame.mic().len(), case.mic_size, "{}", case.name);
+
+            let header_ies = frame.header_ies().map(|ie| (ie.element_id, ie.content));
+            assert!(header_ies.eq(case.header_ies.iter().copied()), "{}: header IEs", case.name);
+            let payload_ies = frame.payload_ies().map(|ie| (ie.group_id, ie.content));
+            assert!(payload_ies.eq(case.payload_ies.iter().copied()), "{}: payload IEs", case.name);
+
+            match (frame.security_header(), &case.security) {
+                (None, None) => assert!(!frame.security_enabled(), "{}", case.name),
+                (Some(header), Some(security)) => {
+                    assert_eq!(header.security_level(), security.level, "{}", case.name);
+                    assert_eq!(header.key_id_mode(), security.key_id_mode, "{}", case.name);
+                    assert_eq!(header.frame_counter, security.frame_counter, "{}", case.name);
+                    assert_eq!(header.key_source(), security.key_source, "{}", case.name);
+                    assert_eq!(header.key_index(), security.key_index, "{}", case.name);
+                    assert_eq!(header.mic_size(), case.mic_size, "{}", case.name);
+                },
+                _ => panic!("{}: unexpected security header", case.name),
+            }
+        }
+    }
+
+    #[test]
+    fn parse_invalid_frames() {
+        let cases: [(&str, &[u8]); 10] = [
+            ("shorter than the minimum size", &[0x02, 0x00]),
+            ("missing sequence number", &[0x02, 0x00, 0x00]),
+            ("reserved frame type", &[0x04, 0x10, 0x01, 0x00, 0x00]),
+            ("reserved frame version", &[0x01, 0x30, 0x01, 0x00, 0x00]),
+            ("reserved address mode", &[0x01, 0x14, 0x01, 0x34, 0x12, 0x00, 0x00]),
+            ("truncated destination address", &[0x61, 0x98, 0x01, 0x34, 0x12, 0x02, 0x00]),
+            ("security on a 2003 frame", &[0x49, 0x88, 0x01, 0x34, 0x12, 0x02, 0x00, 0x01, 0x00, 0x0d, 0x00, 0x00]),
+            (
+                "MIC overlapping the security header",
+                &[
+                    0x49, 0x98, 0x01, 0x34, 0x12, 0x02, 0x00, 0x01, 0x00, 0x0d, 0x01, 0x00, 0x00, 0x00, 0x02, 0xa0,
+                    0x00, 0x00,
+                ],
+            ),
+            (
+                "unterminated header IEs not filling the frame",
+                &[0x02, 0x2a, 0x01, 0x34, 0x12, 0x01, 0x00, 0x04, 0x0d, 0x10, 0x00, 0x00, 0x00],
+            ),
+            ("2006 command without a command id", &[0x63, 0x98, 0x01, 0x34, 0x12, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00]),
+        ];
+
+        for (name, psdu) in cases {
+            assert!(matches!(Frame::parse::<Infallible>(psdu), Err(OTError::Parse)), "{}", name);
+        }
+    }
+}
@@ -0,0 +1,309 @@
+//!
+//! IEEE 802.15.4 Header Information Elements
+//!
+
+use super::frame::{
+    FrameVersion, HeaderIe, FCF_IE_PRESENT, HEADER_IE_TERMINATION_1, HEADER_IE_TERMINATION_2, IE_DESCRIPTOR_SIZE,
+};
+use super::{RadioIEInfo, TransmitFrame, CSL_IE_HEADER_BYTES_HI, CSL_IE_HEADER_BYTES_LO, OT_CSL_IE_SIZE, OT_RADIO_FRAME_MAX_SIZE};
+use crate::error::OTError;
+
+// Vendor specific header IE element id
+pub const VENDOR_SPECIFIC_IE_ID: u8 = 0x00;
+// CSL header IE element id
+pub const CSL_IE_ID: u8 = 0x1a;
+
+// Size of a vendor OUI (in bytes)
+pub const VENDOR_OUI_SIZE: usize = 3;
+// Thread Group company id (used by the Thread vendor specific IEs)
+pub const VENDOR_OUI_THREAD_COMPANY_ID: u32 = 0xeab89b;
+// Nest company id (used by the Time IE)
+pub const VENDOR_OUI_NEST: u32 = 0x18b430;
+// Vendor IE subtype of the Time IE
+pub const VENDOR_IE_TIME: u8 = 0x01;
+// Thread IE subtype of the Enhanced-ACK Link Metrics probing IE
+pub const THREAD_IE_ENH_ACK_PROBING: u8 = 0x00;
+
+// Size of the Time IE content: OUI, subtype, sequence and 64 bit time (in bytes)
+pub const TIME_IE_SIZE: usize = VENDOR_OUI_SIZE + 1 + 1 + 8;
+// Offset of the time sync sequence within the Time IE content
+pub const TIME_IE_SEQUENCE_OFFSET: usize = VENDOR_OUI_SIZE + 1;
+
+/// Decoded IEEE 802.15.4 Header IE
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TypedHeaderIe<'a> {
+    // Coordinated Sampled Listening IE (phase and period in units of 10 symbols)
+    Csl { phase: u16, period: u16 },
+    // Thread network Time IE
+    Time { sequence: u8, time: u64 },
+    // Vendor specific IE other than the Time IE
+    VendorSpecific { oui: u32, content: &'a [u8] },
+    // Header termination IE followed by payload IEs
+    Termination1,
+    // Header termination IE followed by the payload
+    Termination2,
+    // Any other header IE
+    Unknown(HeaderIe<'a>),
+}
+
+impl<'a> HeaderIe<'a> {
+    /// Decode the content of this IE based on its element id
+    pub fn typed(&self) -> TypedHeaderIe<'a> {
+        let content = self.content;
+        match self.element_id {
+            CSL_IE_ID if content.len() >= OT_CSL_IE_SIZE => TypedHeaderIe::Csl {
+                phase: u16::from_le_bytes([content[0], content[1]]),
+                period: u16::from_le_bytes([content[2], content[3]]),
+            },
+            VENDOR_SPECIFIC_IE_ID if content.len() >= VENDOR_OUI_SIZE => {
+                let oui = read_oui(content);
+                if oui == VENDOR_OUI_NEST && content.len() == TIME_IE_SIZE && content[VENDOR_OUI_SIZE] == VENDOR_IE_TIME {
+                    let mut time = [0u8; 8];
+                    time.copy_from_slice(&content[TIME_IE_SEQUENCE_OFFSET + 1..]);
+                    TypedHeaderIe::Time {
+                        sequence: content[TIME_IE_SEQUENCE_OFFSET],
+                        time: u64::from_le_bytes(time),
+                    }
+                } else {
+                    TypedHeaderIe::VendorSpecific {
+                        oui,
+                        content: &content[VENDOR_OUI_SIZE..],
+                    }
+                }
+            },
+            HEADER_IE_TERMINATION_1 => TypedHeaderIe::Termination1,
+            HEADER_IE_TERMINATION_2 => TypedHeaderIe::Termination2,
+            _ => TypedHeaderIe::Unknown(*self),
+        }
+    }
+}
+
+/// Read a little-endian vendor OUI
+fn read_oui(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0])
+}
+
+/// Encode a header IE descriptor
+pub fn header_ie_descriptor<E>(element_id: u8, length: usize) -> Result<[u8; IE_DESCRIPTOR_SIZE], OTError<E>> {
+    if length > 0x7f {
+        return Err(OTError::InvalidArgs);
+    }
+    Ok(((element_id as u16) << 7 | length as u16).to_le_bytes())
+}
+
+/// Writer appending header IEs to a byte buffer
+///
+/// The encoded bytes are meant to be handed to `FrameBuilder::header_ies`.
+pub struct HeaderIeWriter<'a> {
+    buffer: &'a mut [u8],
+    length: usize,
+}
+
+impl<'a> HeaderIeWriter<'a> {
+    /// Create a writer over an empty buffer
+    pub fn new(buffer: &'a mut [u8]) -> Self {
+        Self { buffer, length: 0 }
+    }
+
+    /// Get the encoded header IEs
+    pub fn bytes(&self) -> &[u8] {
+        &self.buffer[..self.length]
+    }
+
+    /// Get the length of the encoded header IEs (in bytes)
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    /// Check whether no header IE was written yet
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Append a header IE, returning the offset of its content within the buffer
+    pub fn append<E>(&mut self, element_id: u8, content: &[u8]) -> Result<usize, OTError<E>> {
+        let descriptor = header_ie_descriptor(element_id, content.len())?;
+        let start = self.length;
+        let end = start + IE_DESCRIPTOR_SIZE + content.len();
+        let destination = self.buffer.get_mut(start..end).ok_or(OTError::NoBuffers)?;
+        destination[..IE_DESCRIPTOR_SIZE].copy_from_slice(&descriptor);
+        destination[IE_DESCRIPTOR_SIZE..].copy_from_slice(content);
+        self.length = end;
+        Ok(start + IE_DESCRIPTOR_SIZE)
+    }
+
+    /// Append a CSL IE (phase and period in units of 10 symbols)
+    pub fn append_csl<E>(&mut self, phase: u16, period: u16) -> Result<usize, OTError<E>> {
+        let mut content = [0u8; OT_CSL_IE_SIZE];
+        write_csl(&mut content, phase, period);
+        self.append(CSL_IE_ID, &content)
+    }
+
+    /// Append a Time IE, returning the offset of the time sync sequence within the buffer
+    ///
+    /// The returned offset (plus the offset of the header IEs in the PSDU) is what `RadioIEInfo::time_ie_offset`
+    /// expects.
+    pub fn append_time<E>(&mut self, sequence: u8, time: u64) -> Result<usize, OTError<E>> {
+        let mut content = [0u8; TIME_IE_SIZE];
+        content[..VENDOR_OUI_SIZE].copy_from_slice(&VENDOR_OUI_NEST.to_le_bytes()[..VENDOR_OUI_SIZE]);
+        content[VENDOR_OUI_SIZE] = VENDOR_IE_TIME;
+        write_time(&mut content[TIME_IE_SEQUENCE_OFFSET..], sequence, time);
+        Ok(self.append(VENDOR_SPECIFIC_IE_ID, &content)? + TIME_IE_SEQUENCE_OFFSET)
+    }
+
+    /// Append a vendor specific IE
+    pub fn append_vendor_specific<E>(&mut self, oui: u32, content: &[u8]) -> Result<usize, OTError<E>> {
+        let length = VENDOR_OUI_SIZE + content.len();
+        let descriptor = header_ie_descriptor(VENDOR_SPECIFIC_IE_ID, length)?;
+        let start = self.length;
+        let end = start + IE_DESCRIPTOR_SIZE + length;
+        let destination = self.buffer.get_mut(start..end).ok_or(OTError::NoBuffers)?;
+        destination[..IE_DESCRIPTOR_SIZE].copy_from_slice(&descriptor);
+        destination[IE_DESCRIPTOR_SIZE..IE_DESCRIPTOR_SIZE + VENDOR_OUI_SIZE]
+            .copy_from_slice(&oui.to_le_bytes()[..VENDOR_OUI_SIZE]);
+        destination[IE_DESCRIPTOR_SIZE + VENDOR_OUI_SIZE..].copy_from_slice(content);
+        self.length = end;
+        Ok(start + IE_DESCRIPTOR_SIZE)
+    }
+
+    /// Append the HT1 termination IE (payload IEs follow)
+    ///
+    /// Not needed for IEs handed to `FrameBuilder`, which adds the terminations itself.
+    pub fn append_termination_1<E>(&mut self) -> Result<usize, OTError<E>> {
+        self.append(HEADER_IE_TERMINATION_1, &[])
+    }
+
+    /// Append the HT2 termination IE (the payload follows)
+    ///
+    /// Not needed for IEs handed to `FrameBuilder`, which adds the terminations itself.
+    pub fn append_termination_2<E>(&mut self) -> Result<usize, OTError<E>> {
+        self.append(HEADER_IE_TERMINATION_2, &[])
+    }
+}
+
+/// Write CSL IE content
+fn write_csl(content: &mut [u8], phase: u16, period: u16) {
+    content[0..2].copy_from_slice(&phase.to_le_bytes());
+    content[2..4].copy_from_slice(&period.to_le_bytes());
+}
+
+/// Write the sequence and time of a Time IE starting at the sequence byte
+fn write_time(content: &mut [u8], sequence: u8, time: u64) {
+    content[0] = sequence;
+    content[1..9].copy_from_slice(&time.to_le_bytes());
+}
+
+/// Find the offset of the content of the first header IE matching the predicate in a PSDU
+fn find_header_ie<E>(psdu: &[u8], mut predicate: impl FnMut(&HeaderIe) -> bool) -> Result<Option<(usize, usize)>, OTError<E>> {
+    let frame = super::frame::Frame::parse(psdu)?;
+    let mut offset = frame.header_ie_range().start;
+    for ie in frame.header_ies() {
+        if predicate(&ie) {
+            return Ok(Some((offset + IE_DESCRIPTOR_SIZE, ie.content.len())));
+        }
+        offset += IE_DESCRIPTOR_SIZE + ie.content.len();
+    }
+    Ok(None)
+}
+
+/// Find the offset of the CSL IE content in a PSDU
+pub fn find_csl_ie<E>(psdu: &[u8]) -> Result<Option<usize>, OTError<E>> {
+    let found = find_header_ie(psdu, |ie| ie.element_id == CSL_IE_ID && ie.content.len() == OT_CSL_IE_SIZE)?;
+    Ok(found.map(|(offset, _)| offset))
+}
+
+/// Find the offset of the Time IE sync sequence in a PSDU (the value for `RadioIEInfo::time_ie_offset`)
+pub fn find_time_ie<E>(psdu: &[u8]) -> Result<Option<usize>, OTError<E>> {
+    let found = find_header_ie(psdu, |ie| matches!(ie.typed(), TypedHeaderIe::Time { .. }))?;
+    Ok(found.map(|(offset, _)| offset + TIME_IE_SEQUENCE_OFFSET))
+}
+
+/// Update the CSL IE of a PSDU in place
+///
+/// This is meant to be called right before the frame goes on air, once the CSL phase is known.
+///
+/// Returns:
+///     OTError::NotFound if the frame does not carry a CSL IE
+pub fn update_csl_ie<E>(psdu: &mut [u8], phase: u16, period: u16) -> Result<(), OTError<E>> {
+    let offset = find_csl_ie(psdu)?.ok_or(OTError::NotFound)?;
+
+    // The CSL IE descriptor is fixed, so check it to catch stale offsets
+    if psdu[offset - IE_DESCRIPTOR_SIZE..offset] != [CSL_IE_HEADER_BYTES_LO, CSL_IE_HEADER_BYTES_HI] {
+        return Err(OTError::Parse);
+    }
+    write_csl(&mut psdu[offset..offset + OT_CSL_IE_SIZE], phase, period);
+    Ok(())
+}
+
+/// Update the Time IE of a PSDU in place from the header IE information
+///
+/// Params:
+///     psdu - the frame about to be sent
+///     ie_info - the header IE information of the frame
+///     now - the local radio time (in microseconds) at which the frame is sent
+pub fn update_time_ie<E>(psdu: &mut [u8], ie_info: &RadioIEInfo, now: u64) -> Result<(), OTError<E>> {
+    let offset = ie_info.time_ie_offset as usize;
+    if offset == 0 {
+        return Err(OTError::NotFound);
+    }
+
+    let time = now.wrapping_add_signed(ie_info.network_time_offset);
+    let content = psdu.get_mut(offset..offset + 9).ok_or(OTError::InvalidArgs)?;
+    write_time(content, ie_info.time_sync_sequency, time);
+    Ok(())
+}
+
+/// Insert a header IE into an already built IEEE 802.15.4-2015 frame
+///
+/// The IE is placed after the existing header IEs (before any termination IE). An HT2 termination is added when the
+/// frame had no IEs yet and carries a payload.
+///
+/// Returns:
+///     The offset of the IE content within the PSDU
+///     OTError::InvalidArgs if the frame is not an IEEE 802.15.4-2015 frame
+///     OTError::NoBuffers if the frame would exceed OT_RADIO_FRAME_MAX_SIZE
+pub fn insert_header_ie<E>(frame: &mut TransmitFrame, element_id: u8, content: &[u8]) -> Result<usize, OTError<E>> {
+    let descriptor = header_ie_descriptor(element_id, content.len())?;
+    let (insert_offset, add_termination) = {
+        let parsed = frame.parse()?;
+        if parsed.frame_version() != FrameVersion::Version2015 {
+            return Err(OTError::InvalidArgs);
+        }
+
+        let range = parsed.header_ie_range();
+        if parsed.ie_present() {
+            let mut offset = range.start;
+            for ie in parsed.header_ies() {
+                if ie.is_termination() {
+                    break;
+                }
+                offset += IE_DESCRIPTOR_SIZE + ie.content.len();
+            }
+            (offset, false)
+        } else {
+            (range.start, !parsed.payload().is_empty())
+        }
+    };
+
+    let ie_length = IE_DESCRIPTOR_SIZE + content.len();
+    let inserted = ie_length + if add_termination { IE_DESCRIPTOR_SIZE } else { 0 };
+    let length = frame.length();
+    if length + inserted > OT_RADIO_FRAME_MAX_SIZE {
+        return Err(OTError::NoBuffers);
+    }
+
+    frame.set_length(length + inserted)?;
+    let psdu = frame.psdu_mut();
+    psdu.copy_within(insert_offset..length, insert_offset + inserted);
+    psdu[insert_offset..insert_offset + IE_DESCRIPTOR_SIZE].copy_from_slice(&descriptor);
+    psdu[insert_offset + IE_DESCRIPTOR_SIZE..insert_offset + ie_length].copy_from_slice(content);
+    if add_termination {
+        let termination = header_ie_descriptor(HEADER_IE_TERMINATION_2, 0)?;
+        psdu[insert_offset + ie_length..insert_offset + inserted].copy_from_slice(&termination);
+    }
+
+    let fcf = u16::from_le_bytes([psdu[0], psdu[1]]) | FCF_IE_PRESENT;
+    psdu[..2].copy_from_slice(&fcf.to_le_bytes());
+
+    Ok(insert_offset + IE_DESCRIPTOR_SIZE)
+}
@@ -0,0 +1,622 @@
+//!
+//! Link Metrics (Thread 1.2)
+//!
+//! A `LinkMetricsEngine` runs the Subject side of Link Metrics for a radio:
+//!     - Enhanced-ACK based probing: initiators registered through `configure_enh_ack_probing` get up to
+//!       OT_ENH_PROBING_IE_DATA_MAX_SIZE bytes of metrics measured on the acknowledged frame in every Enhanced ACK
+//!       (see `EnhAckContent::link_metrics`)
+//!     - forward tracking series: the metrics of the frames received from an initiator are aggregated per series
+//!       until the initiator queries them
+//!     - single probe: the metrics of the query frame itself are reported
+//!
+//! Values are reported the way Thread encodes them: the LQI as is, the link margin and the RSSI scaled to 0 - 255.
+//! LQI and RSSI of a series are averaged with an exponential moving average (weight 1/8).
+//!
+
+use super::frame::{Frame, FrameType, MacAddress};
+use super::srcmatch::DATA_REQUEST_COMMAND_ID;
+use super::{OTExtAddress, OTLinkMetrics, OTShortAddress, OT_ENH_PROBING_IE_DATA_MAX_SIZE};
+use crate::error::OTError;
+
+// Series ID of a single probe query
+pub const LINK_METRICS_SINGLE_PROBE_ID: u8 = 0;
+// Largest forward tracking series ID (255 is reserved)
+pub const LINK_METRICS_MAX_SERIES_ID: u8 = 254;
+
+// Report sub-TLV type IDs of the metrics (PDU count is a 4 byte value, the others are 1 byte averages)
+pub const LINK_METRICS_TYPE_ID_PDU_COUNT: u8 = 0x40;
+pub const LINK_METRICS_TYPE_ID_LQI: u8 = 0x09;
+pub const LINK_METRICS_TYPE_ID_LINK_MARGIN: u8 = 0x0a;
+pub const LINK_METRICS_TYPE_ID_RSSI: u8 = 0x0b;
+
+// Maximum size of an encoded report: PDU count (1 + 4) and three averages (1 + 1 each)
+pub const LINK_METRICS_REPORT_MAX_SIZE: usize = 5 + 3 * 2;
+
+// Largest link margin and lowest RSSI that can be reported (in dB and dBm)
+const LINK_MARGIN_MAX: i16 = 130;
+const RSSI_MIN: i16 = -130;
+// Fractional bits kept by the averages and weight of a new sample (1 / 2^AVERAGE_COEFF_SHIFT)
+const AVERAGE_PRECISION_SHIFT: u32 = 3;
+const AVERAGE_COEFF_SHIFT: u32 = 3;
+
+impl OTLinkMetrics {
+    /// Get the number of metrics selected
+    pub fn count(&self) -> usize {
+        [self.pdu_count, self.lqi, self.link_margin, self.rssi].iter().filter(|selected| **selected).count()
+    }
+
+    /// Check whether no metric is selected
+    pub fn is_empty(&self) -> bool {
+        self.count() == 0
+    }
+}
+
+/// Scale a link margin (dB) to its reported value
+pub fn scale_link_margin(link_margin: i16) -> u8 {
+    (link_margin.clamp(0, LINK_MARGIN_MAX) as i32 * 255 / LINK_MARGIN_MAX as i32) as u8
+}
+
+/// Scale an RSSI (dBm) to its reported value
+pub fn scale_rssi(rssi: i8) -> u8 {
+    (((rssi as i16).clamp(RSSI_MIN, 0) - RSSI_MIN) as i32 * 255 / -RSSI_MIN as i32) as u8
+}
+
+/// Frame types counted by a forward tracking series
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SeriesFrameTypes {
+    // MLE Link Probe messages carrying the series ID
+    pub link_probe: bool,
+    // MAC data frames
+    pub mac_data: bool,
+    // MAC data request commands
+    pub mac_data_request: bool,
+    // MAC ACKs
+    pub mac_ack: bool,
+}
+
+impl SeriesFrameTypes {
+    /// Check whether a frame is counted (link probes are recorded with `LinkMetricsEngine::record_link_probe`)
+    fn counts(&self, frame: &Frame) -> bool {
+        match frame.frame_type() {
+            FrameType::Data => self.mac_data,
+            FrameType::Ack => self.mac_ack,
+            FrameType::MacCommand => self.mac_data_request && frame.command_id() == Some(DATA_REQUEST_COMMAND_ID),
+            FrameType::Beacon => false,
+        }
+    }
+}
+
+/// Aggregated metrics
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LinkMetricsValues {
+    pub pdu_count: u32,
+    pub lqi: u8,
+    // Link margin in dB
+    pub link_margin: u8,
+    // RSSI in dBm
+    pub rssi: i8,
+}
+
+/// Metrics answered to a query
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LinkMetricsReport {
+    pub metrics: OTLinkMetrics,
+    pub values: LinkMetricsValues,
+}
+
+impl LinkMetricsReport {
+    /// Encode the report as a sequence of Report sub-TLV values (type ID followed by the value)
+    ///
+    /// Returns:
+    ///     The length of the encoded report
+    ///     OTError::NoBuffers if the buffer is too small
+    pub fn encode<E>(&self, buffer: &mut [u8]) -> Result<usize, OTError<E>> {
+        let mut length = 0;
+        let mut write = |bytes: &[u8]| -> Result<(), OTError<E>> {
+            buffer.get_mut(length..length + bytes.len()).ok_or(OTError::NoBuffers)?.copy_from_slice(bytes);
+            length += bytes.len();
+            Ok(())
+        };
+
+        if self.metrics.pdu_count {
+            write(&[LINK_METRICS_TYPE_ID_PDU_COUNT])?;
+            write(&self.values.pdu_count.to_be_bytes())?;
+        }
+        if self.metrics.lqi {
+            write(&[LINK_METRICS_TYPE_ID_LQI, self.values.lqi])?;
+        }
+        if self.metrics.link_margin {
+            write(&[LINK_METRICS_TYPE_ID_LINK_MARGIN, scale_link_margin(self.values.link_margin as i16)])?;
+        }
+        if self.metrics.rssi {
+            write(&[LINK_METRICS_TYPE_ID_RSSI, scale_rssi(self.values.rssi)])?;
+        }
+        Ok(length)
+    }
+}
+
+/// Exponential moving average of a magnitude (the plain average over the first samples)
+#[derive(Clone, Copy, Debug, Default)]
+struct Average {
+    // Average with AVERAGE_PRECISION_SHIFT fractional bits
+    value: u16,
+    count: u16,
+}
+
+impl Average {
+    fn add(&mut self, sample: u8) {
+        let sample = (sample as u32) << AVERAGE_PRECISION_SHIFT;
+        let weight = (self.count as u32 + 1).min(1 << AVERAGE_COEFF_SHIFT);
+        let value = self.value as u32;
+        self.value = ((value * (weight - 1) + sample) / weight) as u16;
+        self.count = self.count.saturating_add(1);
+    }
+
+    fn get(&self) -> u8 {
+        ((self.value + (1 << (AVERAGE_PRECISION_SHIFT - 1))) >> AVERAGE_PRECISION_SHIFT) as u8
+    }
+}
+
+/// Metrics aggregated by a series
+#[derive(Clone, Copy, Debug, Default)]
+struct Aggregate {
+    pdu_count: u32,
+    lqi: Average,
+    // Average of -RSSI
+    rss: Average,
+}
+
+impl Aggregate {
+    fn add(&mut self, lqi: u8, rssi: i8) {
+        self.pdu_count = self.pdu_count.saturating_add(1);
+        self.lqi.add(lqi);
+        self.rss.add(rssi.min(0).unsigned_abs());
+    }
+
+    fn values(&self, noise_floor: i8) -> LinkMetricsValues {
+        let rssi = -(self.rss.get() as i16);
+        LinkMetricsValues {
+            pdu_count: self.pdu_count,
+            lqi: self.lqi.get(),
+            link_margin: link_margin(rssi, noise_floor),
+            rssi: rssi.max(i8::MIN as i16) as i8,
+        }
+    }
+}
+
+/// Link margin (dB) of an RSSI above the noise floor
+fn link_margin(rssi: i16, noise_floor: i8) -> u8 {
+    (rssi - noise_floor as i16).clamp(0, u8::MAX as i16) as u8
+}
+
+/// Addresses of a Link Metrics initiator
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Initiator {
+    short_address: OTShortAddress,
+    ext_address: OTExtAddress,
+}
+
+impl Initiator {
+    fn matches(&self, address: MacAddress) -> bool {
+        match address {
+            MacAddress::Short(address) => address == self.short_address,
+            MacAddress::Extended(address) => address == self.ext_address,
+            MacAddress::None => false,
+        }
+    }
+}
+
+/// Enhanced-ACK probing registration
+#[derive(Clone, Copy, Debug)]
+struct Probing {
+    initiator: Initiator,
+    metrics: OTLinkMetrics,
+}
+
+/// Forward tracking series
+#[derive(Clone, Copy, Debug)]
+struct Series {
+    initiator: Initiator,
+    series_id: u8,
+    frame_types: SeriesFrameTypes,
+    metrics: OTLinkMetrics,
+    aggregate: Aggregate,
+}
+
+/// Link Metrics Subject with room for INITIATORS Enhanced-ACK probing initiators and SERIES forward tracking series
+pub struct LinkMetricsEngine<const INITIATORS: usize, const SERIES: usize> {
+    // Noise floor used to compute link margins (in dBm)
+    noise_floor: i8,
+    probing: [Option<Probing>; INITIATORS],
+    series: [Option<Series>; SERIES],
+}
+
+impl<const INITIATORS: usize, const SERIES: usize> LinkMetricsEngine<INITIATORS, SERIES> {
+    /// Create an engine without registrations
+    ///
+    /// Params:
+    ///     noise_floor - the noise floor of the radio (in dBm, usually its receive sensitivity)
+    pub const fn new(noise_floor: i8) -> Self {
+        Self {
+            noise_floor,
+            probing: [None; INITIATORS],
+            series: [None; SERIES],
+        }
+    }
+
+    /// Register, update or (with no metric selected) remove an Enhanced-ACK probing initiator
+    ///
+    /// See `OTRadioOperation::configure_enh_ack_probing`.
+    ///
+    /// Returns:
+    ///     OTError::InvalidArgs if the PDU count or more than OT_ENH_PROBING_IE_DATA_MAX_SIZE metrics are selected
+    ///     OTError::NoBuffers if there is no room for another initiator
+    ///     OTError::NotFound when removing an initiator that is not registered
+    pub fn configure_enh_ack_probing<E>(
+        &mut self,
+        link_metrics: OTLinkMetrics,
+        short_address: OTShortAddress,
+        ext_address: OTExtAddress,
+    ) -> Result<(), OTError<E>> {
+        if link_metrics.pdu_count || link_metrics.count() > OT_ENH_PROBING_IE_DATA_MAX_SIZE {
+            return Err(OTError::InvalidArgs);
+        }
+
+        let registered = self
+            .probing
+            .iter_mut()
+            .find(|probing| probing.is_some_and(|probing| probing.initiator.ext_address == ext_address));
+        let initiator = Initiator {
+            short_address,
+            ext_address,
+        };
+
+        match registered {
+            Some(probing) if link_metrics.is_empty() => *probing = None,
+            Some(probing) => {
+                *probing = Some(Probing {
+                    initiator,
+                    metrics: link_metrics,
+                })
+            },
+            None if link_metrics.is_empty() => return Err(OTError::NotFound),
+            None => {
+                let free = self.probing.iter_mut().find(|probing| probing.is_none()).ok_or(OTError::NoBuffers)?;
+                *free = Some(Probing {
+                    initiator,
+                    metrics: link_metrics,
+                });
+            },
+        }
+        Ok(())
+    }
+
+    /// Get the Enhanced-ACK probing data for a frame
+    ///
+    /// Params:
+    ///     address - the source address of the acknowledged frame
+    ///     lqi - the LQI of the acknowledged frame
+    ///     rssi - the RSSI of the acknowledged frame (in dBm)
+    ///     data - the buffer receiving the data
+    ///
+    /// Returns:
+    ///     The length of the data (0 if the sender is not a probing initiator)
+    pub fn enh_ack_data(
+        &self,
+        address: MacAddress,
+        lqi: u8,
+        rssi: i8,
+        data: &mut [u8; OT_ENH_PROBING_IE_DATA_MAX_SIZE],
+    ) -> usize {
+        let Some(probing) = self.probing.iter().flatten().find(|probing| probing.initiator.matches(address)) else {
+            return 0;
+        };
+
+        let mut length = 0;
+        let values = [
+            (probing.metrics.lqi, lqi),
+            (probing.metrics.link_margin, scale_link_margin(link_margin(rssi as i16, self.noise_floor) as i16)),
+            (probing.metrics.rssi, scale_rssi(rssi)),
+        ];
+        for (_, value) in values.iter().filter(|(selected, _)| *selected) {
+            data[length] = *value;
+            length += 1;
+        }
+        length
+    }
+
+    /// Start a forward tracking series
+    ///
+    /// Returns:
+    ///     OTError::InvalidArgs if the series ID is reserved or no metric or frame type is selected
+    ///     OTError::Already if the initiator already has a series with this ID
+    ///     OTError::NoBuffers if there is no room for another series
+    pub fn add_series<E>(
+        &mut self,
+        short_address: OTShortAddress,
+        ext_address: OTExtAddress,
+        series_id: u8,
+        frame_types: SeriesFrameTypes,
+        metrics: OTLinkMetrics,
+    ) -> Result<(), OTError<E>> {
+        if series_id == LINK_METRICS_SINGLE_PROBE_ID
+            || series_id > LINK_METRICS_MAX_SERIES_ID
+            || metrics.is_empty()
+            || frame_types == SeriesFrameTypes::default()
+        {
+            return Err(OTError::InvalidArgs);
+        }
+        if self.find_series(&ext_address, series_id).is_some() {
+            return Err(OTError::Already);
+        }
+
+        let free = self.series.iter_mut().find(|series| series.is_none()).ok_or(OTError::NoBuffers)?;
+        *free = Some(Series {
+            initiator: Initiator {
+                short_address,
+                ext_address,
+            },
+            series_id,
+            frame_types,
+            metrics,
+            aggregate: Aggregate::default(),
+        });
+        Ok(())
+    }
+
+    /// Stop a forward tracking series
+    ///
+    /// Returns:
+    ///     OTError::NotFound if the initiator has no series with this ID
+    pub fn remove_series<E>(&mut self, ext_address: &OTExtAddress, series_id: u8) -> Result<(), OTError<E>> {
+        let index = self.find_series(ext_address, series_id).ok_or(OTError::NotFound)?;
+        self.series[index] = None;
+        Ok(())
+    }
+
+    /// Stop every series and probing registration of an initiator (e.g. when the neighbor is removed)
+    pub fn remove_initiator(&mut self, ext_address: &OTExtAddress) {
+        for series in self.series.iter_mut() {
+            if series.is_some_and(|series| series.initiator.ext_address == *ext_address) {
+                *series = None;
+            }
+        }
+        for probing in self.probing.iter_mut() {
+            if probing.is_some_and(|probing| probing.initiator.ext_address == *ext_address) {
+                *probing = None;
+            }
+        }
+    }
+
+    fn find_series(&self, ext_address: &OTExtAddress, series_id: u8) -> Option<usize> {
+        self.series.iter().position(|series| {
+            series.is_some_and(|series| series.initiator.ext_address == *ext_address && series.series_id == series_id)
+        })
+    }
+
+    fn find_series_mut(&mut self, ext_address: &OTExtAddress, series_id: u8) -> Option<&mut Series> {
+        self.series
+            .iter_mut()
+            .flatten()
+            .find(|series| series.initiator.ext_address == *ext_address && series.series_id == series_id)
+    }
+
+    /// Account a received frame in the series of its sender
+    ///
+    /// Params:
+    ///     frame - the received frame
+    ///     lqi - the LQI of the frame
+    ///     rssi - the RSSI of the frame (in dBm)
+    pub fn record_frame(&mut self, frame: &Frame, lqi: u8, rssi: i8) {
+        let address = frame.src_address();
+        for series in self.series.iter_mut().flatten() {
+            if series.initiator.matches(address) && series.frame_types.counts(frame) {
+                series.aggregate.add(lqi, rssi);
+            }
+        }
+    }
+
+    /// Account an MLE Link Probe received from an initiator
+    pub fn record_link_probe(&mut self, ext_address: &OTExtAddress, series_id: u8, lqi: u8, rssi: i8) {
+        if let Some(series) = self.find_series_mut(ext_address, series_id) {
+            if series.frame_types.link_probe {
+                series.aggregate.add(lqi, rssi);
+            }
+        }
+    }
+
+    /// Get the report of a forward tracking series
+    ///
+    /// Returns:
+    ///     OTError::NotFound if the initiator has no series with this ID
+    pub fn series_report<E>(&self, ext_address: &OTExtAddress, series_id: u8) -> Result<LinkMetricsReport, OTError<E>> {
+        let series = self
+            .series
+            .iter()
+            .flatten()
+            .find(|series| series.initiator.ext_address == *ext_address && series.series_id == series_id)
+            .ok_or(OTError::NotFound)?;
+        Ok(LinkMetricsReport {
+            metrics: series.metrics,
+            values: series.aggregate.values(self.noise_floor),
+        })
+    }
+
+    /// Get the report of a single probe query, measured on the query frame
+    pub fn single_probe_report(&self, metrics: OTLinkMetrics, lqi: u8, rssi: i8) -> LinkMetricsReport {
+        let mut aggregate = Aggregate::default();
+        aggregate.add(lqi, rssi);
+        LinkMetricsReport {
+            metrics,
+            values: LinkMetricsValues {
+                rssi,
+                ..aggregate.values(self.noise_floor)
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use super::*;
+    use crate::radio::builder::FrameBuilder;
+    use crate::radio::frame::FrameVersion;
+    use crate::radio::TransmitFrame;
+
+    const NOISE_FLOOR: i8 = -100;
+    const SHORT_ADDRESS: OTShortAddress = 0x0001;
+    const EXT_ADDRESS: OTExtAddress = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88];
+    const SERIES_ID: u8 = 1;
+
+    const LQI_MARGIN_RSSI: OTLinkMetrics = OTLinkMetrics {
+        pdu_count: false,
+        lqi: true,
+        link_margin: true,
+        rssi: true,
+        reserved: false,
+    };
+    const MARGIN_RSSI: OTLinkMetrics = OTLinkMetrics {
+        lqi: false,
+        ..LQI_MARGIN_RSSI
+    };
+    const ALL_METRICS: OTLinkMetrics = OTLinkMetrics {
+        pdu_count: true,
+        ..LQI_MARGIN_RSSI
+    };
+
+    /// Build a frame sent by the initiator
+    fn frame_from_initiator(builder: FrameBuilder) -> TransmitFrame {
+        let mut frame = TransmitFrame::new();
+        builder
+            .sequence_number(Some(1))
+            .dst(Some(0x1234), MacAddress::Short(0x0000))
+            .src(None, MacAddress::Short(SHORT_ADDRESS))
+            .build::<Infallible>(&mut frame)
+            .unwrap();
+        frame
+    }
+
+    #[test]
+    fn scaling() {
+        let link_margins = [(-5, 0), (0, 0), (40, 78), (65, 127), (130, 255), (200, 255)];
+        for (link_margin, expected) in link_margins {
+            assert_eq!(scale_link_margin(link_margin), expected, "link margin {}", link_margin);
+        }
+
+        let rssis = [(-128, 3), (-65, 127), (-60, 137), (0, 255), (10, 255)];
+        for (rssi, expected) in rssis {
+            assert_eq!(scale_rssi(rssi), expected, "rssi {}", rssi);
+        }
+    }
+
+    #[test]
+    fn enh_ack_probing() {
+        let mut engine = LinkMetricsEngine::<1, 1>::new(NOISE_FLOOR);
+        // The PDU count can not be probed and the IE only has room for two metrics
+        for metrics in [ALL_METRICS, LQI_MARGIN_RSSI] {
+            assert_eq!(
+                engine.configure_enh_ack_probing::<Infallible>(metrics, SHORT_ADDRESS, EXT_ADDRESS),
+                Err(OTError::InvalidArgs)
+            );
+        }
+        engine.configure_enh_ack_probing::<Infallible>(MARGIN_RSSI, SHORT_ADDRESS, EXT_ADDRESS).unwrap();
+        assert_eq!(
+            engine.configure_enh_ack_probing::<Infallible>(MARGIN_RSSI, 0x0002, [0; 8]),
+            Err(OTError::NoBuffers)
+        );
+
+        let mut data = [0; OT_ENH_PROBING_IE_DATA_MAX_SIZE];
+        // Link margin 40 dB above the noise floor
+        assert_eq!(engine.enh_ack_data(MacAddress::Short(SHORT_ADDRESS), 200, -60, &mut data), 2);
+        assert_eq!(data, [78, 137]);
+        assert_eq!(engine.enh_ack_data(MacAddress::Extended(EXT_ADDRESS), 200, -60, &mut data), 2);
+        assert_eq!(engine.enh_ack_data(MacAddress::Short(0x0002), 200, -60, &mut data), 0);
+
+        // Selecting no metric removes the initiator
+        let none = OTLinkMetrics::default();
+        engine.configure_enh_ack_probing::<Infallible>(none, SHORT_ADDRESS, EXT_ADDRESS).unwrap();
+        assert_eq!(engine.enh_ack_data(MacAddress::Short(SHORT_ADDRESS), 200, -60, &mut data), 0);
+        assert_eq!(
+            engine.configure_enh_ack_probing::<Infallible>(none, SHORT_ADDRESS, EXT_ADDRESS),
+            Err(OTError::NotFound)
+        );
+    }
+
+    #[test]
+    fn series_averages() {
+        let mut engine = LinkMetricsEngine::<1, 2>::new(NOISE_FLOOR);
+        let data_frames = SeriesFrameTypes {
+            mac_data: true,
+            ..Default::default()
+        };
+        engine.add_series::<Infallible>(SHORT_ADDRESS, EXT_ADDRESS, SERIES_ID, data_frames, ALL_METRICS).unwrap();
+        assert_eq!(
+            engine.add_series::<Infallible>(SHORT_ADDRESS, EXT_ADDRESS, SERIES_ID, data_frames, ALL_METRICS),
+            Err(OTError::Already)
+        );
+
+        let data = frame_from_initiator(FrameBuilder::data(FrameVersion::Version2006));
+        let data_request =
+            frame_from_initiator(FrameBuilder::mac_command(FrameVersion::Version2006, DATA_REQUEST_COMMAND_ID));
+        let data = Frame::parse::<Infallible>(data.psdu()).unwrap();
+        let data_request = Frame::parse::<Infallible>(data_request.psdu()).unwrap();
+
+        // The first samples are averaged evenly, data requests are not counted by this series
+        engine.record_frame(&data, 100, -50);
+        engine.record_frame(&data_request, 0, -90);
+        engine.record_frame(&data, 200, -70);
+        let report = engine.series_report::<Infallible>(&EXT_ADDRESS, SERIES_ID).unwrap();
+        assert_eq!(
+            report.values,
+            LinkMetricsValues {
+                pdu_count: 2,
+                lqi: 150,
+                link_margin: 40,
+                rssi: -60,
+            }
+        );
+
+        let mut encoded = [0; LINK_METRICS_REPORT_MAX_SIZE];
+        assert_eq!(report.encode::<Infallible>(&mut encoded), Ok(LINK_METRICS_REPORT_MAX_SIZE));
+        assert_eq!(encoded, [0x40, 0, 0, 0, 2, 0x09, 150, 0x0a, 78, 0x0b, 137]);
+        assert_eq!(report.encode::<Infallible>(&mut encoded[..4]), Err(OTError::NoBuffers));
+
+        // Past 8 samples a new sample weighs 1/8
+        engine.remove_series::<Infallible>(&EXT_ADDRESS, SERIES_ID).unwrap();
+        engine.add_series::<Infallible>(SHORT_ADDRESS, EXT_ADDRESS, SERIES_ID, data_frames, ALL_METRICS).unwrap();
+        for _ in 0..8 {
+            engine.record_frame(&data, 0, -40);
+        }
+        engine.record_frame(&data, 80, -120);
+        let report = engine.series_report::<Infallible>(&EXT_ADDRESS, SERIES_ID).unwrap();
+        assert_eq!(
+            report.values,
+            LinkMetricsValues {
+                pdu_count: 9,
+                lqi: 10,
+                link_margin: 50,
+                rssi: -50,
+            }
+        );
+
+        engine.remove_initiator(&EXT_ADDRESS);
+        assert_eq!(engine.series_report::<Infallible>(&EXT_ADDRESS, SERIES_ID), Err(OTError::NotFound));
+    }
+
+    #[test]
+    fn single_probe_keeps_the_rssi() {
+        let engine = LinkMetricsEngine::<1, 1>::new(NOISE_FLOOR);
+        let report = engine.single_probe_report(LQI_MARGIN_RSSI, 180, -73);
+        assert_eq!(
+            report.values,
+            LinkMetricsValues {
+                pdu_count: 1,
+                lqi: 180,
+                link_margin: 27,
+                rssi: -73,
+            }
+        );
+    }
+}
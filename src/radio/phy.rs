@@ -0,0 +1,144 @@
+//!
+//! PHY Profiles
+//!
+//! Timing derived from the PHY (symbol time, frame duration, turnaround time, CSL units) depends on the channel page
+//! the radio operates on. A `PhyProfile` describes one IEEE 802.15.4 O-QPSK PHY:
+//!     - page 0: 2.4 GHz, channels 11 to 26, 5 MHz apart from 2405 MHz
+//!     - page 2: 915 MHz, channels 1 to 10, 2 MHz apart from 906 MHz
+//!
+//! Both run at 62.5 ksymbol/s and 250 kbit/s (IEEE 802.15.4-2006, 6.5.3.2), but components doing timing math (see
+//! `SubMac`, `CslReceiver`, `CslTransmitter` and `EnhAckGenerator`) take the profile of their radio rather than the
+//! 2.4 GHz constants so that they follow the channel page in use.
+//!
+
+use super::{
+    OT_RADIO_2P4GHZ_OQPSK_CHANNEL_MAX, OT_RADIO_2P4GHZ_OQPSK_CHANNEL_MIN, OT_RADIO_915MHZ_OQPSK_CHANNEL_MAX,
+    OT_RADIO_915MHZ_OQPSK_CHANNEL_MIN, OT_RADIO_BITS_PER_OCTET, OT_RADIO_CHANNEL_PAGE_0, OT_RADIO_CHANNEL_PAGE_2,
+};
+
+// aTurnaroundTime (IEEE 802.15.4-2006) in symbols
+pub const PHY_TURNAROUND_TIME: u32 = 12;
+// aCCATime (IEEE 802.15.4-2006) in symbols
+pub const PHY_CCA_DURATION: u32 = 8;
+// Octets sent before the end of the SFD: preamble (4) and SFD (1)
+pub const PHY_SHR_SIZE: u32 = 5;
+// Size of the PHY header (in bytes)
+pub const PHY_PHR_SIZE: u32 = 1;
+
+/// Rates, timing and channels of an IEEE 802.15.4 PHY
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PhyProfile {
+    pub channel_page: u8,
+    pub channel_min: u8,
+    pub channel_max: u8,
+    // Symbol rate in symbols per second
+    pub symbol_rate: u32,
+    // Bit rate in bits per second
+    pub bit_rate: u32,
+    pub symbols_per_octet: u32,
+    // RX-to-TX or TX-to-RX turnaround time in symbols
+    pub turnaround_time: u32,
+    // CCA duration in symbols
+    pub cca_duration: u32,
+    // Center frequency of `channel_min` in kHz
+    pub first_channel_frequency: u32,
+    // Spacing between two channels in kHz
+    pub channel_spacing: u32,
+}
+
+/// 2.4 GHz O-QPSK (channel page 0)
+pub const PHY_PROFILE_PAGE_0: PhyProfile = PhyProfile {
+    channel_page: OT_RADIO_CHANNEL_PAGE_0,
+    channel_min: OT_RADIO_2P4GHZ_OQPSK_CHANNEL_MIN as u8,
+    channel_max: OT_RADIO_2P4GHZ_OQPSK_CHANNEL_MAX as u8,
+    symbol_rate: 62_500,
+    bit_rate: 250_000,
+    symbols_per_octet: 2,
+    turnaround_time: PHY_TURNAROUND_TIME,
+    cca_duration: PHY_CCA_DURATION,
+    first_channel_frequency: 2_405_000,
+    channel_spacing: 5_000,
+};
+
+/// 915 MHz O-QPSK (channel page 2)
+pub const PHY_PROFILE_PAGE_2: PhyProfile = PhyProfile {
+    channel_page: OT_RADIO_CHANNEL_PAGE_2,
+    channel_min: OT_RADIO_915MHZ_OQPSK_CHANNEL_MIN as u8,
+    channel_max: OT_RADIO_915MHZ_OQPSK_CHANNEL_MAX as u8,
+    symbol_rate: 62_500,
+    bit_rate: 250_000,
+    symbols_per_octet: 2,
+    turnaround_time: PHY_TURNAROUND_TIME,
+    cca_duration: PHY_CCA_DURATION,
+    first_channel_frequency: 906_000,
+    channel_spacing: 2_000,
+};
+
+impl PhyProfile {
+    /// Get the profile of a channel page (None if the page is not supported)
+    pub fn for_page(channel_page: u8) -> Option<&'static Self> {
+        [&PHY_PROFILE_PAGE_0, &PHY_PROFILE_PAGE_2]
+            .into_iter()
+            .find(|profile| profile.channel_page == channel_page)
+    }
+
+    /// Get the profile of a channel (None if no supported page has this channel)
+    pub fn for_channel(channel: u8) -> Option<&'static Self> {
+        [&PHY_PROFILE_PAGE_0, &PHY_PROFILE_PAGE_2]
+            .into_iter()
+            .find(|profile| profile.contains(channel))
+    }
+
+    pub fn contains(&self, channel: u8) -> bool {
+        (self.channel_min..=self.channel_max).contains(&channel)
+    }
+
+    /// Get the channels of the profile (bit n set for channel n)
+    pub fn channel_mask(&self) -> u32 {
+        (self.channel_min..=self.channel_max).fold(0, |mask, channel| mask | 1 << channel)
+    }
+
+    /// Get the center frequency of a channel in kHz (None if the channel is not in the profile)
+    pub fn channel_frequency(&self, channel: u8) -> Option<u32> {
+        if !self.contains(channel) {
+            return None;
+        }
+        Some(self.first_channel_frequency + (channel - self.channel_min) as u32 * self.channel_spacing)
+    }
+
+    /// Get the duration of a symbol in microseconds
+    pub const fn symbol_time(&self) -> u32 {
+        1_000_000 / self.symbol_rate
+    }
+
+    /// Get the duration of 10 symbols in microseconds (the unit of the CSL period and phase)
+    pub const fn ten_symbols_time(&self) -> u32 {
+        10 * self.symbol_time()
+    }
+
+    /// Get the duration of an octet in microseconds
+    pub const fn octet_time(&self) -> u32 {
+        OT_RADIO_BITS_PER_OCTET as u32 * 1_000_000 / self.bit_rate
+    }
+
+    /// Get the turnaround time in microseconds
+    pub const fn turnaround_duration(&self) -> u32 {
+        self.turnaround_time * self.symbol_time()
+    }
+
+    /// Get the CCA duration in microseconds
+    pub const fn cca_duration_us(&self) -> u32 {
+        self.cca_duration * self.symbol_time()
+    }
+
+    /// Get the time a frame is on air, from the start of the preamble to the end of the PSDU (in microseconds)
+    pub const fn frame_duration(&self, psdu_length: usize) -> u64 {
+        (PHY_SHR_SIZE + PHY_PHR_SIZE) as u64 * self.octet_time() as u64 + psdu_length as u64 * self.octet_time() as u64
+    }
+}
+
+impl Default for PhyProfile {
+    fn default() -> Self {
+        PHY_PROFILE_PAGE_0
+    }
+}
@@ -0,0 +1,208 @@
+//!
+//! Static Frame Buffer Pool
+//!
+//! `OTRadioOperation::transmit` only lends the frame to the driver, which is not enough for a driver sending it from
+//! an interrupt. A `FramePool` is a fixed set of `TransmitFrame` or `ReceiveFrame` buffers (usually in a `static`)
+//! handing out owned `PooledFrame` handles: the driver copies the frame into a handle, keeps it until `tx_done` (or
+//! until the received frame was processed) and drops it, which returns the buffer to the pool.
+//!
+//! The pool is shared through atomics so handles can be taken and dropped from interrupt handlers (the target must
+//! support atomic compare and swap on 32 bits).
+//!
+
+use core::cell::UnsafeCell;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use super::{ReceiveFrame, TransmitFrame};
+use crate::error::OTError;
+
+// Maximum number of frames in a pool (one bit of the allocation mask per frame)
+pub const FRAME_POOL_MAX_SIZE: usize = u32::BITS as usize;
+
+/// Frame buffer that can be held in a pool
+pub trait PoolFrame {
+    /// Empty the frame before handing it out
+    fn clear(&mut self);
+}
+
+impl PoolFrame for TransmitFrame {
+    fn clear(&mut self) {
+        let _ = self.set_length::<()>(0);
+    }
+}
+
+impl PoolFrame for ReceiveFrame {
+    fn clear(&mut self) {
+        let _ = self.set_length::<()>(0);
+    }
+}
+
+/// Fixed set of frame buffers
+pub struct FramePool<T, const N: usize> {
+    // Bit i is set while frame i is handed out
+    in_use: AtomicU32,
+    frames: [UnsafeCell<T>; N],
+}
+
+// A frame is only reachable through the single handle owning its bit of `in_use`
+unsafe impl<T: Send, const N: usize> Sync for FramePool<T, N> {}
+
+/// Pool of frames to transmit
+pub type TransmitFramePool<const N: usize> = FramePool<TransmitFrame, N>;
+
+/// Pool of received frames
+pub type ReceiveFramePool<const N: usize> = FramePool<ReceiveFrame, N>;
+
+impl<const N: usize> FramePool<TransmitFrame, N> {
+    /// Create a pool of N empty frames to transmit (N <= FRAME_POOL_MAX_SIZE)
+    pub const fn new() -> Self {
+        assert!(N <= FRAME_POOL_MAX_SIZE);
+        Self {
+            in_use: AtomicU32::new(0),
+            frames: [const { UnsafeCell::new(TransmitFrame::new()) }; N],
+        }
+    }
+}
+
+impl<const N: usize> Default for FramePool<TransmitFrame, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> FramePool<ReceiveFrame, N> {
+    /// Create a pool of N empty received frames (N <= FRAME_POOL_MAX_SIZE)
+    pub const fn new() -> Self {
+        assert!(N <= FRAME_POOL_MAX_SIZE);
+        Self {
+            in_use: AtomicU32::new(0),
+            frames: [const { UnsafeCell::new(ReceiveFrame::new()) }; N],
+        }
+    }
+}
+
+impl<const N: usize> Default for FramePool<ReceiveFrame, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: PoolFrame, const N: usize> FramePool<T, N> {
+    /// Take an empty frame out of the pool
+    ///
+    /// Returns:
+    ///     OTError::NoBuffers if every frame is handed out
+    pub fn allocate<E>(&self) -> Result<PooledFrame<'_, T, N>, OTError<E>> {
+        let mut in_use = self.in_use.load(Ordering::Relaxed);
+        loop {
+            let index = (!in_use).trailing_zeros() as usize;
+            if index >= N {
+                return Err(OTError::NoBuffers);
+            }
+            match self.in_use.compare_exchange_weak(
+                in_use,
+                in_use | (1 << index),
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => {
+                    let mut frame = PooledFrame { pool: self, index };
+                    frame.clear();
+                    return Ok(frame);
+                },
+                Err(current) => in_use = current,
+            }
+        }
+    }
+
+    /// Get the number of frames left in the pool
+    pub fn available(&self) -> usize {
+        N - self.in_use.load(Ordering::Relaxed).count_ones() as usize
+    }
+
+    /// Get the number of frames of the pool
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+}
+
+/// Owned handle to a frame of a pool, returning the frame to the pool when dropped
+pub struct PooledFrame<'a, T, const N: usize> {
+    pool: &'a FramePool<T, N>,
+    index: usize,
+}
+
+impl<T, const N: usize> PooledFrame<'_, T, N> {
+    /// Return the frame to the pool (same as dropping the handle)
+    pub fn release(self) {}
+}
+
+impl<T, const N: usize> Deref for PooledFrame<'_, T, N> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.pool.frames[self.index].get() }
+    }
+}
+
+impl<T, const N: usize> DerefMut for PooledFrame<'_, T, N> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.pool.frames[self.index].get() }
+    }
+}
+
+impl<T, const N: usize> Drop for PooledFrame<'_, T, N> {
+    fn drop(&mut self) {
+        self.pool.in_use.fetch_and(!(1 << self.index), Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use core::convert::Infallible;
+
+    use super::*;
+
+    static TX_POOL: TransmitFramePool<2> = TransmitFramePool::new();
+
+    #[test]
+    fn exhausted_pool_reports_no_buffers() {
+        let first = TX_POOL.allocate::<Infallible>().unwrap();
+        let second = TX_POOL.allocate::<Infallible>().unwrap();
+        assert_eq!(TX_POOL.available(), 0);
+        assert_eq!(TX_POOL.allocate::<Infallible>().err(), Some(OTError::NoBuffers));
+
+        // A dropped or released handle returns its frame to the pool
+        drop(first);
+        assert_eq!(TX_POOL.available(), 1);
+        let third = TX_POOL.allocate::<Infallible>().unwrap();
+        second.release();
+        third.release();
+        assert_eq!(TX_POOL.available(), TX_POOL.capacity());
+    }
+
+    #[test]
+    fn frames_are_handed_out_empty() {
+        let pool = ReceiveFramePool::<1>::new();
+        let mut frame = pool.allocate::<Infallible>().unwrap();
+        frame.set_length::<Infallible>(10).unwrap();
+        frame.psdu_mut().fill(0xaa);
+        drop(frame);
+
+        let frame = pool.allocate::<Infallible>().unwrap();
+        assert_eq!(frame.length(), 0);
+    }
+
+    #[test]
+    fn largest_pool() {
+        let pool = TransmitFramePool::<FRAME_POOL_MAX_SIZE>::new();
+        let frames: Vec<_> = (0..FRAME_POOL_MAX_SIZE).map(|_| pool.allocate::<Infallible>().unwrap()).collect();
+        assert_eq!(pool.available(), 0);
+        assert_eq!(pool.allocate::<Infallible>().err(), Some(OTError::NoBuffers));
+
+        drop(frames);
+        assert_eq!(pool.available(), FRAME_POOL_MAX_SIZE);
+    }
+}
@@ -0,0 +1,487 @@
+//!
+//! Transmit Power Table
+//!
+//! Radios with a power calibration (see `OTRadioOperationOptional::add_calibrated_power`) transmit with an opaque raw
+//! power setting instead of a power in dBm. `PowerTable` keeps everything needed to pick that setting for a channel:
+//!     - the regulatory limits of the current region (ISO 3166 alpha-2 code, see `OTRadioOperation::set_region`)
+//!     - the max power of each channel (see `OTRadioOperation::set_channel_max_transmit_power`)
+//!     - the target power of each channel, or the transmit power when there is none
+//!     - the calibration table mapping an actual power to a raw power setting for each channel
+//!
+//! The target power of a channel is limited by the region and channel max powers. The raw setting is the one of the
+//! highest calibrated power not above the target (or the lowest calibrated power if all are above it). With
+//! interpolation enabled, raw settings are read as little endian integers and interpolated linearly between the two
+//! calibrated powers around the target.
+//!
+//! Powers are in units of 0.01 dBm unless stated otherwise.
+//!
+
+use crate::error::OTError;
+
+// Maximum size of a raw power setting (in bytes)
+pub const RAW_POWER_SETTING_MAX_SIZE: usize = 16;
+// Number of channels in the table (bit n of a channel mask is channel n)
+pub const POWER_TABLE_CHANNELS: usize = u32::BITS as usize;
+// Maximum size of a raw power setting that can be interpolated (in bytes)
+const INTERPOLATION_MAX_SIZE: usize = 8;
+
+/// Get the region code of an ISO 3166 alpha-2 code (e.g. `region_code(b"US")`)
+pub const fn region_code(code: &[u8; 2]) -> u16 {
+    u16::from_be_bytes(*code)
+}
+
+/// Regulatory power limit of a region on some channels
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RegionLimit {
+    // Region code (see `region_code`)
+    pub region: u16,
+    // Channels the limit applies to (bit n set for channel n)
+    pub channels: u32,
+    // Maximum power in 0.01 dBm
+    pub max_power: i16,
+}
+
+/// Power and raw power setting resolved for a channel
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PowerSetting {
+    // Target power after the region and channel limits
+    pub target_power: i16,
+    // Power actually transmitted with the raw setting
+    pub actual_power: i16,
+    raw: [u8; RAW_POWER_SETTING_MAX_SIZE],
+    length: usize,
+}
+
+impl PowerSetting {
+    /// Get the raw power setting
+    pub fn raw(&self) -> &[u8] {
+        &self.raw[..self.length]
+    }
+}
+
+/// Entry of the calibration table
+#[derive(Clone, Copy, Debug)]
+struct CalibratedPower {
+    channel: u8,
+    actual_power: i16,
+    raw: [u8; RAW_POWER_SETTING_MAX_SIZE],
+    length: usize,
+}
+
+impl CalibratedPower {
+    const EMPTY: Self = Self {
+        channel: 0,
+        actual_power: 0,
+        raw: [0; RAW_POWER_SETTING_MAX_SIZE],
+        length: 0,
+    };
+
+    fn raw(&self) -> &[u8] {
+        &self.raw[..self.length]
+    }
+
+    fn setting(&self, target_power: i16) -> PowerSetting {
+        PowerSetting {
+            target_power,
+            actual_power: self.actual_power,
+            raw: self.raw,
+            length: self.length,
+        }
+    }
+}
+
+/// Power table with REGIONS regulatory limits and CALIBRATIONS calibrated powers
+pub struct PowerTable<const REGIONS: usize, const CALIBRATIONS: usize> {
+    region: u16,
+    region_limits: [RegionLimit; REGIONS],
+    region_count: usize,
+    // Power of the channels without target power (in dBm, see `OTRadioConfiguration::set_transmit_power`)
+    transmit_power: i8,
+    channel_max_powers: [Option<i16>; POWER_TABLE_CHANNELS],
+    channel_target_powers: [Option<i16>; POWER_TABLE_CHANNELS],
+    calibrations: [CalibratedPower; CALIBRATIONS],
+    calibration_count: usize,
+    interpolation: bool,
+}
+
+impl<const REGIONS: usize, const CALIBRATIONS: usize> PowerTable<REGIONS, CALIBRATIONS> {
+    /// Create an empty table (no region, no limit, 0 dBm transmit power)
+    pub const fn new() -> Self {
+        Self {
+            region: 0,
+            region_limits: [RegionLimit {
+                region: 0,
+                channels: 0,
+                max_power: 0,
+            }; REGIONS],
+            region_count: 0,
+            transmit_power: 0,
+            channel_max_powers: [None; POWER_TABLE_CHANNELS],
+            channel_target_powers: [None; POWER_TABLE_CHANNELS],
+            calibrations: [CalibratedPower::EMPTY; CALIBRATIONS],
+            calibration_count: 0,
+            interpolation: false,
+        }
+    }
+
+    /// Select the region whose limits apply (see `OTRadioOperation::set_region`)
+    pub fn set_region(&mut self, region_code: u16) {
+        self.region = region_code;
+    }
+
+    pub fn region(&self) -> u16 {
+        self.region
+    }
+
+    /// Add a regulatory limit (a region may have several limits for different channels)
+    ///
+    /// Returns:
+    ///     OTError::NoBuffers if the region limit table is full
+    pub fn add_region_limit<E>(&mut self, limit: RegionLimit) -> Result<(), OTError<E>> {
+        let entry = self.region_limits.get_mut(self.region_count).ok_or(OTError::NoBuffers)?;
+        *entry = limit;
+        self.region_count += 1;
+        Ok(())
+    }
+
+    /// Remove all regulatory limits
+    pub fn clear_region_limits(&mut self) {
+        self.region_count = 0;
+    }
+
+    /// Get the regulatory limit of a channel in the current region (None if there is none)
+    pub fn region_limit(&self, channel: u8) -> Option<i16> {
+        let mask = 1u32.checked_shl(channel as u32)?;
+        self.region_limits[..self.region_count]
+            .iter()
+            .filter(|limit| limit.region == self.region && limit.channels & mask != 0)
+            .map(|limit| limit.max_power)
+            .min()
+    }
+
+    /// Set the power of the channels without target power (in dBm, see `OTRadioConfiguration::set_transmit_power`)
+    pub fn set_transmit_power(&mut self, power: i8) {
+        self.transmit_power = power;
+    }
+
+    /// Get the power of the channels without target power (in dBm)
+    pub fn transmit_power(&self) -> i8 {
+        self.transmit_power
+    }
+
+    /// Set the max power of a channel (see `OTRadioOperation::set_channel_max_transmit_power`)
+    ///
+    /// Params:
+    ///     channel - the channel
+    ///     max_power - the max power in dBm
+    ///
+    /// Returns:
+    ///     OTError::InvalidArgs if the channel is out of the table
+    pub fn set_channel_max_transmit_power<E>(&mut self, channel: u8, max_power: i8) -> Result<(), OTError<E>> {
+        let entry = self.channel_max_powers.get_mut(channel as usize).ok_or(OTError::InvalidArgs)?;
+        *entry = Some(max_power as i16 * 100);
+        Ok(())
+    }
+
+    /// Set the target power of a channel (see `OTRadioOperationOptional::set_channel_target_power`)
+    ///
+    /// Returns:
+    ///     OTError::InvalidArgs if the channel is out of the table
+    pub fn set_channel_target_power<E>(&mut self, channel: u8, target_power: i16) -> Result<(), OTError<E>> {
+        let entry = self.channel_target_powers.get_mut(channel as usize).ok_or(OTError::InvalidArgs)?;
+        *entry = Some(target_power);
+        Ok(())
+    }
+
+    /// Add a calibrated power (see `OTRadioOperationOptional::add_calibrated_power`)
+    ///
+    /// Params:
+    ///     channel - the channel
+    ///     actual_power - the power measured with the raw power setting
+    ///     raw_power_setting - the raw power setting
+    ///
+    /// Returns:
+    ///     OTError::InvalidArgs if the channel is out of the table or the raw power setting is empty or too long
+    ///     OTError::Already if the power is already calibrated on the channel
+    ///     OTError::NoBuffers if the calibration table is full
+    pub fn add_calibrated_power<E>(
+        &mut self,
+        channel: u8,
+        actual_power: i16,
+        raw_power_setting: &[u8],
+    ) -> Result<(), OTError<E>> {
+        if channel as usize >= POWER_TABLE_CHANNELS
+            || raw_power_setting.is_empty()
+            || raw_power_setting.len() > RAW_POWER_SETTING_MAX_SIZE
+        {
+            return Err(OTError::InvalidArgs);
+        }
+        if self.calibrations().any(|entry| entry.channel == channel && entry.actual_power == actual_power) {
+            return Err(OTError::Already);
+        }
+
+        let entry = self.calibrations.get_mut(self.calibration_count).ok_or(OTError::NoBuffers)?;
+        *entry = CalibratedPower {
+            channel,
+            actual_power,
+            raw: [0; RAW_POWER_SETTING_MAX_SIZE],
+            length: raw_power_setting.len(),
+        };
+        entry.raw[..raw_power_setting.len()].copy_from_slice(raw_power_setting);
+        self.calibration_count += 1;
+        Ok(())
+    }
+
+    /// Remove all calibrated powers (see `OTRadioOperationOptional::clear_calibrated_powers`)
+    pub fn clear_calibrated_powers(&mut self) {
+        self.calibration_count = 0;
+    }
+
+    /// Enable/Disable the interpolation of raw power settings between calibrated powers
+    pub fn set_interpolation(&mut self, enabled: bool) {
+        self.interpolation = enabled;
+    }
+
+    fn calibrations(&self) -> impl Iterator<Item = &CalibratedPower> {
+        self.calibrations[..self.calibration_count].iter()
+    }
+
+    /// Get the target power of a channel, limited by the region and the channel max power
+    pub fn target_power(&self, channel: u8) -> i16 {
+        let index = channel as usize;
+        let target = self
+            .channel_target_powers
+            .get(index)
+            .copied()
+            .flatten()
+            .unwrap_or(self.transmit_power as i16 * 100);
+        let channel_max = self.channel_max_powers.get(index).copied().flatten().unwrap_or(i16::MAX);
+        let region_max = self.region_limit(channel).unwrap_or(i16::MAX);
+        target.min(channel_max).min(region_max)
+    }
+
+    /// Resolve the power setting of a channel
+    ///
+    /// Returns:
+    ///     OTError::NotFound if no power is calibrated on the channel
+    pub fn power_setting<E>(&self, channel: u8) -> Result<PowerSetting, OTError<E>> {
+        let target_power = self.target_power(channel);
+        let mut lower: Option<&CalibratedPower> = None;
+        let mut upper: Option<&CalibratedPower> = None;
+        for entry in self.calibrations().filter(|entry| entry.channel == channel) {
+            if entry.actual_power <= target_power {
+                if lower.is_none_or(|lower| entry.actual_power > lower.actual_power) {
+                    lower = Some(entry);
+                }
+            } else if upper.is_none_or(|upper| entry.actual_power < upper.actual_power) {
+                upper = Some(entry);
+            }
+        }
+
+        match (lower, upper) {
+            (Some(lower), Some(upper)) if self.interpolation && lower.actual_power != target_power => {
+                Ok(Self::interpolate(lower, upper, target_power).unwrap_or_else(|| lower.setting(target_power)))
+            },
+            (Some(lower), _) => Ok(lower.setting(target_power)),
+            (None, Some(upper)) => Ok(upper.setting(target_power)),
+            (None, None) => Err(OTError::NotFound),
+        }
+    }
+
+    /// Interpolate the raw power settings of two calibrated powers around the target power
+    ///
+    /// Returns:
+    ///     None if the raw power settings do not have the same size or cannot be read as integers
+    fn interpolate(lower: &CalibratedPower, upper: &CalibratedPower, target_power: i16) -> Option<PowerSetting> {
+        let length = lower.length;
+        if upper.length != length || length > INTERPOLATION_MAX_SIZE {
+            return None;
+        }
+
+        let read = |raw: &[u8]| {
+            let mut bytes = [0u8; INTERPOLATION_MAX_SIZE];
+            bytes[..raw.len()].copy_from_slice(raw);
+            u64::from_le_bytes(bytes) as i128
+        };
+        let (low, high) = (read(lower.raw()), read(upper.raw()));
+        let span = (upper.actual_power - lower.actual_power) as i128;
+        let value = low + (high - low) * (target_power - lower.actual_power) as i128 / span;
+
+        let mut raw = [0; RAW_POWER_SETTING_MAX_SIZE];
+        raw[..length].copy_from_slice(&(value as u64).to_le_bytes()[..length]);
+        Some(PowerSetting {
+            target_power,
+            actual_power: target_power,
+            raw,
+            length,
+        })
+    }
+
+    /// Write the raw power setting of a channel (for `OTRadioOperationHandles::get_raw_power_setting`)
+    ///
+    /// Returns:
+    ///     The size of the raw power setting
+    ///     OTError::NotFound if no power is calibrated on the channel
+    ///     OTError::NoBuffers if the buffer is too small
+    pub fn raw_power_setting<E>(&self, channel: u8, buffer: &mut [u8]) -> Result<usize, OTError<E>> {
+        let setting = self.power_setting(channel)?;
+        let raw = setting.raw();
+        buffer.get_mut(..raw.len()).ok_or(OTError::NoBuffers)?.copy_from_slice(raw);
+        Ok(raw.len())
+    }
+}
+
+impl<const REGIONS: usize, const CALIBRATIONS: usize> Default for PowerTable<REGIONS, CALIBRATIONS> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use super::*;
+
+    const US: u16 = region_code(b"US");
+    const EU: u16 = region_code(b"EU");
+
+    // (channel, actual power, raw power setting)
+    type Calibration<'a> = (u8, i16, &'a [u8]);
+
+    /// Table with the given calibrated powers
+    fn calibrated(calibrations: &[Calibration]) -> PowerTable<0, 8> {
+        let mut table = PowerTable::new();
+        for &(channel, actual_power, raw) in calibrations {
+            table.add_calibrated_power::<Infallible>(channel, actual_power, raw).unwrap();
+        }
+        table
+    }
+
+    #[test]
+    fn target_power_is_limited_by_region_and_channel() {
+        let mut table = PowerTable::<3, 0>::new();
+        table.add_region_limit::<Infallible>(RegionLimit { region: US, channels: 0b11 << 11, max_power: 800 }).unwrap();
+        table.add_region_limit::<Infallible>(RegionLimit { region: US, channels: 1 << 11, max_power: 500 }).unwrap();
+        table.add_region_limit::<Infallible>(RegionLimit { region: EU, channels: 1 << 11, max_power: 300 }).unwrap();
+        assert_eq!(table.add_region_limit::<Infallible>(RegionLimit::default()), Err(OTError::NoBuffers));
+
+        table.set_transmit_power(10);
+        table.set_channel_max_transmit_power::<Infallible>(12, 6).unwrap();
+        table.set_channel_max_transmit_power::<Infallible>(13, -10).unwrap();
+        table.set_channel_target_power::<Infallible>(14, 200).unwrap();
+        assert_eq!(table.set_channel_max_transmit_power::<Infallible>(32, 0), Err(OTError::InvalidArgs));
+        assert_eq!(table.set_channel_target_power::<Infallible>(32, 0), Err(OTError::InvalidArgs));
+
+        // (name, region, channel, target power)
+        let cases = [
+            ("no region, transmit power", 0, 11, 1000),
+            ("lowest region limit", US, 11, 500),
+            ("channel max below the region limit", US, 12, 600),
+            ("other region", EU, 11, 300),
+            ("no limit in the region", EU, 12, 600),
+            ("negative channel max", 0, 13, -1000),
+            ("channel target power", US, 14, 200),
+            ("channel out of the table", US, 40, 1000),
+        ];
+
+        for (name, region, channel, expected) in cases {
+            table.set_region(region);
+            assert_eq!(table.target_power(channel), expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn calibrated_power_below_the_target_is_selected() {
+        let mut table = calibrated(&[(11, 1000, &[0x20]), (11, 0, &[0x10]), (11, 2000, &[0x30])]);
+
+        // (name, target power, actual power, raw power setting)
+        let cases = [
+            ("exact", 1000, 1000, [0x20]),
+            ("between", 1500, 1000, [0x20]),
+            ("above all", 2500, 2000, [0x30]),
+            ("below all, lowest", -500, 0, [0x10]),
+        ];
+
+        for (name, target_power, actual_power, raw) in cases {
+            table.set_channel_target_power::<Infallible>(11, target_power).unwrap();
+            let setting = table.power_setting::<Infallible>(11).unwrap();
+            assert_eq!(setting.target_power, target_power, "{}", name);
+            assert_eq!(setting.actual_power, actual_power, "{}", name);
+            assert_eq!(setting.raw(), raw, "{}", name);
+        }
+
+        assert_eq!(table.power_setting::<Infallible>(12), Err(OTError::NotFound));
+    }
+
+    #[test]
+    fn calibrated_powers_are_checked() {
+        let mut table = calibrated(&[(11, 0, &[0x10])]);
+
+        // (name, calibrated power, error)
+        let cases: [(&str, Calibration, OTError<Infallible>); 4] = [
+            ("already calibrated", (11, 0, &[0x11]), OTError::Already),
+            ("channel out of the table", (32, 0, &[0x10]), OTError::InvalidArgs),
+            ("empty setting", (11, 100, &[]), OTError::InvalidArgs),
+            ("setting too long", (11, 100, &[0; RAW_POWER_SETTING_MAX_SIZE + 1]), OTError::InvalidArgs),
+        ];
+        for (name, (channel, actual_power, raw), error) in cases {
+            assert_eq!(table.add_calibrated_power(channel, actual_power, raw), Err(error), "{}", name);
+        }
+
+        for actual_power in 1..8 {
+            table.add_calibrated_power::<Infallible>(11, actual_power, &[0x10]).unwrap();
+        }
+        assert_eq!(table.add_calibrated_power::<Infallible>(11, 8, &[0x10]), Err(OTError::NoBuffers));
+
+        table.clear_calibrated_powers();
+        assert_eq!(table.power_setting::<Infallible>(11), Err(OTError::NotFound));
+    }
+
+    #[test]
+    fn raw_settings_are_interpolated() {
+        let mut table = calibrated(&[
+            (11, 0, &[0x00, 0x01]),
+            (11, 1000, &[0x00, 0x02]),
+            (12, 0, &[0x10]),
+            (12, 1000, &[0x20, 0x00]),
+            (13, 0, &[0x10; INTERPOLATION_MAX_SIZE + 1]),
+            (13, 1000, &[0x20; INTERPOLATION_MAX_SIZE + 1]),
+        ]);
+        table.set_interpolation(true);
+
+        // (name, channel, target power, actual power, raw power setting)
+        let cases: [(&str, u8, i16, i16, &[u8]); 5] = [
+            ("halfway", 11, 500, 500, &[0x80, 0x01]),
+            ("quarter", 11, 250, 250, &[0x40, 0x01]),
+            ("calibrated power", 11, 0, 0, &[0x00, 0x01]),
+            ("mismatched lengths, lower", 12, 500, 0, &[0x10]),
+            ("too long, lower", 13, 500, 0, &[0x10; INTERPOLATION_MAX_SIZE + 1]),
+        ];
+
+        for (name, channel, target_power, actual_power, raw) in cases {
+            table.set_channel_target_power::<Infallible>(channel, target_power).unwrap();
+            let setting = table.power_setting::<Infallible>(channel).unwrap();
+            assert_eq!(setting.actual_power, actual_power, "{}", name);
+            assert_eq!(setting.raw(), raw, "{}", name);
+        }
+
+        // Without interpolation the lower calibrated power is used
+        table.set_interpolation(false);
+        table.set_channel_target_power::<Infallible>(11, 500).unwrap();
+        assert_eq!(table.power_setting::<Infallible>(11).unwrap().raw(), [0x00, 0x01]);
+    }
+
+    #[test]
+    fn raw_power_setting_needs_a_large_enough_buffer() {
+        let table = calibrated(&[(11, 0, &[0x01, 0x02, 0x03])]);
+
+        let mut buffer = [0; 2];
+        assert_eq!(table.raw_power_setting::<Infallible>(11, &mut buffer), Err(OTError::NoBuffers));
+
+        let mut buffer = [0; 4];
+        assert_eq!(table.raw_power_setting::<Infallible>(11, &mut buffer), Ok(3));
+        assert_eq!(buffer, [0x01, 0x02, 0x03, 0x00]);
+        assert_eq!(table.raw_power_setting::<Infallible>(12, &mut buffer), Err(OTError::NotFound));
+    }
+}
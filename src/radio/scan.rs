@@ -0,0 +1,653 @@
+//!
+//! Energy Scan Service
+//!
+//! `OTRadioOperationEnergyScan::energy_scan` measures a single channel. `EnergyScanner` walks a channel mask (the
+//! supported or preferred channels of the radio, or any subset of them) one channel at a time and ranks the channels by
+//! their maximum RSSI, quietest first, e.g. to pick the channel of a new network.
+//!
+//! The scanner is driven from the platform main loop:
+//!     - `start` begins the scan of the first channel
+//!     - `process` must be called while `is_scanning`, it moves on to the next channel once the current one is done
+//!     - the radio reports each channel through the `OTRadioOperationEnergyScanHandles` of the scanner
+//!
+//! Radios that do not advertise `Capabilities::EnergyScan` are put in receive mode on each channel instead and their
+//! RSSI is sampled on every `process` call for the duration of the channel.
+//!
+//! A channel without a valid measurement (`OT_RADIO_RSSI_INVALID`) is left out of the report.
+//!
+
+use core::marker::PhantomData;
+
+use super::{
+    Capabilities, OTRadioCapabilities, OTRadioConfiguration, OTRadioOperation, OTRadioOperationEnergyScan,
+    OTRadioOperationEnergyScanHandles, OT_RADIO_RSSI_INVALID,
+};
+use crate::error::OTError;
+
+// Number of channels a channel mask can hold
+pub const SCAN_MAX_CHANNELS: usize = u32::BITS as usize;
+
+/// Channels to scan
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScanChannels {
+    // Every channel of `OTRadioOperation::get_supported_channel_mask`
+    Supported,
+    // Every channel of `OTRadioOperation::get_preferred_channel_mask`
+    Preferred,
+    // The given channels (bit n set to scan channel n), restricted to the supported ones
+    Mask(u32),
+}
+
+/// Maximum RSSI measured on a channel
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ChannelEnergy {
+    pub channel: u8,
+    // Maximum RSSI in dBm
+    pub max_rssi: i8,
+    // Whether the channel is in the preferred channel mask of the radio
+    pub preferred: bool,
+}
+
+/// Channels of an energy scan ranked from the quietest to the noisiest
+///
+/// Channels with the same energy are ranked preferred first, then by channel number.
+#[derive(Clone, Copy, Debug)]
+pub struct EnergyScanReport {
+    channels: [ChannelEnergy; SCAN_MAX_CHANNELS],
+    count: usize,
+}
+
+impl EnergyScanReport {
+    pub const fn new() -> Self {
+        Self {
+            channels: [ChannelEnergy {
+                channel: 0,
+                max_rssi: 0,
+                preferred: false,
+            }; SCAN_MAX_CHANNELS],
+            count: 0,
+        }
+    }
+
+    /// Get the ranked channels
+    pub fn channels(&self) -> &[ChannelEnergy] {
+        &self.channels[..self.count]
+    }
+
+    /// Get the quietest channel
+    pub fn best(&self) -> Option<&ChannelEnergy> {
+        self.channels().first()
+    }
+
+    /// Get the quietest preferred channel
+    pub fn best_preferred(&self) -> Option<&ChannelEnergy> {
+        self.channels().iter().find(|result| result.preferred)
+    }
+
+    /// Get the result of a channel
+    pub fn channel(&self, channel: u8) -> Option<&ChannelEnergy> {
+        self.channels().iter().find(|result| result.channel == channel)
+    }
+
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    fn clear(&mut self) {
+        self.count = 0;
+    }
+
+    /// Insert a result at its rank
+    fn insert(&mut self, result: ChannelEnergy) {
+        if self.count == SCAN_MAX_CHANNELS {
+            return;
+        }
+        let key = |entry: &ChannelEnergy| (entry.max_rssi, !entry.preferred, entry.channel);
+        let index = self.channels().partition_point(|entry| key(entry) < key(&result));
+        self.channels.copy_within(index..self.count, index + 1);
+        self.channels[index] = result;
+        self.count += 1;
+    }
+}
+
+impl Default for EnergyScanReport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Scan of the current channel
+#[derive(Clone, Copy, Debug)]
+struct ChannelScan {
+    channel: u8,
+    // End of the measurement (in microseconds, local radio clock)
+    end: u64,
+    // Maximum valid RSSI sampled so far (get_rssi fallback only)
+    max_rssi: Option<i8>,
+}
+
+/// Energy scan of a channel mask
+pub struct EnergyScanner<E> {
+    // Channels left to scan
+    remaining: u32,
+    preferred: u32,
+    // Time spent on each channel (in milliseconds)
+    duration: u16,
+    // Whether the radio performs the energy scans itself
+    energy_scan: bool,
+    current: Option<ChannelScan>,
+    // Result reported through `OTRadioOperationEnergyScanHandles`
+    scan_done: Option<i8>,
+    report: EnergyScanReport,
+    _error: PhantomData<E>,
+}
+
+impl<E> EnergyScanner<E> {
+    pub const fn new() -> Self {
+        Self {
+            remaining: 0,
+            preferred: 0,
+            duration: 0,
+            energy_scan: false,
+            current: None,
+            scan_done: None,
+            report: EnergyScanReport::new(),
+            _error: PhantomData,
+        }
+    }
+
+    pub fn is_scanning(&self) -> bool {
+        self.current.is_some()
+    }
+
+    /// Get the report of the last scan (partial while scanning)
+    pub fn report(&self) -> &EnergyScanReport {
+        &self.report
+    }
+
+    /// Start scanning
+    ///
+    /// Params:
+    ///     channels - the channels to scan
+    ///     duration - the time spent on each channel (in milliseconds)
+    ///
+    /// Returns:
+    ///     OTError::Busy if a scan is in progress
+    ///     OTError::InvalidArgs if there is no supported channel to scan
+    pub fn start<R>(&mut self, radio: &mut R, channels: ScanChannels, duration: u16) -> Result<(), OTError<E>>
+    where
+        R: OTRadioOperation<Error = E> + OTRadioConfiguration<Error = E> + OTRadioOperationEnergyScan<Error = E>,
+    {
+        if self.is_scanning() {
+            return Err(OTError::Busy);
+        }
+
+        let supported = radio.get_supported_channel_mask().map_err(OTError::Platform)?;
+        let preferred = radio.get_preferred_channel_mask().map_err(OTError::Platform)?;
+        let mask = match channels {
+            ScanChannels::Supported => supported,
+            ScanChannels::Preferred => preferred & supported,
+            ScanChannels::Mask(mask) => mask & supported,
+        };
+        if mask == 0 {
+            return Err(OTError::InvalidArgs);
+        }
+
+        let capabilities = radio.radio_capabilities().map_err(OTError::Platform)?;
+        self.energy_scan = capabilities & Capabilities::EnergyScan as OTRadioCapabilities != 0;
+        self.remaining = mask;
+        self.preferred = preferred;
+        self.duration = duration;
+        self.scan_done = None;
+        self.report.clear();
+        self.next_channel(radio)
+    }
+
+    /// Stop the scan in progress, keeping the channels measured so far
+    pub fn abort(&mut self) {
+        self.remaining = 0;
+        self.current = None;
+    }
+
+    /// Collect the measurement of the current channel and move on to the next one once it is done
+    ///
+    /// Returns:
+    ///     True once every channel was scanned (see `report`)
+    pub fn process<R>(&mut self, radio: &mut R) -> Result<bool, OTError<E>>
+    where
+        R: OTRadioOperation<Error = E> + OTRadioConfiguration<Error = E> + OTRadioOperationEnergyScan<Error = E>,
+    {
+        let Some(mut scan) = self.current else {
+            return Ok(true);
+        };
+
+        let now = radio.get_now();
+        let max_rssi = if self.energy_scan {
+            match self.scan_done.take() {
+                Some(max_rssi) => Some(max_rssi),
+                // The result was not reported through the handles, so ask the radio once the channel is over
+                None if now >= scan.end => Some(radio.energy_scan_done().map_err(OTError::Platform)?),
+                None => return Ok(false),
+            }
+        } else {
+            let rssi = radio.get_rssi().map_err(OTError::Platform)?;
+            if rssi != OT_RADIO_RSSI_INVALID as i8 {
+                scan.max_rssi = Some(scan.max_rssi.map_or(rssi, |max_rssi| max_rssi.max(rssi)));
+            }
+            self.current = Some(scan);
+            if now < scan.end {
+                return Ok(false);
+            }
+            scan.max_rssi
+        };
+
+        if let Some(max_rssi) = max_rssi.filter(|max_rssi| *max_rssi != OT_RADIO_RSSI_INVALID as i8) {
+            self.report.insert(ChannelEnergy {
+                channel: scan.channel,
+                max_rssi,
+                preferred: self.preferred & (1 << scan.channel) != 0,
+            });
+        }
+        self.next_channel(radio)?;
+        Ok(!self.is_scanning())
+    }
+
+    /// Start measuring the next channel of the mask
+    fn next_channel<R>(&mut self, radio: &mut R) -> Result<(), OTError<E>>
+    where
+        R: OTRadioOperation<Error = E> + OTRadioConfiguration<Error = E> + OTRadioOperationEnergyScan<Error = E>,
+    {
+        if self.remaining == 0 {
+            self.current = None;
+            return Ok(());
+        }
+
+        let channel = self.remaining.trailing_zeros() as u8;
+        self.remaining &= !(1 << channel);
+        self.current = Some(ChannelScan {
+            channel,
+            end: radio.get_now() + self.duration as u64 * 1000,
+            max_rssi: None,
+        });
+
+        let result = if self.energy_scan {
+            radio.energy_scan(channel, self.duration.min(i16::MAX as u16) as i16)
+        } else {
+            radio.receive(channel)
+        };
+        if result.is_err() {
+            self.abort();
+        }
+        result
+    }
+}
+
+impl<E> Default for EnergyScanner<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E> OTRadioOperationEnergyScanHandles for EnergyScanner<E> {
+    type Error = E;
+
+    fn energy_scan_done(&mut self, max_rssi: i8) -> Result<(), Self::Error> {
+        self.scan_done = Some(max_rssi);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::radio::{OTExtAddress, OTLinkMetrics, OTPanId, OTRadioFrame, OTShortAddress};
+
+    // Time spent on each channel (in milliseconds)
+    const DURATION: u16 = 2;
+    const INVALID: i8 = OT_RADIO_RSSI_INVALID as i8;
+
+    // (channel, max RSSI, preferred)
+    type Channel = (u8, i8, bool);
+
+    /// Radio with a settable clock and a fixed RSSI on each channel
+    struct MockRadio {
+        now: u64,
+        capabilities: OTRadioCapabilities,
+        supported: u32,
+        preferred: u32,
+        rssi: [i8; SCAN_MAX_CHANNELS],
+        channel: u8,
+        // Channels of each `energy_scan` call
+        scans: Vec<u8>,
+    }
+
+    impl MockRadio {
+        fn new(capabilities: OTRadioCapabilities, channels: &[Channel]) -> Self {
+            let mut radio = Self {
+                now: 0,
+                capabilities,
+                supported: 0,
+                preferred: 0,
+                rssi: [INVALID; SCAN_MAX_CHANNELS],
+                channel: 0,
+                scans: Vec::new(),
+            };
+            for &(channel, rssi, preferred) in channels {
+                radio.supported |= 1 << channel;
+                radio.preferred |= (preferred as u32) << channel;
+                radio.rssi[channel as usize] = rssi;
+            }
+            radio
+        }
+    }
+
+    impl OTRadioConfiguration for MockRadio {
+        type Error = Infallible;
+
+        fn radio_capabilities(&mut self) -> Result<OTRadioCapabilities, Self::Error> {
+            Ok(self.capabilities)
+        }
+
+        fn radio_receive_sensitivity(&mut self) -> Result<u8, Self::Error> {
+            Ok(0)
+        }
+
+        fn radio_ieee_eui_64(&mut self) -> Result<[u8; 8], Self::Error> {
+            Ok([0; 8])
+        }
+
+        fn set_pan_id(&mut self, _pan_id: OTPanId) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn set_extended_address(&mut self, _address: OTExtAddress) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn set_short_address(&mut self, _address: OTShortAddress) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn get_transmit_power(&mut self) -> Result<i8, OTError<Self::Error>> {
+            Ok(0)
+        }
+
+        fn set_transmit_power(&mut self, _power: i8) -> Result<(), OTError<Self::Error>> {
+            Ok(())
+        }
+
+        fn get_cca_energy_detect_threshold(&mut self) -> Result<i8, OTError<Self::Error>> {
+            Ok(0)
+        }
+
+        fn set_cca_energy_detect_threshold(&mut self, _threshold: i8) -> Result<(), OTError<Self::Error>> {
+            Ok(())
+        }
+
+        fn get_fem_lna_gain(&mut self) -> Result<i8, OTError<Self::Error>> {
+            Ok(0)
+        }
+
+        fn set_fem_lna_gain(&mut self, _gain: i8) -> Result<(), OTError<Self::Error>> {
+            Ok(())
+        }
+
+        fn get_promiscuous(&mut self) -> Result<bool, Self::Error> {
+            Ok(false)
+        }
+
+        fn set_promiscuous(&mut self, _enabled: bool) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn set_rx_on_when_idle(&mut self, _enabled: bool) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn get_now(&mut self) -> u64 {
+            self.now
+        }
+
+        fn get_bus_speed(&mut self) -> u32 {
+            0
+        }
+    }
+
+    impl OTRadioOperation for MockRadio {
+        type Error = Infallible;
+
+        fn enable(&mut self) -> Result<(), OTError<Self::Error>> {
+            Ok(())
+        }
+
+        fn disable(&mut self) -> Result<(), OTError<Self::Error>> {
+            Ok(())
+        }
+
+        fn is_enabled(&mut self) -> Result<bool, Self::Error> {
+            Ok(true)
+        }
+
+        fn sleep(&mut self) -> Result<(), OTError<Self::Error>> {
+            Ok(())
+        }
+
+        fn receive(&mut self, channel: u8) -> Result<(), OTError<Self::Error>> {
+            self.channel = channel;
+            Ok(())
+        }
+
+        fn receive_at(&mut self, _channel: u8, _start: u32, _duration: u32) -> Result<(), OTError<Self::Error>> {
+            Ok(())
+        }
+
+        fn receive_frame(&mut self) -> Result<OTRadioFrame<'_>, OTError<Self::Error>> {
+            Err(OTError::NoFrameReceived)
+        }
+
+        fn transmit(&mut self, _frame: OTRadioFrame) -> Result<(), OTError<Self::Error>> {
+            Err(OTError::NotImplemented)
+        }
+
+        fn tx_started(&mut self) {}
+
+        fn tx_done(&mut self) {}
+
+        fn diag_tx_done(&mut self) {}
+
+        fn get_rssi(&mut self) -> Result<i8, Self::Error> {
+            Ok(self.rssi[self.channel as usize])
+        }
+
+        fn enable_src_match(&mut self, _enabled: bool) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn add_src_match_short_entry(&mut self, _address: OTShortAddress) -> Result<(), OTError<Self::Error>> {
+            Ok(())
+        }
+
+        fn add_src_match_ext_entry(&mut self, _address: OTExtAddress) -> Result<(), OTError<Self::Error>> {
+            Ok(())
+        }
+
+        fn clear_src_match_short_entry(&mut self, _address: OTShortAddress) -> Result<(), OTError<Self::Error>> {
+            Ok(())
+        }
+
+        fn clear_src_match_ext_entry(&mut self, _address: OTExtAddress) -> Result<(), OTError<Self::Error>> {
+            Ok(())
+        }
+
+        fn clear_src_match_short_entries(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn clear_src_match_ext_entries(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn get_supported_channel_mask(&mut self) -> Result<u32, Self::Error> {
+            Ok(self.supported)
+        }
+
+        fn get_preferred_channel_mask(&mut self) -> Result<u32, Self::Error> {
+            Ok(self.preferred)
+        }
+
+        fn set_channel_max_transmit_power(&mut self, _channel: u8, _max_power: i8) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn set_region(&mut self, _region_code: u16) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn get_region(&mut self) -> Result<u16, Self::Error> {
+            Ok(0)
+        }
+
+        fn configure_enh_ack_probing(
+            &mut self,
+            _link_metrics: OTLinkMetrics,
+            _short_address: OTShortAddress,
+            _ext_address: OTExtAddress,
+        ) -> Result<(), OTError<Self::Error>> {
+            Err(OTError::NotImplemented)
+        }
+    }
+
+    impl OTRadioOperationEnergyScan for MockRadio {
+        type Error = Infallible;
+
+        fn energy_scan(&mut self, channel: u8, _duration: i16) -> Result<(), OTError<Self::Error>> {
+            self.channel = channel;
+            self.scans.push(channel);
+            Ok(())
+        }
+
+        fn energy_scan_done(&mut self) -> Result<i8, Self::Error> {
+            Ok(self.rssi[self.channel as usize])
+        }
+    }
+
+    /// Process the scan until it is done, advancing the clock by 1 ms between calls
+    fn run(scanner: &mut EnergyScanner<Infallible>, radio: &mut MockRadio) {
+        while !scanner.process(radio).unwrap() {
+            radio.now += 1000;
+        }
+    }
+
+    fn ranked_channels(report: &EnergyScanReport) -> Vec<u8> {
+        report.channels().iter().map(|result| result.channel).collect()
+    }
+
+    #[test]
+    fn channels_are_ranked_quietest_first() {
+        // (name, channels in scan order, ranked channels)
+        let cases: [(&str, &[Channel], &[u8]); 4] = [
+            ("by energy", &[(11, -60, false), (12, -90, false), (13, -75, false)], &[12, 13, 11]),
+            ("preferred first", &[(11, -80, false), (12, -80, true), (13, -90, false)], &[13, 12, 11]),
+            ("then by channel", &[(15, -80, false), (11, -80, false), (13, -80, false)], &[11, 13, 15]),
+            ("mixed", &[(26, -70, true), (20, -70, false), (25, -95, false), (11, -70, true)], &[25, 11, 26, 20]),
+        ];
+
+        for (name, channels, expected) in cases {
+            let mut report = EnergyScanReport::new();
+            for &(channel, max_rssi, preferred) in channels {
+                report.insert(ChannelEnergy {
+                    channel,
+                    max_rssi,
+                    preferred,
+                });
+            }
+            assert_eq!(ranked_channels(&report), expected, "{}", name);
+            assert_eq!(report.best().map(|result| result.channel), expected.first().copied(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn energy_scan_ranks_the_supported_channels() {
+        let channels = [(11, -80, false), (12, -90, true), (13, -90, false), (14, -60, true)];
+        let mut radio = MockRadio::new(Capabilities::EnergyScan as OTRadioCapabilities, &channels);
+        let mut scanner = EnergyScanner::new();
+
+        scanner.start(&mut radio, ScanChannels::Supported, DURATION).unwrap();
+        assert_eq!(scanner.start(&mut radio, ScanChannels::Supported, DURATION), Err(OTError::Busy));
+
+        // The first channel is reported through the handles, the others are read once their duration is over
+        OTRadioOperationEnergyScanHandles::energy_scan_done(&mut scanner, -80).unwrap();
+        assert!(!scanner.process(&mut radio).unwrap());
+        run(&mut scanner, &mut radio);
+
+        assert_eq!(radio.scans, [11, 12, 13, 14]);
+        let report = scanner.report();
+        assert_eq!(ranked_channels(report), [12, 13, 11, 14]);
+        assert_eq!(report.best_preferred().map(|result| result.channel), Some(12));
+        assert_eq!(report.channel(14).map(|result| result.max_rssi), Some(-60));
+
+        // Only the preferred channels
+        scanner.start(&mut radio, ScanChannels::Preferred, DURATION).unwrap();
+        run(&mut scanner, &mut radio);
+        assert_eq!(ranked_channels(scanner.report()), [12, 14]);
+    }
+
+    #[test]
+    fn channels_without_a_valid_measurement_are_skipped() {
+        let channels = [(11, -80, false), (12, INVALID, false), (13, -70, false)];
+
+        // (name, radio capabilities)
+        let cases = [
+            ("energy scan", Capabilities::EnergyScan as OTRadioCapabilities),
+            ("rssi sampling", 0),
+        ];
+
+        for (name, capabilities) in cases {
+            let mut radio = MockRadio::new(capabilities, &channels);
+            let mut scanner = EnergyScanner::new();
+            scanner.start(&mut radio, ScanChannels::Supported, DURATION).unwrap();
+            run(&mut scanner, &mut radio);
+            assert_eq!(ranked_channels(scanner.report()), [11, 13], "{}", name);
+        }
+    }
+
+    #[test]
+    fn rssi_sampling_keeps_the_maximum() {
+        let mut radio = MockRadio::new(0, &[(11, -80, false)]);
+        let mut scanner = EnergyScanner::new();
+        scanner.start(&mut radio, ScanChannels::Supported, DURATION).unwrap();
+
+        // (RSSI sampled on the channel) an invalid sample does not count
+        for rssi in [-80, -65, INVALID, -75] {
+            radio.rssi[11] = rssi;
+            assert!(!scanner.process(&mut radio).unwrap());
+            radio.now += 500;
+        }
+        radio.now = 2000;
+        radio.rssi[11] = -90;
+        assert!(scanner.process(&mut radio).unwrap());
+        assert_eq!(scanner.report().channel(11).map(|result| result.max_rssi), Some(-65));
+        assert!(radio.scans.is_empty());
+    }
+
+    #[test]
+    fn start_needs_a_supported_channel() {
+        let mut radio = MockRadio::new(Capabilities::EnergyScan as OTRadioCapabilities, &[(11, -80, false)]);
+        let mut scanner = EnergyScanner::new();
+
+        // (name, channels)
+        let cases = [("no preferred channel", ScanChannels::Preferred), ("unsupported", ScanChannels::Mask(1 << 12))];
+        for (name, channels) in cases {
+            assert_eq!(scanner.start(&mut radio, channels, DURATION), Err(OTError::InvalidArgs), "{}", name);
+            assert!(!scanner.is_scanning(), "{}", name);
+        }
+    }
+}
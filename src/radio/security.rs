@@ -0,0 +1,593 @@
+//!
+//! Software IEEE 802.15.4 MAC Frame Security (AES-CCM*)
+//!
+//! Radios that do not advertise `Capabilities::TransmitSec` leave frame security to the host. This module provides a
+//! portable AES-CCM* implementation of the IEEE 802.15.4 security procedures so that such radios can still secure
+//! outgoing frames and unsecure incoming ones.
+//!
+
+use core::convert::Infallible;
+
+use super::frame::{Frame, FRAME_COUNTER_SIZE, SECURITY_CONTROL_SIZE};
+use super::{OTExtAddress, OTKeyType, OTMacKey, OTMacKeyMaterial, OTRadioConfigurationCapTransmit, OT_EXT_ADDRESS_SIZE};
+use crate::crypto::aes::{Aes128, AesBlock, AES_BLOCK_SIZE};
+use crate::error::OTError;
+
+// Size of the IEEE 802.15.4 CCM* nonce (in bytes)
+pub const NONCE_SIZE: usize = 13;
+// Size of the CCM* length field (15 - NONCE_SIZE)
+const LENGTH_FIELD_SIZE: usize = AES_BLOCK_SIZE - 1 - NONCE_SIZE;
+// Security levels at or above this value encrypt the payload
+const SECURITY_LEVEL_ENC: u8 = 4;
+// Key identifier mode using the key index as an index into the key sequence
+pub const KEY_ID_MODE_1: u8 = 1;
+
+/// Build the IEEE 802.15.4 CCM* nonce
+///
+/// Params:
+///     ext_address - extended address of the frame originator (OpenThread byte order)
+///     frame_counter - the frame counter of the frame
+///     security_level - the security level of the frame
+pub fn nonce(ext_address: &OTExtAddress, frame_counter: u32, security_level: u8) -> [u8; NONCE_SIZE] {
+    let mut nonce = [0u8; NONCE_SIZE];
+    nonce[..OT_EXT_ADDRESS_SIZE].copy_from_slice(ext_address);
+    nonce[OT_EXT_ADDRESS_SIZE..OT_EXT_ADDRESS_SIZE + FRAME_COUNTER_SIZE].copy_from_slice(&frame_counter.to_be_bytes());
+    nonce[NONCE_SIZE - 1] = security_level;
+    nonce
+}
+
+/// AES-CCM* with the IEEE 802.15.4 parameters (13 byte nonce, 2 byte length field)
+pub struct AesCcm {
+    aes: Aes128,
+}
+
+impl AesCcm {
+    /// Create a CCM* instance for a MAC key
+    pub fn new(key: &OTMacKey) -> Self {
+        Self { aes: Aes128::new(key) }
+    }
+
+    /// Build a counter block (A_i)
+    fn counter_block(nonce: &[u8; NONCE_SIZE], counter: u16) -> AesBlock {
+        let mut block = [0u8; AES_BLOCK_SIZE];
+        block[0] = (LENGTH_FIELD_SIZE - 1) as u8;
+        block[1..1 + NONCE_SIZE].copy_from_slice(nonce);
+        block[1 + NONCE_SIZE..].copy_from_slice(&counter.to_be_bytes());
+        block
+    }
+
+    /// Compute the CBC-MAC authentication tag over the header and the plaintext payload
+    fn authenticate(&self, nonce: &[u8; NONCE_SIZE], header: &[u8], payload: &[u8], mic_length: usize) -> AesBlock {
+        let mut x = [0u8; AES_BLOCK_SIZE];
+        x[0] = (LENGTH_FIELD_SIZE - 1) as u8 | (((mic_length as u8).saturating_sub(2) / 2) << 3);
+        if !header.is_empty() {
+            x[0] |= 1 << 6;
+        }
+        x[1..1 + NONCE_SIZE].copy_from_slice(nonce);
+        x[1 + NONCE_SIZE..].copy_from_slice(&(payload.len() as u16).to_be_bytes());
+        self.aes.encrypt_block(&mut x);
+
+        if !header.is_empty() {
+            // Additional data is prefixed with its 2 byte length (frames never exceed 0xfeff bytes)
+            let mut block = [0u8; AES_BLOCK_SIZE];
+            block[..2].copy_from_slice(&(header.len() as u16).to_be_bytes());
+            let mut position = 2;
+            for byte in header {
+                block[position] = *byte;
+                position += 1;
+                if position == AES_BLOCK_SIZE {
+                    self.chain(&mut x, &block);
+                    block = [0u8; AES_BLOCK_SIZE];
+                    position = 0;
+                }
+            }
+            if position != 0 {
+                self.chain(&mut x, &block);
+            }
+        }
+
+        for chunk in payload.chunks(AES_BLOCK_SIZE) {
+            let mut block = [0u8; AES_BLOCK_SIZE];
+            block[..chunk.len()].copy_from_slice(chunk);
+            self.chain(&mut x, &block);
+        }
+
+        x
+    }
+
+    /// Perform one CBC-MAC step
+    fn chain(&self, x: &mut AesBlock, block: &AesBlock) {
+        for (value, byte) in x.iter_mut().zip(block.iter()) {
+            *value ^= byte;
+        }
+        self.aes.encrypt_block(x);
+    }
+
+    /// Apply the CTR mode key stream to the payload (starting from counter 1)
+    fn apply_key_stream(&self, nonce: &[u8; NONCE_SIZE], payload: &mut [u8]) {
+        for (index, chunk) in payload.chunks_mut(AES_BLOCK_SIZE).enumerate() {
+            let mut stream = Self::counter_block(nonce, index as u16 + 1);
+            self.aes.encrypt_block(&mut stream);
+            for (byte, key) in chunk.iter_mut().zip(stream.iter()) {
+                *byte ^= key;
+            }
+        }
+    }
+
+    /// Encrypt the tag with the first key stream block (counter 0)
+    fn encrypt_tag(&self, nonce: &[u8; NONCE_SIZE], tag: &AesBlock, mic: &mut [u8]) {
+        let mut stream = Self::counter_block(nonce, 0);
+        self.aes.encrypt_block(&mut stream);
+        for (index, byte) in mic.iter_mut().enumerate() {
+            *byte = tag[index] ^ stream[index];
+        }
+    }
+
+    /// Encrypt (when `encrypt` is set) and authenticate a message in place
+    ///
+    /// Params:
+    ///     nonce - the CCM* nonce
+    ///     header - the additional authenticated data
+    ///     payload - the message (encrypted in place when `encrypt` is set)
+    ///     mic - the output MIC (0, 4, 8 or 16 bytes)
+    pub fn seal(&self, nonce: &[u8; NONCE_SIZE], header: &[u8], payload: &mut [u8], mic: &mut [u8], encrypt: bool) {
+        if !mic.is_empty() {
+            let tag = if encrypt {
+                self.authenticate(nonce, header, payload, mic.len())
+            } else {
+                self.authenticate_open(nonce, header, payload, mic.len())
+            };
+            self.encrypt_tag(nonce, &tag, mic);
+        }
+        if encrypt {
+            self.apply_key_stream(nonce, payload);
+        }
+    }
+
+    /// Decrypt (when `encrypt` is set) and verify a message in place
+    ///
+    /// Returns:
+    ///     true if the MIC matched
+    pub fn open(&self, nonce: &[u8; NONCE_SIZE], header: &[u8], payload: &mut [u8], mic: &[u8], encrypt: bool) -> bool {
+        if encrypt {
+            self.apply_key_stream(nonce, payload);
+        }
+        if mic.is_empty() {
+            return true;
+        }
+
+        let tag = if encrypt {
+            self.authenticate(nonce, header, payload, mic.len())
+        } else {
+            self.authenticate_open(nonce, header, payload, mic.len())
+        };
+        let mut expected = [0u8; AES_BLOCK_SIZE];
+        self.encrypt_tag(nonce, &tag, &mut expected[..mic.len()]);
+
+        // Constant time comparison
+        expected[..mic.len()].iter().zip(mic.iter()).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+    }
+
+    /// Authenticate an unencrypted payload, which is part of the additional data
+    fn authenticate_open(&self, nonce: &[u8; NONCE_SIZE], header: &[u8], payload: &[u8], mic_length: usize) -> AesBlock {
+        // The header and payload are contiguous in the frame, but are passed separately, so join them block-wise
+        let mut joined = [0u8; super::OT_RADIO_FRAME_MAX_SIZE];
+        let length = header.len() + payload.len();
+        joined[..header.len()].copy_from_slice(header);
+        joined[header.len()..length].copy_from_slice(payload);
+        self.authenticate(nonce, &joined[..length], &[], mic_length)
+    }
+}
+
+/// Get the key bytes of a key material
+///
+/// Returns:
+///     OTError::Security for key references, which cannot be used by a software implementation
+fn literal_key<E>(key: &OTMacKeyMaterial) -> Result<&OTMacKey, OTError<E>> {
+    match key {
+        OTMacKeyMaterial::Key(key) => Ok(key),
+        OTMacKeyMaterial::Ref(_) => Err(OTError::Security),
+    }
+}
+
+/// Secure a frame in place
+///
+/// The auxiliary security header (frame counter and key identifier) must already be filled in. The payload is
+/// encrypted (security levels 4 - 7) and the MIC written into the space reserved by the frame builder.
+///
+/// Params:
+///     psdu - the frame to secure (including the FCS)
+///     key - the MAC key
+///     ext_address - the extended address of this device
+pub fn secure_frame<E>(psdu: &mut [u8], key: &OTMacKeyMaterial, ext_address: &OTExtAddress) -> Result<(), OTError<E>> {
+    process_frame(psdu, key, ext_address, true)
+}
+
+/// Unsecure a received frame in place
+///
+/// Params:
+///     psdu - the received frame (including the FCS)
+///     key - the MAC key selected from the key identifier
+///     ext_address - the extended address of the frame originator
+///
+/// Returns:
+///     OTError::Security if the MIC does not match
+pub fn unsecure_frame<E>(psdu: &mut [u8], key: &OTMacKeyMaterial, ext_address: &OTExtAddress) -> Result<(), OTError<E>> {
+    process_frame(psdu, key, ext_address, false)
+}
+
+fn process_frame<E>(psdu: &mut [u8], key: &OTMacKeyMaterial, ext_address: &OTExtAddress, secure: bool) -> Result<(), OTError<E>> {
+    let (header_length, payload_end, security_level, frame_counter) = {
+        let frame = Frame::parse(psdu)?;
+        let header = frame.security_header().ok_or(OTError::Security)?;
+        // Frame counter suppression requires TSCH ASN based nonces, which are not supported
+        let frame_counter = header.frame_counter.ok_or(OTError::Security)?;
+        (frame.payload_range().start, frame.payload_range().end, header.security_level(), frame_counter)
+    };
+
+    let ccm = AesCcm::new(literal_key(key)?);
+    let nonce = nonce(ext_address, frame_counter, security_level);
+    let mic_length = super::frame::mic_size(security_level);
+    let encrypt = security_level >= SECURITY_LEVEL_ENC;
+
+    let (header, rest) = psdu.split_at_mut(header_length);
+    let (payload, footer) = rest.split_at_mut(payload_end - header_length);
+    let mic = &mut footer[..mic_length];
+
+    if secure {
+        ccm.seal(&nonce, header, payload, mic, encrypt);
+        Ok(())
+    } else if ccm.open(&nonce, header, payload, mic, encrypt) {
+        Ok(())
+    } else {
+        Err(OTError::Security)
+    }
+}
+
+/// Software MAC security state for radios without `Capabilities::TransmitSec`
+///
+/// Keeps the keys and frame counter the way a `TransmitSec` capable radio would, so it can be handed the same
+/// `OTRadioConfigurationCapTransmit` calls and then process frames before they are sent.
+pub struct MacSecurity {
+    key_id_mode: u8,
+    key_id: u8,
+    previous_key: Option<OTMacKeyMaterial>,
+    current_key: Option<OTMacKeyMaterial>,
+    next_key: Option<OTMacKeyMaterial>,
+    frame_counter: u32,
+}
+
+impl MacSecurity {
+    /// Create a security state without keys
+    pub const fn new() -> Self {
+        Self {
+            key_id_mode: KEY_ID_MODE_1,
+            key_id: 0,
+            previous_key: None,
+            current_key: None,
+            next_key: None,
+            frame_counter: 0,
+        }
+    }
+
+    /// Get the next MAC frame counter value
+    pub fn frame_counter(&self) -> u32 {
+        self.frame_counter
+    }
+
+    /// Get the current key index
+    pub fn key_id(&self) -> u8 {
+        self.key_id
+    }
+
+    /// Select the key for a received key index
+    fn key_for_index<E>(&self, key_index: u8) -> Result<&OTMacKeyMaterial, OTError<E>> {
+        let key = if key_index == self.key_id {
+            &self.current_key
+        } else if key_index == self.key_id.wrapping_sub(1) {
+            &self.previous_key
+        } else if key_index == self.key_id.wrapping_add(1) {
+            &self.next_key
+        } else {
+            &None
+        };
+        key.as_ref().ok_or(OTError::Security)
+    }
+
+    /// Process security of an outgoing frame in place
+    ///
+    /// When the header is not updated yet the frame counter is assigned (and incremented) and the key index written
+    /// into the auxiliary security header before the frame is secured. Frames that are not secured, or that were
+    /// already secured by the core (e.g. key id mode 2 frames), are left as is.
+    ///
+    /// Params:
+    ///     psdu - the frame to send (including the FCS)
+    ///     ext_address - the extended address of this device
+    ///     is_header_updated - whether the frame counter is already set (e.g. on a retransmission)
+    ///     is_security_processed - whether the frame was already secured by the core
+    ///
+    /// Returns:
+    ///     OTError::Security if the frame uses another key id mode and was not secured by the core
+    pub fn process_transmit<E>(
+        &mut self,
+        psdu: &mut [u8],
+        ext_address: &OTExtAddress,
+        is_header_updated: bool,
+        is_security_processed: bool,
+    ) -> Result<(), OTError<E>> {
+        let (security_offset, key_id_mode) = {
+            let frame = Frame::parse(psdu)?;
+            let Some(header) = frame.security_header() else {
+                return Ok(());
+            };
+            if header.frame_counter.is_none() {
+                return Err(OTError::Security);
+            }
+            let security_offset = frame.header_ie_range().start - header.size();
+            (security_offset, header.key_id_mode())
+        };
+
+        if is_security_processed {
+            return Ok(());
+        }
+        if key_id_mode != self.key_id_mode {
+            return Err(OTError::Security);
+        }
+
+        if !is_header_updated {
+            let counter_offset = security_offset + SECURITY_CONTROL_SIZE;
+            psdu[counter_offset..counter_offset + FRAME_COUNTER_SIZE].copy_from_slice(&self.frame_counter.to_le_bytes());
+            if key_id_mode == KEY_ID_MODE_1 {
+                psdu[counter_offset + FRAME_COUNTER_SIZE] = self.key_id;
+            }
+            self.frame_counter = self.frame_counter.checked_add(1).ok_or(OTError::Security)?;
+        }
+
+        let key = self.current_key.as_ref().ok_or(OTError::Security)?;
+        secure_frame(psdu, key, ext_address)
+    }
+
+    /// Secure an Enhanced ACK in place
+    ///
+    /// The frame counter is assigned (and incremented) and the key selected from the key index of the ACK, which is
+    /// the key index of the acknowledged frame.
+    ///
+    /// Params:
+    ///     psdu - the ACK to send (including the FCS)
+    ///     ext_address - the extended address of this device
+    ///
+    /// Returns:
+    ///     The frame counter and key index of the ACK
+    pub fn process_ack<E>(&mut self, psdu: &mut [u8], ext_address: &OTExtAddress) -> Result<(u32, u8), OTError<E>> {
+        let (counter_offset, key_index) = {
+            let frame = Frame::parse(psdu)?;
+            let header = frame.security_header().ok_or(OTError::Security)?;
+            if header.frame_counter.is_none() || header.key_id_mode() != KEY_ID_MODE_1 {
+                return Err(OTError::Security);
+            }
+            let security_offset = frame.header_ie_range().start - header.size();
+            (security_offset + SECURITY_CONTROL_SIZE, header.key_index().ok_or(OTError::Security)?)
+        };
+
+        let key = self.key_for_index(key_index)?;
+        let frame_counter = self.frame_counter;
+        psdu[counter_offset..counter_offset + FRAME_COUNTER_SIZE].copy_from_slice(&frame_counter.to_le_bytes());
+        secure_frame(psdu, key, ext_address)?;
+        self.frame_counter = frame_counter.checked_add(1).ok_or(OTError::Security)?;
+        Ok((frame_counter, key_index))
+    }
+
+    /// Process security of a received frame in place
+    ///
+    /// Params:
+    ///     psdu - the received frame (including the FCS)
+    ///     ext_address - the extended address of the frame originator
+    pub fn process_receive<E>(&self, psdu: &mut [u8], ext_address: &OTExtAddress) -> Result<(), OTError<E>> {
+        let key_index = {
+            let frame = Frame::parse(psdu)?;
+            let Some(header) = frame.security_header() else {
+                return Ok(());
+            };
+            if header.key_id_mode() != self.key_id_mode {
+                return Err(OTError::Security);
+            }
+            header.key_index().unwrap_or(self.key_id)
+        };
+
+        unsecure_frame(psdu, self.key_for_index(key_index)?, ext_address)
+    }
+}
+
+impl Default for MacSecurity {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OTRadioConfigurationCapTransmit for MacSecurity {
+    type Error = Infallible;
+
+    fn set_mac_key(
+        &mut self,
+        key_id_mode: u8,
+        key_id: u8,
+        previous_key: OTMacKeyMaterial,
+        current_key: OTMacKeyMaterial,
+        next_key: OTMacKeyMaterial,
+        _key_type: OTKeyType,
+    ) -> Result<(), Self::Error> {
+        self.key_id_mode = key_id_mode;
+        self.key_id = key_id;
+        self.previous_key = Some(previous_key);
+        self.current_key = Some(current_key);
+        self.next_key = Some(next_key);
+        Ok(())
+    }
+
+    fn set_mac_frame_counter(&mut self, mac_frame_counter: u32) -> Result<(), Self::Error> {
+        self.frame_counter = mac_frame_counter;
+        Ok(())
+    }
+
+    fn set_mac_frame_counter_if_larger(&mut self, mac_frame_counter: u32) -> Result<(), Self::Error> {
+        self.frame_counter = self.frame_counter.max(mac_frame_counter);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::radio::builder::{FrameBuilder, SecurityConfig};
+    use crate::radio::frame::{FrameVersion, MacAddress, FCS_SIZE};
+    use crate::radio::TransmitFrame;
+
+    // IEEE 802.15.4-2006 Annex C.2 key and originator (0xacde480000000001)
+    const KEY: OTMacKey = [
+        0xc0, 0xc1, 0xc2, 0xc3, 0xc4, 0xc5, 0xc6, 0xc7, 0xc8, 0xc9, 0xca, 0xcb, 0xcc, 0xcd, 0xce, 0xcf,
+    ];
+    const EXT_ADDRESS: OTExtAddress = [0xac, 0xde, 0x48, 0x00, 0x00, 0x00, 0x00, 0x01];
+
+    // Annex C.2.1: beacon frame, MIC-64
+    const BEACON: [u8; 34] = [
+        0x08, 0xd0, 0x84, 0x21, 0x43, 0x01, 0x00, 0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0x02, 0x05, 0x00, 0x00, 0x00,
+        0x55, 0xcf, 0x00, 0x00, 0x51, 0x52, 0x53, 0x54, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+    const BEACON_SECURED: [u8; 34] = [
+        0x08, 0xd0, 0x84, 0x21, 0x43, 0x01, 0x00, 0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0x02, 0x05, 0x00, 0x00, 0x00,
+        0x55, 0xcf, 0x00, 0x00, 0x51, 0x52, 0x53, 0x54, 0x22, 0x3b, 0xc1, 0xec, 0x84, 0x1a, 0xb5, 0x53,
+    ];
+
+    // Annex C.2.2: data frame, ENC
+    const DATA: [u8; 30] = [
+        0x69, 0xdc, 0x84, 0x21, 0x43, 0x02, 0x00, 0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0x01, 0x00, 0x00, 0x00, 0x00,
+        0x48, 0xde, 0xac, 0x04, 0x05, 0x00, 0x00, 0x00, 0x61, 0x62, 0x63, 0x64,
+    ];
+    const DATA_SECURED: [u8; 30] = [
+        0x69, 0xdc, 0x84, 0x21, 0x43, 0x02, 0x00, 0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0x01, 0x00, 0x00, 0x00, 0x00,
+        0x48, 0xde, 0xac, 0x04, 0x05, 0x00, 0x00, 0x00, 0xd4, 0x3e, 0x02, 0x2b,
+    ];
+
+    // Annex C.2.3: MAC command frame, ENC-MIC-64
+    const COMMAND: [u8; 38] = [
+        0x2b, 0xdc, 0x84, 0x21, 0x43, 0x02, 0x00, 0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0xff, 0xff, 0x01, 0x00, 0x00,
+        0x00, 0x00, 0x48, 0xde, 0xac, 0x06, 0x05, 0x00, 0x00, 0x00, 0x01, 0xce, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00,
+    ];
+    const COMMAND_SECURED: [u8; 38] = [
+        0x2b, 0xdc, 0x84, 0x21, 0x43, 0x02, 0x00, 0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0xff, 0xff, 0x01, 0x00, 0x00,
+        0x00, 0x00, 0x48, 0xde, 0xac, 0x06, 0x05, 0x00, 0x00, 0x00, 0x01, 0xd8, 0x4f, 0xde, 0x52, 0x90, 0x61, 0xf9,
+        0xc6, 0xf1,
+    ];
+
+    /// Append an (unchecked) FCS to an Annex C frame
+    fn with_fcs(frame: &[u8]) -> ([u8; 64], usize) {
+        let mut psdu = [0u8; 64];
+        psdu[..frame.len()].copy_from_slice(frame);
+        (psdu, frame.len() + FCS_SIZE)
+    }
+
+    #[test]
+    fn annex_c_vectors() {
+        let key = OTMacKeyMaterial::Key(KEY);
+
+        for (name, plain, secured) in [
+            ("beacon", &BEACON[..], &BEACON_SECURED[..]),
+            ("data", &DATA[..], &DATA_SECURED[..]),
+            ("command", &COMMAND[..], &COMMAND_SECURED[..]),
+        ] {
+            let (mut psdu, length) = with_fcs(plain);
+            secure_frame::<Infallible>(&mut psdu[..length], &key, &EXT_ADDRESS).unwrap();
+            assert_eq!(&psdu[..secured.len()], secured, "{}: secure", name);
+
+            unsecure_frame::<Infallible>(&mut psdu[..length], &key, &EXT_ADDRESS).unwrap();
+            let mic_start = plain.len() - mic_size_of(plain);
+            assert_eq!(&psdu[..mic_start], &plain[..mic_start], "{}: unsecure", name);
+        }
+    }
+
+    /// Size of the MIC at the end of an Annex C frame
+    fn mic_size_of(frame: &[u8]) -> usize {
+        let (psdu, length) = with_fcs(frame);
+        Frame::parse::<Infallible>(&psdu[..length]).unwrap().mic().len()
+    }
+
+    #[test]
+    fn annex_c_tampered_frame_is_rejected() {
+        let key = OTMacKeyMaterial::Key(KEY);
+        let (mut psdu, length) = with_fcs(&COMMAND_SECURED);
+        psdu[2] ^= 0x01;
+
+        let result = unsecure_frame::<Infallible>(&mut psdu[..length], &key, &EXT_ADDRESS);
+        assert!(matches!(result, Err(OTError::Security)));
+    }
+
+    /// Build a MAC security context using key id mode 1 and the Annex C key
+    fn mac_security() -> MacSecurity {
+        let mut security = MacSecurity::new();
+        let key = OTMacKeyMaterial::Key(KEY);
+        security.set_mac_key(KEY_ID_MODE_1, 1, key, key, key, OTKeyType::LiteralKey).unwrap();
+        security.set_mac_frame_counter(5).unwrap();
+        security
+    }
+
+    #[test]
+    fn transmit_assigns_frame_counter_and_key_index() {
+        let payload = [0x61, 0x62, 0x63, 0x64];
+        let mut tx_frame = TransmitFrame::new();
+        FrameBuilder::data(FrameVersion::Version2006)
+            .dst(Some(0x4321), MacAddress::Short(0x0002))
+            .src(None, MacAddress::Extended(EXT_ADDRESS))
+            .security(SecurityConfig {
+                security_level: 5,
+                key_id_mode: KEY_ID_MODE_1,
+                frame_counter: Some(0),
+                key_source: &[],
+                key_index: 0,
+            })
+            .payload(&payload)
+            .build::<Infallible>(&mut tx_frame)
+            .unwrap();
+        let length = tx_frame.length();
+
+        let mut security = mac_security();
+        security.process_transmit::<Infallible>(tx_frame.psdu_mut(), &EXT_ADDRESS, false, false).unwrap();
+        assert_eq!(security.frame_counter(), 6);
+        {
+            let frame = Frame::parse::<Infallible>(tx_frame.psdu()).unwrap();
+            let header = frame.security_header().unwrap();
+            assert_eq!(header.frame_counter, Some(5));
+            assert_eq!(header.key_index(), Some(1));
+            assert_ne!(frame.payload(), &payload[..]);
+        }
+
+        // A retransmission keeps the frame counter
+        let mut retransmission = [0u8; 64];
+        retransmission[..length].copy_from_slice(tx_frame.psdu());
+        security.process_receive::<Infallible>(&mut retransmission[..length], &EXT_ADDRESS).unwrap();
+        security.process_transmit::<Infallible>(&mut retransmission[..length], &EXT_ADDRESS, true, false).unwrap();
+        assert_eq!(security.frame_counter(), 6);
+        assert_eq!(&retransmission[..length], tx_frame.psdu());
+
+        security.process_receive::<Infallible>(tx_frame.psdu_mut(), &EXT_ADDRESS).unwrap();
+        assert_eq!(Frame::parse::<Infallible>(tx_frame.psdu()).unwrap().payload(), &payload[..]);
+    }
+
+    #[test]
+    fn transmit_with_another_key_id_mode() {
+        let mut security = mac_security();
+
+        // Secured by the core: left untouched
+        let (mut psdu, length) = with_fcs(&COMMAND_SECURED);
+        security.process_transmit::<Infallible>(&mut psdu[..length], &EXT_ADDRESS, true, true).unwrap();
+        assert_eq!(&psdu[..COMMAND_SECURED.len()], &COMMAND_SECURED[..]);
+
+        // Not secured by anyone: rejected
+        let (mut psdu, length) = with_fcs(&COMMAND);
+        assert!(matches!(
+            security.process_transmit::<Infallible>(&mut psdu[..length], &EXT_ADDRESS, false, false),
+            Err(OTError::Security)
+        ));
+        assert_eq!(security.frame_counter(), 5);
+    }
+}
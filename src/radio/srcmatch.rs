@@ -0,0 +1,237 @@
+//!
+//! Software Source Address Match Table
+//!
+//! Radios acknowledging frames in software (or forwarding the decision to a hardware auto-ACK) keep the source match
+//! table of `OTRadioOperation` in a `SrcMatchTable` and ask it for the frame pending bit of the ACK to a data request:
+//!     - source match disabled: the bit is set on every ACK to a data request
+//!     - source match enabled: the bit is set when the source address of the data request is in the table
+//!
+
+use super::frame::{Frame, MacAddress};
+use super::{OTExtAddress, OTShortAddress, OT_EXT_ADDRESS_SIZE};
+use crate::error::OTError;
+
+// MAC command frame identifier of a data request
+pub const DATA_REQUEST_COMMAND_ID: u8 = 0x04;
+
+/// Fixed capacity table of SHORT short addresses and EXT extended addresses
+pub struct SrcMatchTable<const SHORT: usize, const EXT: usize> {
+    enabled: bool,
+    short_entries: [OTShortAddress; SHORT],
+    short_count: usize,
+    ext_entries: [OTExtAddress; EXT],
+    ext_count: usize,
+}
+
+impl<const SHORT: usize, const EXT: usize> SrcMatchTable<SHORT, EXT> {
+    /// Create an empty table with source match disabled
+    pub const fn new() -> Self {
+        Self {
+            enabled: false,
+            short_entries: [0; SHORT],
+            short_count: 0,
+            ext_entries: [[0; OT_EXT_ADDRESS_SIZE]; EXT],
+            ext_count: 0,
+        }
+    }
+
+    /// Enable/Disable source address match (see `OTRadioOperation::enable_src_match`)
+    pub fn enable(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Check whether source address match is enabled
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Add a short address (adding an address already in the table does nothing)
+    ///
+    /// Returns:
+    ///     OTError::NoBuffers if the short address table is full
+    pub fn add_short_entry<E>(&mut self, address: OTShortAddress) -> Result<(), OTError<E>> {
+        if self.contains_short(address) {
+            return Ok(());
+        }
+        let entry = self.short_entries.get_mut(self.short_count).ok_or(OTError::NoBuffers)?;
+        *entry = address;
+        self.short_count += 1;
+        Ok(())
+    }
+
+    /// Add an extended address (adding an address already in the table does nothing)
+    ///
+    /// Returns:
+    ///     OTError::NoBuffers if the extended address table is full
+    pub fn add_ext_entry<E>(&mut self, address: OTExtAddress) -> Result<(), OTError<E>> {
+        if self.contains_ext(&address) {
+            return Ok(());
+        }
+        let entry = self.ext_entries.get_mut(self.ext_count).ok_or(OTError::NoBuffers)?;
+        *entry = address;
+        self.ext_count += 1;
+        Ok(())
+    }
+
+    /// Remove a short address
+    ///
+    /// Returns:
+    ///     OTError::NoAddress if the address is not in the table
+    pub fn clear_short_entry<E>(&mut self, address: OTShortAddress) -> Result<(), OTError<E>> {
+        let index = self.short_entries().iter().position(|entry| *entry == address).ok_or(OTError::NoAddress)?;
+        self.short_count -= 1;
+        self.short_entries.swap(index, self.short_count);
+        Ok(())
+    }
+
+    /// Remove an extended address
+    ///
+    /// Returns:
+    ///     OTError::NoAddress if the address is not in the table
+    pub fn clear_ext_entry<E>(&mut self, address: OTExtAddress) -> Result<(), OTError<E>> {
+        let index = self.ext_entries().iter().position(|entry| *entry == address).ok_or(OTError::NoAddress)?;
+        self.ext_count -= 1;
+        self.ext_entries.swap(index, self.ext_count);
+        Ok(())
+    }
+
+    /// Remove all short addresses
+    pub fn clear_short_entries(&mut self) {
+        self.short_count = 0;
+    }
+
+    /// Remove all extended addresses
+    pub fn clear_ext_entries(&mut self) {
+        self.ext_count = 0;
+    }
+
+    /// Get the short addresses in the table
+    pub fn short_entries(&self) -> &[OTShortAddress] {
+        &self.short_entries[..self.short_count]
+    }
+
+    /// Get the extended addresses in the table
+    pub fn ext_entries(&self) -> &[OTExtAddress] {
+        &self.ext_entries[..self.ext_count]
+    }
+
+    /// Check whether a short address is in the table
+    pub fn contains_short(&self, address: OTShortAddress) -> bool {
+        self.short_entries().contains(&address)
+    }
+
+    /// Check whether an extended address is in the table
+    pub fn contains_ext(&self, address: &OTExtAddress) -> bool {
+        self.ext_entries().contains(address)
+    }
+
+    /// Decide the frame pending bit of an ACK to a frame sent from the given address
+    ///
+    /// The frame is assumed to be a data request.
+    pub fn frame_pending_for_address(&self, address: MacAddress) -> bool {
+        if !self.enabled {
+            return true;
+        }
+        match address {
+            MacAddress::Short(address) => self.contains_short(address),
+            MacAddress::Extended(address) => self.contains_ext(&address),
+            MacAddress::None => false,
+        }
+    }
+
+    /// Decide the frame pending bit of an ACK to a frame (only set for data requests)
+    pub fn frame_pending(&self, frame: &Frame) -> bool {
+        frame.command_id() == Some(DATA_REQUEST_COMMAND_ID) && self.frame_pending_for_address(frame.src_address())
+    }
+}
+
+impl<const SHORT: usize, const EXT: usize> Default for SrcMatchTable<SHORT, EXT> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use super::*;
+    use crate::radio::builder::FrameBuilder;
+    use crate::radio::frame::FrameVersion;
+    use crate::radio::TransmitFrame;
+
+    const EXT_ADDRESS: OTExtAddress = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88];
+
+    /// Build a frame from the given source to a parent
+    fn frame_from(builder: FrameBuilder, src: MacAddress) -> TransmitFrame {
+        let mut frame = TransmitFrame::new();
+        builder
+            .sequence_number(Some(1))
+            .dst(Some(0x1234), MacAddress::Short(0x0000))
+            .src(None, src)
+            .build::<Infallible>(&mut frame)
+            .unwrap();
+        frame
+    }
+
+    #[test]
+    fn entries_are_unique_and_bounded() {
+        let mut table = SrcMatchTable::<2, 1>::new();
+        table.add_short_entry::<Infallible>(0x0001).unwrap();
+        table.add_short_entry::<Infallible>(0x0001).unwrap();
+        table.add_short_entry::<Infallible>(0x0002).unwrap();
+        assert_eq!(table.short_entries(), &[0x0001, 0x0002]);
+        assert_eq!(table.add_short_entry::<Infallible>(0x0003), Err(OTError::NoBuffers));
+
+        table.add_ext_entry::<Infallible>(EXT_ADDRESS).unwrap();
+        table.add_ext_entry::<Infallible>(EXT_ADDRESS).unwrap();
+        assert_eq!(table.ext_entries(), &[EXT_ADDRESS]);
+        assert_eq!(table.add_ext_entry::<Infallible>([0; OT_EXT_ADDRESS_SIZE]), Err(OTError::NoBuffers));
+
+        // A removed entry frees its slot and keeps the other entries
+        table.clear_short_entry::<Infallible>(0x0001).unwrap();
+        assert_eq!(table.short_entries(), &[0x0002]);
+        table.add_short_entry::<Infallible>(0x0003).unwrap();
+        assert!(table.contains_short(0x0002) && table.contains_short(0x0003));
+
+        assert_eq!(table.clear_short_entry::<Infallible>(0x0001), Err(OTError::NoAddress));
+        assert_eq!(table.clear_ext_entry::<Infallible>([0; OT_EXT_ADDRESS_SIZE]), Err(OTError::NoAddress));
+        table.clear_ext_entry::<Infallible>(EXT_ADDRESS).unwrap();
+        assert!(table.ext_entries().is_empty());
+
+        table.clear_short_entries();
+        assert!(table.short_entries().is_empty());
+    }
+
+    #[test]
+    fn frame_pending() {
+        let data_request = FrameBuilder::mac_command(FrameVersion::Version2006, DATA_REQUEST_COMMAND_ID);
+        let data = FrameBuilder::data(FrameVersion::Version2006);
+
+        let mut table = SrcMatchTable::<2, 2>::new();
+        table.add_short_entry::<Infallible>(0x0001).unwrap();
+        table.add_ext_entry::<Infallible>(EXT_ADDRESS).unwrap();
+
+        // (name, frame, source match enabled, frame pending)
+        let cases = [
+            ("disabled, data request", frame_from(data_request, MacAddress::Short(0x0002)), false, true),
+            ("disabled, data frame", frame_from(data, MacAddress::Short(0x0002)), false, false),
+            ("enabled, short match", frame_from(data_request, MacAddress::Short(0x0001)), true, true),
+            ("enabled, short mismatch", frame_from(data_request, MacAddress::Short(0x0002)), true, false),
+            ("enabled, ext match", frame_from(data_request, MacAddress::Extended(EXT_ADDRESS)), true, true),
+            (
+                "enabled, ext mismatch",
+                frame_from(data_request, MacAddress::Extended([0; OT_EXT_ADDRESS_SIZE])),
+                true,
+                false,
+            ),
+            ("enabled, data frame", frame_from(data, MacAddress::Short(0x0001)), true, false),
+        ];
+
+        for (name, frame, enabled, expected) in cases {
+            table.enable(enabled);
+            let frame = Frame::parse::<Infallible>(frame.psdu()).unwrap();
+            assert_eq!(table.frame_pending(&frame), expected, "{}", name);
+        }
+    }
+}
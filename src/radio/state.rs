@@ -0,0 +1,656 @@
+//!
+//! Radio State Machine
+//!
+//! `RadioStateMachine` wraps a radio, tracks its `OTRadioState` and rejects the operations that are not legal in the
+//! current state with `OTError::InvalidState` (without calling the radio).
+//!
+//! Transitions:
+//!     operation    | legal from                                         | new state
+//!     enable       | Disabled                                           | Sleep
+//!     disable      | Sleep                                              | Disabled
+//!     sleep        | Sleep, Receive                                     | Sleep
+//!     receive      | Sleep, Receive, Transmit                           | Receive
+//!     receive_at   | Sleep, Receive                                     | unchanged
+//!     transmit     | Receive, Sleep (with Capabilities::SleepToTx)      | Transmit
+//!     tx_done      | Transmit                                           | Receive
+//!     energy_scan  | Sleep, Receive                                     | unchanged
+//!
+//! The radio reports the end of a transmission through its handles, which the wrapper does not see: the platform
+//! must call `OTRadioOperation::tx_done` (otPlatRadioTxDone) on the wrapper when it happens.
+//!
+//! `TypedRadio` offers the same state machine at compile time: each state is a type and the transitions consume the
+//! radio, so an illegal call does not build.
+//!
+
+use core::marker::PhantomData;
+
+use super::{
+    Capabilities, OTExtAddress, OTKeyType, OTLinkMetrics, OTMacKeyMaterial, OTPanId, OTRadioCapabilities,
+    OTRadioConfiguration, OTRadioConfigurationCapTransmit, OTRadioFrame, OTRadioOperation, OTRadioOperationEnergyScan,
+    OTRadioOperationOptional, OTRadioState, OTShortAddress,
+};
+use crate::error::OTError;
+
+/// Radio wrapper enforcing the legal state transitions
+pub struct RadioStateMachine<R> {
+    radio: R,
+    state: OTRadioState,
+    capabilities: OTRadioCapabilities,
+}
+
+impl<R, E> RadioStateMachine<R>
+where
+    R: OTRadioOperation<Error = E> + OTRadioConfiguration<Error = E>,
+{
+    /// Wrap a radio, reading its capabilities and whether it is enabled (Sleep) or not (Disabled)
+    pub fn new(mut radio: R) -> Result<Self, E> {
+        let capabilities = radio.radio_capabilities()?;
+        let state = if OTRadioOperation::is_enabled(&mut radio)? {
+            OTRadioState::Sleep
+        } else {
+            OTRadioState::Disabled
+        };
+        Ok(Self {
+            radio,
+            state,
+            capabilities,
+        })
+    }
+}
+
+impl<R> RadioStateMachine<R> {
+    /// Get the current state of the radio
+    pub fn state(&self) -> OTRadioState {
+        self.state
+    }
+
+    pub fn radio(&mut self) -> &mut R {
+        &mut self.radio
+    }
+
+    pub fn into_inner(self) -> R {
+        self.radio
+    }
+
+    /// Check the radio is in one of the given states
+    fn expect<E>(&self, states: &[OTRadioState]) -> Result<(), OTError<E>> {
+        if states.contains(&self.state) {
+            Ok(())
+        } else {
+            Err(OTError::InvalidState)
+        }
+    }
+
+    /// Check a transmission can start from the current state
+    fn expect_transmit<E>(&self) -> Result<(), OTError<E>> {
+        match self.state {
+            OTRadioState::Receive => Ok(()),
+            OTRadioState::Sleep if self.capabilities & Capabilities::SleepToTx as OTRadioCapabilities != 0 => Ok(()),
+            _ => Err(OTError::InvalidState),
+        }
+    }
+}
+
+impl<R: OTRadioOperation> OTRadioOperation for RadioStateMachine<R> {
+    type Error = R::Error;
+
+    fn enable(&mut self) -> Result<(), OTError<Self::Error>> {
+        self.expect(&[OTRadioState::Disabled])?;
+        self.radio.enable()?;
+        self.state = OTRadioState::Sleep;
+        Ok(())
+    }
+
+    fn disable(&mut self) -> Result<(), OTError<Self::Error>> {
+        self.expect(&[OTRadioState::Sleep])?;
+        self.radio.disable()?;
+        self.state = OTRadioState::Disabled;
+        Ok(())
+    }
+
+    fn is_enabled(&mut self) -> Result<bool, Self::Error> {
+        self.radio.is_enabled()
+    }
+
+    fn sleep(&mut self) -> Result<(), OTError<Self::Error>> {
+        self.expect(&[OTRadioState::Sleep, OTRadioState::Receive])?;
+        self.radio.sleep()?;
+        self.state = OTRadioState::Sleep;
+        Ok(())
+    }
+
+    fn receive(&mut self, channel: u8) -> Result<(), OTError<Self::Error>> {
+        self.expect(&[OTRadioState::Sleep, OTRadioState::Receive, OTRadioState::Transmit])?;
+        self.radio.receive(channel)?;
+        self.state = OTRadioState::Receive;
+        Ok(())
+    }
+
+    fn receive_at(&mut self, channel: u8, start: u32, duration: u32) -> Result<(), OTError<Self::Error>> {
+        self.expect(&[OTRadioState::Sleep, OTRadioState::Receive])?;
+        self.radio.receive_at(channel, start, duration)
+    }
+
+    fn receive_frame(&mut self) -> Result<OTRadioFrame<'_>, OTError<Self::Error>> {
+        self.radio.receive_frame()
+    }
+
+    fn transmit(&mut self, frame: OTRadioFrame) -> Result<(), OTError<Self::Error>> {
+        self.expect_transmit()?;
+        self.radio.transmit(frame)?;
+        self.state = OTRadioState::Transmit;
+        Ok(())
+    }
+
+    fn tx_started(&mut self) {
+        self.radio.tx_started();
+    }
+
+    /// Returns the radio to Receive (ignored outside of Transmit)
+    fn tx_done(&mut self) {
+        if self.state == OTRadioState::Transmit {
+            self.state = OTRadioState::Receive;
+        }
+        self.radio.tx_done();
+    }
+
+    fn diag_tx_done(&mut self) {
+        if self.state == OTRadioState::Transmit {
+            self.state = OTRadioState::Receive;
+        }
+        self.radio.diag_tx_done();
+    }
+
+    fn get_rssi(&mut self) -> Result<i8, Self::Error> {
+        self.radio.get_rssi()
+    }
+
+    fn enable_src_match(&mut self, enabled: bool) -> Result<(), Self::Error> {
+        self.radio.enable_src_match(enabled)
+    }
+
+    fn add_src_match_short_entry(&mut self, address: OTShortAddress) -> Result<(), OTError<Self::Error>> {
+        self.radio.add_src_match_short_entry(address)
+    }
+
+    fn add_src_match_ext_entry(&mut self, address: OTExtAddress) -> Result<(), OTError<Self::Error>> {
+        self.radio.add_src_match_ext_entry(address)
+    }
+
+    fn clear_src_match_short_entry(&mut self, address: OTShortAddress) -> Result<(), OTError<Self::Error>> {
+        self.radio.clear_src_match_short_entry(address)
+    }
+
+    fn clear_src_match_ext_entry(&mut self, address: OTExtAddress) -> Result<(), OTError<Self::Error>> {
+        self.radio.clear_src_match_ext_entry(address)
+    }
+
+    fn clear_src_match_short_entries(&mut self) -> Result<(), Self::Error> {
+        self.radio.clear_src_match_short_entries()
+    }
+
+    fn clear_src_match_ext_entries(&mut self) -> Result<(), Self::Error> {
+        self.radio.clear_src_match_ext_entries()
+    }
+
+    fn get_supported_channel_mask(&mut self) -> Result<u32, Self::Error> {
+        self.radio.get_supported_channel_mask()
+    }
+
+    fn get_preferred_channel_mask(&mut self) -> Result<u32, Self::Error> {
+        self.radio.get_preferred_channel_mask()
+    }
+
+    fn set_channel_max_transmit_power(&mut self, channel: u8, max_power: i8) -> Result<(), Self::Error> {
+        self.radio.set_channel_max_transmit_power(channel, max_power)
+    }
+
+    fn set_region(&mut self, region_code: u16) -> Result<(), Self::Error> {
+        self.radio.set_region(region_code)
+    }
+
+    fn get_region(&mut self) -> Result<u16, Self::Error> {
+        self.radio.get_region()
+    }
+
+    fn configure_enh_ack_probing(
+        &mut self,
+        link_metrics: OTLinkMetrics,
+        short_address: OTShortAddress,
+        ext_address: OTExtAddress,
+    ) -> Result<(), OTError<Self::Error>> {
+        self.radio.configure_enh_ack_probing(link_metrics, short_address, ext_address)
+    }
+}
+
+impl<R: OTRadioConfiguration> OTRadioConfiguration for RadioStateMachine<R> {
+    type Error = R::Error;
+
+    fn radio_capabilities(&mut self) -> Result<OTRadioCapabilities, Self::Error> {
+        self.radio.radio_capabilities()
+    }
+
+    fn radio_receive_sensitivity(&mut self) -> Result<u8, Self::Error> {
+        self.radio.radio_receive_sensitivity()
+    }
+
+    fn radio_ieee_eui_64(&mut self) -> Result<[u8; 8], Self::Error> {
+        self.radio.radio_ieee_eui_64()
+    }
+
+    fn set_pan_id(&mut self, pan_id: OTPanId) -> Result<(), Self::Error> {
+        self.radio.set_pan_id(pan_id)
+    }
+
+    fn set_extended_address(&mut self, address: OTExtAddress) -> Result<(), Self::Error> {
+        self.radio.set_extended_address(address)
+    }
+
+    fn set_short_address(&mut self, address: OTShortAddress) -> Result<(), Self::Error> {
+        self.radio.set_short_address(address)
+    }
+
+    fn get_transmit_power(&mut self) -> Result<i8, OTError<Self::Error>> {
+        self.radio.get_transmit_power()
+    }
+
+    fn set_transmit_power(&mut self, power: i8) -> Result<(), OTError<Self::Error>> {
+        self.radio.set_transmit_power(power)
+    }
+
+    fn get_cca_energy_detect_threshold(&mut self) -> Result<i8, OTError<Self::Error>> {
+        self.radio.get_cca_energy_detect_threshold()
+    }
+
+    fn set_cca_energy_detect_threshold(&mut self, threshold: i8) -> Result<(), OTError<Self::Error>> {
+        self.radio.set_cca_energy_detect_threshold(threshold)
+    }
+
+    fn get_fem_lna_gain(&mut self) -> Result<i8, OTError<Self::Error>> {
+        self.radio.get_fem_lna_gain()
+    }
+
+    fn set_fem_lna_gain(&mut self, gain: i8) -> Result<(), OTError<Self::Error>> {
+        self.radio.set_fem_lna_gain(gain)
+    }
+
+    fn get_promiscuous(&mut self) -> Result<bool, Self::Error> {
+        self.radio.get_promiscuous()
+    }
+
+    fn set_promiscuous(&mut self, enabled: bool) -> Result<(), Self::Error> {
+        self.radio.set_promiscuous(enabled)
+    }
+
+    fn set_rx_on_when_idle(&mut self, enabled: bool) -> Result<(), Self::Error> {
+        self.radio.set_rx_on_when_idle(enabled)
+    }
+
+    fn get_now(&mut self) -> u64 {
+        self.radio.get_now()
+    }
+
+    fn get_bus_speed(&mut self) -> u32 {
+        self.radio.get_bus_speed()
+    }
+}
+
+impl<R: OTRadioConfigurationCapTransmit> OTRadioConfigurationCapTransmit for RadioStateMachine<R> {
+    type Error = R::Error;
+
+    fn set_mac_key(
+        &mut self,
+        key_id_mode: u8,
+        key_id: u8,
+        previous_key: OTMacKeyMaterial,
+        current_key: OTMacKeyMaterial,
+        next_key: OTMacKeyMaterial,
+        key_type: OTKeyType,
+    ) -> Result<(), Self::Error> {
+        self.radio.set_mac_key(key_id_mode, key_id, previous_key, current_key, next_key, key_type)
+    }
+
+    fn set_mac_frame_counter(&mut self, mac_frame_counter: u32) -> Result<(), Self::Error> {
+        self.radio.set_mac_frame_counter(mac_frame_counter)
+    }
+
+    fn set_mac_frame_counter_if_larger(&mut self, mac_frame_counter: u32) -> Result<(), Self::Error> {
+        self.radio.set_mac_frame_counter_if_larger(mac_frame_counter)
+    }
+}
+
+impl<R: OTRadioOperationEnergyScan> OTRadioOperationEnergyScan for RadioStateMachine<R> {
+    type Error = R::Error;
+
+    fn energy_scan(&mut self, channel: u8, duration: i16) -> Result<(), OTError<Self::Error>> {
+        self.expect(&[OTRadioState::Sleep, OTRadioState::Receive])?;
+        self.radio.energy_scan(channel, duration)
+    }
+
+    fn energy_scan_done(&mut self) -> Result<i8, Self::Error> {
+        self.radio.energy_scan_done()
+    }
+}
+
+/// Reports the tracked state instead of asking the radio
+impl<R: OTRadioOperationOptional> OTRadioOperationOptional for RadioStateMachine<R> {
+    type Error = R::Error;
+
+    fn get_state(&mut self) -> Result<OTRadioState, Self::Error> {
+        Ok(self.state)
+    }
+
+    fn add_calibrated_power(
+        &mut self,
+        channel: u8,
+        actual_power: i16,
+        raw_power_setting: &[u8],
+    ) -> Result<(), OTError<Self::Error>> {
+        self.radio.add_calibrated_power(channel, actual_power, raw_power_setting)
+    }
+
+    fn clear_calibrated_powers(&mut self) -> Result<(), OTError<Self::Error>> {
+        self.radio.clear_calibrated_powers()
+    }
+
+    fn set_channel_target_power(&mut self, channel: u8, target_power: i16) -> Result<(), OTError<Self::Error>> {
+        self.radio.set_channel_target_power(channel, target_power)
+    }
+}
+
+/// Disabled state of a `TypedRadio`
+pub struct Disabled;
+
+/// Sleep state of a `TypedRadio`
+pub struct Sleep;
+
+/// Receive state of a `TypedRadio`
+pub struct Receive;
+
+/// Transmit state of a `TypedRadio`
+pub struct Transmit;
+
+/// Radio whose state is checked at compile time
+///
+/// A failed transition gives the radio back in its previous state along with the error.
+pub struct TypedRadio<R, S> {
+    machine: RadioStateMachine<R>,
+    _state: PhantomData<S>,
+}
+
+/// Result of a transition of a `TypedRadio` from state S to state T
+pub type Transition<R, S, T> =
+    Result<TypedRadio<R, T>, (TypedRadio<R, S>, OTError<<R as OTRadioOperation>::Error>)>;
+
+impl<R, E> TypedRadio<R, Disabled>
+where
+    R: OTRadioOperation<Error = E> + OTRadioConfiguration<Error = E>,
+{
+    /// Wrap a disabled radio
+    ///
+    /// Returns:
+    ///     OTError::InvalidState if the radio is already enabled
+    pub fn new(radio: R) -> Result<Self, OTError<E>> {
+        let machine = RadioStateMachine::new(radio).map_err(OTError::Platform)?;
+        machine.expect(&[OTRadioState::Disabled])?;
+        Ok(Self::with(machine))
+    }
+}
+
+impl<R, S> TypedRadio<R, S> {
+    fn with(machine: RadioStateMachine<R>) -> Self {
+        Self {
+            machine,
+            _state: PhantomData,
+        }
+    }
+
+    pub fn radio(&mut self) -> &mut R {
+        self.machine.radio()
+    }
+
+    pub fn into_inner(self) -> R {
+        self.machine.into_inner()
+    }
+}
+
+impl<R: OTRadioOperation, S> TypedRadio<R, S> {
+    /// Run a transition on the state machine
+    fn transition<T>(
+        mut self,
+        f: impl FnOnce(&mut RadioStateMachine<R>) -> Result<(), OTError<R::Error>>,
+    ) -> Transition<R, S, T> {
+        match f(&mut self.machine) {
+            Ok(()) => Ok(TypedRadio::with(self.machine)),
+            Err(error) => Err((self, error)),
+        }
+    }
+}
+
+impl<R: OTRadioOperation> TypedRadio<R, Disabled> {
+    pub fn enable(self) -> Transition<R, Disabled, Sleep> {
+        self.transition(|machine| machine.enable())
+    }
+}
+
+impl<R: OTRadioOperation> TypedRadio<R, Sleep> {
+    pub fn disable(self) -> Transition<R, Sleep, Disabled> {
+        self.transition(|machine| machine.disable())
+    }
+
+    pub fn receive(self, channel: u8) -> Transition<R, Sleep, Receive> {
+        self.transition(|machine| machine.receive(channel))
+    }
+
+    /// Returns:
+    ///     OTError::InvalidState if the radio does not advertise Capabilities::SleepToTx
+    pub fn transmit(self, frame: OTRadioFrame) -> Transition<R, Sleep, Transmit> {
+        self.transition(|machine| machine.transmit(frame))
+    }
+
+    /// Schedule a receive window (see `OTRadioOperation::receive_at`)
+    pub fn receive_at(&mut self, channel: u8, start: u32, duration: u32) -> Result<(), OTError<R::Error>> {
+        self.machine.receive_at(channel, start, duration)
+    }
+}
+
+impl<R: OTRadioOperation + OTRadioOperationEnergyScan<Error = <R as OTRadioOperation>::Error>> TypedRadio<R, Sleep> {
+    /// Start an energy scan (see `OTRadioOperationEnergyScan::energy_scan`)
+    pub fn energy_scan(&mut self, channel: u8, duration: i16) -> Result<(), OTError<<R as OTRadioOperation>::Error>> {
+        self.machine.energy_scan(channel, duration)
+    }
+}
+
+impl<R: OTRadioOperation> TypedRadio<R, Receive> {
+    pub fn sleep(self) -> Transition<R, Receive, Sleep> {
+        self.transition(|machine| machine.sleep())
+    }
+
+    pub fn transmit(self, frame: OTRadioFrame) -> Transition<R, Receive, Transmit> {
+        self.transition(|machine| machine.transmit(frame))
+    }
+
+    /// Keep receiving, on another channel
+    pub fn receive(&mut self, channel: u8) -> Result<(), OTError<R::Error>> {
+        self.machine.receive(channel)
+    }
+
+    /// Schedule a receive window (see `OTRadioOperation::receive_at`)
+    pub fn receive_at(&mut self, channel: u8, start: u32, duration: u32) -> Result<(), OTError<R::Error>> {
+        self.machine.receive_at(channel, start, duration)
+    }
+
+    pub fn receive_frame(&mut self) -> Result<OTRadioFrame<'_>, OTError<R::Error>> {
+        self.machine.receive_frame()
+    }
+}
+
+impl<R: OTRadioOperation + OTRadioOperationEnergyScan<Error = <R as OTRadioOperation>::Error>> TypedRadio<R, Receive> {
+    /// Start an energy scan (see `OTRadioOperationEnergyScan::energy_scan`)
+    pub fn energy_scan(&mut self, channel: u8, duration: i16) -> Result<(), OTError<<R as OTRadioOperation>::Error>> {
+        self.machine.energy_scan(channel, duration)
+    }
+}
+
+impl<R: OTRadioOperation> TypedRadio<R, Transmit> {
+    /// Signal the end of the transmission (see `OTRadioOperation::tx_done`)
+    pub fn tx_done(mut self) -> TypedRadio<R, Receive> {
+        self.machine.tx_done();
+        TypedRadio::with(self.machine)
+    }
+
+    /// Switch to receive before the end of the transmission was signalled
+    pub fn receive(self, channel: u8) -> Transition<R, Transmit, Receive> {
+        self.transition(|machine| machine.receive(channel))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use super::*;
+    use crate::radio::builder::FrameBuilder;
+    use crate::radio::frame::{FrameVersion, MacAddress};
+    use crate::radio::{OTFrameInformation, OTMacKeyMaterial, RadioIEInfo, TransmitFrame, OT_MAC_KEY_SIZE};
+    use crate::sim::{SimMedium, SimRadio};
+
+    const CHANNEL: u8 = 11;
+
+    static NO_IE_INFO: RadioIEInfo = RadioIEInfo {
+        network_time_offset: 0,
+        time_ie_offset: 0,
+        time_sync_sequency: 0,
+    };
+
+    fn data_frame() -> TransmitFrame {
+        let mut frame = TransmitFrame::new();
+        FrameBuilder::data(FrameVersion::Version2006)
+            .sequence_number(Some(1))
+            .dst(Some(0x1234), MacAddress::Short(0xffff))
+            .src(None, MacAddress::Short(0x0001))
+            .payload(b"state")
+            .build::<Infallible>(&mut frame)
+            .unwrap();
+        frame
+    }
+
+    fn tx_frame(psdu: &[u8]) -> OTRadioFrame<'_> {
+        OTRadioFrame {
+            psdu,
+            channel: CHANNEL,
+            radio_type: 0,
+            frame_information: OTFrameInformation::TxInfo {
+                aes_key: OTMacKeyMaterial::Key([0; OT_MAC_KEY_SIZE]),
+                io_info: &NO_IE_INFO,
+                tx_delay_base_time: 0,
+                tx_delay: 0,
+                max_csma_backoffs: 0,
+                max_frame_retries: 0,
+                rx_channel_after_tx_done: CHANNEL,
+                is_header_updated: false,
+                is_a_retx: false,
+                csma_ca_enabled: false,
+                csl_present: false,
+                is_security_processed: false,
+            },
+        }
+    }
+
+    #[test]
+    fn illegal_transitions_are_rejected() {
+        let medium = SimMedium::new(1);
+        let frame = data_frame();
+        let mut machine = RadioStateMachine::new(medium.add_node([1; 8])).unwrap();
+        assert_eq!(machine.state(), OTRadioState::Disabled);
+
+        // (name, state of the machine, operation)
+        type Operation = fn(&mut RadioStateMachine<SimRadio>, &TransmitFrame) -> Result<(), OTError<Infallible>>;
+        let cases: [(&str, OTRadioState, Operation); 9] = [
+            ("disable from disabled", OTRadioState::Disabled, |machine, _| machine.disable()),
+            ("sleep from disabled", OTRadioState::Disabled, |machine, _| machine.sleep()),
+            ("receive from disabled", OTRadioState::Disabled, |machine, _| machine.receive(CHANNEL)),
+            ("receive_at from disabled", OTRadioState::Disabled, |machine, _| machine.receive_at(CHANNEL, 0, 100)),
+            ("energy_scan from disabled", OTRadioState::Disabled, |machine, _| machine.energy_scan(CHANNEL, 1)),
+            ("transmit from disabled", OTRadioState::Disabled, |machine, frame| {
+                machine.transmit(tx_frame(frame.psdu()))
+            }),
+            ("enable from sleep", OTRadioState::Sleep, |machine, _| machine.enable()),
+            ("disable from receive", OTRadioState::Receive, |machine, _| machine.disable()),
+            ("enable from receive", OTRadioState::Receive, |machine, _| machine.enable()),
+        ];
+
+        for (name, state, operation) in cases {
+            match state {
+                OTRadioState::Disabled => {},
+                OTRadioState::Sleep => machine.sleep().unwrap(),
+                _ => machine.receive(CHANNEL).unwrap(),
+            }
+            assert_eq!(machine.state(), state, "{}", name);
+            assert_eq!(operation(&mut machine, &frame), Err(OTError::InvalidState), "{}", name);
+
+            // The radio was not called
+            assert_eq!(machine.state(), state, "{}", name);
+            assert_eq!(machine.radio().state(), state, "{}", name);
+            if name == "transmit from disabled" {
+                machine.enable().unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn transmit_from_sleep_needs_the_capability() {
+        let medium = SimMedium::new(2);
+        let frame = data_frame();
+        let mut machine = RadioStateMachine::new(medium.add_node([1; 8])).unwrap();
+        machine.enable().unwrap();
+
+        machine.capabilities &= !(Capabilities::SleepToTx as OTRadioCapabilities);
+        assert_eq!(machine.transmit(tx_frame(frame.psdu())), Err(OTError::InvalidState));
+        assert_eq!(machine.radio().state(), OTRadioState::Sleep);
+
+        machine.capabilities |= Capabilities::SleepToTx as OTRadioCapabilities;
+        machine.transmit(tx_frame(frame.psdu())).unwrap();
+        assert_eq!(machine.state(), OTRadioState::Transmit);
+        assert_eq!(machine.radio().state(), OTRadioState::Transmit);
+
+        // The end of the transmission returns to Receive, a second tx_done does nothing
+        machine.tx_done();
+        assert_eq!(machine.state(), OTRadioState::Receive);
+        machine.tx_done();
+        assert_eq!(machine.state(), OTRadioState::Receive);
+    }
+
+    #[test]
+    fn typed_radio_transitions() {
+        let medium = SimMedium::new(3);
+        let frame = data_frame();
+        let radio = TypedRadio::new(medium.add_node([1; 8])).unwrap();
+
+        let mut radio = radio.enable().map_err(|(_, error)| error).unwrap();
+        radio.receive_at(CHANNEL, 0, 100).unwrap();
+        radio.energy_scan(CHANNEL, 1).unwrap();
+
+        // Sleep to Transmit (the sim advertises Capabilities::SleepToTx)
+        let radio = radio.transmit(tx_frame(frame.psdu())).map_err(|(_, error)| error).unwrap();
+        let mut radio = radio.tx_done();
+        assert_eq!(radio.radio().state(), OTRadioState::Transmit);
+
+        radio.receive(CHANNEL + 1).unwrap();
+        assert_eq!(radio.radio().state(), OTRadioState::Receive);
+        radio.receive_at(CHANNEL, 0, 100).unwrap();
+        radio.energy_scan(CHANNEL, 1).unwrap();
+
+        let radio = radio.sleep().map_err(|(_, error)| error).unwrap();
+        let radio = radio.disable().map_err(|(_, error)| error).unwrap();
+        assert_eq!(radio.into_inner().state(), OTRadioState::Disabled);
+    }
+
+    #[test]
+    fn typed_radio_needs_a_disabled_radio() {
+        let medium = SimMedium::new(4);
+        let mut radio = medium.add_node([1; 8]);
+        OTRadioOperation::enable(&mut radio).unwrap();
+        assert!(matches!(TypedRadio::new(radio), Err(OTError::InvalidState)));
+    }
+}
@@ -0,0 +1,648 @@
+//!
+//! Software SubMac (CSMA-CA, frame retries and ACK timeout)
+//!
+//! Radios that do not advertise `Capabilities::CSMABackoff`, `Capabilities::TransmitRetries` or
+//! `Capabilities::AckTimeout` only know how to put a single frame on air. The SubMac wraps such a radio and
+//! performs the IEEE 802.15.4 unslotted CSMA-CA procedure, retransmits unacknowledged frames and watches the
+//! ACK wait window, reporting the outcome through `OTRadioOperationHandles::tx_done`.
+//!
+//! Backoffs and the ACK wait window are shorter than a millisecond or not a whole number of milliseconds, so the
+//! SubMac is timed by an `OTAlarmMicro`. It is driven from the platform main loop:
+//!     - `transmit` starts sending a frame
+//!     - `process` must be called whenever the alarm may have fired
+//!     - `receive_frame` must be called whenever the radio may have received a frame
+//!
+
+use super::frame::{Frame, FrameType};
+use super::phy::{PhyProfile, PHY_PROFILE_PAGE_0};
+use super::{
+    OTFrameInformation, OTMacKeyMaterial, OTRadioFrame, OTRadioOperation, OTRadioOperationHandles, RadioIEInfo,
+    ReceiveFrame, TransmitFrame,
+};
+use crate::alarm::OTAlarmMicro;
+use crate::entropy::OTEntropy;
+use crate::error::OTError;
+
+// aUnitBackoffPeriod (IEEE 802.15.4-2006) in symbols
+pub const UNIT_BACKOFF_PERIOD: u32 = 20;
+// macMinBE (IEEE 802.15.4-2006)
+pub const MIN_BACKOFF_EXPONENT: u8 = 3;
+// macMaxBE (IEEE 802.15.4-2006)
+pub const MAX_BACKOFF_EXPONENT: u8 = 5;
+// Time to wait for an ACK after a frame was sent (in microseconds)
+pub const ACK_TIMEOUT: u32 = 16_000;
+
+/// State of the SubMac transmit procedure
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SubMacState {
+    // No transmission in progress
+    Idle,
+    // Waiting for the random CSMA-CA backoff to elapse
+    CsmaBackoff,
+    // Waiting for the ACK of a sent frame
+    AckWait,
+}
+
+/// Transmit information of the frame being sent
+struct PendingTransmit {
+    channel: u8,
+    radio_type: u8,
+    aes_key: OTMacKeyMaterial,
+    ie_info: RadioIEInfo,
+    tx_delay_base_time: u32,
+    tx_delay: u32,
+    max_csma_backoffs: u8,
+    max_frame_retries: u8,
+    rx_channel_after_tx_done: u8,
+    is_header_updated: bool,
+    is_a_retx: bool,
+    csma_ca_enabled: bool,
+    csl_present: bool,
+    is_security_processed: bool,
+    sequence_number: Option<u8>,
+    ack_request: bool,
+}
+
+impl PendingTransmit {
+    /// Borrow the pending frame as a radio frame
+    fn radio_frame<'f>(&'f self, frame: &'f TransmitFrame) -> OTRadioFrame<'f> {
+        OTRadioFrame {
+            psdu: frame.psdu(),
+            channel: self.channel,
+            radio_type: self.radio_type,
+            frame_information: OTFrameInformation::TxInfo {
+                aes_key: self.aes_key,
+                io_info: &self.ie_info,
+                tx_delay_base_time: self.tx_delay_base_time,
+                tx_delay: self.tx_delay,
+                max_csma_backoffs: self.max_csma_backoffs,
+                max_frame_retries: self.max_frame_retries,
+                rx_channel_after_tx_done: self.rx_channel_after_tx_done,
+                is_header_updated: self.is_header_updated,
+                is_a_retx: self.is_a_retx,
+                csma_ca_enabled: self.csma_ca_enabled,
+                csl_present: self.csl_present,
+                is_security_processed: self.is_security_processed,
+            },
+        }
+    }
+}
+
+/// Software SubMac wrapping a radio, an alarm and an entropy source
+pub struct SubMac<R, A, N> {
+    radio: R,
+    alarm: A,
+    entropy: N,
+    state: SubMacState,
+    tx_frame: TransmitFrame,
+    rx_frame: ReceiveFrame,
+    pending: Option<PendingTransmit>,
+    csma_backoffs: u8,
+    frame_retries: u8,
+    profile: PhyProfile,
+}
+
+impl<R, A, N> SubMac<R, A, N>
+where
+    R: OTRadioOperation,
+    A: OTAlarmMicro,
+    N: OTEntropy,
+{
+    /// Create a SubMac around a radio
+    pub fn new(radio: R, alarm: A, entropy: N) -> Self {
+        Self {
+            radio,
+            alarm,
+            entropy,
+            state: SubMacState::Idle,
+            tx_frame: TransmitFrame::new(),
+            rx_frame: ReceiveFrame::new(),
+            pending: None,
+            csma_backoffs: 0,
+            frame_retries: 0,
+            profile: PHY_PROFILE_PAGE_0,
+        }
+    }
+
+    /// Set the PHY profile of the radio (page 0 by default)
+    pub fn set_phy_profile(&mut self, profile: PhyProfile) {
+        self.profile = profile;
+    }
+
+    /// Get the PHY profile of the radio
+    pub fn phy_profile(&self) -> &PhyProfile {
+        &self.profile
+    }
+
+    /// Get the current SubMac state
+    pub fn state(&self) -> SubMacState {
+        self.state
+    }
+
+    /// Get the wrapped radio
+    pub fn radio(&mut self) -> &mut R {
+        &mut self.radio
+    }
+
+    /// Get the wrapped alarm
+    pub fn alarm(&mut self) -> &mut A {
+        &mut self.alarm
+    }
+
+    /// Release the wrapped radio, alarm and entropy source
+    pub fn into_inner(self) -> (R, A, N) {
+        (self.radio, self.alarm, self.entropy)
+    }
+
+    /// Start sending a frame
+    ///
+    /// The frame is copied, so the caller's buffer may be reused immediately. The outcome is reported through
+    /// `OTRadioOperationHandles::tx_done` from `process` or `receive_frame`.
+    ///
+    /// Returns:
+    ///     OTError::InvalidState if a transmission is already in progress
+    ///     OTError::InvalidArgs if the frame does not carry transmit information
+    pub fn transmit(&mut self, frame: OTRadioFrame) -> Result<(), OTError<R::Error>> {
+        if self.state != SubMacState::Idle {
+            return Err(OTError::InvalidState);
+        }
+
+        let OTFrameInformation::TxInfo {
+            aes_key,
+            io_info,
+            tx_delay_base_time,
+            tx_delay,
+            max_csma_backoffs,
+            max_frame_retries,
+            rx_channel_after_tx_done,
+            is_header_updated,
+            is_a_retx,
+            csma_ca_enabled,
+            csl_present,
+            is_security_processed,
+        } = frame.frame_information else {
+            return Err(OTError::InvalidArgs);
+        };
+
+        let parsed = Frame::parse(frame.psdu)?;
+        let sequence_number = parsed.sequence_number();
+        let ack_request = parsed.ack_request();
+
+        self.tx_frame.set_length(frame.psdu.len())?;
+        self.tx_frame.psdu_mut().copy_from_slice(frame.psdu);
+        self.pending = Some(PendingTransmit {
+            channel: frame.channel,
+            radio_type: frame.radio_type,
+            aes_key,
+            ie_info: *io_info,
+            tx_delay_base_time,
+            tx_delay,
+            max_csma_backoffs,
+            max_frame_retries,
+            rx_channel_after_tx_done,
+            is_header_updated,
+            is_a_retx,
+            csma_ca_enabled,
+            csl_present,
+            is_security_processed,
+            sequence_number,
+            ack_request,
+        });
+        self.csma_backoffs = 0;
+        self.frame_retries = 0;
+
+        self.start_csma_backoff()
+    }
+
+    /// Abort the transmission in progress (if any), reporting OTError::Abort
+    pub fn abort<H>(&mut self, handles: &mut H) -> Result<(), OTError<R::Error>>
+    where
+        H: OTRadioOperationHandles<Error = R::Error>,
+    {
+        if self.state == SubMacState::Idle {
+            return Ok(());
+        }
+        self.alarm.stop_alarm();
+        self.finish(handles, false, Err(OTError::Abort))
+    }
+
+    /// Handle the alarm if it has fired
+    pub fn process<H>(&mut self, handles: &mut H) -> Result<(), OTError<R::Error>>
+    where
+        H: OTRadioOperationHandles<Error = R::Error>,
+    {
+        if !self.alarm.alarm_fired() {
+            return Ok(());
+        }
+
+        match self.state {
+            SubMacState::Idle => Ok(()),
+            SubMacState::CsmaBackoff => self.attempt_transmit(handles),
+            SubMacState::AckWait => self.handle_ack_timeout(handles),
+        }
+    }
+
+    /// Receive a frame from the radio
+    ///
+    /// An ACK matching the frame in flight completes the transmission and is not returned.
+    ///
+    /// Returns:
+    ///     The received frame, or None if no frame was available or the frame was the awaited ACK
+    pub fn receive_frame<H>(&mut self, handles: &mut H) -> Result<Option<&ReceiveFrame>, OTError<R::Error>>
+    where
+        H: OTRadioOperationHandles<Error = R::Error>,
+    {
+        match self.radio.receive_frame() {
+            Ok(frame) => self.rx_frame.copy_from(&frame)?,
+            Err(OTError::NoFrameReceived) => return Ok(None),
+            Err(error) => return Err(error),
+        }
+
+        if self.state == SubMacState::AckWait && self.is_awaited_ack() {
+            self.alarm.stop_alarm();
+            self.finish(handles, true, Ok(()))?;
+            return Ok(None);
+        }
+
+        Ok(Some(&self.rx_frame))
+    }
+
+    /// Check whether the last received frame acknowledges the frame in flight
+    fn is_awaited_ack(&self) -> bool {
+        let (Ok(frame), Some(pending)) = (self.rx_frame.parse::<()>(), self.pending.as_ref()) else {
+            return false;
+        };
+        frame.frame_type() == FrameType::Ack
+            && (pending.sequence_number.is_none() || frame.sequence_number() == pending.sequence_number)
+    }
+
+    /// Draw a random number from the entropy source
+    fn random(&mut self) -> Result<u32, OTError<R::Error>> {
+        let mut bytes = [0u8; 4];
+        self.entropy.get_entropy(&mut bytes).map_err(|_| OTError::Failed)?;
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    /// Get the CSMA-CA backoff for the current attempt (in microseconds)
+    fn backoff_duration(&mut self) -> Result<u32, OTError<R::Error>> {
+        let exponent = (MIN_BACKOFF_EXPONENT + self.csma_backoffs).min(MAX_BACKOFF_EXPONENT);
+        let periods = self.random()? % (1 << exponent);
+        Ok(periods * UNIT_BACKOFF_PERIOD * self.profile.symbol_time())
+    }
+
+    /// Wait for a random backoff before the next transmit attempt
+    fn start_csma_backoff(&mut self) -> Result<(), OTError<R::Error>> {
+        let csma_ca_enabled = self.pending.as_ref().is_some_and(|pending| pending.csma_ca_enabled);
+        let backoff = if csma_ca_enabled { self.backoff_duration()? } else { 0 };
+
+        self.state = SubMacState::CsmaBackoff;
+        let now = self.alarm.get_now();
+        self.alarm.start_alarm_at(now, backoff);
+        Ok(())
+    }
+
+    /// Put the frame on air after the backoff elapsed
+    fn attempt_transmit<H>(&mut self, handles: &mut H) -> Result<(), OTError<R::Error>>
+    where
+        H: OTRadioOperationHandles<Error = R::Error>,
+    {
+        let Some(pending) = self.pending.as_ref() else {
+            self.state = SubMacState::Idle;
+            return Ok(());
+        };
+        let ack_request = pending.ack_request;
+        let max_csma_backoffs = pending.max_csma_backoffs;
+
+        match self.radio.transmit(pending.radio_frame(&self.tx_frame)) {
+            Ok(()) if ack_request => {
+                self.state = SubMacState::AckWait;
+                let now = self.alarm.get_now();
+                self.alarm.start_alarm_at(now, ACK_TIMEOUT);
+                Ok(())
+            },
+            Ok(()) => self.finish(handles, false, Ok(())),
+            Err(OTError::ChannelAccessFailure) if self.csma_backoffs < max_csma_backoffs => {
+                self.csma_backoffs += 1;
+                self.start_csma_backoff()
+            },
+            Err(error) => self.finish(handles, false, Err(error)),
+        }
+    }
+
+    /// Retransmit the frame or give up once the ACK wait window expired
+    fn handle_ack_timeout<H>(&mut self, handles: &mut H) -> Result<(), OTError<R::Error>>
+    where
+        H: OTRadioOperationHandles<Error = R::Error>,
+    {
+        let Some(pending) = self.pending.as_mut() else {
+            self.state = SubMacState::Idle;
+            return Ok(());
+        };
+
+        if self.frame_retries < pending.max_frame_retries {
+            self.frame_retries += 1;
+            self.csma_backoffs = 0;
+            pending.is_a_retx = true;
+            // The frame counter must stay the same across retransmissions
+            pending.is_header_updated = true;
+            return self.start_csma_backoff();
+        }
+
+        self.finish(handles, false, Err(OTError::NoAck))
+    }
+
+    /// Report the outcome of the transmission and return to the receive channel
+    fn finish<H>(&mut self, handles: &mut H, with_ack: bool, result: Result<(), OTError<R::Error>>) -> Result<(), OTError<R::Error>>
+    where
+        H: OTRadioOperationHandles<Error = R::Error>,
+    {
+        self.state = SubMacState::Idle;
+        self.csma_backoffs = 0;
+        self.frame_retries = 0;
+
+        let Some(pending) = self.pending.take() else {
+            return Ok(());
+        };
+
+        let ack_frame = if with_ack { Some(self.rx_frame.as_radio_frame()) } else { None };
+        handles
+            .tx_done(pending.radio_frame(&self.tx_frame), ack_frame, result)
+            .map_err(OTError::Platform)?;
+
+        if pending.rx_channel_after_tx_done != pending.channel {
+            self.radio.receive(pending.rx_channel_after_tx_done)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::radio::builder::FrameBuilder;
+    use crate::radio::frame::{FrameVersion, MacAddress};
+    use crate::radio::OTRadioConfiguration;
+    use crate::sim::{SimAlarm, SimEntropy, SimMedium, SimRadio};
+
+    const PAN_ID: u16 = 0x1234;
+    const CHANNEL: u8 = 11;
+    // Time between two steps of the simulation (in microseconds)
+    const STEP: u64 = 10;
+    // Start of a frame relative to the receive timestamp (end of the SFD) in the simulation
+    const SFD_TIME: u64 = 5 * 32;
+
+    type SimSubMac = SubMac<SimRadio, SimAlarm, SimEntropy>;
+
+    /// Outcome of a transmission reported through `tx_done`
+    struct TxDone {
+        result: Result<(), OTError<Infallible>>,
+        with_ack: bool,
+        is_a_retx: bool,
+        time: u64,
+    }
+
+    /// Handles recording the outcome of the transmissions
+    struct Events {
+        medium: SimMedium,
+        tx_done: Vec<TxDone>,
+    }
+
+    impl OTRadioOperationHandles for Events {
+        type Error = Infallible;
+
+        fn tx_started(&mut self, _frame: OTRadioFrame) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn tx_done(
+            &mut self,
+            frame: OTRadioFrame,
+            ack_frame: Option<OTRadioFrame>,
+            result: Result<(), OTError<Self::Error>>,
+        ) -> Result<(), Self::Error> {
+            let OTFrameInformation::TxInfo { is_a_retx, .. } = frame.frame_information else {
+                panic!("tx_done without transmit information");
+            };
+            self.tx_done.push(TxDone {
+                result,
+                with_ack: ack_frame.is_some(),
+                is_a_retx,
+                time: self.medium.now(),
+            });
+            Ok(())
+        }
+
+        fn diag_tx_done(&mut self, _frame: OTRadioFrame, _result: Result<(), OTError<Self::Error>>) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn get_raw_power_setting(&mut self, _channel: u8, _raw_power_setting_buffer: &mut [u8]) -> Result<(), OTError<Self::Error>> {
+            Err(OTError::NotImplemented)
+        }
+    }
+
+    /// Add a node listening on CHANNEL with the short address `0x0100 + node id`
+    fn add_node(medium: &SimMedium, eui64: u8) -> SimRadio {
+        let mut radio = medium.add_node([eui64; 8]);
+        radio.set_pan_id(PAN_ID).unwrap();
+        radio.set_short_address(0x0100 + radio.node_id() as u16).unwrap();
+        radio.enable().unwrap();
+        radio.receive(CHANNEL).unwrap();
+        radio
+    }
+
+    /// Set up a SubMac sender (node 0) and a receiver (node 1)
+    fn setup(seed: u64) -> (SimMedium, SimSubMac, SimRadio, Events) {
+        let medium = SimMedium::new(seed);
+        let mut radio = add_node(&medium, 1);
+        radio.set_ack_handling(false);
+        let submac = SubMac::new(radio, medium.alarm(), medium.entropy());
+        let receiver = add_node(&medium, 2);
+        let events = Events {
+            medium: medium.clone(),
+            tx_done: Vec::new(),
+        };
+        (medium, submac, receiver, events)
+    }
+
+    /// Send a data frame requesting an ACK to a node
+    fn transmit(
+        submac: &mut SimSubMac,
+        dst: usize,
+        max_csma_backoffs: u8,
+        max_frame_retries: u8,
+        csma_ca_enabled: bool,
+    ) -> Result<(), OTError<Infallible>> {
+        let mut frame = TransmitFrame::new();
+        FrameBuilder::data(FrameVersion::Version2006)
+            .sequence_number(Some(1))
+            .dst(Some(PAN_ID), MacAddress::Short(0x0100 + dst as u16))
+            .src(None, MacAddress::Short(0x0100))
+            .ack_request(true)
+            .payload(b"submac")
+            .build(&mut frame)?;
+
+        let ie_info = RadioIEInfo::default();
+        submac.transmit(OTRadioFrame {
+            psdu: frame.psdu(),
+            channel: CHANNEL,
+            radio_type: 0,
+            frame_information: OTFrameInformation::TxInfo {
+                aes_key: OTMacKeyMaterial::Key([0; 16]),
+                io_info: &ie_info,
+                tx_delay_base_time: 0,
+                tx_delay: 0,
+                max_csma_backoffs,
+                max_frame_retries,
+                rx_channel_after_tx_done: CHANNEL,
+                is_header_updated: false,
+                is_a_retx: false,
+                csma_ca_enabled,
+                csl_present: false,
+                is_security_processed: false,
+            },
+        })
+    }
+
+    /// Run the simulation until the transmission is reported (or for at most the given time)
+    fn run(medium: &SimMedium, submac: &mut SimSubMac, events: &mut Events, duration: u64) {
+        let end = medium.now() + duration;
+        let reported = events.tx_done.len();
+        while medium.now() < end && events.tx_done.len() == reported {
+            medium.advance(STEP);
+            submac.process(events).unwrap();
+            while submac.receive_frame(events).unwrap().is_some() {}
+        }
+    }
+
+    /// Get the start times of the frames a node received
+    fn frame_starts(receiver: &mut SimRadio) -> Vec<u64> {
+        let mut starts = Vec::new();
+        while let Ok(frame) = receiver.receive_frame() {
+            if let OTFrameInformation::RxInfo { timestamp, .. } = frame.frame_information {
+                starts.push(timestamp - SFD_TIME);
+            }
+        }
+        starts
+    }
+
+    #[test]
+    fn csma_backoffs_are_whole_unit_backoff_periods() {
+        let (medium, mut submac, mut receiver, mut events) = setup(1);
+        let backoff_period = (UNIT_BACKOFF_PERIOD * submac.phy_profile().symbol_time()) as u64;
+        let max_backoff = (1 << MIN_BACKOFF_EXPONENT) * backoff_period;
+
+        let mut backoffs = Vec::new();
+        for _ in 0..16 {
+            let start = medium.now();
+            transmit(&mut submac, receiver.node_id(), 4, 0, true).unwrap();
+            run(&medium, &mut submac, &mut events, 100_000);
+            // The alarm is only checked once per step, so a zero backoff elapses on the first one
+            let backoff = frame_starts(&mut receiver)[0] - start;
+            backoffs.push(if backoff == STEP { 0 } else { backoff });
+        }
+
+        assert!(events.tx_done.iter().all(|tx| tx.result == Ok(()) && tx.with_ack && !tx.is_a_retx));
+        assert!(backoffs.iter().all(|backoff| backoff % backoff_period == 0 && *backoff < max_backoff));
+        // Backoffs are not rounded up to milliseconds
+        assert!(backoffs.iter().any(|backoff| backoff % 1000 != 0));
+    }
+
+    #[test]
+    fn without_csma_the_frame_is_sent_right_away() {
+        let (medium, mut submac, mut receiver, mut events) = setup(2);
+
+        medium.advance(1234);
+        transmit(&mut submac, receiver.node_id(), 4, 0, false).unwrap();
+        assert_eq!(submac.state(), SubMacState::CsmaBackoff);
+        run(&medium, &mut submac, &mut events, 100_000);
+
+        assert_eq!(frame_starts(&mut receiver), [1234 + STEP]);
+        assert!(events.tx_done[0].result.is_ok());
+        assert_eq!(submac.state(), SubMacState::Idle);
+    }
+
+    #[test]
+    fn retries_until_the_ack_timeout_gives_up() {
+        let (medium, mut submac, mut receiver, mut events) = setup(3);
+        // The receiver gets every attempt, but its ACKs never make it back
+        medium.set_link(receiver.node_id(), 0, None);
+
+        transmit(&mut submac, receiver.node_id(), 4, 3, false).unwrap();
+        run(&medium, &mut submac, &mut events, 1_000_000);
+
+        let starts = frame_starts(&mut receiver);
+        assert_eq!(starts.len(), 4);
+        for attempts in starts.windows(2) {
+            assert!(attempts[1] - attempts[0] >= ACK_TIMEOUT as u64);
+            assert!(attempts[1] - attempts[0] <= ACK_TIMEOUT as u64 + 2 * STEP);
+        }
+        assert_eq!(events.tx_done.len(), 1);
+        assert_eq!(events.tx_done[0].result, Err(OTError::NoAck));
+        assert!(!events.tx_done[0].with_ack);
+        assert!(events.tx_done[0].is_a_retx);
+        assert!(events.tx_done[0].time >= starts[3] + ACK_TIMEOUT as u64);
+
+        // Once the ACKs get through the first attempt is enough
+        medium.set_link(receiver.node_id(), 0, Some(Default::default()));
+        transmit(&mut submac, receiver.node_id(), 4, 3, false).unwrap();
+        run(&medium, &mut submac, &mut events, 1_000_000);
+        assert_eq!(frame_starts(&mut receiver).len(), 1);
+        assert_eq!(events.tx_done[1].result, Ok(()));
+        assert!(events.tx_done[1].with_ack && !events.tx_done[1].is_a_retx);
+    }
+
+    #[test]
+    fn busy_channel_exhausts_the_csma_backoffs() {
+        let (medium, mut submac, mut receiver, mut events) = setup(4);
+        let mut jammer = add_node(&medium, 3);
+        jammer.set_ack_handling(false);
+        // The receiver does not hear the jammer, so only the sender's CCA is affected
+        medium.set_link(jammer.node_id(), receiver.node_id(), None);
+
+        let mut jam = TransmitFrame::new();
+        FrameBuilder::data(FrameVersion::Version2006)
+            .dst(Some(PAN_ID), MacAddress::Short(0xffff))
+            .payload(&[0; 100])
+            .build::<Infallible>(&mut jam)
+            .unwrap();
+        let ie_info = RadioIEInfo::default();
+        let jam_frame = || OTRadioFrame {
+            psdu: jam.psdu(),
+            channel: CHANNEL,
+            radio_type: 0,
+            frame_information: OTFrameInformation::TxInfo {
+                aes_key: OTMacKeyMaterial::Key([0; 16]),
+                io_info: &ie_info,
+                tx_delay_base_time: 0,
+                tx_delay: 0,
+                max_csma_backoffs: 0,
+                max_frame_retries: 0,
+                rx_channel_after_tx_done: CHANNEL,
+                is_header_updated: false,
+                is_a_retx: false,
+                csma_ca_enabled: false,
+                csl_present: false,
+                is_security_processed: false,
+            },
+        };
+
+        jammer.transmit(jam_frame()).unwrap();
+        transmit(&mut submac, receiver.node_id(), 4, 3, true).unwrap();
+        while events.tx_done.is_empty() && medium.now() < 1_000_000 {
+            medium.advance(STEP);
+            // Keep the channel busy back to back
+            let _ = jammer.transmit(jam_frame());
+            submac.process(&mut events).unwrap();
+        }
+
+        assert_eq!(events.tx_done.len(), 1);
+        assert_eq!(events.tx_done[0].result, Err(OTError::ChannelAccessFailure));
+        assert!(!events.tx_done[0].is_a_retx);
+        assert!(frame_starts(&mut receiver).is_empty());
+    }
+}
@@ -0,0 +1,466 @@
+//!
+//! OpenThread Settings Store on top of the Flash Driver
+//!
+//! Implements the `otPlatSettings*` key/value API on the two swap areas of an `OTFlash` driver. Records are only
+//! ever appended, and record states are changed by clearing flag bits, so every flash write only turns 1 bits into
+//! 0 bits. When the active swap area is full its live records are compacted into the other swap area.
+//!
+//! Swap area layout:
+//! ```text
+//! [marker (4 bytes)][record][record]...
+//! ```
+//!
+//! Record layout (data padded to a multiple of 4 bytes):
+//! ```text
+//! [key (2 bytes)][flags (2 bytes)][length (2 bytes)][reserved (2 bytes)][data]
+//! ```
+//!
+//! A record is written in three steps (header, data, then the add complete flag) so that a power failure at any
+//! point leaves either the old or the new value visible, never a partially written one.
+//!
+
+use crate::error::OTError;
+use crate::flash::OTFlash;
+
+// Marker of the swap area holding the settings
+const SWAP_ACTIVE: u32 = 0xbe5c_c5ee;
+// Marker of a swap area whose content was moved to the other swap area
+const SWAP_INACTIVE: u32 = 0xbe5c_c5ec;
+// Size of the swap area marker (in bytes)
+const SWAP_MARKER_SIZE: u32 = 4;
+// Size of a record header (in bytes)
+const RECORD_HEADER_SIZE: u32 = 8;
+// Flash write alignment of the records (in bytes)
+const RECORD_ALIGNMENT: u32 = 4;
+// Maximum size of a setting value (in bytes)
+pub const SETTINGS_MAX_VALUE_SIZE: usize = 255;
+
+// Record flags (active low)
+// Set when the record header was written
+const FLAG_ADD_BEGIN: u16 = 1 << 0;
+// Set when the record data was fully written
+const FLAG_ADD_COMPLETE: u16 = 1 << 1;
+// Set when the record was deleted
+const FLAG_DELETE: u16 = 1 << 2;
+// Set when the record replaces every earlier value of its key
+const FLAG_FIRST: u16 = 1 << 3;
+
+/// Header of a settings record
+#[derive(Clone, Copy, Debug)]
+struct RecordHeader {
+    key: u16,
+    flags: u16,
+    length: u16,
+}
+
+impl RecordHeader {
+    /// Decode a record header
+    fn from_bytes(bytes: &[u8; RECORD_HEADER_SIZE as usize]) -> Self {
+        Self {
+            key: u16::from_le_bytes([bytes[0], bytes[1]]),
+            flags: u16::from_le_bytes([bytes[2], bytes[3]]),
+            length: u16::from_le_bytes([bytes[4], bytes[5]]),
+        }
+    }
+
+    /// Encode a record header
+    fn to_bytes(self) -> [u8; RECORD_HEADER_SIZE as usize] {
+        let mut bytes = [0xffu8; RECORD_HEADER_SIZE as usize];
+        bytes[0..2].copy_from_slice(&self.key.to_le_bytes());
+        bytes[2..4].copy_from_slice(&self.flags.to_le_bytes());
+        bytes[4..6].copy_from_slice(&self.length.to_le_bytes());
+        bytes
+    }
+
+    /// Check whether the flash at this position was never written
+    fn is_erased(&self) -> bool {
+        self.key == 0xffff && self.flags == 0xffff && self.length == 0xffff
+    }
+
+    /// Check whether a (cleared) flag is set
+    fn has(&self, flag: u16) -> bool {
+        self.flags & flag == 0
+    }
+
+    /// Check whether the record was completely written
+    fn is_complete(&self) -> bool {
+        self.has(FLAG_ADD_BEGIN) && self.has(FLAG_ADD_COMPLETE)
+    }
+
+    /// Check whether the record was completely written and not deleted
+    fn is_valid(&self) -> bool {
+        self.is_complete() && !self.has(FLAG_DELETE)
+    }
+
+    /// Get the size of the record including its padded data (in bytes)
+    fn size(&self) -> u32 {
+        RECORD_HEADER_SIZE + (self.length as u32).next_multiple_of(RECORD_ALIGNMENT)
+    }
+}
+
+/// OpenThread settings store
+pub struct Settings<F> {
+    flash: F,
+    swap_index: u8,
+    swap_size: u32,
+    // Offset of the first free byte in the active swap area
+    swap_used: u32,
+}
+
+impl<F: OTFlash> Settings<F> {
+    /// Create a settings store on top of a flash driver
+    ///
+    /// `init` must be called before the store is used.
+    pub fn new(flash: F) -> Self {
+        Self {
+            flash,
+            swap_index: 0,
+            swap_size: 0,
+            swap_used: SWAP_MARKER_SIZE,
+        }
+    }
+
+    /// Release the flash driver
+    pub fn into_inner(self) -> F {
+        self.flash
+    }
+
+    /// Initialize the store, recovering the active swap area (otPlatSettingsInit)
+    pub fn init(&mut self) -> Result<(), OTError<F::Error>> {
+        self.flash.init().map_err(OTError::Platform)?;
+        self.swap_size = self.flash.get_swap_size().map_err(OTError::Platform)?;
+
+        // If a power failure left both swap areas active both hold a complete copy, so the first one is used
+        if self.read_marker(0)? == SWAP_ACTIVE {
+            self.swap_index = 0;
+        } else if self.read_marker(1)? == SWAP_ACTIVE {
+            self.swap_index = 1;
+        } else {
+            self.swap_index = 0;
+            self.flash.flash_erase(0).map_err(OTError::Platform)?;
+            self.write(0, 0, &SWAP_ACTIVE.to_le_bytes())?;
+        }
+
+        self.swap_used = self.find_swap_end(self.swap_index)?;
+        Ok(())
+    }
+
+    /// Get a value of a key (otPlatSettingsGet)
+    ///
+    /// Params:
+    ///     key - the setting key
+    ///     index - the index of the value among the values of the key
+    ///     buffer - buffer receiving (up to its length) the value
+    ///
+    /// Returns:
+    ///     The full length of the value
+    ///     OTError::NotFound if the key has no value at the index
+    pub fn get(&mut self, key: u16, index: usize, buffer: &mut [u8]) -> Result<usize, OTError<F::Error>> {
+        let (offset, header) = self.find_value(key, index)?.ok_or(OTError::NotFound)?;
+        let length = (header.length as usize).min(buffer.len());
+        self.read(self.swap_index, offset + RECORD_HEADER_SIZE, &mut buffer[..length])?;
+        Ok(header.length as usize)
+    }
+
+    /// Set the value of a key, replacing all its existing values (otPlatSettingsSet)
+    pub fn set(&mut self, key: u16, value: &[u8]) -> Result<(), OTError<F::Error>> {
+        self.append(key, value, true)
+    }
+
+    /// Add a value to a key (otPlatSettingsAdd)
+    pub fn add(&mut self, key: u16, value: &[u8]) -> Result<(), OTError<F::Error>> {
+        self.append(key, value, false)
+    }
+
+    /// Delete one value (or all values when index is None) of a key (otPlatSettingsDelete)
+    ///
+    /// Returns:
+    ///     OTError::NotFound if the key has no value at the index
+    pub fn delete(&mut self, key: u16, index: Option<usize>) -> Result<(), OTError<F::Error>> {
+        let mut deleted = false;
+        let mut current = 0;
+        let mut offset = SWAP_MARKER_SIZE;
+
+        while offset < self.swap_used {
+            let header = self.read_header(self.swap_index, offset)?;
+            if header.key == key && self.is_live(offset, &header)? {
+                if index.is_none_or(|index| index == current) {
+                    self.write_flags(offset, header.flags & !FLAG_DELETE)?;
+                    deleted = true;
+                }
+                current += 1;
+            }
+            offset += header.size();
+        }
+
+        if deleted {
+            Ok(())
+        } else {
+            Err(OTError::NotFound)
+        }
+    }
+
+    /// Remove all settings (otPlatSettingsWipe)
+    pub fn wipe(&mut self) -> Result<(), OTError<F::Error>> {
+        self.flash.flash_erase(1).map_err(OTError::Platform)?;
+        self.flash.flash_erase(0).map_err(OTError::Platform)?;
+        self.write(0, 0, &SWAP_ACTIVE.to_le_bytes())?;
+        self.swap_index = 0;
+        self.swap_used = SWAP_MARKER_SIZE;
+        Ok(())
+    }
+
+    /// Append a record, compacting the swap areas when the active one is full
+    fn append(&mut self, key: u16, value: &[u8], first: bool) -> Result<(), OTError<F::Error>> {
+        if value.len() > SETTINGS_MAX_VALUE_SIZE {
+            return Err(OTError::InvalidArgs);
+        }
+
+        let mut header = RecordHeader {
+            key,
+            flags: 0xffff,
+            length: value.len() as u16,
+        };
+        if first {
+            header.flags &= !FLAG_FIRST;
+        }
+
+        if self.swap_used + header.size() > self.swap_size {
+            self.swap()?;
+            if self.swap_used + header.size() > self.swap_size {
+                return Err(OTError::NoBuffers);
+            }
+        }
+
+        let offset = self.swap_used;
+        header.flags &= !FLAG_ADD_BEGIN;
+        self.write(self.swap_index, offset, &header.to_bytes())?;
+        self.write(self.swap_index, offset + RECORD_HEADER_SIZE, value)?;
+        self.write_flags(offset, header.flags & !FLAG_ADD_COMPLETE)?;
+        self.swap_used += header.size();
+        Ok(())
+    }
+
+    /// Move the live records into the other swap area
+    fn swap(&mut self) -> Result<(), OTError<F::Error>> {
+        let source = self.swap_index;
+        let destination = 1 - source;
+        self.flash.flash_erase(destination).map_err(OTError::Platform)?;
+
+        let mut buffer = [0u8; SETTINGS_MAX_VALUE_SIZE];
+        let mut destination_offset = SWAP_MARKER_SIZE;
+        let mut offset = SWAP_MARKER_SIZE;
+        while offset < self.swap_used {
+            let header = self.read_header(source, offset)?;
+            if self.is_live(offset, &header)? {
+                let length = header.length as usize;
+                self.read(source, offset + RECORD_HEADER_SIZE, &mut buffer[..length])?;
+                self.write(destination, destination_offset, &header.to_bytes())?;
+                self.write(destination, destination_offset + RECORD_HEADER_SIZE, &buffer[..length])?;
+                destination_offset += header.size();
+            }
+            offset += header.size();
+        }
+
+        // Only mark the new swap area active once it holds every record
+        self.write(destination, 0, &SWAP_ACTIVE.to_le_bytes())?;
+        self.write(source, 0, &SWAP_INACTIVE.to_le_bytes())?;
+        self.swap_index = destination;
+        self.swap_used = destination_offset;
+        Ok(())
+    }
+
+    /// Find the record holding the value at an index of a key
+    fn find_value(&mut self, key: u16, index: usize) -> Result<Option<(u32, RecordHeader)>, OTError<F::Error>> {
+        let mut current = 0;
+        let mut offset = SWAP_MARKER_SIZE;
+        while offset < self.swap_used {
+            let header = self.read_header(self.swap_index, offset)?;
+            if header.key == key && self.is_live(offset, &header)? {
+                if current == index {
+                    return Ok(Some((offset, header)));
+                }
+                current += 1;
+            }
+            offset += header.size();
+        }
+        Ok(None)
+    }
+
+    /// Check whether a record is valid and not replaced by a later record of the same key
+    ///
+    /// A complete FIRST record replaces the earlier values of its key even once deleted, so that deleting a value never
+    /// brings back the values it replaced.
+    fn is_live(&mut self, offset: u32, header: &RecordHeader) -> Result<bool, OTError<F::Error>> {
+        if !header.is_valid() {
+            return Ok(false);
+        }
+
+        let mut later = offset + header.size();
+        while later < self.swap_used {
+            let other = self.read_header(self.swap_index, later)?;
+            if other.key == header.key && other.is_complete() && other.has(FLAG_FIRST) {
+                return Ok(false);
+            }
+            later += other.size();
+        }
+        Ok(true)
+    }
+
+    /// Find the end of the records of a swap area
+    fn find_swap_end(&mut self, swap_index: u8) -> Result<u32, OTError<F::Error>> {
+        let mut offset = SWAP_MARKER_SIZE;
+        while offset + RECORD_HEADER_SIZE <= self.swap_size {
+            let header = self.read_header(swap_index, offset)?;
+            // A record whose header was not completely written marks the end of the records
+            if header.is_erased() || !header.has(FLAG_ADD_BEGIN) {
+                break;
+            }
+            offset += header.size();
+        }
+        Ok(offset.min(self.swap_size))
+    }
+
+    fn read_marker(&mut self, swap_index: u8) -> Result<u32, OTError<F::Error>> {
+        let mut marker = [0u8; SWAP_MARKER_SIZE as usize];
+        self.read(swap_index, 0, &mut marker)?;
+        Ok(u32::from_le_bytes(marker))
+    }
+
+    fn read_header(&mut self, swap_index: u8, offset: u32) -> Result<RecordHeader, OTError<F::Error>> {
+        let mut bytes = [0u8; RECORD_HEADER_SIZE as usize];
+        self.read(swap_index, offset, &mut bytes)?;
+        Ok(RecordHeader::from_bytes(&bytes))
+    }
+
+    fn write_flags(&mut self, offset: u32, flags: u16) -> Result<(), OTError<F::Error>> {
+        self.write(self.swap_index, offset + 2, &flags.to_le_bytes())
+    }
+
+    fn read(&mut self, swap_index: u8, offset: u32, buffer: &mut [u8]) -> Result<(), OTError<F::Error>> {
+        self.flash.flash_read(swap_index, offset, buffer).map_err(OTError::Platform)
+    }
+
+    fn write(&mut self, swap_index: u8, offset: u32, buffer: &[u8]) -> Result<(), OTError<F::Error>> {
+        self.flash.flash_write(swap_index, offset, buffer).map_err(OTError::Platform)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use super::*;
+
+    const SWAP_SIZE: usize = 256;
+
+    /// Flash kept in RAM, where writes can only clear bits
+    struct RamFlash {
+        swaps: [[u8; SWAP_SIZE]; 2],
+    }
+
+    impl RamFlash {
+        fn new() -> Self {
+            Self {
+                swaps: [[0xff; SWAP_SIZE]; 2],
+            }
+        }
+    }
+
+    impl OTFlash for RamFlash {
+        type Error = Infallible;
+
+        fn init(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn get_swap_size(&mut self) -> Result<u32, Self::Error> {
+            Ok(SWAP_SIZE as u32)
+        }
+
+        fn flash_erase(&mut self, swap_index: u8) -> Result<(), Self::Error> {
+            self.swaps[swap_index as usize] = [0xff; SWAP_SIZE];
+            Ok(())
+        }
+
+        fn flash_read(&mut self, swap_index: u8, offset: u32, buffer: &mut [u8]) -> Result<(), Self::Error> {
+            let offset = offset as usize;
+            buffer.copy_from_slice(&self.swaps[swap_index as usize][offset..offset + buffer.len()]);
+            Ok(())
+        }
+
+        fn flash_write(&mut self, swap_index: u8, offset: u32, buffer: &[u8]) -> Result<(), Self::Error> {
+            let offset = offset as usize;
+            for (cell, byte) in self.swaps[swap_index as usize][offset..].iter_mut().zip(buffer) {
+                *cell &= byte;
+            }
+            Ok(())
+        }
+    }
+
+    fn settings() -> Settings<RamFlash> {
+        let mut settings = Settings::new(RamFlash::new());
+        settings.init().unwrap();
+        settings
+    }
+
+    fn get(settings: &mut Settings<RamFlash>, key: u16, index: usize) -> Result<[u8; 4], OTError<Infallible>> {
+        let mut buffer = [0u8; 4];
+        settings.get(key, index, &mut buffer)?;
+        Ok(buffer)
+    }
+
+    #[test]
+    fn set_replaces_values() {
+        let mut settings = settings();
+        settings.set(1, b"AAAA").unwrap();
+        settings.add(1, b"BBBB").unwrap();
+        settings.set(1, b"CCCC").unwrap();
+
+        assert_eq!(get(&mut settings, 1, 0), Ok(*b"CCCC"));
+        assert_eq!(get(&mut settings, 1, 1), Err(OTError::NotFound));
+    }
+
+    #[test]
+    fn delete_replaced_value_stays_deleted() {
+        let mut settings = settings();
+        settings.set(1, b"AAAA").unwrap();
+        settings.set(1, b"BBBB").unwrap();
+        settings.delete(1, None).unwrap();
+
+        assert_eq!(get(&mut settings, 1, 0), Err(OTError::NotFound));
+        assert_eq!(settings.delete(1, None), Err(OTError::NotFound));
+    }
+
+    #[test]
+    fn delete_first_value_keeps_added_values() {
+        let mut settings = settings();
+        settings.set(1, b"ZZZZ").unwrap();
+        settings.set(1, b"AAAA").unwrap();
+        settings.add(1, b"BBBB").unwrap();
+        settings.delete(1, Some(0)).unwrap();
+
+        assert_eq!(get(&mut settings, 1, 0), Ok(*b"BBBB"));
+        assert_eq!(get(&mut settings, 1, 1), Err(OTError::NotFound));
+    }
+
+    #[test]
+    fn deleted_values_stay_deleted_after_swap() {
+        let mut settings = settings();
+        settings.set(1, b"AAAA").unwrap();
+        settings.set(1, b"BBBB").unwrap();
+        settings.delete(1, None).unwrap();
+
+        // Fill the swap area with another key until the records are compacted
+        for _ in 0..32 {
+            settings.set(2, b"DDDD").unwrap();
+        }
+        assert_eq!(get(&mut settings, 1, 0), Err(OTError::NotFound));
+        assert_eq!(get(&mut settings, 2, 0), Ok(*b"DDDD"));
+
+        let mut settings = Settings::new(settings.into_inner());
+        settings.init().unwrap();
+        assert_eq!(get(&mut settings, 1, 0), Err(OTError::NotFound));
+        assert_eq!(get(&mut settings, 2, 0), Ok(*b"DDDD"));
+    }
+}
@@ -0,0 +1,1448 @@
+//!
+//! In-Memory Simulated IEEE 802.15.4 Medium
+//!
+//! A `SimMedium` models a set of radios sharing a virtual air interface and a virtual clock. Each node gets a
+//! `SimRadio` (implementing the radio traits), a `SimAlarm` and a `SimEntropy`, so the upper layers can be run
+//! against several nodes at once without hardware. Time only moves when `SimMedium::advance` is called, which
+//! keeps multi-node scenarios fully deterministic.
+//!
+//! The medium models:
+//!     - channels (nodes only hear transmissions on the channel they listen on)
+//!     - a directed link matrix with per-link RSSI and loss probability
+//!     - collisions between overlapping transmissions heard by the same receiver
+//!     - immediate and enhanced ACK generation, with the frame pending bit taken from the source match table
+//!       (a `SrcMatchTable` of SIM_SRC_MATCH_ENTRIES short and extended addresses)
+//!
+//! Like a hardware radio, a `SimRadio` completes transmissions and energy scans asynchronously: after advancing the
+//! medium, `SimRadio::process` reports them to the `OTRadioOperationHandles` and `OTRadioOperationEnergyScanHandles`
+//! of the node. With `SimRadio::set_ack_handling(false)` it only puts frames on air instead, for a software `SubMac`
+//! to drive.
+//!
+//! The simulation is only built with the `sim` feature (and for the tests of this crate).
+//!
+
+use core::cell::RefCell;
+use core::convert::Infallible;
+
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+
+use crate::alarm::{OTAlarm, OTAlarmMicro};
+use crate::entropy::OTEntropy;
+use crate::error::OTError;
+use crate::radio::builder::FrameBuilder;
+use crate::radio::frame::{Frame, FrameType, FrameVersion, MacAddress};
+use crate::radio::srcmatch::SrcMatchTable;
+use crate::radio::{
+    Capabilities, OTExtAddress, OTFrameInformation, OTKeyType, OTLinkMetrics, OTPanId, OTRadioCapabilities,
+    OTRadioConfiguration, OTRadioConfigurationCapTransmit, OTRadioFrame, OTRadioOperation, OTRadioOperationEnergyScan, OTRadioOperationEnergyScanHandles,
+    OTMacKeyMaterial, OTRadioOperationHandles, OTRadioState, OTShortAddress, RadioIEInfo, ReceiveFrame, TransmitFrame,
+    OT_PANID_BROADCAST, OT_RADIO_2P4GHZ_OQPSK_CHANNEL_MASK, OT_RADIO_BITS_PER_OCTET, OT_RADIO_BIT_RATE,
+    OT_RADIO_FRAME_MAX_SIZE, OT_RADIO_SYMBOL_TIME,
+};
+
+// Octets sent ahead of the PSDU: preamble (4), SFD (1) and PHR (1)
+const SHR_PHR_SIZE: u64 = 6;
+// Octets sent before the end of the SFD: preamble (4) and SFD (1)
+const SHR_SIZE: u64 = 5;
+// aTurnaroundTime (IEEE 802.15.4-2006) in symbols
+const TURNAROUND_TIME: u64 = 12;
+// Time a sender waits for an ACK after its frame (in microseconds): the ACK starts aTurnaroundTime after the frame,
+// so even the longest one has been delivered by then
+const ACK_WAIT_TIME: u64 = TURNAROUND_TIME * OT_RADIO_SYMBOL_TIME as u64 + air_time(OT_RADIO_FRAME_MAX_SIZE);
+// Short address used for broadcasts
+const BROADCAST_SHORT_ADDRESS: OTShortAddress = 0xffff;
+// Number of short and extended addresses in the source match table of a simulated radio
+pub const SIM_SRC_MATCH_ENTRIES: usize = 64;
+// Energy measured on an idle channel (in dBm)
+pub const SIM_NOISE_FLOOR: i8 = -100;
+// Receive sensitivity of the simulated radios (in dBm)
+pub const SIM_RECEIVE_SENSITIVITY: i8 = -100;
+
+/// Index of a node in the simulated medium
+pub type NodeId = usize;
+
+/// Directed link between two nodes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SimLink {
+    // Signal strength measured by the receiver (in dBm)
+    pub rssi: i8,
+    // Probability that a frame is lost on this link (in percent)
+    pub loss_percent: u8,
+}
+
+impl Default for SimLink {
+    fn default() -> Self {
+        Self {
+            rssi: -50,
+            loss_percent: 0,
+        }
+    }
+}
+
+/// Time on air of a PSDU (in microseconds)
+const fn air_time(psdu_length: usize) -> u64 {
+    (psdu_length as u64 + SHR_PHR_SIZE) * octet_time()
+}
+
+/// Time needed to send one octet (in microseconds)
+const fn octet_time() -> u64 {
+    (OT_RADIO_BITS_PER_OCTET * 1_000_000 / OT_RADIO_BIT_RATE) as u64
+}
+
+/// Small deterministic pseudo random number generator (xorshift64*)
+#[derive(Clone, Debug)]
+struct Prng {
+    state: u64,
+}
+
+impl Prng {
+    fn new(seed: u64) -> Self {
+        Self { state: seed.max(1) }
+    }
+
+    fn next(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+}
+
+/// A frame on air
+struct Transmission {
+    sender: NodeId,
+    channel: u8,
+    start: u64,
+    end: u64,
+    psdu: Vec<u8>,
+    // Whether the receivers were already handed the frame
+    delivered: bool,
+}
+
+/// A frame waiting in a node's receive queue
+struct Reception {
+    psdu: Vec<u8>,
+    channel: u8,
+    timestamp: u64,
+    rssi: i8,
+    lqi: u8,
+    acked_with_frame_pending: bool,
+}
+
+impl Reception {
+    /// Get the receive information of the frame
+    fn rx_info(&self) -> OTFrameInformation<'static> {
+        OTFrameInformation::RxInfo {
+            timestamp: self.timestamp,
+            ack_frame_counter: 0,
+            ack_key_id: 0,
+            rssi: self.rssi,
+            lqi: self.lqi,
+            acked_with_frame_pending: self.acked_with_frame_pending,
+            acked_with_sec_enh_ack: false,
+        }
+    }
+}
+
+/// Transmit information of the frame being sent, handed back in `tx_done`
+struct TxInfo {
+    channel: u8,
+    radio_type: u8,
+    aes_key: OTMacKeyMaterial,
+    ie_info: RadioIEInfo,
+    tx_delay_base_time: u32,
+    tx_delay: u32,
+    max_csma_backoffs: u8,
+    max_frame_retries: u8,
+    rx_channel_after_tx_done: u8,
+    is_header_updated: bool,
+    is_a_retx: bool,
+    csma_ca_enabled: bool,
+    csl_present: bool,
+    is_security_processed: bool,
+}
+
+impl TxInfo {
+    /// Copy the transmit information of a radio frame
+    ///
+    /// Returns:
+    ///     OTError::InvalidArgs if the frame does not hold transmit information
+    fn from_radio_frame(frame: &OTRadioFrame) -> Result<Self, OTError<Infallible>> {
+        let OTFrameInformation::TxInfo {
+            aes_key,
+            io_info,
+            tx_delay_base_time,
+            tx_delay,
+            max_csma_backoffs,
+            max_frame_retries,
+            rx_channel_after_tx_done,
+            is_header_updated,
+            is_a_retx,
+            csma_ca_enabled,
+            csl_present,
+            is_security_processed,
+        } = frame.frame_information
+        else {
+            return Err(OTError::InvalidArgs);
+        };
+
+        Ok(Self {
+            channel: frame.channel,
+            radio_type: frame.radio_type,
+            aes_key,
+            ie_info: *io_info,
+            tx_delay_base_time,
+            tx_delay,
+            max_csma_backoffs,
+            max_frame_retries,
+            rx_channel_after_tx_done,
+            is_header_updated,
+            is_a_retx,
+            csma_ca_enabled,
+            csl_present,
+            is_security_processed,
+        })
+    }
+
+    /// Borrow the sent frame as a radio frame
+    fn radio_frame<'f>(&'f self, frame: &'f TransmitFrame) -> OTRadioFrame<'f> {
+        OTRadioFrame {
+            psdu: frame.psdu(),
+            channel: self.channel,
+            radio_type: self.radio_type,
+            frame_information: OTFrameInformation::TxInfo {
+                aes_key: self.aes_key,
+                io_info: &self.ie_info,
+                tx_delay_base_time: self.tx_delay_base_time,
+                tx_delay: self.tx_delay,
+                max_csma_backoffs: self.max_csma_backoffs,
+                max_frame_retries: self.max_frame_retries,
+                rx_channel_after_tx_done: self.rx_channel_after_tx_done,
+                is_header_updated: self.is_header_updated,
+                is_a_retx: self.is_a_retx,
+                csma_ca_enabled: self.csma_ca_enabled,
+                csl_present: self.csl_present,
+                is_security_processed: self.is_security_processed,
+            },
+        }
+    }
+}
+
+/// Transmission of a node waiting to be reported (see `SimRadio::process`)
+struct PendingTx {
+    // End of the frame on air
+    end: u64,
+    // Sequence number of the frame if it requested an ACK
+    ack_request: Option<Option<u8>>,
+    // ACK received for the frame
+    ack: Option<Reception>,
+    // Whether the channel was busy, in which case the frame was never sent
+    channel_access_failure: bool,
+    // Whether `tx_started` was reported
+    started: bool,
+}
+
+/// Scheduled receive window (see `OTRadioOperation::receive_at`)
+#[derive(Clone, Copy)]
+struct ReceiveWindow {
+    channel: u8,
+    start: u64,
+    end: u64,
+}
+
+/// Energy scan in progress
+#[derive(Clone, Copy)]
+struct EnergyScan {
+    channel: u8,
+    end: u64,
+    max_rssi: i8,
+}
+
+/// Radio state of a simulated node
+struct Node {
+    eui64: [u8; 8],
+    ext_address: OTExtAddress,
+    short_address: OTShortAddress,
+    pan_id: OTPanId,
+    state: OTRadioState,
+    channel: u8,
+    promiscuous: bool,
+    rx_on_when_idle: bool,
+    transmit_power: i8,
+    cca_threshold: i8,
+    fem_lna_gain: i8,
+    region: u16,
+    mac_frame_counter: u32,
+    src_match: SrcMatchTable<SIM_SRC_MATCH_ENTRIES, SIM_SRC_MATCH_ENTRIES>,
+    receive_window: Option<ReceiveWindow>,
+    energy_scan: Option<EnergyScan>,
+    transmitting_until: u64,
+    pending_tx: Option<PendingTx>,
+    rx_queue: VecDeque<Reception>,
+}
+
+impl Node {
+    fn new(eui64: [u8; 8]) -> Self {
+        Self {
+            eui64,
+            ext_address: eui64,
+            short_address: BROADCAST_SHORT_ADDRESS,
+            pan_id: OT_PANID_BROADCAST,
+            state: OTRadioState::Disabled,
+            channel: 11,
+            promiscuous: false,
+            rx_on_when_idle: true,
+            transmit_power: 0,
+            cca_threshold: -75,
+            fem_lna_gain: 0,
+            region: 0,
+            mac_frame_counter: 0,
+            src_match: SrcMatchTable::new(),
+            receive_window: None,
+            energy_scan: None,
+            transmitting_until: 0,
+            pending_tx: None,
+            rx_queue: VecDeque::new(),
+        }
+    }
+
+    /// Get the channel the node is listening on at the given time (if any)
+    fn listening_channel(&self, now: u64) -> Option<u8> {
+        if now < self.transmitting_until {
+            return None;
+        }
+        match self.state {
+            OTRadioState::Receive | OTRadioState::Transmit => Some(self.channel),
+            OTRadioState::Sleep => self
+                .receive_window
+                .filter(|window| window.start <= now && now < window.end)
+                .map(|window| window.channel),
+            _ => None,
+        }
+    }
+
+    /// Check whether the node accepts a frame based on its address filter
+    fn accepts(&self, frame: &Frame) -> bool {
+        if self.promiscuous || frame.frame_type() == FrameType::Ack {
+            return true;
+        }
+
+        if let Some(pan_id) = frame.dst_pan_id() {
+            if pan_id != OT_PANID_BROADCAST && pan_id != self.pan_id {
+                return false;
+            }
+        }
+
+        match frame.dst_address() {
+            MacAddress::None => true,
+            MacAddress::Short(address) => address == BROADCAST_SHORT_ADDRESS || address == self.short_address,
+            MacAddress::Extended(address) => address == self.ext_address,
+        }
+    }
+
+    /// Check whether a frame is the ACK the node is waiting for
+    fn awaits_ack(&self, frame: &Frame) -> bool {
+        frame.frame_type() == FrameType::Ack
+            && self
+                .pending_tx
+                .as_ref()
+                .is_some_and(|tx| tx.ack.is_none() && tx.ack_request == Some(frame.sequence_number()))
+    }
+}
+
+/// Shared state of the medium
+struct MediumState {
+    now: u64,
+    nodes: Vec<Node>,
+    links: BTreeMap<(NodeId, NodeId), Option<SimLink>>,
+    default_link: Option<SimLink>,
+    transmissions: Vec<Transmission>,
+    prng: Prng,
+}
+
+impl MediumState {
+    /// Get the link from a sender to a receiver (None if they cannot hear each other)
+    fn link(&self, from: NodeId, to: NodeId) -> Option<SimLink> {
+        if from == to {
+            return None;
+        }
+        self.links.get(&(from, to)).copied().unwrap_or(self.default_link)
+    }
+
+    /// Get the strongest signal a node hears on a channel right now
+    fn channel_rssi(&self, node: NodeId, channel: u8) -> i8 {
+        self.transmissions
+            .iter()
+            .filter(|tx| tx.channel == channel && tx.start <= self.now && self.now < tx.end)
+            .filter_map(|tx| self.link(tx.sender, node))
+            .map(|link| link.rssi)
+            .fold(SIM_NOISE_FLOOR, i8::max)
+    }
+
+    /// Put a frame on air
+    fn start_transmission(&mut self, sender: NodeId, channel: u8, start: u64, psdu: Vec<u8>) {
+        let end = start + air_time(psdu.len());
+        self.nodes[sender].transmitting_until = end;
+        self.transmissions.push(Transmission {
+            sender,
+            channel,
+            start,
+            end,
+            psdu,
+            delivered: false,
+        });
+        // Frames starting right away are not an event of `advance_to` anymore
+        if start <= self.now {
+            self.update_energy_scans();
+        }
+    }
+
+    /// Update the energy scans with the transmissions active now
+    fn update_energy_scans(&mut self) {
+        for node in 0..self.nodes.len() {
+            if let Some(scan) = self.nodes[node].energy_scan {
+                if self.now <= scan.end {
+                    let rssi = self.channel_rssi(node, scan.channel);
+                    if let Some(scan) = self.nodes[node].energy_scan.as_mut() {
+                        scan.max_rssi = scan.max_rssi.max(rssi);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Advance the clock, delivering every frame that ends on the way
+    fn advance_to(&mut self, time: u64) {
+        loop {
+            let next = self
+                .transmissions
+                .iter()
+                .filter(|tx| !tx.delivered)
+                .flat_map(|tx| [tx.start, tx.end])
+                .filter(|instant| *instant > self.now && *instant <= time)
+                .min();
+
+            let Some(next) = next else {
+                break;
+            };
+            self.now = next;
+            self.update_energy_scans();
+
+            while let Some(index) = self.transmissions.iter().position(|tx| !tx.delivered && tx.end <= self.now) {
+                self.transmissions[index].delivered = true;
+                self.deliver(index);
+            }
+        }
+
+        self.now = self.now.max(time);
+        self.update_energy_scans();
+
+        // Frames that ended long ago cannot collide with anything anymore
+        let horizon = self.now.saturating_sub(air_time(OT_RADIO_FRAME_MAX_SIZE) * 2);
+        self.transmissions.retain(|tx| !tx.delivered || tx.end >= horizon);
+
+        for node in self.nodes.iter_mut() {
+            if node.state == OTRadioState::Transmit && self.now >= node.transmitting_until {
+                node.state = OTRadioState::Receive;
+            }
+        }
+    }
+
+    /// Hand a finished transmission to every node that could receive it
+    fn deliver(&mut self, index: usize) {
+        let (sender, channel, start, end) = {
+            let tx = &self.transmissions[index];
+            (tx.sender, tx.channel, tx.start, tx.end)
+        };
+        let psdu = self.transmissions[index].psdu.clone();
+        let Ok(frame) = Frame::parse::<Infallible>(&psdu) else {
+            return;
+        };
+
+        for receiver in 0..self.nodes.len() {
+            let Some(link) = self.link(sender, receiver) else {
+                continue;
+            };
+            // The receiver must listen on the channel for the whole frame
+            if self.nodes[receiver].listening_channel(start) != Some(channel)
+                || self.nodes[receiver].listening_channel(end - 1) != Some(channel)
+            {
+                continue;
+            }
+            if link.rssi < SIM_RECEIVE_SENSITIVITY {
+                continue;
+            }
+
+            let collided = self.transmissions.iter().enumerate().any(|(other, tx)| {
+                other != index
+                    && tx.channel == channel
+                    && tx.start < end
+                    && start < tx.end
+                    && (tx.sender == receiver || self.link(tx.sender, receiver).is_some())
+            });
+            if collided {
+                continue;
+            }
+
+            if link.loss_percent > 0 && (self.prng.next() % 100) < link.loss_percent as u64 {
+                continue;
+            }
+
+            if !self.nodes[receiver].accepts(&frame) {
+                continue;
+            }
+
+            let mut acked_with_frame_pending = false;
+            let is_unicast = !matches!(frame.dst_address(), MacAddress::None | MacAddress::Short(BROADCAST_SHORT_ADDRESS));
+            if frame.ack_request() && is_unicast && !self.nodes[receiver].promiscuous {
+                let frame_pending = self.nodes[receiver].src_match.frame_pending(&frame);
+                if let Some(ack) = Self::build_ack(&frame, frame_pending) {
+                    acked_with_frame_pending = frame_pending;
+                    let ack_start = end + TURNAROUND_TIME * OT_RADIO_SYMBOL_TIME as u64;
+                    self.start_transmission(receiver, channel, ack_start, ack);
+                }
+            }
+
+            let reception = Reception {
+                psdu: psdu.clone(),
+                channel,
+                timestamp: start + SHR_SIZE * octet_time(),
+                rssi: link.rssi,
+                lqi: Self::lqi(link.rssi),
+                acked_with_frame_pending,
+            };
+            let node = &mut self.nodes[receiver];
+            if node.awaits_ack(&frame) {
+                if let Some(tx) = node.pending_tx.as_mut() {
+                    tx.ack = Some(reception);
+                }
+            } else {
+                node.rx_queue.push_back(reception);
+            }
+        }
+    }
+
+    /// Build the ACK for a received frame
+    fn build_ack(frame: &Frame, frame_pending: bool) -> Option<Vec<u8>> {
+        let mut ack = TransmitFrame::new();
+        let builder = if frame.frame_version() == FrameVersion::Version2015 {
+            FrameBuilder::enh_ack(frame.sequence_number(), frame_pending).dst(None, frame.src_address())
+        } else {
+            FrameBuilder::imm_ack(frame.sequence_number()?, frame_pending)
+        };
+        builder.build::<Infallible>(&mut ack).ok()?;
+        Some(ack.psdu().to_vec())
+    }
+
+    /// Map an RSSI to a link quality indicator
+    fn lqi(rssi: i8) -> u8 {
+        ((rssi as i16 - SIM_NOISE_FLOOR as i16).clamp(0, 63) * 4) as u8
+    }
+}
+
+/// Simulated IEEE 802.15.4 medium shared by a set of nodes
+#[derive(Clone)]
+pub struct SimMedium {
+    state: Rc<RefCell<MediumState>>,
+}
+
+impl SimMedium {
+    /// Create a medium where every node hears every other node with the default link
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: Rc::new(RefCell::new(MediumState {
+                now: 0,
+                nodes: Vec::new(),
+                links: BTreeMap::new(),
+                default_link: Some(SimLink::default()),
+                transmissions: Vec::new(),
+                prng: Prng::new(seed),
+            })),
+        }
+    }
+
+    /// Add a node to the medium
+    pub fn add_node(&self, eui64: [u8; 8]) -> SimRadio {
+        let mut state = self.state.borrow_mut();
+        state.nodes.push(Node::new(eui64));
+        SimRadio {
+            medium: self.clone(),
+            node: state.nodes.len() - 1,
+            rx_frame: ReceiveFrame::new(),
+            tx_frame: TransmitFrame::new(),
+            tx_info: None,
+            ack_frame: ReceiveFrame::new(),
+            ack_handling: true,
+        }
+    }
+
+    /// Create an alarm running on the medium's virtual clock
+    pub fn alarm(&self) -> SimAlarm {
+        SimAlarm {
+            medium: self.clone(),
+            deadline: None,
+        }
+    }
+
+    /// Create a deterministic entropy source
+    pub fn entropy(&self) -> SimEntropy {
+        let seed = self.state.borrow_mut().prng.next();
+        SimEntropy { prng: Prng::new(seed) }
+    }
+
+    /// Set the link used between nodes without an explicit link (None disconnects them)
+    pub fn set_default_link(&self, link: Option<SimLink>) {
+        self.state.borrow_mut().default_link = link;
+    }
+
+    /// Set the directed link from one node to another (None disconnects them)
+    pub fn set_link(&self, from: NodeId, to: NodeId, link: Option<SimLink>) {
+        self.state.borrow_mut().links.insert((from, to), link);
+    }
+
+    /// Set the link between two nodes in both directions (None disconnects them)
+    pub fn set_symmetric_link(&self, a: NodeId, b: NodeId, link: Option<SimLink>) {
+        self.set_link(a, b, link);
+        self.set_link(b, a, link);
+    }
+
+    /// Get the current virtual time (in microseconds)
+    pub fn now(&self) -> u64 {
+        self.state.borrow().now
+    }
+
+    /// Advance the virtual clock, delivering the frames that end on the way
+    pub fn advance(&self, duration: u64) {
+        let mut state = self.state.borrow_mut();
+        let time = state.now + duration;
+        state.advance_to(time);
+    }
+
+    /// Advance the virtual clock up to an absolute time (in microseconds)
+    pub fn advance_to(&self, time: u64) {
+        self.state.borrow_mut().advance_to(time);
+    }
+}
+
+/// Radio of a node in the simulated medium
+pub struct SimRadio {
+    medium: SimMedium,
+    node: NodeId,
+    rx_frame: ReceiveFrame,
+    // Copy of the frame being transmitted, handed back in `tx_done`
+    tx_frame: TransmitFrame,
+    // Transmit information of the frame (None when no transmission is waiting to be reported)
+    tx_info: Option<TxInfo>,
+    ack_frame: ReceiveFrame,
+    // Whether transmissions wait for their ACK and are reported by `process`
+    ack_handling: bool,
+}
+
+impl SimRadio {
+    /// Get the node id of this radio
+    pub fn node_id(&self) -> NodeId {
+        self.node
+    }
+
+    /// Get the medium this radio is attached to
+    pub fn medium(&self) -> &SimMedium {
+        &self.medium
+    }
+
+    /// Get the current radio state
+    pub fn state(&self) -> OTRadioState {
+        self.medium.state.borrow().nodes[self.node].state
+    }
+
+    /// Enable (default) or disable the ACK handling of transmissions
+    ///
+    /// Without it the radio behaves like one lacking `Capabilities::AckTimeout`, as expected by the `SubMac`:
+    /// `transmit` fails with OTError::ChannelAccessFailure when the channel is busy, nothing is reported by `process`
+    /// and the ACKs are queued like any other received frame.
+    pub fn set_ack_handling(&mut self, enabled: bool) {
+        self.ack_handling = enabled;
+    }
+
+    /// Get the number of frames waiting in the receive queue
+    pub fn pending_frames(&self) -> usize {
+        self.medium.state.borrow().nodes[self.node].rx_queue.len()
+    }
+
+    /// Report the transmissions and energy scans that completed
+    ///
+    /// Must be called after advancing the medium. A transmission is reported once its ACK was received, or once the
+    /// ACK wait time is over (OTError::NoAck), and right away when the channel was busy
+    /// (OTError::ChannelAccessFailure).
+    pub fn process<H>(&mut self, handles: &mut H) -> Result<(), OTError<Infallible>>
+    where
+        H: OTRadioOperationHandles<Error = Infallible> + OTRadioOperationEnergyScanHandles<Error = Infallible>,
+    {
+        let now = self.medium.now();
+
+        let scan = self.with_node(|node| match node.energy_scan {
+            Some(scan) if now >= scan.end => node.energy_scan.take(),
+            _ => None,
+        });
+        if let Some(scan) = scan {
+            handles.energy_scan_done(scan.max_rssi).map_err(OTError::Platform)?;
+        }
+
+        let Some(tx_info) = self.tx_info.as_ref() else {
+            return Ok(());
+        };
+
+        let (started, outcome) = self.with_node(|node| {
+            let Some(tx) = node.pending_tx.as_mut() else {
+                return (false, None);
+            };
+            let started = !tx.started && !tx.channel_access_failure;
+            tx.started = true;
+
+            let outcome = if tx.channel_access_failure {
+                Some((None, Err(OTError::ChannelAccessFailure)))
+            } else if tx.ack_request.is_none() {
+                (now >= tx.end).then_some((None, Ok(())))
+            } else if let Some(ack) = tx.ack.take() {
+                Some((Some(ack), Ok(())))
+            } else {
+                (now >= tx.end + ACK_WAIT_TIME).then_some((None, Err(OTError::NoAck)))
+            };
+            if outcome.is_some() {
+                node.pending_tx = None;
+            }
+            (started, outcome)
+        });
+
+        let frame = tx_info.radio_frame(&self.tx_frame);
+        if started {
+            handles.tx_started(frame.clone()).map_err(OTError::Platform)?;
+        }
+        let Some((ack, result)) = outcome else {
+            return Ok(());
+        };
+
+        let ack_frame = match ack {
+            Some(ack) => {
+                self.ack_frame.copy_from(&OTRadioFrame {
+                    psdu: &ack.psdu,
+                    channel: ack.channel,
+                    radio_type: 0,
+                    frame_information: ack.rx_info(),
+                })?;
+                Some(self.ack_frame.as_radio_frame())
+            },
+            None => None,
+        };
+        handles.tx_done(frame, ack_frame, result).map_err(OTError::Platform)?;
+        self.tx_info = None;
+        Ok(())
+    }
+
+    fn with_node<T>(&self, f: impl FnOnce(&mut Node) -> T) -> T {
+        f(&mut self.medium.state.borrow_mut().nodes[self.node])
+    }
+}
+
+impl OTRadioConfiguration for SimRadio {
+    type Error = Infallible;
+
+    fn radio_capabilities(&mut self) -> Result<OTRadioCapabilities, Self::Error> {
+        Ok((Capabilities::EnergyScan | Capabilities::ReceiveTiming) | Capabilities::SleepToTx as OTRadioCapabilities)
+    }
+
+    fn radio_receive_sensitivity(&mut self) -> Result<u8, Self::Error> {
+        Ok(SIM_RECEIVE_SENSITIVITY as u8)
+    }
+
+    fn radio_ieee_eui_64(&mut self) -> Result<[u8; 8], Self::Error> {
+        Ok(self.with_node(|node| node.eui64))
+    }
+
+    fn set_pan_id(&mut self, pan_id: OTPanId) -> Result<(), Self::Error> {
+        self.with_node(|node| node.pan_id = pan_id);
+        Ok(())
+    }
+
+    fn set_extended_address(&mut self, address: OTExtAddress) -> Result<(), Self::Error> {
+        self.with_node(|node| node.ext_address = address);
+        Ok(())
+    }
+
+    fn set_short_address(&mut self, address: OTShortAddress) -> Result<(), Self::Error> {
+        self.with_node(|node| node.short_address = address);
+        Ok(())
+    }
+
+    fn get_transmit_power(&mut self) -> Result<i8, OTError<Self::Error>> {
+        Ok(self.with_node(|node| node.transmit_power))
+    }
+
+    fn set_transmit_power(&mut self, power: i8) -> Result<(), OTError<Self::Error>> {
+        self.with_node(|node| node.transmit_power = power);
+        Ok(())
+    }
+
+    fn get_cca_energy_detect_threshold(&mut self) -> Result<i8, OTError<Self::Error>> {
+        Ok(self.with_node(|node| node.cca_threshold))
+    }
+
+    fn set_cca_energy_detect_threshold(&mut self, threshold: i8) -> Result<(), OTError<Self::Error>> {
+        self.with_node(|node| node.cca_threshold = threshold);
+        Ok(())
+    }
+
+    fn get_fem_lna_gain(&mut self) -> Result<i8, OTError<Self::Error>> {
+        Ok(self.with_node(|node| node.fem_lna_gain))
+    }
+
+    fn set_fem_lna_gain(&mut self, gain: i8) -> Result<(), OTError<Self::Error>> {
+        self.with_node(|node| node.fem_lna_gain = gain);
+        Ok(())
+    }
+
+    fn get_promiscuous(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.with_node(|node| node.promiscuous))
+    }
+
+    fn set_promiscuous(&mut self, enabled: bool) -> Result<(), Self::Error> {
+        self.with_node(|node| node.promiscuous = enabled);
+        Ok(())
+    }
+
+    fn set_rx_on_when_idle(&mut self, enabled: bool) -> Result<(), Self::Error> {
+        self.with_node(|node| node.rx_on_when_idle = enabled);
+        Ok(())
+    }
+
+    fn get_now(&mut self) -> u64 {
+        self.medium.now()
+    }
+
+    fn get_bus_speed(&mut self) -> u32 {
+        0
+    }
+}
+
+/// The simulated radio does not secure frames (no `Capabilities::TransmitSec`), so it only keeps the frame counter
+impl OTRadioConfigurationCapTransmit for SimRadio {
+    type Error = Infallible;
+
+    fn set_mac_key(
+        &mut self,
+        _key_id_mode: u8,
+        _key_id: u8,
+        _previous_key: OTMacKeyMaterial,
+        _current_key: OTMacKeyMaterial,
+        _next_key: OTMacKeyMaterial,
+        _key_type: OTKeyType,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn set_mac_frame_counter(&mut self, mac_frame_counter: u32) -> Result<(), Self::Error> {
+        self.with_node(|node| node.mac_frame_counter = mac_frame_counter);
+        Ok(())
+    }
+
+    fn set_mac_frame_counter_if_larger(&mut self, mac_frame_counter: u32) -> Result<(), Self::Error> {
+        self.with_node(|node| node.mac_frame_counter = node.mac_frame_counter.max(mac_frame_counter));
+        Ok(())
+    }
+}
+
+impl OTRadioOperation for SimRadio {
+    type Error = Infallible;
+
+    fn enable(&mut self) -> Result<(), OTError<Self::Error>> {
+        self.with_node(|node| {
+            if node.state == OTRadioState::Disabled {
+                node.state = OTRadioState::Sleep;
+            }
+        });
+        Ok(())
+    }
+
+    fn disable(&mut self) -> Result<(), OTError<Self::Error>> {
+        self.with_node(|node| {
+            node.state = OTRadioState::Disabled;
+            node.receive_window = None;
+        });
+        Ok(())
+    }
+
+    fn is_enabled(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.with_node(|node| node.state != OTRadioState::Disabled))
+    }
+
+    fn sleep(&mut self) -> Result<(), OTError<Self::Error>> {
+        self.with_node(|node| match node.state {
+            OTRadioState::Disabled => Err(OTError::InvalidState),
+            _ => {
+                node.state = OTRadioState::Sleep;
+                Ok(())
+            },
+        })
+    }
+
+    fn receive(&mut self, channel: u8) -> Result<(), OTError<Self::Error>> {
+        self.with_node(|node| match node.state {
+            OTRadioState::Disabled => Err(OTError::InvalidState),
+            _ => {
+                node.state = OTRadioState::Receive;
+                node.channel = channel;
+                Ok(())
+            },
+        })
+    }
+
+    fn receive_at(&mut self, channel: u8, start: u32, duration: u32) -> Result<(), OTError<Self::Error>> {
+        let now = self.medium.now();
+        self.with_node(|node| {
+            if node.state == OTRadioState::Disabled {
+                return Err(OTError::InvalidState);
+            }
+
+            // The start time is the lower 32 bits of the radio clock, so pick the next matching instant
+            let mut start = (now & !0xffff_ffff) | start as u64;
+            if start + (duration as u64) < now {
+                start += 1 << 32;
+            }
+            node.receive_window = Some(ReceiveWindow {
+                channel,
+                start,
+                end: start + duration as u64,
+            });
+            Ok(())
+        })
+    }
+
+    fn receive_frame(&mut self) -> Result<OTRadioFrame<'_>, OTError<Self::Error>> {
+        let reception = self
+            .with_node(|node| node.rx_queue.pop_front())
+            .ok_or(OTError::NoFrameReceived)?;
+
+        let frame = OTRadioFrame {
+            psdu: &reception.psdu,
+            channel: reception.channel,
+            radio_type: 0,
+            frame_information: reception.rx_info(),
+        };
+        self.rx_frame.copy_from(&frame)?;
+        Ok(self.rx_frame.as_radio_frame())
+    }
+
+    fn transmit(&mut self, frame: OTRadioFrame) -> Result<(), OTError<Self::Error>> {
+        let mut state = self.medium.state.borrow_mut();
+        let now = state.now;
+        let node = &state.nodes[self.node];
+
+        match node.state {
+            OTRadioState::Sleep | OTRadioState::Receive => {},
+            OTRadioState::Transmit if now >= node.transmitting_until => {},
+            _ => return Err(OTError::InvalidState),
+        }
+        // The outcome of the previous frame must be reported by `process` first
+        if self.tx_info.is_some() {
+            return Err(OTError::InvalidState);
+        }
+
+        if !self.ack_handling {
+            if state.channel_rssi(self.node, frame.channel) >= node.cca_threshold {
+                return Err(OTError::ChannelAccessFailure);
+            }
+            state.nodes[self.node].state = OTRadioState::Transmit;
+            state.nodes[self.node].channel = frame.channel;
+            state.start_transmission(self.node, frame.channel, now, frame.psdu.to_vec());
+            return Ok(());
+        }
+
+        let tx_info = TxInfo::from_radio_frame(&frame)?;
+
+        // Keep the frame to hand it back in `tx_done`
+        self.tx_frame.set_length(frame.psdu.len())?;
+        self.tx_frame.psdu_mut().copy_from_slice(frame.psdu);
+        self.tx_info = Some(tx_info);
+
+        let ack_request = Frame::parse::<Infallible>(frame.psdu)
+            .ok()
+            .filter(|parsed| parsed.ack_request())
+            .map(|parsed| parsed.sequence_number());
+        let mut pending = PendingTx {
+            end: now + air_time(frame.psdu.len()),
+            ack_request,
+            ack: None,
+            channel_access_failure: false,
+            started: false,
+        };
+
+        // Clear channel assessment, the failure is reported by `SimRadio::process`
+        let cca_threshold = node.cca_threshold;
+        if state.channel_rssi(self.node, frame.channel) >= cca_threshold {
+            pending.channel_access_failure = true;
+            state.nodes[self.node].pending_tx = Some(pending);
+            return Ok(());
+        }
+
+        state.nodes[self.node].state = OTRadioState::Transmit;
+        state.nodes[self.node].channel = frame.channel;
+        state.nodes[self.node].pending_tx = Some(pending);
+        state.start_transmission(self.node, frame.channel, now, frame.psdu.to_vec());
+        Ok(())
+    }
+
+    fn tx_started(&mut self) {}
+
+    fn tx_done(&mut self) {}
+
+    fn diag_tx_done(&mut self) {}
+
+    fn get_rssi(&mut self) -> Result<i8, Self::Error> {
+        let state = self.medium.state.borrow();
+        let channel = state.nodes[self.node].channel;
+        Ok(state.channel_rssi(self.node, channel))
+    }
+
+    fn enable_src_match(&mut self, enabled: bool) -> Result<(), Self::Error> {
+        self.with_node(|node| node.src_match.enable(enabled));
+        Ok(())
+    }
+
+    fn add_src_match_short_entry(&mut self, address: OTShortAddress) -> Result<(), OTError<Self::Error>> {
+        self.with_node(|node| node.src_match.add_short_entry(address))
+    }
+
+    fn add_src_match_ext_entry(&mut self, address: OTExtAddress) -> Result<(), OTError<Self::Error>> {
+        self.with_node(|node| node.src_match.add_ext_entry(address))
+    }
+
+    fn clear_src_match_short_entry(&mut self, address: OTShortAddress) -> Result<(), OTError<Self::Error>> {
+        self.with_node(|node| node.src_match.clear_short_entry(address))
+    }
+
+    fn clear_src_match_ext_entry(&mut self, address: OTExtAddress) -> Result<(), OTError<Self::Error>> {
+        self.with_node(|node| node.src_match.clear_ext_entry(address))
+    }
+
+    fn clear_src_match_short_entries(&mut self) -> Result<(), Self::Error> {
+        self.with_node(|node| node.src_match.clear_short_entries());
+        Ok(())
+    }
+
+    fn clear_src_match_ext_entries(&mut self) -> Result<(), Self::Error> {
+        self.with_node(|node| node.src_match.clear_ext_entries());
+        Ok(())
+    }
+
+    fn get_supported_channel_mask(&mut self) -> Result<u32, Self::Error> {
+        Ok(OT_RADIO_2P4GHZ_OQPSK_CHANNEL_MASK as u32)
+    }
+
+    fn get_preferred_channel_mask(&mut self) -> Result<u32, Self::Error> {
+        Ok(OT_RADIO_2P4GHZ_OQPSK_CHANNEL_MASK as u32)
+    }
+
+    fn set_channel_max_transmit_power(&mut self, _channel: u8, _max_power: i8) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn set_region(&mut self, region_code: u16) -> Result<(), Self::Error> {
+        self.with_node(|node| node.region = region_code);
+        Ok(())
+    }
+
+    fn get_region(&mut self) -> Result<u16, Self::Error> {
+        Ok(self.with_node(|node| node.region))
+    }
+
+    fn configure_enh_ack_probing(
+        &mut self,
+        _link_metrics: OTLinkMetrics,
+        _short_address: OTShortAddress,
+        _ext_address: OTExtAddress,
+    ) -> Result<(), OTError<Self::Error>> {
+        Err(OTError::NotImplemented)
+    }
+}
+
+impl OTRadioOperationEnergyScan for SimRadio {
+    type Error = Infallible;
+
+    fn energy_scan(&mut self, channel: u8, duration: i16) -> Result<(), OTError<Self::Error>> {
+        let mut state = self.medium.state.borrow_mut();
+        if state.nodes[self.node].state == OTRadioState::Disabled {
+            return Err(OTError::InvalidState);
+        }
+
+        let max_rssi = state.channel_rssi(self.node, channel);
+        let end = state.now + (duration.max(0) as u64) * 1000;
+        state.nodes[self.node].energy_scan = Some(EnergyScan { channel, end, max_rssi });
+        Ok(())
+    }
+
+    fn energy_scan_done(&mut self) -> Result<i8, Self::Error> {
+        Ok(self
+            .with_node(|node| node.energy_scan.take())
+            .map_or(SIM_NOISE_FLOOR, |scan| scan.max_rssi))
+    }
+}
+
+/// Millisecond (`OTAlarm`) or microsecond (`OTAlarmMicro`) alarm running on the virtual clock of a medium
+pub struct SimAlarm {
+    medium: SimMedium,
+    deadline: Option<u64>,
+}
+
+impl OTAlarm for SimAlarm {
+    /// Returns true once after the alarm deadline has passed
+    fn alarm_fired(&mut self) -> bool {
+        match self.deadline {
+            Some(deadline) if self.medium.now() >= deadline => {
+                self.deadline = None;
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn alarm_fired_diagnostics(&mut self) -> bool {
+        false
+    }
+
+    fn start_alarm_at(&mut self, t0: u32, dt: u32) {
+        // Extend the 32 bit millisecond reference to the 64 bit microsecond clock
+        let now_ms = self.medium.now() / 1000;
+        let mut base = (now_ms & !0xffff_ffff) | t0 as u64;
+        if base > now_ms {
+            base = base.saturating_sub(1 << 32);
+        }
+        self.deadline = Some((base + dt as u64) * 1000);
+    }
+
+    fn stop_alarm(&mut self) {
+        self.deadline = None;
+    }
+
+    fn get_now(&mut self) -> u32 {
+        (self.medium.now() / 1000) as u32
+    }
+}
+
+impl OTAlarmMicro for SimAlarm {
+    /// Returns true once after the alarm deadline has passed
+    fn alarm_fired(&mut self) -> bool {
+        OTAlarm::alarm_fired(self)
+    }
+
+    fn start_alarm_at(&mut self, t0: u32, dt: u32) {
+        // Extend the 32 bit microsecond reference to the 64 bit clock
+        let now = self.medium.now();
+        let mut base = (now & !0xffff_ffff) | t0 as u64;
+        if base > now {
+            base = base.saturating_sub(1 << 32);
+        }
+        self.deadline = Some(base + dt as u64);
+    }
+
+    fn stop_alarm(&mut self) {
+        self.deadline = None;
+    }
+
+    fn get_now(&mut self) -> u32 {
+        self.medium.now() as u32
+    }
+}
+
+/// Deterministic entropy source for simulated nodes
+pub struct SimEntropy {
+    prng: Prng,
+}
+
+impl OTEntropy for SimEntropy {
+    type Error = Infallible;
+
+    fn get_entropy(&mut self, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        for chunk in buffer.chunks_mut(8) {
+            let bytes = self.prng.next().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::radio::srcmatch::DATA_REQUEST_COMMAND_ID;
+
+    const PAN_ID: OTPanId = 0x1234;
+    const CHANNEL: u8 = 11;
+
+    /// Outcome of a transmission reported through `tx_done`
+    struct TxDone {
+        result: Result<(), OTError<Infallible>>,
+        // Frame pending bit of the ACK (None if there was no ACK)
+        ack_frame_pending: Option<bool>,
+    }
+
+    /// Handles recording what a radio reports
+    #[derive(Default)]
+    struct Events {
+        tx_started: usize,
+        tx_done: Vec<TxDone>,
+        energy_scan_done: Vec<i8>,
+    }
+
+    impl OTRadioOperationHandles for Events {
+        type Error = Infallible;
+
+        fn tx_started(&mut self, _frame: OTRadioFrame) -> Result<(), Self::Error> {
+            self.tx_started += 1;
+            Ok(())
+        }
+
+        fn tx_done(
+            &mut self,
+            _frame: OTRadioFrame,
+            ack_frame: Option<OTRadioFrame>,
+            result: Result<(), OTError<Self::Error>>,
+        ) -> Result<(), Self::Error> {
+            let ack_frame_pending = ack_frame.map(|ack| Frame::parse::<Infallible>(ack.psdu).unwrap().frame_pending());
+            self.tx_done.push(TxDone {
+                result,
+                ack_frame_pending,
+            });
+            Ok(())
+        }
+
+        fn diag_tx_done(&mut self, _frame: OTRadioFrame, _result: Result<(), OTError<Self::Error>>) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn get_raw_power_setting(&mut self, _channel: u8, _raw_power_setting_buffer: &mut [u8]) -> Result<(), OTError<Self::Error>> {
+            Err(OTError::NotImplemented)
+        }
+    }
+
+    impl OTRadioOperationEnergyScanHandles for Events {
+        type Error = Infallible;
+
+        fn energy_scan_done(&mut self, max_rssi: i8) -> Result<(), Self::Error> {
+            self.energy_scan_done.push(max_rssi);
+            Ok(())
+        }
+    }
+
+    /// Add a receiving node with the short address `0x0100 + node id`
+    fn add_node(medium: &SimMedium) -> SimRadio {
+        let node_id = medium.state.borrow().nodes.len();
+        let mut radio = medium.add_node([node_id as u8; 8]);
+        let short_address = 0x0100 + radio.node_id() as OTShortAddress;
+        radio.set_pan_id(PAN_ID).unwrap();
+        radio.set_short_address(short_address).unwrap();
+        radio.enable().unwrap();
+        radio.receive(CHANNEL).unwrap();
+        radio
+    }
+
+    /// Send a data frame requesting an ACK to another node
+    fn send(radio: &mut SimRadio, dst: NodeId, sequence_number: u8) -> Result<(), OTError<Infallible>> {
+        send_frame(radio, FrameBuilder::data(FrameVersion::Version2006).payload(b"sim"), dst, sequence_number)
+    }
+
+    /// Send a frame requesting an ACK to another node
+    fn send_frame(
+        radio: &mut SimRadio,
+        builder: FrameBuilder,
+        dst: NodeId,
+        sequence_number: u8,
+    ) -> Result<(), OTError<Infallible>> {
+        let mut frame = TransmitFrame::new();
+        builder
+            .sequence_number(Some(sequence_number))
+            .dst(Some(PAN_ID), MacAddress::Short(0x0100 + dst as OTShortAddress))
+            .src(None, MacAddress::Short(0x0100 + radio.node_id() as OTShortAddress))
+            .ack_request(true)
+            .build(&mut frame)?;
+        radio.transmit(OTRadioFrame {
+            psdu: frame.psdu(),
+            channel: CHANNEL,
+            radio_type: 0,
+            frame_information: OTFrameInformation::TxInfo {
+                aes_key: OTMacKeyMaterial::Key([0; 16]),
+                io_info: &RadioIEInfo {
+                    network_time_offset: 0,
+                    time_ie_offset: 0,
+                    time_sync_sequency: 0,
+                },
+                tx_delay_base_time: 0,
+                tx_delay: 0,
+                max_csma_backoffs: 0,
+                max_frame_retries: 0,
+                rx_channel_after_tx_done: CHANNEL,
+                is_header_updated: false,
+                is_a_retx: false,
+                csma_ca_enabled: false,
+                csl_present: false,
+                is_security_processed: false,
+            },
+        })
+    }
+
+    #[test]
+    fn ack_with_frame_pending() {
+        let medium = SimMedium::new(1);
+        let mut sender = add_node(&medium);
+        let mut receiver = add_node(&medium);
+        let mut events = Events::default();
+
+        receiver.enable_src_match(true).unwrap();
+        receiver.add_src_match_short_entry(0x0100).unwrap();
+
+        let data_request = FrameBuilder::mac_command(FrameVersion::Version2006, DATA_REQUEST_COMMAND_ID);
+        send_frame(&mut sender, data_request, receiver.node_id(), 7).unwrap();
+        sender.process(&mut events).unwrap();
+        assert_eq!(events.tx_started, 1);
+        assert!(events.tx_done.is_empty());
+
+        medium.advance(10_000);
+        sender.process(&mut events).unwrap();
+        assert_eq!(events.tx_started, 1);
+        assert_eq!(events.tx_done.len(), 1);
+        assert_eq!(events.tx_done[0].result, Ok(()));
+        assert_eq!(events.tx_done[0].ack_frame_pending, Some(true));
+        // The ACK is handed to `tx_done` only
+        assert_eq!(sender.pending_frames(), 0);
+
+        let frame = receiver.receive_frame().unwrap();
+        assert!(matches!(
+            frame.frame_information,
+            OTFrameInformation::RxInfo {
+                acked_with_frame_pending: true,
+                ..
+            }
+        ));
+
+        // Without a source match entry the ACK has no frame pending
+        receiver.clear_src_match_short_entries().unwrap();
+        send_frame(&mut sender, data_request, receiver.node_id(), 8).unwrap();
+        medium.advance(10_000);
+        sender.process(&mut events).unwrap();
+        assert_eq!(events.tx_done[1].ack_frame_pending, Some(false));
+    }
+
+    #[test]
+    fn collision_and_channel_access_failure() {
+        let medium = SimMedium::new(2);
+        let mut first = add_node(&medium);
+        let receiver = add_node(&medium);
+        let mut second = add_node(&medium);
+        let (mut first_events, mut second_events) = (Events::default(), Events::default());
+
+        // Hidden senders collide at the receiver
+        medium.set_symmetric_link(first.node_id(), second.node_id(), None);
+        send(&mut first, receiver.node_id(), 1).unwrap();
+        send(&mut second, receiver.node_id(), 2).unwrap();
+        medium.advance(10_000);
+        first.process(&mut first_events).unwrap();
+        second.process(&mut second_events).unwrap();
+        assert_eq!(first_events.tx_done[0].result, Err(OTError::NoAck));
+        assert_eq!(second_events.tx_done[0].result, Err(OTError::NoAck));
+        assert_eq!(receiver.pending_frames(), 0);
+
+        // A sender hearing the other one finds the channel busy
+        medium.set_symmetric_link(first.node_id(), second.node_id(), Some(SimLink::default()));
+        send(&mut first, receiver.node_id(), 3).unwrap();
+        send(&mut second, receiver.node_id(), 4).unwrap();
+        second.process(&mut second_events).unwrap();
+        assert_eq!(second_events.tx_started, 1);
+        assert_eq!(second_events.tx_done[1].result, Err(OTError::ChannelAccessFailure));
+
+        medium.advance(10_000);
+        first.process(&mut first_events).unwrap();
+        assert_eq!(first_events.tx_done[1].result, Ok(()));
+        assert_eq!(receiver.pending_frames(), 1);
+    }
+
+    #[test]
+    fn link_loss() {
+        let medium = SimMedium::new(3);
+        let mut sender = add_node(&medium);
+        let receiver = add_node(&medium);
+        let mut events = Events::default();
+
+        medium.set_link(
+            sender.node_id(),
+            receiver.node_id(),
+            Some(SimLink {
+                rssi: -50,
+                loss_percent: 100,
+            }),
+        );
+        send(&mut sender, receiver.node_id(), 1).unwrap();
+        medium.advance(10_000);
+        sender.process(&mut events).unwrap();
+        assert_eq!(events.tx_done[0].result, Err(OTError::NoAck));
+        assert_eq!(events.tx_done[0].ack_frame_pending, None);
+        assert_eq!(receiver.pending_frames(), 0);
+
+        // The frame gets through but its ACK is lost on the way back
+        medium.set_link(sender.node_id(), receiver.node_id(), Some(SimLink::default()));
+        medium.set_link(receiver.node_id(), sender.node_id(), None);
+        send(&mut sender, receiver.node_id(), 2).unwrap();
+        medium.advance(10_000);
+        sender.process(&mut events).unwrap();
+        assert_eq!(events.tx_done[1].result, Err(OTError::NoAck));
+        assert_eq!(receiver.pending_frames(), 1);
+
+        medium.set_link(receiver.node_id(), sender.node_id(), Some(SimLink::default()));
+        send(&mut sender, receiver.node_id(), 3).unwrap();
+        medium.advance(10_000);
+        sender.process(&mut events).unwrap();
+        assert_eq!(events.tx_done[2].result, Ok(()));
+    }
+
+    #[test]
+    fn transmit_waits_for_the_previous_outcome() {
+        let medium = SimMedium::new(5);
+        let mut sender = add_node(&medium);
+        let receiver = add_node(&medium);
+        let mut events = Events::default();
+
+        send(&mut sender, receiver.node_id(), 1).unwrap();
+        medium.advance(10_000);
+        assert_eq!(send(&mut sender, receiver.node_id(), 2), Err(OTError::InvalidState));
+
+        sender.process(&mut events).unwrap();
+        assert_eq!(events.tx_done.len(), 1);
+        assert_eq!(events.tx_done[0].result, Ok(()));
+        send(&mut sender, receiver.node_id(), 2).unwrap();
+    }
+
+    #[test]
+    fn energy_scan_done() {
+        let medium = SimMedium::new(4);
+        let mut scanner = add_node(&medium);
+        let mut sender = add_node(&medium);
+        let mut events = Events::default();
+
+        medium.set_symmetric_link(scanner.node_id(), sender.node_id(), Some(SimLink { rssi: -60, loss_percent: 0 }));
+        scanner.energy_scan(CHANNEL, 2).unwrap();
+        medium.advance(500);
+        send(&mut sender, 2, 1).unwrap();
+
+        medium.advance(1000);
+        scanner.process(&mut events).unwrap();
+        assert!(events.energy_scan_done.is_empty());
+
+        medium.advance(1000);
+        scanner.process(&mut events).unwrap();
+        assert_eq!(events.energy_scan_done, vec![-60]);
+
+        scanner.process(&mut events).unwrap();
+        assert_eq!(events.energy_scan_done.len(), 1);
+    }
+}
@@ -0,0 +1,531 @@
+//!
+//! Spinel Co-Processor Protocol
+//!
+//! Spinel is the protocol spoken between an OpenThread host and a radio co-processor (RCP). A Spinel frame is:
+//! ```text
+//! [header (1 byte)][command (packed uint)][payload]
+//! ```
+//!
+//! Property commands (get, set, insert, remove and their answers) carry the property id (packed uint) at the start of
+//! their payload, followed by the property value encoded with the datatype language of the codec module.
+//!
+
+use crate::error::OTError;
+
+pub mod client;
+
+pub mod codec;
+
+pub mod hdlc;
+
+pub mod raw;
+
+pub mod rcp;
+
+pub mod spi;
+
+use codec::{Decoder, Encoder};
+
+// Supported Spinel protocol version
+pub const PROTOCOL_VERSION_MAJOR: u32 = 4;
+pub const PROTOCOL_VERSION_MINOR: u32 = 3;
+// RCP API version implemented by the radio properties
+pub const RCP_API_VERSION: u32 = 10;
+// Oldest host RCP API version the radio properties are compatible with
+pub const MIN_HOST_API_VERSION: u32 = 4;
+// Spinel protocol type of a Thread interface
+pub const PROTOCOL_TYPE_THREAD: u32 = 3;
+// Maximum size of a Spinel frame (in bytes)
+pub const FRAME_MAX_SIZE: usize = 1300;
+
+// Header bits that must be 0b10 in every frame
+const HEADER_FLAG: u8 = 0x80;
+const HEADER_FLAG_MASK: u8 = 0xc0;
+// Header interface identifier bits
+const HEADER_IID_OFFSET: u8 = 4;
+const HEADER_IID_MASK: u8 = 0x30;
+// Header transaction identifier bits
+const HEADER_TID_MASK: u8 = 0x0f;
+// Number of transaction identifiers (0 is reserved for unsolicited frames)
+pub const TID_COUNT: u8 = 16;
+
+// Capabilities (reported in Property::Caps)
+pub const CAP_LOCK: u32 = 1;
+pub const CAP_MCU_POWER_STATE: u32 = 13;
+pub const CAP_CONFIG_RADIO: u32 = 34;
+pub const CAP_MAC_RAW: u32 = 513;
+pub const CAP_RCP_API_VERSION: u32 = 64;
+
+/// Spinel frame header
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Header {
+    // Interface identifier (0-3)
+    pub iid: u8,
+    // Transaction identifier (0 for unsolicited frames)
+    pub tid: u8,
+}
+
+impl Header {
+    pub const fn new(iid: u8, tid: u8) -> Self {
+        Self { iid, tid }
+    }
+
+    /// Decode a header byte
+    pub fn from_byte<E>(byte: u8) -> Result<Self, OTError<E>> {
+        if byte & HEADER_FLAG_MASK != HEADER_FLAG {
+            return Err(OTError::Parse);
+        }
+        Ok(Self {
+            iid: (byte & HEADER_IID_MASK) >> HEADER_IID_OFFSET,
+            tid: byte & HEADER_TID_MASK,
+        })
+    }
+
+    /// Encode the header byte
+    pub fn to_byte(self) -> u8 {
+        HEADER_FLAG | ((self.iid << HEADER_IID_OFFSET) & HEADER_IID_MASK) | (self.tid & HEADER_TID_MASK)
+    }
+
+    /// Check whether the frame is not an answer to a request
+    pub fn is_unsolicited(&self) -> bool {
+        self.tid == 0
+    }
+}
+
+/// Spinel commands
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Command {
+    Noop = 0,
+    Reset = 1,
+    PropValueGet = 2,
+    PropValueSet = 3,
+    PropValueInsert = 4,
+    PropValueRemove = 5,
+    PropValueIs = 6,
+    PropValueInserted = 7,
+    PropValueRemoved = 8,
+}
+
+impl Command {
+    pub fn from_id(id: u32) -> Option<Self> {
+        Some(match id {
+            0 => Command::Noop,
+            1 => Command::Reset,
+            2 => Command::PropValueGet,
+            3 => Command::PropValueSet,
+            4 => Command::PropValueInsert,
+            5 => Command::PropValueRemove,
+            6 => Command::PropValueIs,
+            7 => Command::PropValueInserted,
+            8 => Command::PropValueRemoved,
+            _ => return None,
+        })
+    }
+
+    pub fn id(self) -> u32 {
+        self as u32
+    }
+
+    /// Check whether the command carries a property id
+    pub fn is_property_command(self) -> bool {
+        !matches!(self, Command::Noop | Command::Reset)
+    }
+}
+
+/// Spinel status codes (the value of Property::LastStatus)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Ok = 0,
+    Failure = 1,
+    Unimplemented = 2,
+    InvalidArgument = 3,
+    InvalidState = 4,
+    InvalidCommand = 5,
+    InvalidInterface = 6,
+    InternalError = 7,
+    SecurityError = 8,
+    ParseError = 9,
+    InProgress = 10,
+    NoMem = 11,
+    Busy = 12,
+    PropNotFound = 13,
+    Dropped = 14,
+    Empty = 15,
+    CmdTooBig = 16,
+    NoAck = 17,
+    CcaFailure = 18,
+    Already = 19,
+    ItemNotFound = 20,
+    InvalidCommandForProp = 21,
+
+    // Reset reasons, reported unsolicited after the co-processor started
+    ResetPowerOn = 112,
+    ResetExternal = 113,
+    ResetSoftware = 114,
+    ResetFault = 115,
+    ResetCrash = 116,
+    ResetAssert = 117,
+    ResetOther = 118,
+    ResetUnknown = 119,
+    ResetWatchdog = 120,
+}
+
+impl Status {
+    pub fn from_code(code: u32) -> Option<Self> {
+        Some(match code {
+            0 => Status::Ok,
+            1 => Status::Failure,
+            2 => Status::Unimplemented,
+            3 => Status::InvalidArgument,
+            4 => Status::InvalidState,
+            5 => Status::InvalidCommand,
+            6 => Status::InvalidInterface,
+            7 => Status::InternalError,
+            8 => Status::SecurityError,
+            9 => Status::ParseError,
+            10 => Status::InProgress,
+            11 => Status::NoMem,
+            12 => Status::Busy,
+            13 => Status::PropNotFound,
+            14 => Status::Dropped,
+            15 => Status::Empty,
+            16 => Status::CmdTooBig,
+            17 => Status::NoAck,
+            18 => Status::CcaFailure,
+            19 => Status::Already,
+            20 => Status::ItemNotFound,
+            21 => Status::InvalidCommandForProp,
+            112 => Status::ResetPowerOn,
+            113 => Status::ResetExternal,
+            114 => Status::ResetSoftware,
+            115 => Status::ResetFault,
+            116 => Status::ResetCrash,
+            117 => Status::ResetAssert,
+            118 => Status::ResetOther,
+            119 => Status::ResetUnknown,
+            120 => Status::ResetWatchdog,
+            _ => return None,
+        })
+    }
+
+    pub fn code(self) -> u32 {
+        self as u32
+    }
+
+    /// Check whether the status reports a co-processor reset
+    pub fn is_reset(self) -> bool {
+        (Status::ResetPowerOn as u32..=Status::ResetWatchdog as u32).contains(&(self as u32))
+    }
+
+    /// Get the status reporting an OpenThread error
+    pub fn from_error<E>(error: &OTError<E>) -> Self {
+        match error {
+            OTError::Failed => Status::Failure,
+            OTError::Dropped => Status::Dropped,
+            OTError::NoBuffers => Status::NoMem,
+            OTError::Busy => Status::Busy,
+            OTError::Parse => Status::ParseError,
+            OTError::InvalidArgs => Status::InvalidArgument,
+            OTError::Security => Status::SecurityError,
+            OTError::NotImplemented => Status::Unimplemented,
+            OTError::InvalidState => Status::InvalidState,
+            OTError::NoAck => Status::NoAck,
+            OTError::ChannelAccessFailure => Status::CcaFailure,
+            OTError::Already => Status::Already,
+            OTError::NotFound | OTError::NoAddress => Status::ItemNotFound,
+            OTError::InvalidCommand => Status::InvalidCommand,
+            _ => Status::Failure,
+        }
+    }
+
+    /// Convert the status into the matching OpenThread result
+    pub fn into_result<E>(self) -> Result<(), OTError<E>> {
+        Err(match self {
+            Status::Ok => return Ok(()),
+            Status::Dropped => OTError::Dropped,
+            Status::NoMem => OTError::NoBuffers,
+            Status::Busy => OTError::Busy,
+            Status::ParseError => OTError::Parse,
+            Status::InvalidArgument => OTError::InvalidArgs,
+            Status::SecurityError => OTError::Security,
+            Status::Unimplemented | Status::PropNotFound => OTError::NotImplemented,
+            Status::InvalidState => OTError::InvalidState,
+            Status::NoAck => OTError::NoAck,
+            Status::CcaFailure => OTError::ChannelAccessFailure,
+            Status::Already => OTError::Already,
+            Status::ItemNotFound => OTError::NotFound,
+            Status::InvalidCommand | Status::InvalidCommandForProp => OTError::InvalidCommand,
+            _ => OTError::Failed,
+        })
+    }
+}
+
+/// Spinel property ids used by a radio co-processor
+///
+/// The comment of each property gives the datatype format of its value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Property {
+    // i - Status
+    LastStatus = 0x00,
+    // ii - major, minor
+    ProtocolVersion = 0x01,
+    // U
+    NcpVersion = 0x02,
+    // i
+    InterfaceType = 0x03,
+    // i
+    VendorId = 0x04,
+    // A(i)
+    Caps = 0x05,
+    // E - factory EUI-64
+    HwAddr = 0x08,
+    // C - OTMcuPowerState
+    McuPowerState = 0x0d,
+
+    // b
+    PhyEnabled = 0x20,
+    // C
+    PhyChan = 0x21,
+    // A(C)
+    PhyChanSupported = 0x22,
+    // L - kHz
+    PhyFreq = 0x23,
+    // c - dBm
+    PhyCcaThreshold = 0x24,
+    // c - dBm
+    PhyTxPower = 0x25,
+    // c - dBm
+    PhyRssi = 0x26,
+    // c - dBm
+    PhyRxSensitivity = 0x27,
+    // A(C)
+    PhyChanPreferred = 0x29,
+    // c - dB
+    PhyFemLnaGain = 0x2a,
+    // Cc - channel, max power
+    PhyChanMaxPower = 0x2b,
+    // S
+    PhyRegionCode = 0x2c,
+    // CsD - channel, actual power, raw power setting
+    PhyCalibratedPower = 0x2d,
+    // Cs - channel, target power
+    PhyChanTargetPower = 0x2e,
+
+    // C - 0 idle, 1 beacon scan, 2 energy scan
+    MacScanState = 0x30,
+    // A(C)
+    MacScanMask = 0x31,
+    // S - ms per channel
+    MacScanPeriod = 0x32,
+    // E
+    Mac15_4Laddr = 0x34,
+    // S
+    Mac15_4Saddr = 0x35,
+    // S
+    Mac15_4Panid = 0x36,
+    // b
+    MacRawStreamEnabled = 0x37,
+    // C - 0 off, 1 network, 2 full
+    MacPromiscuousMode = 0x38,
+    // Cc - channel, max RSSI
+    MacEnergyScanResult = 0x39,
+    // b
+    MacRxOnWhenIdleMode = 0x3b,
+
+    // D
+    StreamDebug = 0x70,
+    // crate-specific frame metadata, see the raw module
+    StreamRaw = 0x71,
+    // UD
+    StreamLog = 0x74,
+
+    // i
+    RcpApiVersion = 0xb0,
+    // i
+    RcpMinHostApiVersion = 0xb1,
+
+    // CCddd - key id mode, key id, previous, current and next key
+    RcpMacKey = 0x800,
+    // Lb - frame counter, set only if larger
+    RcpMacFrameCounter = 0x801,
+    // X - radio time (us)
+    RcpTimestamp = 0x802,
+    // SEC - short address, extended address, link metrics flags
+    RcpEnhAckProbing = 0x803,
+    // L - ppm
+    RcpCslAccuracy = 0x804,
+    // C - 10 us units
+    RcpCslUncertainty = 0x805,
+
+    // i - OTRadioCapabilities
+    RadioCaps = 0x120b,
+    // D
+    RadioCoexMetrics = 0x120c,
+    // b
+    RadioCoexEnable = 0x120d,
+
+    // b
+    MacSrcMatchEnabled = 0x1303,
+    // A(S)
+    MacSrcMatchShortAddresses = 0x1304,
+    // A(E)
+    MacSrcMatchExtendedAddresses = 0x1305,
+}
+
+impl Property {
+    pub fn from_id(id: u32) -> Option<Self> {
+        Some(match id {
+            0x00 => Property::LastStatus,
+            0x01 => Property::ProtocolVersion,
+            0x02 => Property::NcpVersion,
+            0x03 => Property::InterfaceType,
+            0x04 => Property::VendorId,
+            0x05 => Property::Caps,
+            0x08 => Property::HwAddr,
+            0x0d => Property::McuPowerState,
+            0x20 => Property::PhyEnabled,
+            0x21 => Property::PhyChan,
+            0x22 => Property::PhyChanSupported,
+            0x23 => Property::PhyFreq,
+            0x24 => Property::PhyCcaThreshold,
+            0x25 => Property::PhyTxPower,
+            0x26 => Property::PhyRssi,
+            0x27 => Property::PhyRxSensitivity,
+            0x29 => Property::PhyChanPreferred,
+            0x2a => Property::PhyFemLnaGain,
+            0x2b => Property::PhyChanMaxPower,
+            0x2c => Property::PhyRegionCode,
+            0x2d => Property::PhyCalibratedPower,
+            0x2e => Property::PhyChanTargetPower,
+            0x30 => Property::MacScanState,
+            0x31 => Property::MacScanMask,
+            0x32 => Property::MacScanPeriod,
+            0x34 => Property::Mac15_4Laddr,
+            0x35 => Property::Mac15_4Saddr,
+            0x36 => Property::Mac15_4Panid,
+            0x37 => Property::MacRawStreamEnabled,
+            0x38 => Property::MacPromiscuousMode,
+            0x39 => Property::MacEnergyScanResult,
+            0x3b => Property::MacRxOnWhenIdleMode,
+            0x70 => Property::StreamDebug,
+            0x71 => Property::StreamRaw,
+            0x74 => Property::StreamLog,
+            0xb0 => Property::RcpApiVersion,
+            0xb1 => Property::RcpMinHostApiVersion,
+            0x800 => Property::RcpMacKey,
+            0x801 => Property::RcpMacFrameCounter,
+            0x802 => Property::RcpTimestamp,
+            0x803 => Property::RcpEnhAckProbing,
+            0x804 => Property::RcpCslAccuracy,
+            0x805 => Property::RcpCslUncertainty,
+            0x120b => Property::RadioCaps,
+            0x120c => Property::RadioCoexMetrics,
+            0x120d => Property::RadioCoexEnable,
+            0x1303 => Property::MacSrcMatchEnabled,
+            0x1304 => Property::MacSrcMatchShortAddresses,
+            0x1305 => Property::MacSrcMatchExtendedAddresses,
+            _ => return None,
+        })
+    }
+
+    pub fn id(self) -> u32 {
+        self as u32
+    }
+}
+
+/// Allocator of the transaction identifiers of outstanding requests
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TidAllocator {
+    // Bit n set when tid n is in use
+    in_use: u16,
+    // Tid tried first by the next allocation
+    next: u8,
+}
+
+impl TidAllocator {
+    pub const fn new() -> Self {
+        Self { in_use: 0, next: 1 }
+    }
+
+    /// Allocate a free transaction identifier (never 0)
+    ///
+    /// Returns:
+    ///     OTError::Busy if every transaction identifier is in use
+    pub fn allocate<E>(&mut self) -> Result<u8, OTError<E>> {
+        for _ in 1..TID_COUNT {
+            let tid = self.next.max(1);
+            self.next = (tid % (TID_COUNT - 1)) + 1;
+            if self.in_use & (1 << tid) == 0 {
+                self.in_use |= 1 << tid;
+                return Ok(tid);
+            }
+        }
+        Err(OTError::Busy)
+    }
+
+    /// Release a transaction identifier once its answer was received
+    pub fn release(&mut self, tid: u8) {
+        self.in_use &= !(1 << (tid & HEADER_TID_MASK));
+    }
+
+    /// Check whether a transaction identifier is awaiting an answer
+    pub fn is_in_use(&self, tid: u8) -> bool {
+        tid != 0 && self.in_use & (1 << (tid & HEADER_TID_MASK)) != 0
+    }
+}
+
+/// Parsed Spinel frame
+#[derive(Clone, Debug)]
+pub struct SpinelFrame<'a> {
+    pub header: Header,
+    pub command: Command,
+    // Command payload (starting with the property id for property commands)
+    pub payload: &'a [u8],
+}
+
+impl<'a> SpinelFrame<'a> {
+    /// Parse a Spinel frame
+    ///
+    /// Returns:
+    ///     OTError::Parse if the header is invalid
+    ///     OTError::InvalidCommand if the command is unknown
+    pub fn parse<E>(bytes: &'a [u8]) -> Result<Self, OTError<E>> {
+        let mut decoder = Decoder::new(bytes);
+        let header = Header::from_byte(decoder.read_u8()?)?;
+        let command = Command::from_id(decoder.read_packed_uint()?).ok_or(OTError::InvalidCommand)?;
+        Ok(Self {
+            header,
+            command,
+            payload: decoder.remaining(),
+        })
+    }
+
+    /// Get the property id and a decoder over the value of a property command
+    pub fn property<E>(&self) -> Result<(u32, Decoder<'a>), OTError<E>> {
+        if !self.command.is_property_command() {
+            return Err(OTError::InvalidCommand);
+        }
+        let mut decoder = Decoder::new(self.payload);
+        let property = decoder.read_packed_uint()?;
+        Ok((property, Decoder::new(decoder.remaining())))
+    }
+}
+
+impl Encoder<'_> {
+    /// Write the header and command of a frame
+    pub fn write_command<E>(&mut self, header: Header, command: Command) -> Result<(), OTError<E>> {
+        self.write_u8(header.to_byte())?;
+        self.write_packed_uint(command.id())
+    }
+
+    /// Write the header, command and property id of a property frame
+    pub fn write_property_command<E>(
+        &mut self,
+        header: Header,
+        command: Command,
+        property: Property,
+    ) -> Result<(), OTError<E>> {
+        self.write_command(header, command)?;
+        self.write_packed_uint(property.id())
+    }
+}
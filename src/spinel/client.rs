@@ -0,0 +1,1109 @@
+//!
+//! Host-Side Spinel Radio Client
+//!
+//! `SpinelRadio` implements the OTRadio traits for an OpenThread host by sending Spinel requests to a radio
+//! co-processor over a `SpinelTransport`. Requests block until the RCP answers (or RESPONSE_TIMEOUT elapses, giving
+//! OTError::ResponseTimeout), while the frames the RCP sends on its own (received frames, transmit done, energy scan
+//! results) are queued as they arrive. Transmit done is reported through `OTRadioOperationHandles` by `process`.
+//!
+//! `RcpLoopback` is a transport serving an in-process `Rcp`, so the client can be run against any radio driver
+//! without hardware.
+//!
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+use crate::alarm::OTAlarm;
+use crate::error::OTError;
+use crate::radio::{
+    OTExtAddress, OTKeyType, OTLinkMetrics, OTMacKeyMaterial, OTPanId, OTRadioCapabilities, OTRadioConfiguration,
+    OTRadioConfigurationCapTransmit, OTRadioFrame, OTRadioOperation, OTRadioOperationEnergyScan,
+    OTRadioOperationHandles, OTRadioState, OTShortAddress, ReceiveFrame, TransmitFrame, OT_MAC_KEY_SIZE,
+    OT_RADIO_RSSI_INVALID,
+};
+
+use super::codec::{Decoder, Encoder};
+use super::raw::{self, TxMetadata};
+use super::rcp::Rcp;
+use super::{Command, Header, Property, SpinelFrame, Status, TidAllocator, FRAME_MAX_SIZE};
+
+// Time to wait for the answer to a request (in ms)
+pub const RESPONSE_TIMEOUT: u32 = 2000;
+// Time to wait for the outcome of a transmission (in ms)
+pub const TX_TIMEOUT: u32 = 1000;
+// Maximum number of received frames waiting to be read
+pub const RX_QUEUE_SIZE: usize = 16;
+
+// Spinel interface identifier used by the client
+const IID: u8 = 0;
+
+// Values of Property::MacScanState
+const SCAN_STATE_IDLE: u8 = 0;
+const SCAN_STATE_ENERGY: u8 = 2;
+
+// Values of Property::MacPromiscuousMode
+const PROMISCUOUS_MODE_OFF: u8 = 0;
+const PROMISCUOUS_MODE_FULL: u8 = 2;
+
+// Link metrics flags of Property::RcpEnhAckProbing
+const LINK_METRICS_PDU_COUNT: u8 = 1 << 0;
+const LINK_METRICS_LQI: u8 = 1 << 1;
+const LINK_METRICS_LINK_MARGIN: u8 = 1 << 2;
+const LINK_METRICS_RSSI: u8 = 1 << 3;
+
+/// Frame level transport to a radio co-processor (e.g. HDLC-lite over a UART)
+pub trait SpinelTransport {
+    type Error;
+
+    /// Send a Spinel frame
+    fn send(&mut self, frame: &[u8]) -> Result<(), Self::Error>;
+
+    /// Receive a Spinel frame without blocking
+    ///
+    /// Returns:
+    ///     The length of the frame written to the buffer, or None if no frame is available
+    fn receive(&mut self, buffer: &mut [u8]) -> Result<Option<usize>, Self::Error>;
+
+    /// Get the bus speed in bits/second (0 if unknown)
+    fn bus_speed(&mut self) -> u32;
+}
+
+/// Request waiting for its answer
+#[derive(Clone, Copy)]
+struct Awaited {
+    tid: u8,
+    // Command of a successful answer
+    command: Command,
+    property: Property,
+}
+
+/// Answer to the awaited request
+enum Answer {
+    // Range of the property value in the receive buffer
+    Value(usize, usize),
+    Status(Status),
+}
+
+/// Outcome of a transmission received from the RCP
+struct TxOutcome {
+    status: Status,
+    // Frame counter assigned by the RCP, if it updated the frame header
+    frame_counter: Option<u32>,
+    ack_frame: Option<ReceiveFrame>,
+}
+
+/// Transmission waiting for its outcome
+struct PendingTx {
+    tid: u8,
+    started: u32,
+    metadata: TxMetadata,
+    done: Option<TxOutcome>,
+}
+
+/// Radio served by a radio co-processor over Spinel
+pub struct SpinelRadio<T, A> {
+    transport: T,
+    alarm: A,
+    tids: TidAllocator,
+    tx_buffer: [u8; FRAME_MAX_SIZE],
+    rx_buffer: [u8; FRAME_MAX_SIZE],
+    awaited: Option<Awaited>,
+    answer: Option<Answer>,
+    rx_queue: VecDeque<ReceiveFrame>,
+    rx_frame: ReceiveFrame,
+    tx_frame: TransmitFrame,
+    pending_tx: Option<PendingTx>,
+    energy_scan_result: Option<i8>,
+    energy_scan_running: bool,
+    // The RCP reported a reset since the last call to `take_rcp_reset`
+    rcp_reset: Option<Status>,
+    capabilities: Option<OTRadioCapabilities>,
+    state: OTRadioState,
+    channel: u8,
+    last_timestamp: u64,
+}
+
+impl<T, A> SpinelRadio<T, A>
+where
+    T: SpinelTransport,
+    A: OTAlarm,
+{
+    /// Create a radio client over a transport
+    ///
+    /// The alarm is only used to time out requests.
+    pub fn new(transport: T, alarm: A) -> Self {
+        Self {
+            transport,
+            alarm,
+            tids: TidAllocator::new(),
+            tx_buffer: [0; FRAME_MAX_SIZE],
+            rx_buffer: [0; FRAME_MAX_SIZE],
+            awaited: None,
+            answer: None,
+            rx_queue: VecDeque::new(),
+            rx_frame: ReceiveFrame::new(),
+            tx_frame: TransmitFrame::new(),
+            pending_tx: None,
+            energy_scan_result: None,
+            energy_scan_running: false,
+            rcp_reset: None,
+            capabilities: None,
+            state: OTRadioState::Disabled,
+            channel: 0,
+            last_timestamp: 0,
+        }
+    }
+
+    pub fn transport(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    pub fn into_inner(self) -> (T, A) {
+        (self.transport, self.alarm)
+    }
+
+    /// Get the last known radio state
+    pub fn state(&self) -> OTRadioState {
+        self.state
+    }
+
+    /// Get the reset reported by the RCP since the last call, if any
+    pub fn take_rcp_reset(&mut self) -> Option<Status> {
+        self.rcp_reset.take()
+    }
+
+    /// Reset the RCP and wait for it to report the reset
+    pub fn reset(&mut self) -> Result<(), OTError<T::Error>> {
+        let mut encoder = Encoder::new(&mut self.tx_buffer);
+        encoder.write_command(Header::new(IID, 0), Command::Reset)?;
+        let length = encoder.len();
+        self.rcp_reset = None;
+        self.transport.send(&self.tx_buffer[..length]).map_err(OTError::Platform)?;
+
+        let start = self.alarm.get_now();
+        while self.rcp_reset.is_none() {
+            self.poll()?;
+            if self.alarm.get_now().wrapping_sub(start) >= RESPONSE_TIMEOUT {
+                return Err(OTError::ResponseTimeout);
+            }
+        }
+
+        self.pending_tx = None;
+        self.energy_scan_running = false;
+        self.state = OTRadioState::Disabled;
+        Ok(())
+    }
+
+    /// Read the RCP version string into a buffer
+    ///
+    /// Returns:
+    ///     The length of the version string
+    pub fn rcp_version(&mut self, buffer: &mut [u8]) -> Result<usize, OTError<T::Error>> {
+        let version = self.get(Property::NcpVersion)?.read_utf8()?.as_bytes();
+        let length = version.len().min(buffer.len());
+        buffer[..length].copy_from_slice(&version[..length]);
+        Ok(length)
+    }
+
+    /// Get the RCP API version
+    pub fn rcp_api_version(&mut self) -> Result<u32, OTError<T::Error>> {
+        self.get(Property::RcpApiVersion)?.read_packed_uint()
+    }
+
+    /// Handle the frames received from the RCP and report a finished transmission
+    pub fn process<H>(&mut self, handles: &mut H) -> Result<(), OTError<T::Error>>
+    where
+        H: OTRadioOperationHandles<Error = OTError<T::Error>>,
+    {
+        while self.poll()? {}
+
+        let Some(pending) = self.pending_tx.as_mut() else {
+            return Ok(());
+        };
+        let (result, frame_counter, ack_frame) = match pending.done.take() {
+            Some(outcome) => (outcome.status.into_result(), outcome.frame_counter, outcome.ack_frame),
+            None if self.alarm.get_now().wrapping_sub(pending.started) >= TX_TIMEOUT => {
+                (Err(OTError::ResponseTimeout), None, None)
+            },
+            None => return Ok(()),
+        };
+
+        if let Some(mut pending) = self.pending_tx.take() {
+            self.tids.release(pending.tid);
+            self.state = OTRadioState::Receive;
+
+            // Hand the frame back as sent, so that the core keeps track of the frame counter used by the RCP
+            if let Some(frame_counter) = frame_counter {
+                raw::set_frame_counter(self.tx_frame.psdu_mut(), frame_counter);
+                pending.metadata.is_header_updated = true;
+            }
+
+            let frame = pending
+                .metadata
+                .radio_frame(self.tx_frame.psdu(), OTMacKeyMaterial::Key([0; OT_MAC_KEY_SIZE]));
+            let ack_frame = ack_frame.as_ref().map(ReceiveFrame::as_radio_frame);
+            handles.tx_done(frame, ack_frame, result)?;
+        }
+        Ok(())
+    }
+
+    /// Get the value of a property
+    fn get(&mut self, property: Property) -> Result<Decoder<'_>, OTError<T::Error>> {
+        self.request(Command::PropValueGet, property, |_| Ok(()))
+    }
+
+    /// Set the value of a property
+    fn set(
+        &mut self,
+        property: Property,
+        value: impl FnOnce(&mut Encoder) -> Result<(), OTError<T::Error>>,
+    ) -> Result<(), OTError<T::Error>> {
+        self.request(Command::PropValueSet, property, value).map(|_| ())
+    }
+
+    /// Send a request and wait for its answer
+    ///
+    /// Returns:
+    ///     A decoder over the value of the answer
+    ///     OTError::ResponseTimeout if the RCP did not answer in time
+    ///     The error reported by the RCP status otherwise
+    fn request(
+        &mut self,
+        command: Command,
+        property: Property,
+        value: impl FnOnce(&mut Encoder) -> Result<(), OTError<T::Error>>,
+    ) -> Result<Decoder<'_>, OTError<T::Error>> {
+        let tid = self.tids.allocate()?;
+        let result = self.exchange(tid, command, property, value);
+        self.tids.release(tid);
+        self.awaited = None;
+
+        match result? {
+            Answer::Value(start, end) => Ok(Decoder::new(&self.rx_buffer[start..end])),
+            Answer::Status(status) => {
+                status.into_result()?;
+                Ok(Decoder::new(&[]))
+            },
+        }
+    }
+
+    fn exchange(
+        &mut self,
+        tid: u8,
+        command: Command,
+        property: Property,
+        value: impl FnOnce(&mut Encoder) -> Result<(), OTError<T::Error>>,
+    ) -> Result<Answer, OTError<T::Error>> {
+        let mut encoder = Encoder::new(&mut self.tx_buffer);
+        encoder.write_property_command(Header::new(IID, tid), command, property)?;
+        value(&mut encoder)?;
+        let length = encoder.len();
+
+        let answer_command = match command {
+            Command::PropValueInsert => Command::PropValueInserted,
+            Command::PropValueRemove => Command::PropValueRemoved,
+            _ => Command::PropValueIs,
+        };
+        self.awaited = Some(Awaited {
+            tid,
+            command: answer_command,
+            property,
+        });
+        self.answer = None;
+        self.transport.send(&self.tx_buffer[..length]).map_err(OTError::Platform)?;
+
+        let start = self.alarm.get_now();
+        loop {
+            self.poll()?;
+            if let Some(answer) = self.answer.take() {
+                return Ok(answer);
+            }
+            if self.alarm.get_now().wrapping_sub(start) >= RESPONSE_TIMEOUT {
+                return Err(OTError::ResponseTimeout);
+            }
+        }
+    }
+
+    /// Receive and handle a frame from the RCP
+    ///
+    /// Returns:
+    ///     Whether a frame was received
+    fn poll(&mut self) -> Result<bool, OTError<T::Error>> {
+        let Some(length) = self.transport.receive(&mut self.rx_buffer).map_err(OTError::Platform)? else {
+            return Ok(false);
+        };
+
+        // Malformed frames are dropped
+        if let Ok(frame) = SpinelFrame::parse::<T::Error>(&self.rx_buffer[..length]) {
+            if let Ok((id, value)) = frame.property::<T::Error>() {
+                let offset = length - value.remaining().len();
+                self.dispatch(frame.header, frame.command, id, offset, length)?;
+            }
+        }
+        Ok(true)
+    }
+
+    /// Route a received property frame to the awaited request, the pending transmission or the unsolicited frames
+    fn dispatch(
+        &mut self,
+        header: Header,
+        command: Command,
+        id: u32,
+        start: usize,
+        end: usize,
+    ) -> Result<(), OTError<T::Error>> {
+        let property = Property::from_id(id);
+        let mut value = Decoder::new(&self.rx_buffer[start..end]);
+
+        if let Some(awaited) = self.awaited.filter(|awaited| awaited.tid == header.tid && !header.is_unsolicited()) {
+            if property == Some(Property::LastStatus) && awaited.property != Property::LastStatus {
+                let status = Status::from_code(value.read_packed_uint()?).unwrap_or(Status::Failure);
+                self.answer = Some(Answer::Status(status));
+            } else if property == Some(awaited.property) && command == awaited.command {
+                self.answer = Some(Answer::Value(start, end));
+            }
+            return Ok(());
+        }
+
+        if let Some(pending) = self.pending_tx.as_mut().filter(|pending| pending.tid == header.tid) {
+            if property == Some(Property::LastStatus) && !header.is_unsolicited() {
+                let tx_done = raw::decode_tx_done::<T::Error>(&mut value)?;
+                let ack_frame = match tx_done.ack_frame {
+                    Some(ack_frame) => {
+                        let mut copy = ReceiveFrame::new();
+                        copy.copy_from::<T::Error>(&ack_frame)?;
+                        Some(copy)
+                    },
+                    None => None,
+                };
+                pending.done = Some(TxOutcome {
+                    status: tx_done.status,
+                    frame_counter: tx_done.is_header_updated.then_some(tx_done.frame_counter),
+                    ack_frame,
+                });
+            }
+            return Ok(());
+        }
+
+        if !header.is_unsolicited() || command != Command::PropValueIs {
+            return Ok(());
+        }
+
+        match property {
+            Some(Property::LastStatus) => {
+                if let Some(status) = Status::from_code(value.read_packed_uint()?).filter(|status| status.is_reset()) {
+                    self.rcp_reset = Some(status);
+                }
+            },
+            Some(Property::StreamRaw) => {
+                let frame = raw::decode_rx_frame::<T::Error>(&mut value)?;
+                // The oldest frame is dropped when the host does not keep up
+                if self.rx_queue.len() == RX_QUEUE_SIZE {
+                    self.rx_queue.pop_front();
+                }
+                let mut copy = ReceiveFrame::new();
+                copy.copy_from::<T::Error>(&frame)?;
+                self.rx_queue.push_back(copy);
+            },
+            Some(Property::MacEnergyScanResult) => {
+                let _channel = value.read_u8()?;
+                let max_rssi = value.read_i8()?;
+                self.energy_scan_result = Some(self.energy_scan_result.map_or(max_rssi, |rssi| rssi.max(max_rssi)));
+            },
+            Some(Property::MacScanState) => {
+                let scan_state = value.read_u8()?;
+                self.energy_scan_running &= scan_state != SCAN_STATE_IDLE;
+            },
+            _ => (),
+        }
+        Ok(())
+    }
+
+    /// Read an array of channels into a channel mask
+    fn get_channel_mask(&mut self, property: Property) -> Result<u32, OTError<T::Error>> {
+        let mut value = self.get(property)?;
+        let mut mask = 0u32;
+        while !value.is_empty() {
+            mask |= 1u32.checked_shl(value.read_u8()? as u32).ok_or(OTError::Parse)?;
+        }
+        Ok(mask)
+    }
+}
+
+/// Wrap an error of the client into the error of a radio trait returning OTError<Self::Error>
+fn lift<E>(error: OTError<E>) -> OTError<OTError<E>> {
+    error.map_platform(OTError::Platform)
+}
+
+impl<T, A> OTRadioConfiguration for SpinelRadio<T, A>
+where
+    T: SpinelTransport,
+    A: OTAlarm,
+{
+    type Error = OTError<T::Error>;
+
+    fn radio_capabilities(&mut self) -> Result<OTRadioCapabilities, Self::Error> {
+        if let Some(capabilities) = self.capabilities {
+            return Ok(capabilities);
+        }
+        let capabilities = self.get(Property::RadioCaps)?.read_packed_uint()? as OTRadioCapabilities;
+        self.capabilities = Some(capabilities);
+        Ok(capabilities)
+    }
+
+    fn radio_receive_sensitivity(&mut self) -> Result<u8, Self::Error> {
+        Ok(self.get(Property::PhyRxSensitivity)?.read_i8()? as u8)
+    }
+
+    fn radio_ieee_eui_64(&mut self) -> Result<[u8; 8], Self::Error> {
+        self.get(Property::HwAddr)?.read_eui64()
+    }
+
+    fn set_pan_id(&mut self, pan_id: OTPanId) -> Result<(), Self::Error> {
+        self.set(Property::Mac15_4Panid, |encoder| encoder.write_u16(pan_id))
+    }
+
+    fn set_extended_address(&mut self, address: OTExtAddress) -> Result<(), Self::Error> {
+        self.set(Property::Mac15_4Laddr, |encoder| encoder.write_eui64(&address))
+    }
+
+    fn set_short_address(&mut self, address: OTShortAddress) -> Result<(), Self::Error> {
+        self.set(Property::Mac15_4Saddr, |encoder| encoder.write_u16(address))
+    }
+
+    fn get_transmit_power(&mut self) -> Result<i8, OTError<Self::Error>> {
+        self.get(Property::PhyTxPower).and_then(|mut value| value.read_i8()).map_err(lift)
+    }
+
+    fn set_transmit_power(&mut self, power: i8) -> Result<(), OTError<Self::Error>> {
+        self.set(Property::PhyTxPower, |encoder| encoder.write_i8(power)).map_err(lift)
+    }
+
+    fn get_cca_energy_detect_threshold(&mut self) -> Result<i8, OTError<Self::Error>> {
+        self.get(Property::PhyCcaThreshold).and_then(|mut value| value.read_i8()).map_err(lift)
+    }
+
+    fn set_cca_energy_detect_threshold(&mut self, threshold: i8) -> Result<(), OTError<Self::Error>> {
+        self.set(Property::PhyCcaThreshold, |encoder| encoder.write_i8(threshold)).map_err(lift)
+    }
+
+    fn get_fem_lna_gain(&mut self) -> Result<i8, OTError<Self::Error>> {
+        self.get(Property::PhyFemLnaGain).and_then(|mut value| value.read_i8()).map_err(lift)
+    }
+
+    fn set_fem_lna_gain(&mut self, gain: i8) -> Result<(), OTError<Self::Error>> {
+        self.set(Property::PhyFemLnaGain, |encoder| encoder.write_i8(gain)).map_err(lift)
+    }
+
+    fn get_promiscuous(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.get(Property::MacPromiscuousMode)?.read_u8()? != PROMISCUOUS_MODE_OFF)
+    }
+
+    fn set_promiscuous(&mut self, enabled: bool) -> Result<(), Self::Error> {
+        let mode = if enabled { PROMISCUOUS_MODE_FULL } else { PROMISCUOUS_MODE_OFF };
+        self.set(Property::MacPromiscuousMode, |encoder| encoder.write_u8(mode))
+    }
+
+    fn set_rx_on_when_idle(&mut self, enabled: bool) -> Result<(), Self::Error> {
+        self.set(Property::MacRxOnWhenIdleMode, |encoder| encoder.write_bool(enabled))
+    }
+
+    /// Returns the last known radio time if the RCP can not be reached
+    fn get_now(&mut self) -> u64 {
+        if let Ok(now) = self.get(Property::RcpTimestamp).and_then(|mut value| value.read_u64()) {
+            self.last_timestamp = now;
+        }
+        self.last_timestamp
+    }
+
+    fn get_bus_speed(&mut self) -> u32 {
+        self.transport.bus_speed()
+    }
+}
+
+impl<T, A> OTRadioConfigurationCapTransmit for SpinelRadio<T, A>
+where
+    T: SpinelTransport,
+    A: OTAlarm,
+{
+    type Error = OTError<T::Error>;
+
+    /// Only literal keys can be sent to the RCP (OTError::InvalidArgs otherwise)
+    fn set_mac_key(
+        &mut self,
+        key_id_mode: u8,
+        key_id: u8,
+        previous_key: OTMacKeyMaterial,
+        current_key: OTMacKeyMaterial,
+        next_key: OTMacKeyMaterial,
+        _key_type: OTKeyType,
+    ) -> Result<(), Self::Error> {
+        let (
+            OTMacKeyMaterial::Key(previous_key),
+            OTMacKeyMaterial::Key(current_key),
+            OTMacKeyMaterial::Key(next_key),
+        ) = (previous_key, current_key, next_key)
+        else {
+            return Err(OTError::InvalidArgs);
+        };
+
+        self.set(Property::RcpMacKey, |encoder| {
+            encoder.write_u8(key_id_mode)?;
+            encoder.write_u8(key_id)?;
+            encoder.write_data_with_len(&previous_key)?;
+            encoder.write_data_with_len(&current_key)?;
+            encoder.write_data_with_len(&next_key)
+        })
+    }
+
+    fn set_mac_frame_counter(&mut self, mac_frame_counter: u32) -> Result<(), Self::Error> {
+        self.set(Property::RcpMacFrameCounter, |encoder| {
+            encoder.write_u32(mac_frame_counter)?;
+            encoder.write_bool(false)
+        })
+    }
+
+    fn set_mac_frame_counter_if_larger(&mut self, mac_frame_counter: u32) -> Result<(), Self::Error> {
+        self.set(Property::RcpMacFrameCounter, |encoder| {
+            encoder.write_u32(mac_frame_counter)?;
+            encoder.write_bool(true)
+        })
+    }
+}
+
+impl<T, A> OTRadioOperation for SpinelRadio<T, A>
+where
+    T: SpinelTransport,
+    A: OTAlarm,
+{
+    type Error = OTError<T::Error>;
+
+    fn enable(&mut self) -> Result<(), OTError<Self::Error>> {
+        self.set(Property::PhyEnabled, |encoder| encoder.write_bool(true)).map_err(lift)?;
+        self.state = OTRadioState::Sleep;
+        Ok(())
+    }
+
+    fn disable(&mut self) -> Result<(), OTError<Self::Error>> {
+        self.set(Property::PhyEnabled, |encoder| encoder.write_bool(false)).map_err(lift)?;
+        self.state = OTRadioState::Disabled;
+        Ok(())
+    }
+
+    fn is_enabled(&mut self) -> Result<bool, Self::Error> {
+        self.get(Property::PhyEnabled)?.read_bool()
+    }
+
+    fn sleep(&mut self) -> Result<(), OTError<Self::Error>> {
+        self.set(Property::MacRawStreamEnabled, |encoder| encoder.write_bool(false)).map_err(lift)?;
+        self.state = OTRadioState::Sleep;
+        Ok(())
+    }
+
+    fn receive(&mut self, channel: u8) -> Result<(), OTError<Self::Error>> {
+        if self.channel != channel {
+            self.set(Property::PhyChan, |encoder| encoder.write_u8(channel)).map_err(lift)?;
+            self.channel = channel;
+        }
+        if self.state != OTRadioState::Receive {
+            self.set(Property::MacRawStreamEnabled, |encoder| encoder.write_bool(true)).map_err(lift)?;
+            self.state = OTRadioState::Receive;
+        }
+        Ok(())
+    }
+
+    fn receive_at(&mut self, _channel: u8, _start: u32, _duration: u32) -> Result<(), OTError<Self::Error>> {
+        Err(OTError::NotImplemented)
+    }
+
+    fn receive_frame(&mut self) -> Result<OTRadioFrame<'_>, OTError<Self::Error>> {
+        while self.poll().map_err(lift)? {}
+        self.rx_frame = self.rx_queue.pop_front().ok_or(OTError::NoFrameReceived)?;
+        Ok(self.rx_frame.as_radio_frame())
+    }
+
+    /// The outcome of the transmission is reported by `process`
+    fn transmit(&mut self, frame: OTRadioFrame) -> Result<(), OTError<Self::Error>> {
+        if self.pending_tx.is_some() {
+            return Err(OTError::InvalidState);
+        }
+
+        let metadata = TxMetadata::from_radio_frame(&frame)?;
+        self.tx_frame.set_length(frame.psdu.len())?;
+        self.tx_frame.psdu_mut().copy_from_slice(frame.psdu);
+
+        let tid = self.tids.allocate()?;
+
+        let mut encoder = Encoder::new(&mut self.tx_buffer);
+        let encoded = encoder
+            .write_property_command(Header::new(IID, tid), Command::PropValueSet, Property::StreamRaw)
+            .and_then(|()| raw::encode_tx_frame(&mut encoder, &frame));
+        if let Err(error) = encoded {
+            self.tids.release(tid);
+            return Err(lift(error));
+        }
+        let length = encoder.len();
+
+        if let Err(error) = self.transport.send(&self.tx_buffer[..length]) {
+            self.tids.release(tid);
+            return Err(OTError::Platform(OTError::Platform(error)));
+        }
+
+        self.pending_tx = Some(PendingTx {
+            tid,
+            started: self.alarm.get_now(),
+            metadata,
+            done: None,
+        });
+        self.state = OTRadioState::Transmit;
+        Ok(())
+    }
+
+    fn tx_started(&mut self) {}
+
+    fn tx_done(&mut self) {}
+
+    fn diag_tx_done(&mut self) {}
+
+    fn get_rssi(&mut self) -> Result<i8, Self::Error> {
+        self.get(Property::PhyRssi)?.read_i8()
+    }
+
+    fn enable_src_match(&mut self, enabled: bool) -> Result<(), Self::Error> {
+        self.set(Property::MacSrcMatchEnabled, |encoder| encoder.write_bool(enabled))
+    }
+
+    fn add_src_match_short_entry(&mut self, address: OTShortAddress) -> Result<(), OTError<Self::Error>> {
+        self.request(Command::PropValueInsert, Property::MacSrcMatchShortAddresses, |encoder| {
+            encoder.write_u16(address)
+        })
+        .map(|_| ())
+        .map_err(lift)
+    }
+
+    fn add_src_match_ext_entry(&mut self, address: OTExtAddress) -> Result<(), OTError<Self::Error>> {
+        self.request(Command::PropValueInsert, Property::MacSrcMatchExtendedAddresses, |encoder| {
+            encoder.write_eui64(&address)
+        })
+        .map(|_| ())
+        .map_err(lift)
+    }
+
+    fn clear_src_match_short_entry(&mut self, address: OTShortAddress) -> Result<(), OTError<Self::Error>> {
+        self.request(Command::PropValueRemove, Property::MacSrcMatchShortAddresses, |encoder| {
+            encoder.write_u16(address)
+        })
+        .map(|_| ())
+        .map_err(lift)
+    }
+
+    fn clear_src_match_ext_entry(&mut self, address: OTExtAddress) -> Result<(), OTError<Self::Error>> {
+        self.request(Command::PropValueRemove, Property::MacSrcMatchExtendedAddresses, |encoder| {
+            encoder.write_eui64(&address)
+        })
+        .map(|_| ())
+        .map_err(lift)
+    }
+
+    fn clear_src_match_short_entries(&mut self) -> Result<(), Self::Error> {
+        self.set(Property::MacSrcMatchShortAddresses, |_| Ok(()))
+    }
+
+    fn clear_src_match_ext_entries(&mut self) -> Result<(), Self::Error> {
+        self.set(Property::MacSrcMatchExtendedAddresses, |_| Ok(()))
+    }
+
+    fn get_supported_channel_mask(&mut self) -> Result<u32, Self::Error> {
+        self.get_channel_mask(Property::PhyChanSupported)
+    }
+
+    fn get_preferred_channel_mask(&mut self) -> Result<u32, Self::Error> {
+        self.get_channel_mask(Property::PhyChanPreferred)
+    }
+
+    fn set_channel_max_transmit_power(&mut self, channel: u8, max_power: i8) -> Result<(), Self::Error> {
+        self.set(Property::PhyChanMaxPower, |encoder| {
+            encoder.write_u8(channel)?;
+            encoder.write_i8(max_power)
+        })
+    }
+
+    fn set_region(&mut self, region_code: u16) -> Result<(), Self::Error> {
+        self.set(Property::PhyRegionCode, |encoder| encoder.write_u16(region_code))
+    }
+
+    fn get_region(&mut self) -> Result<u16, Self::Error> {
+        self.get(Property::PhyRegionCode)?.read_u16()
+    }
+
+    fn configure_enh_ack_probing(
+        &mut self,
+        link_metrics: OTLinkMetrics,
+        short_address: OTShortAddress,
+        ext_address: OTExtAddress,
+    ) -> Result<(), OTError<Self::Error>> {
+        let mut flags = 0;
+        if link_metrics.pdu_count {
+            flags |= LINK_METRICS_PDU_COUNT;
+        }
+        if link_metrics.lqi {
+            flags |= LINK_METRICS_LQI;
+        }
+        if link_metrics.link_margin {
+            flags |= LINK_METRICS_LINK_MARGIN;
+        }
+        if link_metrics.rssi {
+            flags |= LINK_METRICS_RSSI;
+        }
+
+        self.set(Property::RcpEnhAckProbing, |encoder| {
+            encoder.write_u16(short_address)?;
+            encoder.write_eui64(&ext_address)?;
+            encoder.write_u8(flags)
+        })
+        .map_err(lift)
+    }
+}
+
+impl<T, A> OTRadioOperationEnergyScan for SpinelRadio<T, A>
+where
+    T: SpinelTransport,
+    A: OTAlarm,
+{
+    type Error = OTError<T::Error>;
+
+    fn energy_scan(&mut self, channel: u8, duration: i16) -> Result<(), OTError<Self::Error>> {
+        self.set(Property::MacScanMask, |encoder| encoder.write_u8(channel)).map_err(lift)?;
+        self.set(Property::MacScanPeriod, |encoder| encoder.write_u16(duration as u16)).map_err(lift)?;
+        self.energy_scan_result = None;
+        self.energy_scan_running = true;
+        self.set(Property::MacScanState, |encoder| encoder.write_u8(SCAN_STATE_ENERGY))
+            .inspect_err(|_| self.energy_scan_running = false)
+            .map_err(lift)
+    }
+
+    /// Returns OT_RADIO_RSSI_INVALID while the energy scan is running
+    fn energy_scan_done(&mut self) -> Result<i8, Self::Error> {
+        while self.poll()? {}
+        match self.energy_scan_result {
+            Some(max_rssi) if !self.energy_scan_running => Ok(max_rssi),
+            _ => Ok(OT_RADIO_RSSI_INVALID as i8),
+        }
+    }
+}
+
+/// Transport serving an in-process RCP
+pub struct RcpLoopback<R: OTRadioOperation> {
+    rcp: Rcp<R>,
+    // Answers of the RCP not received by the client yet
+    answers: VecDeque<Vec<u8>>,
+}
+
+impl<R, E> RcpLoopback<R>
+where
+    R: OTRadioOperation<Error = E>
+        + OTRadioConfiguration<Error = E>
+        + OTRadioConfigurationCapTransmit<Error = E>
+        + OTRadioOperationEnergyScan<Error = E>,
+{
+    pub fn new(rcp: Rcp<R>) -> Self {
+        Self {
+            rcp,
+            answers: VecDeque::new(),
+        }
+    }
+
+    pub fn rcp(&mut self) -> &mut Rcp<R> {
+        &mut self.rcp
+    }
+}
+
+impl<R, E> SpinelTransport for RcpLoopback<R>
+where
+    R: OTRadioOperation<Error = E>
+        + OTRadioConfiguration<Error = E>
+        + OTRadioConfigurationCapTransmit<Error = E>
+        + OTRadioOperationEnergyScan<Error = E>,
+{
+    type Error = OTError<E>;
+
+    fn send(&mut self, frame: &[u8]) -> Result<(), Self::Error> {
+        let mut answer = [0u8; FRAME_MAX_SIZE];
+        if let Some(length) = self.rcp.handle_frame(frame, &mut answer)? {
+            self.answers.push_back(answer[..length].to_vec());
+        }
+        Ok(())
+    }
+
+    fn receive(&mut self, buffer: &mut [u8]) -> Result<Option<usize>, Self::Error> {
+        if let Some(answer) = self.answers.pop_front() {
+            let length = answer.len().min(buffer.len());
+            buffer[..length].copy_from_slice(&answer[..length]);
+            return Ok(Some(length));
+        }
+        self.rcp.process(buffer)
+    }
+
+    fn bus_speed(&mut self) -> u32 {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use super::*;
+    use crate::radio::builder::{FrameBuilder, SecurityConfig};
+    use crate::radio::frame::{Frame, FrameVersion, MacAddress};
+    use crate::radio::security::KEY_ID_MODE_1;
+    use crate::radio::OTFrameInformation;
+    use crate::sim::{SimAlarm, SimMedium, SimRadio};
+    use crate::spinel::CAP_MAC_RAW;
+
+    type Client = SpinelRadio<RcpLoopback<SimRadio>, SimAlarm>;
+    type ClientError = OTError<OTError<Infallible>>;
+
+    const PAN_ID: OTPanId = 0xface;
+    const CHANNEL: u8 = 15;
+    const HOST_SHORT_ADDRESS: OTShortAddress = 0x0001;
+    const PEER_SHORT_ADDRESS: OTShortAddress = 0x0002;
+
+    /// Outcome of a transmission reported through `tx_done`
+    struct TxDone {
+        result: Result<(), OTError<ClientError>>,
+        psdu: Vec<u8>,
+        is_header_updated: bool,
+        ack_psdu: Option<Vec<u8>>,
+    }
+
+    /// Handles recording the outcome of transmissions
+    #[derive(Default)]
+    struct Events {
+        tx_done: Vec<TxDone>,
+    }
+
+    impl OTRadioOperationHandles for Events {
+        type Error = ClientError;
+
+        fn tx_started(&mut self, _frame: OTRadioFrame) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn tx_done(
+            &mut self,
+            frame: OTRadioFrame,
+            ack_frame: Option<OTRadioFrame>,
+            result: Result<(), OTError<Self::Error>>,
+        ) -> Result<(), Self::Error> {
+            let is_header_updated = TxMetadata::from_radio_frame::<()>(&frame).is_ok_and(|tx| tx.is_header_updated);
+            self.tx_done.push(TxDone {
+                result,
+                psdu: frame.psdu.to_vec(),
+                is_header_updated,
+                ack_psdu: ack_frame.map(|ack| ack.psdu.to_vec()),
+            });
+            Ok(())
+        }
+
+        fn diag_tx_done(&mut self, _frame: OTRadioFrame, _result: Result<(), OTError<Self::Error>>) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn get_raw_power_setting(&mut self, _channel: u8, _raw_power_setting_buffer: &mut [u8]) -> Result<(), OTError<Self::Error>> {
+            Err(OTError::NotImplemented)
+        }
+    }
+
+    /// Build a data frame requesting an ACK
+    fn data_frame(src: OTShortAddress, dst: OTShortAddress, sequence_number: u8) -> TransmitFrame {
+        let mut frame = TransmitFrame::new();
+        FrameBuilder::data(FrameVersion::Version2006)
+            .sequence_number(Some(sequence_number))
+            .dst(Some(PAN_ID), MacAddress::Short(dst))
+            .src(None, MacAddress::Short(src))
+            .ack_request(true)
+            .payload(b"loopback")
+            .build::<Infallible>(&mut frame)
+            .unwrap();
+        frame
+    }
+
+    fn tx_metadata() -> TxMetadata {
+        TxMetadata {
+            channel: CHANNEL,
+            ..Default::default()
+        }
+    }
+
+    /// Create a client served by an RCP on a simulated node, next to a peer node
+    fn setup() -> (SimMedium, Client, SimRadio) {
+        let medium = SimMedium::new(1);
+        let host = medium.add_node([1; 8]);
+        let mut peer = medium.add_node([2; 8]);
+        peer.set_pan_id(PAN_ID).unwrap();
+        peer.set_short_address(PEER_SHORT_ADDRESS).unwrap();
+        peer.enable().unwrap();
+        peer.receive(CHANNEL).unwrap();
+
+        let client = SpinelRadio::new(RcpLoopback::new(Rcp::new(host)), medium.alarm());
+        (medium, client, peer)
+    }
+
+    /// Let the RCP's radio report what completed on the medium
+    fn process_rcp(client: &mut Client) {
+        let (radio, events) = client.transport().rcp().split();
+        radio.process(events).unwrap();
+    }
+
+    #[test]
+    fn reset_and_properties() {
+        let (_medium, mut client, _peer) = setup();
+
+        client.reset().unwrap();
+        assert!(client.take_rcp_reset().is_some_and(|status| status.is_reset()));
+        assert_eq!(client.state(), OTRadioState::Disabled);
+
+        assert_eq!(client.radio_ieee_eui_64(), Ok([1; 8]));
+        let mut caps = client.get(Property::Caps).unwrap();
+        let mut has_mac_raw = false;
+        while !caps.is_empty() {
+            has_mac_raw |= caps.read_packed_uint::<Infallible>().unwrap() == CAP_MAC_RAW;
+        }
+        assert!(has_mac_raw);
+        client.set_transmit_power(7).unwrap();
+        assert_eq!(client.get_transmit_power(), Ok(7));
+        client.set_cca_energy_detect_threshold(-60).unwrap();
+        assert_eq!(client.get_cca_energy_detect_threshold(), Ok(-60));
+        client.set_promiscuous(true).unwrap();
+        assert_eq!(client.get_promiscuous(), Ok(true));
+
+        client.enable().unwrap();
+        assert_eq!(client.is_enabled(), Ok(true));
+        client.receive(CHANNEL).unwrap();
+        assert_eq!(client.state(), OTRadioState::Receive);
+        assert_eq!(client.transport().rcp().radio().state(), OTRadioState::Receive);
+    }
+
+    #[test]
+    fn transmit_reports_tx_done() {
+        let (medium, mut client, peer) = setup();
+        let mut events = Events::default();
+
+        client.reset().unwrap();
+        client.set_pan_id(PAN_ID).unwrap();
+        client.set_short_address(HOST_SHORT_ADDRESS).unwrap();
+        client.enable().unwrap();
+        client.receive(CHANNEL).unwrap();
+
+        let frame = data_frame(HOST_SHORT_ADDRESS, PEER_SHORT_ADDRESS, 42);
+        let aes_key = OTMacKeyMaterial::Key([0; OT_MAC_KEY_SIZE]);
+        client.transmit(tx_metadata().radio_frame(frame.psdu(), aes_key)).unwrap();
+        assert_eq!(client.state(), OTRadioState::Transmit);
+
+        // Nothing is reported before the frame and its ACK are over
+        client.process(&mut events).unwrap();
+        assert!(events.tx_done.is_empty());
+
+        medium.advance(10_000);
+        process_rcp(&mut client);
+        client.process(&mut events).unwrap();
+        assert_eq!(events.tx_done.len(), 1);
+        assert_eq!(events.tx_done[0].result, Ok(()));
+        assert!(!events.tx_done[0].is_header_updated);
+        assert_eq!(events.tx_done[0].psdu, frame.psdu());
+        let ack = events.tx_done[0].ack_psdu.as_deref().unwrap();
+        assert_eq!(Frame::parse::<Infallible>(ack).unwrap().sequence_number(), Some(42));
+        assert_eq!(client.state(), OTRadioState::Receive);
+        assert_eq!(peer.pending_frames(), 1);
+
+        // The transaction identifier is free again for the next transmission
+        let frame = data_frame(HOST_SHORT_ADDRESS, PEER_SHORT_ADDRESS, 43);
+        for _ in 0..32 {
+            client.transmit(tx_metadata().radio_frame(frame.psdu(), aes_key)).unwrap();
+            medium.advance(10_000);
+            process_rcp(&mut client);
+            client.process(&mut events).unwrap();
+        }
+        assert_eq!(events.tx_done.len(), 33);
+        assert!(events.tx_done.iter().all(|tx_done| tx_done.result.is_ok()));
+        assert_eq!(peer.pending_frames(), 33);
+    }
+
+    #[test]
+    fn frame_counter_assigned_by_the_rcp() {
+        let (_medium, mut client, _peer) = setup();
+        let mut events = Events::default();
+
+        client.reset().unwrap();
+        client.enable().unwrap();
+        client.receive(CHANNEL).unwrap();
+
+        let mut frame = TransmitFrame::new();
+        FrameBuilder::data(FrameVersion::Version2006)
+            .sequence_number(Some(7))
+            .dst(Some(PAN_ID), MacAddress::Short(PEER_SHORT_ADDRESS))
+            .src(None, MacAddress::Short(HOST_SHORT_ADDRESS))
+            .security(SecurityConfig {
+                security_level: 5,
+                key_id_mode: KEY_ID_MODE_1,
+                frame_counter: Some(0),
+                key_source: &[],
+                key_index: 1,
+            })
+            .payload(b"secured")
+            .build::<Infallible>(&mut frame)
+            .unwrap();
+        let aes_key = OTMacKeyMaterial::Key([0; OT_MAC_KEY_SIZE]);
+        client.transmit(tx_metadata().radio_frame(frame.psdu(), aes_key)).unwrap();
+
+        // The RCP's radio secures the frame with its own frame counter
+        let mut secured = frame.psdu().to_vec();
+        raw::set_frame_counter(&mut secured, 1234);
+        let secured_metadata = TxMetadata {
+            is_header_updated: true,
+            ..tx_metadata()
+        };
+        let (_, rcp_events) = client.transport().rcp().split();
+        rcp_events.tx_done(secured_metadata.radio_frame(&secured, aes_key), None, Ok(())).unwrap();
+
+        client.process(&mut events).unwrap();
+        assert_eq!(events.tx_done.len(), 1);
+        assert_eq!(events.tx_done[0].result, Ok(()));
+        assert!(events.tx_done[0].is_header_updated);
+        assert_eq!(events.tx_done[0].psdu, secured);
+        assert_eq!(raw::frame_counter(&events.tx_done[0].psdu), 1234);
+    }
+
+    #[test]
+    fn received_frames_are_streamed() {
+        let (medium, mut client, mut peer) = setup();
+
+        client.reset().unwrap();
+        client.set_pan_id(PAN_ID).unwrap();
+        client.set_short_address(HOST_SHORT_ADDRESS).unwrap();
+        client.enable().unwrap();
+        client.receive(CHANNEL).unwrap();
+        assert!(matches!(client.receive_frame(), Err(OTError::NoFrameReceived)));
+
+        let frames = [
+            data_frame(PEER_SHORT_ADDRESS, HOST_SHORT_ADDRESS, 1),
+            data_frame(PEER_SHORT_ADDRESS, HOST_SHORT_ADDRESS, 2),
+        ];
+        // The peer outcomes are not of interest, so its frames are only put on air
+        peer.set_ack_handling(false);
+        for frame in frames.iter() {
+            let aes_key = OTMacKeyMaterial::Key([0; OT_MAC_KEY_SIZE]);
+            peer.transmit(tx_metadata().radio_frame(frame.psdu(), aes_key)).unwrap();
+            medium.advance(10_000);
+        }
+
+        for frame in frames.iter() {
+            let received = client.receive_frame().unwrap();
+            assert_eq!(received.psdu, frame.psdu());
+            assert_eq!(received.channel, CHANNEL);
+            assert!(matches!(received.frame_information, OTFrameInformation::RxInfo { rssi: -50, .. }));
+        }
+        assert!(matches!(client.receive_frame(), Err(OTError::NoFrameReceived)));
+    }
+}
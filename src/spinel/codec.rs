@@ -0,0 +1,669 @@
+//!
+//! Spinel Data Encoding
+//!
+//! Encoder and decoder for the Spinel data types and the datatype pack/unpack language used to describe property
+//! values (e.g. "CiD" for a u8 followed by a packed unsigned int and the remaining data).
+//!
+//! Datatype characters:
+//!     '.' - void (nothing is encoded)
+//!     'b' - bool (one byte, 0 or 1)
+//!     'C'/'c' - u8/i8
+//!     'S'/'s' - u16/i16 (little endian)
+//!     'L'/'l' - u32/i32 (little endian)
+//!     'X'/'x' - u64/i64 (little endian)
+//!     'i' - packed unsigned int
+//!     '6' - IPv6 address (16 bytes)
+//!     'E' - EUI-64 (8 bytes)
+//!     'e' - EUI-48 (6 bytes)
+//!     'D' - data filling the rest of the buffer
+//!     'd' - data prefixed by its length (u16)
+//!     'U' - zero terminated UTF-8 string
+//!     't(...)' - struct prefixed by its length (u16)
+//!     'A(...)' - array of the inner type filling the rest of the buffer
+//!
+
+use crate::error::OTError;
+
+// Largest value a packed unsigned int can hold
+pub const MAX_UINT_PACKED: u32 = 2_097_151;
+// Maximum size of a packed unsigned int (in bytes)
+pub const MAX_UINT_PACKED_SIZE: usize = 3;
+// Size of the length prefix of 'd' data and 't(...)' structs (in bytes)
+const LENGTH_PREFIX_SIZE: usize = 2;
+
+/// Get the encoded size of a packed unsigned int (in bytes)
+pub fn packed_uint_size(value: u32) -> usize {
+    match value {
+        0..=0x7f => 1,
+        0x80..=0x3fff => 2,
+        _ => 3,
+    }
+}
+
+/// Encode a packed unsigned int
+///
+/// Returns:
+///     The number of bytes written
+///     OTError::InvalidArgs if the value is above MAX_UINT_PACKED
+///     OTError::NoBuffers if the buffer is too small
+pub fn encode_packed_uint<E>(buffer: &mut [u8], value: u32) -> Result<usize, OTError<E>> {
+    if value > MAX_UINT_PACKED {
+        return Err(OTError::InvalidArgs);
+    }
+
+    let size = packed_uint_size(value);
+    if buffer.len() < size {
+        return Err(OTError::NoBuffers);
+    }
+
+    let mut value = value;
+    for (index, byte) in buffer[..size].iter_mut().enumerate() {
+        *byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if index + 1 < size {
+            *byte |= 0x80;
+        }
+    }
+    Ok(size)
+}
+
+/// Decode a packed unsigned int
+///
+/// Returns:
+///     The value and the number of bytes it was encoded in
+///     OTError::Parse if the buffer does not start with a valid packed unsigned int
+pub fn decode_packed_uint<E>(buffer: &[u8]) -> Result<(u32, usize), OTError<E>> {
+    let mut value = 0u32;
+    for (index, &byte) in buffer.iter().take(MAX_UINT_PACKED_SIZE).enumerate() {
+        value |= ((byte & 0x7f) as u32) << (7 * index);
+        if byte & 0x80 == 0 {
+            return Ok((value, index + 1));
+        }
+    }
+    Err(OTError::Parse)
+}
+
+/// Spinel encoder writing into a byte buffer
+pub struct Encoder<'a> {
+    buffer: &'a mut [u8],
+    position: usize,
+    // Positions of the length prefixes of the open structs
+    structs: [usize; 4],
+    struct_depth: usize,
+}
+
+impl<'a> Encoder<'a> {
+    pub fn new(buffer: &'a mut [u8]) -> Self {
+        Self {
+            buffer,
+            position: 0,
+            structs: [0; 4],
+            struct_depth: 0,
+        }
+    }
+
+    /// Get the number of bytes written
+    pub fn len(&self) -> usize {
+        self.position
+    }
+
+    /// Check whether nothing was written
+    pub fn is_empty(&self) -> bool {
+        self.position == 0
+    }
+
+    /// Get the bytes written
+    pub fn bytes(&self) -> &[u8] {
+        &self.buffer[..self.position]
+    }
+
+    /// Finish encoding, returning the bytes written
+    ///
+    /// Returns:
+    ///     OTError::InvalidState if a struct was not closed
+    pub fn finish<E>(self) -> Result<&'a [u8], OTError<E>> {
+        if self.struct_depth != 0 {
+            return Err(OTError::InvalidState);
+        }
+        let Self { buffer, position, .. } = self;
+        Ok(&buffer[..position])
+    }
+
+    pub fn write_bytes<E>(&mut self, bytes: &[u8]) -> Result<(), OTError<E>> {
+        let end = self.position + bytes.len();
+        if end > self.buffer.len() {
+            return Err(OTError::NoBuffers);
+        }
+        self.buffer[self.position..end].copy_from_slice(bytes);
+        self.position = end;
+        Ok(())
+    }
+
+    pub fn write_bool<E>(&mut self, value: bool) -> Result<(), OTError<E>> {
+        self.write_u8(value as u8)
+    }
+
+    pub fn write_u8<E>(&mut self, value: u8) -> Result<(), OTError<E>> {
+        self.write_bytes(&[value])
+    }
+
+    pub fn write_i8<E>(&mut self, value: i8) -> Result<(), OTError<E>> {
+        self.write_bytes(&value.to_le_bytes())
+    }
+
+    pub fn write_u16<E>(&mut self, value: u16) -> Result<(), OTError<E>> {
+        self.write_bytes(&value.to_le_bytes())
+    }
+
+    pub fn write_i16<E>(&mut self, value: i16) -> Result<(), OTError<E>> {
+        self.write_bytes(&value.to_le_bytes())
+    }
+
+    pub fn write_u32<E>(&mut self, value: u32) -> Result<(), OTError<E>> {
+        self.write_bytes(&value.to_le_bytes())
+    }
+
+    pub fn write_i32<E>(&mut self, value: i32) -> Result<(), OTError<E>> {
+        self.write_bytes(&value.to_le_bytes())
+    }
+
+    pub fn write_u64<E>(&mut self, value: u64) -> Result<(), OTError<E>> {
+        self.write_bytes(&value.to_le_bytes())
+    }
+
+    pub fn write_i64<E>(&mut self, value: i64) -> Result<(), OTError<E>> {
+        self.write_bytes(&value.to_le_bytes())
+    }
+
+    pub fn write_packed_uint<E>(&mut self, value: u32) -> Result<(), OTError<E>> {
+        let size = encode_packed_uint(&mut self.buffer[self.position..], value)?;
+        self.position += size;
+        Ok(())
+    }
+
+    pub fn write_ipv6<E>(&mut self, address: &[u8; 16]) -> Result<(), OTError<E>> {
+        self.write_bytes(address)
+    }
+
+    pub fn write_eui64<E>(&mut self, eui64: &[u8; 8]) -> Result<(), OTError<E>> {
+        self.write_bytes(eui64)
+    }
+
+    pub fn write_eui48<E>(&mut self, eui48: &[u8; 6]) -> Result<(), OTError<E>> {
+        self.write_bytes(eui48)
+    }
+
+    /// Write data filling the rest of the frame ('D')
+    pub fn write_data<E>(&mut self, data: &[u8]) -> Result<(), OTError<E>> {
+        self.write_bytes(data)
+    }
+
+    /// Write data prefixed by its length ('d')
+    pub fn write_data_with_len<E>(&mut self, data: &[u8]) -> Result<(), OTError<E>> {
+        let length = u16::try_from(data.len()).map_err(|_| OTError::InvalidArgs)?;
+        self.write_u16(length)?;
+        self.write_bytes(data)
+    }
+
+    /// Write a zero terminated UTF-8 string ('U')
+    pub fn write_utf8<E>(&mut self, value: &str) -> Result<(), OTError<E>> {
+        self.write_bytes(value.as_bytes())?;
+        self.write_u8(0)
+    }
+
+    /// Open a struct prefixed by its length ('t(...)')
+    ///
+    /// Returns:
+    ///     OTError::NoBuffers if too many structs are nested or the buffer is too small
+    pub fn open_struct<E>(&mut self) -> Result<(), OTError<E>> {
+        if self.struct_depth == self.structs.len() {
+            return Err(OTError::NoBuffers);
+        }
+        self.structs[self.struct_depth] = self.position;
+        self.write_u16(0)?;
+        self.struct_depth += 1;
+        Ok(())
+    }
+
+    /// Close the innermost open struct, writing its length
+    pub fn close_struct<E>(&mut self) -> Result<(), OTError<E>> {
+        if self.struct_depth == 0 {
+            return Err(OTError::InvalidState);
+        }
+        self.struct_depth -= 1;
+        let start = self.structs[self.struct_depth];
+        let length = (self.position - start - LENGTH_PREFIX_SIZE) as u16;
+        self.buffer[start..start + LENGTH_PREFIX_SIZE].copy_from_slice(&length.to_le_bytes());
+        Ok(())
+    }
+
+    /// Pack values following a datatype format string
+    pub fn pack<E>(&mut self, format: &str, values: &[SpinelValue]) -> Result<(), OTError<E>> {
+        let mut values = values.iter();
+        self.pack_format(format.as_bytes(), &mut values)?;
+        if values.next().is_some() {
+            return Err(OTError::InvalidArgs);
+        }
+        Ok(())
+    }
+
+    fn pack_format<'v, E>(
+        &mut self,
+        format: &[u8],
+        values: &mut impl Iterator<Item = &'v SpinelValue<'v>>,
+    ) -> Result<(), OTError<E>> {
+        let mut index = 0;
+        while index < format.len() {
+            let datatype = format[index];
+            index += 1;
+
+            match datatype {
+                b'.' => continue,
+                b't' => {
+                    let (inner, next) = inner_format(format, index)?;
+                    self.open_struct()?;
+                    self.pack_format(inner, values)?;
+                    self.close_struct()?;
+                    index = next;
+                    continue;
+                },
+                b'A' => {
+                    // Arrays are packed from their already encoded elements
+                    let (_, next) = inner_format(format, index)?;
+                    index = next;
+                },
+                _ => (),
+            }
+
+            match (datatype, values.next().ok_or(OTError::InvalidArgs)?) {
+                (b'b', SpinelValue::Bool(value)) => self.write_bool(*value)?,
+                (b'C', SpinelValue::U8(value)) => self.write_u8(*value)?,
+                (b'c', SpinelValue::I8(value)) => self.write_i8(*value)?,
+                (b'S', SpinelValue::U16(value)) => self.write_u16(*value)?,
+                (b's', SpinelValue::I16(value)) => self.write_i16(*value)?,
+                (b'L', SpinelValue::U32(value)) => self.write_u32(*value)?,
+                (b'l', SpinelValue::I32(value)) => self.write_i32(*value)?,
+                (b'X', SpinelValue::U64(value)) => self.write_u64(*value)?,
+                (b'x', SpinelValue::I64(value)) => self.write_i64(*value)?,
+                (b'i', SpinelValue::Uint(value)) => self.write_packed_uint(*value)?,
+                (b'6', SpinelValue::Ipv6(value)) => self.write_ipv6(value)?,
+                (b'E', SpinelValue::Eui64(value)) => self.write_eui64(value)?,
+                (b'e', SpinelValue::Eui48(value)) => self.write_eui48(value)?,
+                (b'D', SpinelValue::Data(value)) | (b'A', SpinelValue::Data(value)) => self.write_data(value)?,
+                (b'd', SpinelValue::Data(value)) => self.write_data_with_len(value)?,
+                (b'U', SpinelValue::Utf8(value)) => self.write_utf8(value)?,
+                _ => return Err(OTError::InvalidArgs),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Spinel decoder reading from a byte buffer
+#[derive(Clone, Debug)]
+pub struct Decoder<'a> {
+    buffer: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Decoder<'a> {
+    pub fn new(buffer: &'a [u8]) -> Self {
+        Self { buffer, position: 0 }
+    }
+
+    /// Get the number of bytes read
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Get the bytes not read yet
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.buffer[self.position..]
+    }
+
+    /// Check whether every byte was read
+    pub fn is_empty(&self) -> bool {
+        self.position == self.buffer.len()
+    }
+
+    pub fn read_bytes<E>(&mut self, length: usize) -> Result<&'a [u8], OTError<E>> {
+        let end = self.position + length;
+        if end > self.buffer.len() {
+            return Err(OTError::Parse);
+        }
+        let bytes = &self.buffer[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn read_array<E, const N: usize>(&mut self) -> Result<[u8; N], OTError<E>> {
+        let mut array = [0u8; N];
+        array.copy_from_slice(self.read_bytes(N)?);
+        Ok(array)
+    }
+
+    pub fn read_bool<E>(&mut self) -> Result<bool, OTError<E>> {
+        match self.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(OTError::Parse),
+        }
+    }
+
+    pub fn read_u8<E>(&mut self) -> Result<u8, OTError<E>> {
+        Ok(self.read_array::<E, 1>()?[0])
+    }
+
+    pub fn read_i8<E>(&mut self) -> Result<i8, OTError<E>> {
+        Ok(i8::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_u16<E>(&mut self) -> Result<u16, OTError<E>> {
+        Ok(u16::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_i16<E>(&mut self) -> Result<i16, OTError<E>> {
+        Ok(i16::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_u32<E>(&mut self) -> Result<u32, OTError<E>> {
+        Ok(u32::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_i32<E>(&mut self) -> Result<i32, OTError<E>> {
+        Ok(i32::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_u64<E>(&mut self) -> Result<u64, OTError<E>> {
+        Ok(u64::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_i64<E>(&mut self) -> Result<i64, OTError<E>> {
+        Ok(i64::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_packed_uint<E>(&mut self) -> Result<u32, OTError<E>> {
+        let (value, size) = decode_packed_uint(self.remaining())?;
+        self.position += size;
+        Ok(value)
+    }
+
+    pub fn read_ipv6<E>(&mut self) -> Result<[u8; 16], OTError<E>> {
+        self.read_array()
+    }
+
+    pub fn read_eui64<E>(&mut self) -> Result<[u8; 8], OTError<E>> {
+        self.read_array()
+    }
+
+    pub fn read_eui48<E>(&mut self) -> Result<[u8; 6], OTError<E>> {
+        self.read_array()
+    }
+
+    /// Read the data filling the rest of the frame ('D')
+    pub fn read_data<E>(&mut self) -> Result<&'a [u8], OTError<E>> {
+        self.read_bytes(self.buffer.len() - self.position)
+    }
+
+    /// Read data prefixed by its length ('d')
+    pub fn read_data_with_len<E>(&mut self) -> Result<&'a [u8], OTError<E>> {
+        let length = self.read_u16()? as usize;
+        self.read_bytes(length)
+    }
+
+    /// Read a zero terminated UTF-8 string ('U')
+    pub fn read_utf8<E>(&mut self) -> Result<&'a str, OTError<E>> {
+        let remaining = self.remaining();
+        let length = remaining.iter().position(|&byte| byte == 0).ok_or(OTError::Parse)?;
+        let value = core::str::from_utf8(&remaining[..length]).map_err(|_| OTError::Parse)?;
+        self.position += length + 1;
+        Ok(value)
+    }
+
+    /// Read a struct prefixed by its length ('t(...)'), returning a decoder over its content
+    pub fn read_struct<E>(&mut self) -> Result<Decoder<'a>, OTError<E>> {
+        Ok(Decoder::new(self.read_data_with_len()?))
+    }
+
+    /// Unpack values following a datatype format string
+    ///
+    /// Returns:
+    ///     The number of values written to `values`
+    ///     OTError::Parse if the data does not match the format
+    ///     OTError::NoBuffers if `values` is too small
+    pub fn unpack<E>(&mut self, format: &str, values: &mut [SpinelValue<'a>]) -> Result<usize, OTError<E>> {
+        let mut count = 0;
+        self.unpack_format(format.as_bytes(), values, &mut count)?;
+        Ok(count)
+    }
+
+    fn unpack_format<E>(
+        &mut self,
+        format: &[u8],
+        values: &mut [SpinelValue<'a>],
+        count: &mut usize,
+    ) -> Result<(), OTError<E>> {
+        let mut index = 0;
+        while index < format.len() {
+            let datatype = format[index];
+            index += 1;
+
+            let value = match datatype {
+                b'.' => continue,
+                b't' => {
+                    let (inner, next) = inner_format(format, index)?;
+                    let mut decoder = self.read_struct()?;
+                    decoder.unpack_format(inner, values, count)?;
+                    index = next;
+                    continue;
+                },
+                b'A' => {
+                    // Arrays are unpacked as their encoded elements, to be read with a decoder
+                    let (_, next) = inner_format(format, index)?;
+                    index = next;
+                    SpinelValue::Data(self.read_data()?)
+                },
+                b'b' => SpinelValue::Bool(self.read_bool()?),
+                b'C' => SpinelValue::U8(self.read_u8()?),
+                b'c' => SpinelValue::I8(self.read_i8()?),
+                b'S' => SpinelValue::U16(self.read_u16()?),
+                b's' => SpinelValue::I16(self.read_i16()?),
+                b'L' => SpinelValue::U32(self.read_u32()?),
+                b'l' => SpinelValue::I32(self.read_i32()?),
+                b'X' => SpinelValue::U64(self.read_u64()?),
+                b'x' => SpinelValue::I64(self.read_i64()?),
+                b'i' => SpinelValue::Uint(self.read_packed_uint()?),
+                b'6' => SpinelValue::Ipv6(self.read_ipv6()?),
+                b'E' => SpinelValue::Eui64(self.read_eui64()?),
+                b'e' => SpinelValue::Eui48(self.read_eui48()?),
+                b'D' => SpinelValue::Data(self.read_data()?),
+                b'd' => SpinelValue::Data(self.read_data_with_len()?),
+                b'U' => SpinelValue::Utf8(self.read_utf8()?),
+                _ => return Err(OTError::InvalidArgs),
+            };
+
+            *values.get_mut(*count).ok_or(OTError::NoBuffers)? = value;
+            *count += 1;
+        }
+        Ok(())
+    }
+}
+
+/// Value of a Spinel datatype
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpinelValue<'a> {
+    Bool(bool),
+    U8(u8),
+    I8(i8),
+    U16(u16),
+    I16(i16),
+    U32(u32),
+    I32(i32),
+    U64(u64),
+    I64(i64),
+    // Packed unsigned int ('i')
+    Uint(u32),
+    Ipv6([u8; 16]),
+    Eui64([u8; 8]),
+    Eui48([u8; 6]),
+    // Data ('D', 'd') or the encoded elements of an array ('A(...)')
+    Data(&'a [u8]),
+    Utf8(&'a str),
+}
+
+impl Default for SpinelValue<'_> {
+    fn default() -> Self {
+        SpinelValue::Bool(false)
+    }
+}
+
+/// Find the inner format of a 't(...)' or 'A(...)' datatype
+///
+/// Params:
+///     format - the format string
+///     index - the index following the 't' or 'A'
+///
+/// Returns:
+///     The inner format and the index following the closing parenthesis
+fn inner_format<E>(format: &[u8], index: usize) -> Result<(&[u8], usize), OTError<E>> {
+    if format.get(index) != Some(&b'(') {
+        return Err(OTError::InvalidArgs);
+    }
+
+    let mut depth = 0;
+    for (position, &datatype) in format.iter().enumerate().skip(index) {
+        match datatype {
+            b'(' => depth += 1,
+            b')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok((&format[index + 1..position], position + 1));
+                }
+            },
+            _ => (),
+        }
+    }
+    Err(OTError::InvalidArgs)
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use super::*;
+
+    #[test]
+    fn packed_uint_round_trip() {
+        let cases: [(u32, &[u8]); 7] = [
+            (0, &[0x00]),
+            (0x7f, &[0x7f]),
+            (0x80, &[0x80, 0x01]),
+            (513, &[0x81, 0x04]),
+            (0x3fff, &[0xff, 0x7f]),
+            (0x4000, &[0x80, 0x80, 0x01]),
+            (MAX_UINT_PACKED, &[0xff, 0xff, 0x7f]),
+        ];
+
+        for (value, encoded) in cases {
+            let mut buffer = [0u8; MAX_UINT_PACKED_SIZE];
+            let size = encode_packed_uint::<Infallible>(&mut buffer, value).unwrap();
+            assert_eq!(&buffer[..size], encoded, "{}", value);
+            assert_eq!(packed_uint_size(value), size, "{}", value);
+            assert_eq!(decode_packed_uint::<Infallible>(encoded), Ok((value, size)), "{}", value);
+        }
+    }
+
+    #[test]
+    fn packed_uint_errors() {
+        let mut buffer = [0u8; MAX_UINT_PACKED_SIZE];
+        assert_eq!(encode_packed_uint::<Infallible>(&mut buffer, MAX_UINT_PACKED + 1), Err(OTError::InvalidArgs));
+        assert_eq!(encode_packed_uint::<Infallible>(&mut buffer[..1], 0x80), Err(OTError::NoBuffers));
+
+        // Truncated, and longer than MAX_UINT_PACKED_SIZE
+        assert_eq!(decode_packed_uint::<Infallible>(&[0x80]), Err(OTError::Parse));
+        assert_eq!(decode_packed_uint::<Infallible>(&[0x80, 0x80, 0x80, 0x01]), Err(OTError::Parse));
+        assert_eq!(decode_packed_uint::<Infallible>(&[]), Err(OTError::Parse));
+    }
+
+    #[test]
+    fn datatypes_round_trip() {
+        let format = "bCcSsLlXxi6EedUt(Ci)D";
+        let values = [
+            SpinelValue::Bool(true),
+            SpinelValue::U8(0xfe),
+            SpinelValue::I8(-2),
+            SpinelValue::U16(0xbeef),
+            SpinelValue::I16(-300),
+            SpinelValue::U32(0xdead_beef),
+            SpinelValue::I32(-70_000),
+            SpinelValue::U64(0x0102_0304_0506_0708),
+            SpinelValue::I64(-5),
+            SpinelValue::Uint(513),
+            SpinelValue::Ipv6([0xfe; 16]),
+            SpinelValue::Eui64([1, 2, 3, 4, 5, 6, 7, 8]),
+            SpinelValue::Eui48([1, 2, 3, 4, 5, 6]),
+            SpinelValue::Data(&[0xaa, 0xbb]),
+            SpinelValue::Utf8("rcp"),
+            SpinelValue::U8(7),
+            SpinelValue::Uint(200),
+            SpinelValue::Data(&[0x01, 0x02, 0x03]),
+        ];
+
+        let mut buffer = [0u8; 128];
+        let mut encoder = Encoder::new(&mut buffer);
+        encoder.pack::<Infallible>(format, &values).unwrap();
+        let encoded = encoder.finish::<Infallible>().unwrap();
+
+        // Spot check the little endian, length prefixed and struct encodings
+        assert_eq!(&encoded[..6], &[0x01, 0xfe, 0xfe, 0xef, 0xbe, 0xd4]);
+        let struct_start = encoded.len() - 3 - 5;
+        assert_eq!(&encoded[struct_start..struct_start + 5], &[0x03, 0x00, 0x07, 0xc8, 0x01]);
+
+        let mut decoded = [SpinelValue::default(); 18];
+        let count = Decoder::new(encoded).unpack::<Infallible>(format, &mut decoded).unwrap();
+        assert_eq!(count, values.len());
+        assert_eq!(decoded, values);
+    }
+
+    #[test]
+    fn arrays_are_encoded_elements() {
+        let mut elements = [0u8; 8];
+        let mut encoder = Encoder::new(&mut elements);
+        for value in [1u16, 2, 3] {
+            encoder.write_u16::<Infallible>(value).unwrap();
+        }
+        let elements = encoder.finish::<Infallible>().unwrap();
+
+        let mut buffer = [0u8; 16];
+        let mut encoder = Encoder::new(&mut buffer);
+        encoder.pack::<Infallible>("CA(S)", &[SpinelValue::U8(9), SpinelValue::Data(elements)]).unwrap();
+        let encoded = encoder.finish::<Infallible>().unwrap();
+
+        let mut values = [SpinelValue::default(); 2];
+        Decoder::new(encoded).unpack::<Infallible>("CA(S)", &mut values).unwrap();
+        let SpinelValue::Data(array) = values[1] else {
+            panic!("array not decoded as data");
+        };
+        let mut decoder = Decoder::new(array);
+        let mut decoded = [0u16; 3];
+        for value in decoded.iter_mut() {
+            *value = decoder.read_u16::<Infallible>().unwrap();
+        }
+        assert_eq!(decoded, [1, 2, 3]);
+        assert!(decoder.is_empty());
+    }
+
+    #[test]
+    fn mismatched_formats_are_rejected() {
+        let mut buffer = [0u8; 16];
+        let mut encoder = Encoder::new(&mut buffer);
+        assert_eq!(encoder.pack::<Infallible>("C", &[SpinelValue::U16(1)]), Err(OTError::InvalidArgs));
+        assert_eq!(encoder.pack::<Infallible>("C", &[]), Err(OTError::InvalidArgs));
+        assert_eq!(encoder.pack::<Infallible>("t(C", &[SpinelValue::U8(1)]), Err(OTError::InvalidArgs));
+
+        let mut values = [SpinelValue::default(); 1];
+        assert_eq!(Decoder::new(&[0x01]).unpack::<Infallible>("S", &mut values), Err(OTError::Parse));
+        assert_eq!(Decoder::new(&[0x01, 0x02]).unpack::<Infallible>("CC", &mut values), Err(OTError::NoBuffers));
+        assert_eq!(Decoder::new(&[0x02]).unpack::<Infallible>("b", &mut values), Err(OTError::Parse));
+    }
+}
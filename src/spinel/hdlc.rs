@@ -0,0 +1,382 @@
+//!
+//! HDLC-Lite Framing
+//!
+//! Framing used to carry Spinel frames over a UART:
+//!
+//! ```text
+//! [flag][escaped frame][escaped FCS (2 bytes, little endian)][flag]
+//! ```
+//!
+//! The flag, escape, XON, XOFF and 0xf8 bytes are escaped as [escape][byte ^ 0x20]. The FCS is the CRC-16/CCITT
+//! (HDLC flavour) of the unescaped frame. Both the encoder and the decoder work incrementally on caller provided
+//! buffers and never allocate.
+//!
+
+use crate::error::OTError;
+
+// Frame delimiter
+pub const HDLC_FLAG: u8 = 0x7e;
+// Escape marker (the following byte is XORed with HDLC_ESCAPE_XOR)
+pub const HDLC_ESCAPE: u8 = 0x7d;
+const HDLC_ESCAPE_XOR: u8 = 0x20;
+// Software flow control bytes, escaped so they never appear in a frame
+const HDLC_XON: u8 = 0x11;
+const HDLC_XOFF: u8 = 0x13;
+// Escaped for compatibility with the OpenThread implementation
+const HDLC_VENDOR: u8 = 0xf8;
+// Size of the FCS (in bytes)
+pub const HDLC_FCS_SIZE: usize = 2;
+
+// Initial FCS value
+const FCS_INIT: u16 = 0xffff;
+// FCS residue of a frame followed by its FCS
+const FCS_GOOD: u16 = 0xf0b8;
+// Reflected CRC-16/CCITT polynomial
+const FCS_POLYNOMIAL: u16 = 0x8408;
+
+/// Update an FCS with a byte
+pub fn fcs_update(fcs: u16, byte: u8) -> u16 {
+    let mut fcs = fcs ^ byte as u16;
+    for _ in 0..8 {
+        fcs = if fcs & 1 != 0 { (fcs >> 1) ^ FCS_POLYNOMIAL } else { fcs >> 1 };
+    }
+    fcs
+}
+
+/// Compute the FCS of a frame (as transmitted, i.e. already complemented)
+pub fn fcs(bytes: &[u8]) -> u16 {
+    !bytes.iter().fold(FCS_INIT, |fcs, &byte| fcs_update(fcs, byte))
+}
+
+/// Check whether a byte has to be escaped
+fn needs_escape(byte: u8) -> bool {
+    matches!(byte, HDLC_FLAG | HDLC_ESCAPE | HDLC_XON | HDLC_XOFF | HDLC_VENDOR)
+}
+
+/// Incremental HDLC-lite encoder writing into a byte buffer
+pub struct HdlcEncoder<'a> {
+    buffer: &'a mut [u8],
+    position: usize,
+    fcs: u16,
+}
+
+impl<'a> HdlcEncoder<'a> {
+    pub fn new(buffer: &'a mut [u8]) -> Self {
+        Self {
+            buffer,
+            position: 0,
+            fcs: FCS_INIT,
+        }
+    }
+
+    /// Get the encoded bytes
+    pub fn bytes(&self) -> &[u8] {
+        &self.buffer[..self.position]
+    }
+
+    /// Get the number of encoded bytes
+    pub fn len(&self) -> usize {
+        self.position
+    }
+
+    /// Check whether nothing was encoded
+    pub fn is_empty(&self) -> bool {
+        self.position == 0
+    }
+
+    /// Drop the encoded bytes (e.g. once they were sent), keeping the frame being encoded
+    pub fn clear(&mut self) {
+        self.position = 0;
+    }
+
+    /// Start a frame
+    pub fn begin_frame<E>(&mut self) -> Result<(), OTError<E>> {
+        self.fcs = FCS_INIT;
+        self.write(HDLC_FLAG)
+    }
+
+    /// Encode frame bytes
+    ///
+    /// Returns:
+    ///     OTError::NoBuffers if the buffer is full (the bytes encoded so far are kept)
+    pub fn encode<E>(&mut self, bytes: &[u8]) -> Result<(), OTError<E>> {
+        for &byte in bytes {
+            self.encode_byte(byte)?;
+            self.fcs = fcs_update(self.fcs, byte);
+        }
+        Ok(())
+    }
+
+    /// End a frame by writing its FCS and the closing flag
+    pub fn end_frame<E>(&mut self) -> Result<(), OTError<E>> {
+        let fcs = !self.fcs;
+        for byte in fcs.to_le_bytes() {
+            self.encode_byte(byte)?;
+        }
+        self.write(HDLC_FLAG)
+    }
+
+    /// Encode a complete frame
+    pub fn encode_frame<E>(&mut self, frame: &[u8]) -> Result<(), OTError<E>> {
+        self.begin_frame()?;
+        self.encode(frame)?;
+        self.end_frame()
+    }
+
+    fn encode_byte<E>(&mut self, byte: u8) -> Result<(), OTError<E>> {
+        if needs_escape(byte) {
+            if self.position + 2 > self.buffer.len() {
+                return Err(OTError::NoBuffers);
+            }
+            self.write(HDLC_ESCAPE)?;
+            self.write(byte ^ HDLC_ESCAPE_XOR)
+        } else {
+            self.write(byte)
+        }
+    }
+
+    fn write<E>(&mut self, byte: u8) -> Result<(), OTError<E>> {
+        let slot = self.buffer.get_mut(self.position).ok_or(OTError::NoBuffers)?;
+        *slot = byte;
+        self.position += 1;
+        Ok(())
+    }
+}
+
+/// Incremental HDLC-lite decoder
+///
+/// Bytes received from the transport are fed one at a time (or by slices) and every complete frame is reported,
+/// either as its content or as an error if it was malformed.
+pub struct HdlcDecoder<'a> {
+    buffer: &'a mut [u8],
+    length: usize,
+    fcs: u16,
+    state: DecoderState,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DecoderState {
+    // Waiting for the flag starting a frame
+    NoSync,
+    // Receiving frame bytes
+    Receiving,
+    // The previous byte was an escape
+    Escaped,
+    // The frame overflowed the buffer, waiting for its end
+    Overflow,
+}
+
+impl<'a> HdlcDecoder<'a> {
+    pub fn new(buffer: &'a mut [u8]) -> Self {
+        Self {
+            buffer,
+            length: 0,
+            fcs: FCS_INIT,
+            state: DecoderState::NoSync,
+        }
+    }
+
+    /// Drop the frame being received and wait for the next flag
+    pub fn reset(&mut self) {
+        self.state = DecoderState::NoSync;
+        self.restart();
+    }
+
+    /// Decode a received byte
+    ///
+    /// Returns:
+    ///     None while no frame was completed
+    ///     The frame content (without FCS) when a valid frame was completed
+    ///     OTError::Parse if a malformed frame or a frame with a bad FCS was completed
+    ///     OTError::NoBuffers if a frame larger than the buffer was completed
+    pub fn decode_byte<E>(&mut self, byte: u8) -> Option<Result<&[u8], OTError<E>>> {
+        match (self.state, byte) {
+            (DecoderState::NoSync, HDLC_FLAG) => {
+                self.restart();
+                self.state = DecoderState::Receiving;
+                None
+            },
+            (DecoderState::NoSync, _) => None,
+            (DecoderState::Receiving, HDLC_FLAG) => self.complete(),
+            (DecoderState::Receiving, HDLC_ESCAPE) => {
+                self.state = DecoderState::Escaped;
+                None
+            },
+            (DecoderState::Receiving, _) => {
+                self.push(byte);
+                None
+            },
+            (DecoderState::Escaped, HDLC_FLAG) => {
+                // A frame can not end in the middle of an escape sequence
+                self.restart();
+                self.state = DecoderState::Receiving;
+                Some(Err(OTError::Parse))
+            },
+            (DecoderState::Escaped, _) => {
+                self.state = DecoderState::Receiving;
+                self.push(byte ^ HDLC_ESCAPE_XOR);
+                None
+            },
+            (DecoderState::Overflow, HDLC_FLAG) => {
+                self.restart();
+                self.state = DecoderState::Receiving;
+                Some(Err(OTError::NoBuffers))
+            },
+            (DecoderState::Overflow, _) => None,
+        }
+    }
+
+    /// Decode received bytes, reporting every completed frame
+    pub fn decode<E>(&mut self, bytes: &[u8], mut handle: impl FnMut(Result<&[u8], OTError<E>>)) {
+        for &byte in bytes {
+            if let Some(result) = self.decode_byte(byte) {
+                handle(result);
+            }
+        }
+    }
+
+    fn push(&mut self, byte: u8) {
+        match self.buffer.get_mut(self.length) {
+            Some(slot) => {
+                *slot = byte;
+                self.length += 1;
+                self.fcs = fcs_update(self.fcs, byte);
+            },
+            None => self.state = DecoderState::Overflow,
+        }
+    }
+
+    fn restart(&mut self) {
+        self.length = 0;
+        self.fcs = FCS_INIT;
+    }
+
+    fn complete<E>(&mut self) -> Option<Result<&[u8], OTError<E>>> {
+        let length = self.length;
+        let fcs = self.fcs;
+        self.restart();
+
+        // Consecutive flags delimit empty frames, which are ignored
+        if length == 0 {
+            return None;
+        }
+        if length < HDLC_FCS_SIZE || fcs != FCS_GOOD {
+            return Some(Err(OTError::Parse));
+        }
+        Some(Ok(&self.buffer[..length - HDLC_FCS_SIZE]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use core::convert::Infallible;
+
+    use super::*;
+
+    /// Decode bytes and collect the completed frames
+    fn decode_all(decoder: &mut HdlcDecoder, bytes: &[u8]) -> Vec<Result<Vec<u8>, OTError<Infallible>>> {
+        let mut frames = Vec::new();
+        decoder.decode(bytes, |result: Result<&[u8], OTError<Infallible>>| {
+            frames.push(result.map(|frame| frame.to_vec()))
+        });
+        frames
+    }
+
+    #[test]
+    fn fcs_is_crc16_x25() {
+        // Check value of CRC-16/X-25
+        assert_eq!(fcs(b"123456789"), 0x906e);
+
+        // A frame followed by its FCS leaves the good residue
+        let frame = b"123456789";
+        let residue = frame
+            .iter()
+            .chain(fcs(frame).to_le_bytes().iter())
+            .fold(FCS_INIT, |fcs, &byte| fcs_update(fcs, byte));
+        assert_eq!(residue, FCS_GOOD);
+    }
+
+    #[test]
+    fn reserved_bytes_are_escaped() {
+        let frame = [HDLC_FLAG, HDLC_ESCAPE, HDLC_XON, HDLC_XOFF, HDLC_VENDOR, 0x01];
+        let mut buffer = [0; 32];
+        let mut encoder = HdlcEncoder::new(&mut buffer);
+        encoder.encode_frame::<Infallible>(&frame).unwrap();
+
+        let bytes = encoder.bytes();
+        let fcs_start = bytes.len() - 1 - HDLC_FCS_SIZE;
+        assert_eq!(
+            &bytes[..fcs_start],
+            &[0x7e, 0x7d, 0x5e, 0x7d, 0x5d, 0x7d, 0x31, 0x7d, 0x33, 0x7d, 0xd8, 0x01]
+        );
+        assert_eq!(&bytes[fcs_start..bytes.len() - 1], &fcs(&frame).to_le_bytes());
+        assert_eq!(bytes[bytes.len() - 1], HDLC_FLAG);
+    }
+
+    #[test]
+    fn encoded_frames_are_decoded() {
+        let frames: [&[u8]; 3] = [&[0x81, 0x02, 0x00], &[HDLC_FLAG, HDLC_ESCAPE, 0x20, HDLC_VENDOR], &[0x42]];
+        let mut buffer = [0; 64];
+        let mut encoder = HdlcEncoder::new(&mut buffer);
+        for frame in frames {
+            encoder.encode_frame::<Infallible>(frame).unwrap();
+        }
+
+        // Garbage before the first flag and the back to back flags between frames are ignored
+        let mut stream = vec![0x01, 0x02];
+        stream.extend_from_slice(encoder.bytes());
+        let mut decoder_buffer = [0; 16];
+        let mut decoder = HdlcDecoder::new(&mut decoder_buffer);
+        let decoded = decode_all(&mut decoder, &stream);
+        let expected: Vec<Result<Vec<u8>, OTError<Infallible>>> =
+            frames.iter().map(|frame| Ok(frame.to_vec())).collect();
+        assert_eq!(decoded, expected);
+    }
+
+    #[test]
+    fn malformed_frames_are_rejected() {
+        let cases: [(&str, &[u8]); 3] = [
+            ("bad fcs", &[0x7e, 0x81, 0x02, 0x00, 0x12, 0x34, 0x7e]),
+            ("shorter than the fcs", &[0x7e, 0x81, 0x7e]),
+            ("escape before the flag", &[0x7e, 0x81, 0x02, 0x7d, 0x7e]),
+        ];
+
+        for (name, bytes) in cases {
+            let mut buffer = [0; 16];
+            let mut decoder = HdlcDecoder::new(&mut buffer);
+            assert_eq!(decode_all(&mut decoder, bytes), vec![Err(OTError::Parse)], "{}", name);
+        }
+    }
+
+    #[test]
+    fn overflow_resyncs_on_the_next_frame() {
+        let mut buffer = [0; 64];
+        let mut encoder = HdlcEncoder::new(&mut buffer);
+        encoder.encode_frame::<Infallible>(&[0x55; 12]).unwrap();
+        encoder.encode_frame::<Infallible>(&[0x66; 4]).unwrap();
+
+        let mut decoder_buffer = [0; 8];
+        let mut decoder = HdlcDecoder::new(&mut decoder_buffer);
+        assert_eq!(
+            decode_all(&mut decoder, encoder.bytes()),
+            vec![Err(OTError::NoBuffers), Ok(vec![0x66; 4])]
+        );
+    }
+
+    #[test]
+    fn encoder_reports_a_full_buffer() {
+        // An escape sequence is never split across the end of the buffer
+        let mut buffer = [0; 2];
+        let mut encoder = HdlcEncoder::new(&mut buffer);
+        encoder.begin_frame::<Infallible>().unwrap();
+        assert_eq!(encoder.encode::<Infallible>(&[HDLC_FLAG]), Err(OTError::NoBuffers));
+        assert_eq!(encoder.bytes(), &[HDLC_FLAG]);
+
+        encoder.clear();
+        assert!(encoder.is_empty());
+        encoder.encode::<Infallible>(&[0x01, 0x02]).unwrap();
+        assert_eq!(encoder.encode::<Infallible>(&[0x03]), Err(OTError::NoBuffers));
+    }
+}
@@ -0,0 +1,279 @@
+//!
+//! Radio Frames in Spinel Values
+//!
+//! Encoding of radio frames carried by the raw stream (Property::StreamRaw) and by transmit done notifications.
+//!
+//! The property identifiers are the Spinel ones, but the metadata layouts below are specific to this crate: they do
+//! not match the `radio_spinel` encoding of upstream OpenThread, so a `SpinelRadio` must talk to an `Rcp` of this
+//! crate (and an upstream host to an upstream RCP).
+//!
+//! Frame to transmit (host to RCP):
+//!     "dCCCbbbbbLLCxCC" - psdu, channel, max CSMA backoffs, max frame retries, CSMA-CA enabled, header updated,
+//!         retransmission, CSL IE present, security processed, tx delay, tx delay base time, rx channel after tx done,
+//!         network time offset, time IE offset, time sync sequence
+//!
+//! Received frame (RCP to host):
+//!     "dcCCXLCbb" - psdu, rssi, lqi, channel, timestamp, ack frame counter, ack key id, acked with frame pending,
+//!         acked with secured enhanced ACK
+//!
+//! Transmit done (RCP to host, the answer to the raw stream set):
+//!     "ibLb" followed by a received frame when an ACK was received - status, header updated, frame counter, ACK
+//!         present
+//!
+
+use crate::error::OTError;
+use crate::radio::frame::{Frame, FRAME_COUNTER_SIZE, SECURITY_CONTROL_SIZE};
+use crate::radio::{OTFrameInformation, OTMacKeyMaterial, OTRadioFrame, RadioIEInfo};
+
+use super::codec::{Decoder, Encoder};
+use super::Status;
+
+/// Transmit information of a frame sent over the raw stream
+#[derive(Clone, Copy, Default)]
+pub struct TxMetadata {
+    pub channel: u8,
+    pub max_csma_backoffs: u8,
+    pub max_frame_retries: u8,
+    pub csma_ca_enabled: bool,
+    pub is_header_updated: bool,
+    pub is_a_retx: bool,
+    pub csl_present: bool,
+    pub is_security_processed: bool,
+    pub tx_delay: u32,
+    pub tx_delay_base_time: u32,
+    pub rx_channel_after_tx_done: u8,
+    pub ie_info: RadioIEInfo,
+}
+
+impl TxMetadata {
+    /// Get the transmit information of a radio frame
+    ///
+    /// Returns:
+    ///     OTError::InvalidArgs if the frame does not hold transmit information
+    pub fn from_radio_frame<E>(frame: &OTRadioFrame) -> Result<Self, OTError<E>> {
+        let OTFrameInformation::TxInfo {
+            io_info,
+            tx_delay_base_time,
+            tx_delay,
+            max_csma_backoffs,
+            max_frame_retries,
+            rx_channel_after_tx_done,
+            is_header_updated,
+            is_a_retx,
+            csma_ca_enabled,
+            csl_present,
+            is_security_processed,
+            ..
+        } = frame.frame_information
+        else {
+            return Err(OTError::InvalidArgs);
+        };
+
+        Ok(Self {
+            channel: frame.channel,
+            max_csma_backoffs,
+            max_frame_retries,
+            csma_ca_enabled,
+            is_header_updated,
+            is_a_retx,
+            csl_present,
+            is_security_processed,
+            tx_delay,
+            tx_delay_base_time,
+            rx_channel_after_tx_done,
+            ie_info: *io_info,
+        })
+    }
+
+    /// Build the radio frame to transmit
+    pub fn radio_frame<'a>(&'a self, psdu: &'a [u8], aes_key: OTMacKeyMaterial) -> OTRadioFrame<'a> {
+        OTRadioFrame {
+            psdu,
+            channel: self.channel,
+            radio_type: 0,
+            frame_information: OTFrameInformation::TxInfo {
+                aes_key,
+                io_info: &self.ie_info,
+                tx_delay_base_time: self.tx_delay_base_time,
+                tx_delay: self.tx_delay,
+                max_csma_backoffs: self.max_csma_backoffs,
+                max_frame_retries: self.max_frame_retries,
+                rx_channel_after_tx_done: self.rx_channel_after_tx_done,
+                is_header_updated: self.is_header_updated,
+                is_a_retx: self.is_a_retx,
+                csma_ca_enabled: self.csma_ca_enabled,
+                csl_present: self.csl_present,
+                is_security_processed: self.is_security_processed,
+            },
+        }
+    }
+}
+
+/// Encode a frame to transmit
+pub fn encode_tx_frame<E>(encoder: &mut Encoder, frame: &OTRadioFrame) -> Result<(), OTError<E>> {
+    let metadata = TxMetadata::from_radio_frame(frame)?;
+    encoder.write_data_with_len(frame.psdu)?;
+    encoder.write_u8(metadata.channel)?;
+    encoder.write_u8(metadata.max_csma_backoffs)?;
+    encoder.write_u8(metadata.max_frame_retries)?;
+    encoder.write_bool(metadata.csma_ca_enabled)?;
+    encoder.write_bool(metadata.is_header_updated)?;
+    encoder.write_bool(metadata.is_a_retx)?;
+    encoder.write_bool(metadata.csl_present)?;
+    encoder.write_bool(metadata.is_security_processed)?;
+    encoder.write_u32(metadata.tx_delay)?;
+    encoder.write_u32(metadata.tx_delay_base_time)?;
+    encoder.write_u8(metadata.rx_channel_after_tx_done)?;
+    encoder.write_i64(metadata.ie_info.network_time_offset)?;
+    encoder.write_u8(metadata.ie_info.time_ie_offset)?;
+    encoder.write_u8(metadata.ie_info.time_sync_sequency)
+}
+
+/// Decode a frame to transmit
+///
+/// Returns:
+///     The PSDU and its transmit information
+pub fn decode_tx_frame<'a, E>(decoder: &mut Decoder<'a>) -> Result<(&'a [u8], TxMetadata), OTError<E>> {
+    let psdu = decoder.read_data_with_len()?;
+    let metadata = TxMetadata {
+        channel: decoder.read_u8()?,
+        max_csma_backoffs: decoder.read_u8()?,
+        max_frame_retries: decoder.read_u8()?,
+        csma_ca_enabled: decoder.read_bool()?,
+        is_header_updated: decoder.read_bool()?,
+        is_a_retx: decoder.read_bool()?,
+        csl_present: decoder.read_bool()?,
+        is_security_processed: decoder.read_bool()?,
+        tx_delay: decoder.read_u32()?,
+        tx_delay_base_time: decoder.read_u32()?,
+        rx_channel_after_tx_done: decoder.read_u8()?,
+        ie_info: RadioIEInfo {
+            network_time_offset: decoder.read_i64()?,
+            time_ie_offset: decoder.read_u8()?,
+            time_sync_sequency: decoder.read_u8()?,
+        },
+    };
+    Ok((psdu, metadata))
+}
+
+/// Encode a received frame
+///
+/// Returns:
+///     OTError::InvalidArgs if the frame does not hold receive information
+pub fn encode_rx_frame<E>(encoder: &mut Encoder, frame: &OTRadioFrame) -> Result<(), OTError<E>> {
+    let OTFrameInformation::RxInfo {
+        timestamp,
+        ack_frame_counter,
+        ack_key_id,
+        rssi,
+        lqi,
+        acked_with_frame_pending,
+        acked_with_sec_enh_ack,
+    } = frame.frame_information
+    else {
+        return Err(OTError::InvalidArgs);
+    };
+
+    encoder.write_data_with_len(frame.psdu)?;
+    encoder.write_i8(rssi)?;
+    encoder.write_u8(lqi)?;
+    encoder.write_u8(frame.channel)?;
+    encoder.write_u64(timestamp)?;
+    encoder.write_u32(ack_frame_counter)?;
+    encoder.write_u8(ack_key_id)?;
+    encoder.write_bool(acked_with_frame_pending)?;
+    encoder.write_bool(acked_with_sec_enh_ack)
+}
+
+/// Decode a received frame
+pub fn decode_rx_frame<'a, E>(decoder: &mut Decoder<'a>) -> Result<OTRadioFrame<'a>, OTError<E>> {
+    let psdu = decoder.read_data_with_len()?;
+    let rssi = decoder.read_i8()?;
+    let lqi = decoder.read_u8()?;
+    let channel = decoder.read_u8()?;
+    Ok(OTRadioFrame {
+        psdu,
+        channel,
+        radio_type: 0,
+        frame_information: OTFrameInformation::RxInfo {
+            timestamp: decoder.read_u64()?,
+            ack_frame_counter: decoder.read_u32()?,
+            ack_key_id: decoder.read_u8()?,
+            rssi,
+            lqi,
+            acked_with_frame_pending: decoder.read_bool()?,
+            acked_with_sec_enh_ack: decoder.read_bool()?,
+        },
+    })
+}
+
+/// Get the frame counter of a secured frame (0 if the frame is not secured or malformed)
+pub fn frame_counter(psdu: &[u8]) -> u32 {
+    Frame::parse::<()>(psdu)
+        .ok()
+        .and_then(|frame| frame.security_header().and_then(|header| header.frame_counter))
+        .unwrap_or(0)
+}
+
+/// Write a frame counter into the auxiliary security header of a frame (nothing is written if the frame is not
+/// secured, has its frame counter suppressed or is malformed)
+pub fn set_frame_counter(psdu: &mut [u8], frame_counter: u32) {
+    let counter_offset = Frame::parse::<()>(psdu).ok().and_then(|frame| {
+        let header = frame.security_header().filter(|header| header.frame_counter.is_some())?;
+        Some(frame.header_ie_range().start - header.size() + SECURITY_CONTROL_SIZE)
+    });
+    if let Some(counter_offset) = counter_offset {
+        psdu[counter_offset..counter_offset + FRAME_COUNTER_SIZE].copy_from_slice(&frame_counter.to_le_bytes());
+    }
+}
+
+/// Outcome of a transmission carried by a transmit done notification
+pub struct TxDone<'a> {
+    pub status: Status,
+    // The RCP updated the frame counter (and CSL IE) of the frame
+    pub is_header_updated: bool,
+    // Frame counter of the transmitted frame (valid when the header was updated)
+    pub frame_counter: u32,
+    pub ack_frame: Option<OTRadioFrame<'a>>,
+}
+
+/// Encode the outcome of a transmission
+pub fn encode_tx_done<E>(encoder: &mut Encoder, tx_done: &TxDone) -> Result<(), OTError<E>> {
+    encoder.write_packed_uint(tx_done.status.code())?;
+    encoder.write_bool(tx_done.is_header_updated)?;
+    encoder.write_u32(tx_done.frame_counter)?;
+    encoder.write_bool(tx_done.ack_frame.is_some())?;
+    if let Some(ack_frame) = tx_done.ack_frame.as_ref() {
+        encode_rx_frame(encoder, ack_frame)?;
+    }
+    Ok(())
+}
+
+/// Decode the outcome of a transmission
+pub fn decode_tx_done<'a, E>(decoder: &mut Decoder<'a>) -> Result<TxDone<'a>, OTError<E>> {
+    let status = Status::from_code(decoder.read_packed_uint()?).unwrap_or(Status::Failure);
+
+    // A transmit request rejected by the RCP is answered with a plain status
+    if decoder.is_empty() {
+        return Ok(TxDone {
+            status,
+            is_header_updated: false,
+            frame_counter: 0,
+            ack_frame: None,
+        });
+    }
+
+    let is_header_updated = decoder.read_bool()?;
+    let frame_counter = decoder.read_u32()?;
+    let ack_frame = if decoder.read_bool()? {
+        Some(decode_rx_frame(decoder)?)
+    } else {
+        None
+    };
+    Ok(TxDone {
+        status,
+        is_header_updated,
+        frame_counter,
+        ack_frame,
+    })
+}
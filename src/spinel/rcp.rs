@@ -0,0 +1,613 @@
+//!
+//! Radio Co-Processor (RCP) Core
+//!
+//! Serves a radio implementing the OTRadio traits to an OpenThread host over Spinel. The transport is left to the
+//! caller: request frames received from the host are passed to `Rcp::handle_frame`, and `Rcp::process` is polled
+//! for the frames the RCP sends on its own (reset notification, received frames, transmit done and energy scan
+//! results). Both write the frame to send into a caller provided buffer (FRAME_MAX_SIZE bytes is always enough).
+//!
+//! The radio driver reports transmit and energy scan completion through the `RcpEvents` handles, which can be
+//! borrowed alongside the radio with `Rcp::split`.
+//!
+//! As in the OpenThread RCP, enabling the raw stream turns the receiver on (on the current channel) and disabling it
+//! puts the radio to sleep, and a raw stream set (a transmit request) is only answered once the transmission is done.
+//!
+
+use core::marker::PhantomData;
+
+use crate::error::OTError;
+use crate::radio::{
+    OTKeyType, OTLinkMetrics, OTMacKey, OTMacKeyMaterial, OTRadioConfiguration, OTRadioConfigurationCapTransmit,
+    OTRadioFrame, OTRadioOperation, OTRadioOperationEnergyScan, OTRadioOperationEnergyScanHandles,
+    OTRadioOperationHandles, ReceiveFrame, TransmitFrame, OT_MAC_KEY_SIZE,
+};
+
+use super::codec::{Decoder, Encoder};
+use super::raw::{self, TxDone, TxMetadata};
+use super::{
+    Command, Header, Property, SpinelFrame, Status, CAP_CONFIG_RADIO, CAP_MAC_RAW, CAP_RCP_API_VERSION,
+    MIN_HOST_API_VERSION, PROTOCOL_TYPE_THREAD, PROTOCOL_VERSION_MAJOR, PROTOCOL_VERSION_MINOR, RCP_API_VERSION,
+};
+
+// Version string reported in Property::NcpVersion
+pub const RCP_VERSION: &str = concat!("ot-rs/", env!("CARGO_PKG_VERSION"), "; RCP");
+// Channel the RCP listens on until the host sets one
+const DEFAULT_CHANNEL: u8 = 11;
+// Default energy scan duration per channel (in ms)
+const DEFAULT_SCAN_PERIOD: u16 = 100;
+
+// Values of Property::MacScanState
+const SCAN_STATE_IDLE: u8 = 0;
+const SCAN_STATE_ENERGY: u8 = 2;
+
+// Values of Property::MacPromiscuousMode
+const PROMISCUOUS_MODE_OFF: u8 = 0;
+const PROMISCUOUS_MODE_FULL: u8 = 2;
+
+// Link metrics flags of Property::RcpEnhAckProbing
+const LINK_METRICS_PDU_COUNT: u8 = 1 << 0;
+const LINK_METRICS_LQI: u8 = 1 << 1;
+const LINK_METRICS_LINK_MARGIN: u8 = 1 << 2;
+const LINK_METRICS_RSSI: u8 = 1 << 3;
+
+/// Outcome of a transmission waiting to be reported to the host
+struct PendingTxDone {
+    status: Status,
+    is_header_updated: bool,
+    frame_counter: u32,
+    ack_frame: Option<ReceiveFrame>,
+}
+
+/// Radio driver callbacks collected by the RCP
+pub struct RcpEvents<E> {
+    tx_done: Option<PendingTxDone>,
+    energy_scan_done: Option<i8>,
+    _error: PhantomData<E>,
+}
+
+impl<E> RcpEvents<E> {
+    const fn new() -> Self {
+        Self {
+            tx_done: None,
+            energy_scan_done: None,
+            _error: PhantomData,
+        }
+    }
+}
+
+impl<E> OTRadioOperationHandles for RcpEvents<E> {
+    type Error = E;
+
+    fn tx_started(&mut self, _frame: OTRadioFrame) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn tx_done(
+        &mut self,
+        frame: OTRadioFrame,
+        ack_frame: Option<OTRadioFrame>,
+        result: Result<(), OTError<Self::Error>>,
+    ) -> Result<(), Self::Error> {
+        let status = match result {
+            Ok(()) => Status::Ok,
+            Err(error) => Status::from_error(&error),
+        };
+        let ack_frame = ack_frame.and_then(|ack_frame| {
+            let mut copy = ReceiveFrame::new();
+            copy.copy_from::<E>(&ack_frame).ok().map(|()| copy)
+        });
+
+        self.tx_done = Some(PendingTxDone {
+            status,
+            is_header_updated: TxMetadata::from_radio_frame::<E>(&frame)
+                .is_ok_and(|metadata| metadata.is_header_updated),
+            frame_counter: raw::frame_counter(frame.psdu),
+            ack_frame,
+        });
+        Ok(())
+    }
+
+    fn diag_tx_done(&mut self, _frame: OTRadioFrame, _result: Result<(), OTError<Self::Error>>) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn get_raw_power_setting(&mut self, _channel: u8, _raw_power_setting_buffer: &mut [u8]) -> Result<(), OTError<Self::Error>> {
+        Err(OTError::NotImplemented)
+    }
+}
+
+impl<E> OTRadioOperationEnergyScanHandles for RcpEvents<E> {
+    type Error = E;
+
+    fn energy_scan_done(&mut self, max_rssi: i8) -> Result<(), Self::Error> {
+        self.energy_scan_done = Some(max_rssi);
+        Ok(())
+    }
+}
+
+/// Radio co-processor serving a radio over Spinel
+pub struct Rcp<R: OTRadioOperation> {
+    radio: R,
+    events: RcpEvents<R::Error>,
+    // Unsolicited status to report (the reset reason after start up)
+    pending_status: Option<Status>,
+    // Header of the transmit request waiting for its transmit done
+    tx_header: Option<Header>,
+    tx_frame: TransmitFrame,
+    tx_metadata: TxMetadata,
+    mac_key: OTMacKeyMaterial,
+    channel: u8,
+    pan_id: u16,
+    short_address: u16,
+    ext_address: [u8; 8],
+    raw_stream_enabled: bool,
+    src_match_enabled: bool,
+    scan_state: u8,
+    scan_mask: u32,
+    scan_period: u16,
+    // Channels of the running energy scan not scanned yet
+    scan_remaining: u32,
+    scan_channel: u8,
+    // The end of the energy scan has to be reported
+    scan_done_pending: bool,
+}
+
+impl<R, E> Rcp<R>
+where
+    R: OTRadioOperation<Error = E>
+        + OTRadioConfiguration<Error = E>
+        + OTRadioConfigurationCapTransmit<Error = E>
+        + OTRadioOperationEnergyScan<Error = E>,
+{
+    /// Create an RCP serving a radio
+    ///
+    /// The host is told the RCP was powered on by the first frame `process` produces.
+    pub fn new(radio: R) -> Self {
+        Self {
+            radio,
+            events: RcpEvents::new(),
+            pending_status: Some(Status::ResetPowerOn),
+            tx_header: None,
+            tx_frame: TransmitFrame::new(),
+            tx_metadata: TxMetadata::default(),
+            mac_key: OTMacKeyMaterial::Key([0; OT_MAC_KEY_SIZE]),
+            channel: DEFAULT_CHANNEL,
+            pan_id: 0xffff,
+            short_address: 0xfffe,
+            ext_address: [0; 8],
+            raw_stream_enabled: false,
+            src_match_enabled: false,
+            scan_state: SCAN_STATE_IDLE,
+            scan_mask: 0,
+            scan_period: DEFAULT_SCAN_PERIOD,
+            scan_remaining: 0,
+            scan_channel: 0,
+            scan_done_pending: false,
+        }
+    }
+
+    pub fn radio(&mut self) -> &mut R {
+        &mut self.radio
+    }
+
+    /// Borrow the radio along with the handles its driver reports completions to
+    pub fn split(&mut self) -> (&mut R, &mut RcpEvents<E>) {
+        (&mut self.radio, &mut self.events)
+    }
+
+    pub fn into_inner(self) -> R {
+        self.radio
+    }
+
+    /// Handle a Spinel frame received from the host
+    ///
+    /// Params:
+    ///     request - the received Spinel frame
+    ///     response - buffer receiving the answer
+    ///
+    /// Returns:
+    ///     The length of the answer, or None if there is no answer (yet)
+    ///     OTError::NoBuffers if the answer does not fit the buffer
+    pub fn handle_frame(&mut self, request: &[u8], response: &mut [u8]) -> Result<Option<usize>, OTError<E>> {
+        // Frames without a valid header can not be answered
+        let Some(header) = request.first().and_then(|&byte| Header::from_byte::<E>(byte).ok()) else {
+            return Ok(None);
+        };
+
+        let result = match SpinelFrame::parse::<E>(request) {
+            Ok(frame) => self.handle_command(&frame, response),
+            Err(error) => Err(error),
+        };
+
+        match result {
+            Ok(length) => Ok(length),
+            Err(error) => write_status(response, header, Status::from_error(&error)).map(Some),
+        }
+    }
+
+    /// Produce the next frame the RCP sends on its own
+    ///
+    /// Params:
+    ///     output - buffer receiving the frame
+    ///
+    /// Returns:
+    ///     The length of the frame, or None if there is nothing to send
+    pub fn process(&mut self, output: &mut [u8]) -> Result<Option<usize>, OTError<E>> {
+        if let Some(status) = self.pending_status.take() {
+            return write_status(output, Header::default(), status).map(Some);
+        }
+
+        if let Some(tx_done) = self.events.tx_done.take() {
+            if let Some(header) = self.tx_header.take() {
+                let mut encoder = Encoder::new(output);
+                encoder.write_property_command(header, Command::PropValueIs, Property::LastStatus)?;
+                raw::encode_tx_done(
+                    &mut encoder,
+                    &TxDone {
+                        status: tx_done.status,
+                        is_header_updated: tx_done.is_header_updated,
+                        frame_counter: tx_done.frame_counter,
+                        ack_frame: tx_done.ack_frame.as_ref().map(ReceiveFrame::as_radio_frame),
+                    },
+                )?;
+                return Ok(Some(encoder.len()));
+            }
+        }
+
+        if let Some(max_rssi) = self.events.energy_scan_done.take() {
+            let channel = self.scan_channel;
+            if self.scan_state == SCAN_STATE_ENERGY && self.scan_next_channel().is_err() {
+                self.scan_remaining = 0;
+                self.scan_done_pending = true;
+            }
+            let mut encoder = Encoder::new(output);
+            encoder.write_property_command(Header::default(), Command::PropValueIs, Property::MacEnergyScanResult)?;
+            encoder.write_u8(channel)?;
+            encoder.write_i8(max_rssi)?;
+            return Ok(Some(encoder.len()));
+        }
+
+        if self.scan_done_pending {
+            self.scan_done_pending = false;
+            self.scan_state = SCAN_STATE_IDLE;
+            let mut encoder = Encoder::new(output);
+            encoder.write_property_command(Header::default(), Command::PropValueIs, Property::MacScanState)?;
+            encoder.write_u8(SCAN_STATE_IDLE)?;
+            return Ok(Some(encoder.len()));
+        }
+
+        if self.raw_stream_enabled {
+            let frame = match self.radio.receive_frame() {
+                Ok(frame) => frame,
+                Err(OTError::NoFrameReceived) => return Ok(None),
+                Err(error) => return Err(error),
+            };
+            let mut encoder = Encoder::new(output);
+            encoder.write_property_command(Header::default(), Command::PropValueIs, Property::StreamRaw)?;
+            raw::encode_rx_frame(&mut encoder, &frame)?;
+            return Ok(Some(encoder.len()));
+        }
+
+        Ok(None)
+    }
+
+    fn handle_command(&mut self, frame: &SpinelFrame, response: &mut [u8]) -> Result<Option<usize>, OTError<E>> {
+        match frame.command {
+            Command::Noop => write_status(response, frame.header, Status::Ok).map(Some),
+            Command::Reset => {
+                self.reset();
+                write_status(response, Header::new(frame.header.iid, 0), Status::ResetSoftware).map(Some)
+            },
+            Command::PropValueGet => {
+                let (id, _) = frame.property()?;
+                let property = Property::from_id(id).ok_or(OTError::NotImplemented)?;
+                self.get_property(frame.header, property, response).map(Some)
+            },
+            Command::PropValueSet => {
+                let (id, value) = frame.property()?;
+                let property = Property::from_id(id).ok_or(OTError::NotImplemented)?;
+                if !self.set_property(frame.header, property, value.clone())? {
+                    return Ok(None);
+                }
+                write_property(response, frame.header, Command::PropValueIs, property, value.remaining()).map(Some)
+            },
+            Command::PropValueInsert | Command::PropValueRemove => {
+                let (id, value) = frame.property()?;
+                let property = Property::from_id(id).ok_or(OTError::NotImplemented)?;
+                let insert = frame.command == Command::PropValueInsert;
+                self.update_property_list(property, value.clone(), insert)?;
+                let command = if insert { Command::PropValueInserted } else { Command::PropValueRemoved };
+                write_property(response, frame.header, command, property, value.remaining()).map(Some)
+            },
+            _ => Err(OTError::InvalidCommand),
+        }
+    }
+
+    /// Encode the value of a property
+    fn get_property(&mut self, header: Header, property: Property, response: &mut [u8]) -> Result<usize, OTError<E>> {
+        let mut encoder = Encoder::new(response);
+        encoder.write_property_command(header, Command::PropValueIs, property)?;
+
+        match property {
+            Property::LastStatus => encoder.write_packed_uint(Status::Ok.code())?,
+            Property::ProtocolVersion => {
+                encoder.write_packed_uint(PROTOCOL_VERSION_MAJOR)?;
+                encoder.write_packed_uint(PROTOCOL_VERSION_MINOR)?;
+            },
+            Property::NcpVersion => encoder.write_utf8(RCP_VERSION)?,
+            Property::InterfaceType => encoder.write_packed_uint(PROTOCOL_TYPE_THREAD)?,
+            Property::VendorId => encoder.write_packed_uint(0)?,
+            Property::Caps => {
+                for capability in [CAP_CONFIG_RADIO, CAP_MAC_RAW, CAP_RCP_API_VERSION] {
+                    encoder.write_packed_uint(capability)?;
+                }
+            },
+            Property::HwAddr => encoder.write_eui64(&self.radio.radio_ieee_eui_64().map_err(OTError::Platform)?)?,
+            Property::RcpApiVersion => encoder.write_packed_uint(RCP_API_VERSION)?,
+            Property::RcpMinHostApiVersion => encoder.write_packed_uint(MIN_HOST_API_VERSION)?,
+            Property::RadioCaps => {
+                encoder.write_packed_uint(self.radio.radio_capabilities().map_err(OTError::Platform)? as u32)?
+            },
+            Property::PhyEnabled => encoder.write_bool(self.radio.is_enabled().map_err(OTError::Platform)?)?,
+            Property::PhyChan => encoder.write_u8(self.channel)?,
+            Property::PhyChanSupported => {
+                write_channel_mask(&mut encoder, self.radio.get_supported_channel_mask().map_err(OTError::Platform)?)?
+            },
+            Property::PhyChanPreferred => {
+                write_channel_mask(&mut encoder, self.radio.get_preferred_channel_mask().map_err(OTError::Platform)?)?
+            },
+            Property::PhyCcaThreshold => encoder.write_i8(self.radio.get_cca_energy_detect_threshold()?)?,
+            Property::PhyTxPower => encoder.write_i8(self.radio.get_transmit_power()?)?,
+            Property::PhyRssi => encoder.write_i8(self.radio.get_rssi().map_err(OTError::Platform)?)?,
+            Property::PhyRxSensitivity => {
+                encoder.write_i8(self.radio.radio_receive_sensitivity().map_err(OTError::Platform)? as i8)?
+            },
+            Property::PhyFemLnaGain => encoder.write_i8(self.radio.get_fem_lna_gain()?)?,
+            Property::PhyRegionCode => encoder.write_u16(self.radio.get_region().map_err(OTError::Platform)?)?,
+            Property::MacScanState => encoder.write_u8(self.scan_state)?,
+            Property::MacScanMask => write_channel_mask(&mut encoder, self.scan_mask)?,
+            Property::MacScanPeriod => encoder.write_u16(self.scan_period)?,
+            Property::Mac15_4Laddr => encoder.write_eui64(&self.ext_address)?,
+            Property::Mac15_4Saddr => encoder.write_u16(self.short_address)?,
+            Property::Mac15_4Panid => encoder.write_u16(self.pan_id)?,
+            Property::MacRawStreamEnabled => encoder.write_bool(self.raw_stream_enabled)?,
+            Property::MacPromiscuousMode => {
+                let promiscuous = self.radio.get_promiscuous().map_err(OTError::Platform)?;
+                encoder.write_u8(if promiscuous { PROMISCUOUS_MODE_FULL } else { PROMISCUOUS_MODE_OFF })?
+            },
+            Property::MacSrcMatchEnabled => encoder.write_bool(self.src_match_enabled)?,
+            Property::RcpTimestamp => encoder.write_u64(self.radio.get_now())?,
+            _ => return Err(OTError::NotImplemented),
+        }
+
+        Ok(encoder.len())
+    }
+
+    /// Apply the new value of a property
+    ///
+    /// Returns:
+    ///     false if the answer is deferred (a transmit request)
+    fn set_property(&mut self, header: Header, property: Property, mut value: Decoder) -> Result<bool, OTError<E>> {
+        match property {
+            Property::PhyEnabled => {
+                if value.read_bool()? {
+                    self.radio.enable()?;
+                } else {
+                    self.radio.disable()?;
+                }
+            },
+            Property::PhyChan => {
+                let channel = value.read_u8()?;
+                if self.raw_stream_enabled {
+                    self.radio.receive(channel)?;
+                }
+                self.channel = channel;
+            },
+            Property::PhyCcaThreshold => self.radio.set_cca_energy_detect_threshold(value.read_i8()?)?,
+            Property::PhyTxPower => self.radio.set_transmit_power(value.read_i8()?)?,
+            Property::PhyFemLnaGain => self.radio.set_fem_lna_gain(value.read_i8()?)?,
+            Property::PhyRegionCode => self.radio.set_region(value.read_u16()?).map_err(OTError::Platform)?,
+            Property::PhyChanMaxPower => {
+                let channel = value.read_u8()?;
+                let max_power = value.read_i8()?;
+                self.radio.set_channel_max_transmit_power(channel, max_power).map_err(OTError::Platform)?;
+            },
+            Property::Mac15_4Panid => {
+                self.pan_id = value.read_u16()?;
+                self.radio.set_pan_id(self.pan_id).map_err(OTError::Platform)?;
+            },
+            Property::Mac15_4Saddr => {
+                self.short_address = value.read_u16()?;
+                self.radio.set_short_address(self.short_address).map_err(OTError::Platform)?;
+            },
+            Property::Mac15_4Laddr => {
+                self.ext_address = value.read_eui64()?;
+                self.radio.set_extended_address(self.ext_address).map_err(OTError::Platform)?;
+            },
+            Property::MacPromiscuousMode => {
+                let mode = value.read_u8()?;
+                self.radio.set_promiscuous(mode != PROMISCUOUS_MODE_OFF).map_err(OTError::Platform)?;
+            },
+            Property::MacRxOnWhenIdleMode => {
+                self.radio.set_rx_on_when_idle(value.read_bool()?).map_err(OTError::Platform)?;
+            },
+            Property::MacRawStreamEnabled => {
+                let enabled = value.read_bool()?;
+                if enabled {
+                    self.radio.receive(self.channel)?;
+                } else {
+                    self.radio.sleep()?;
+                }
+                self.raw_stream_enabled = enabled;
+            },
+            Property::MacSrcMatchEnabled => {
+                self.src_match_enabled = value.read_bool()?;
+                self.radio.enable_src_match(self.src_match_enabled).map_err(OTError::Platform)?;
+            },
+            Property::MacSrcMatchShortAddresses => {
+                self.radio.clear_src_match_short_entries().map_err(OTError::Platform)?;
+                while !value.is_empty() {
+                    self.radio.add_src_match_short_entry(value.read_u16()?)?;
+                }
+            },
+            Property::MacSrcMatchExtendedAddresses => {
+                self.radio.clear_src_match_ext_entries().map_err(OTError::Platform)?;
+                while !value.is_empty() {
+                    self.radio.add_src_match_ext_entry(value.read_eui64()?)?;
+                }
+            },
+            Property::RcpMacKey => {
+                let key_id_mode = value.read_u8()?;
+                let key_id = value.read_u8()?;
+                let previous_key = read_mac_key(&mut value)?;
+                let current_key = read_mac_key(&mut value)?;
+                let next_key = read_mac_key(&mut value)?;
+                self.radio
+                    .set_mac_key(
+                        key_id_mode,
+                        key_id,
+                        OTMacKeyMaterial::Key(previous_key),
+                        OTMacKeyMaterial::Key(current_key),
+                        OTMacKeyMaterial::Key(next_key),
+                        OTKeyType::LiteralKey,
+                    )
+                    .map_err(OTError::Platform)?;
+                self.mac_key = OTMacKeyMaterial::Key(current_key);
+            },
+            Property::RcpMacFrameCounter => {
+                let frame_counter = value.read_u32()?;
+                // Hosts using an older API version do not send the flag
+                let if_larger = !value.is_empty() && value.read_bool()?;
+                if if_larger {
+                    self.radio.set_mac_frame_counter_if_larger(frame_counter).map_err(OTError::Platform)?;
+                } else {
+                    self.radio.set_mac_frame_counter(frame_counter).map_err(OTError::Platform)?;
+                }
+            },
+            Property::RcpEnhAckProbing => {
+                let short_address = value.read_u16()?;
+                let ext_address = value.read_eui64()?;
+                let flags = value.read_u8()?;
+                let link_metrics = OTLinkMetrics {
+                    pdu_count: flags & LINK_METRICS_PDU_COUNT != 0,
+                    lqi: flags & LINK_METRICS_LQI != 0,
+                    link_margin: flags & LINK_METRICS_LINK_MARGIN != 0,
+                    rssi: flags & LINK_METRICS_RSSI != 0,
+                    reserved: false,
+                };
+                self.radio.configure_enh_ack_probing(link_metrics, short_address, ext_address)?;
+            },
+            Property::MacScanMask => {
+                let mut mask = 0u32;
+                while !value.is_empty() {
+                    let channel = value.read_u8()?;
+                    mask |= 1u32.checked_shl(channel as u32).ok_or(OTError::InvalidArgs)?;
+                }
+                self.scan_mask = mask;
+            },
+            Property::MacScanPeriod => self.scan_period = value.read_u16()?,
+            Property::MacScanState => match value.read_u8()? {
+                SCAN_STATE_IDLE => {
+                    self.scan_state = SCAN_STATE_IDLE;
+                    self.scan_remaining = 0;
+                },
+                SCAN_STATE_ENERGY => {
+                    self.scan_remaining = if self.scan_mask == 0 { 1 << self.channel } else { self.scan_mask };
+                    self.scan_state = SCAN_STATE_ENERGY;
+                    if let Err(error) = self.scan_next_channel() {
+                        self.scan_state = SCAN_STATE_IDLE;
+                        self.scan_remaining = 0;
+                        return Err(error);
+                    }
+                },
+                _ => return Err(OTError::InvalidArgs),
+            },
+            Property::StreamRaw => {
+                if self.tx_header.is_some() {
+                    return Err(OTError::Busy);
+                }
+                let (psdu, metadata) = raw::decode_tx_frame(&mut value)?;
+                self.tx_frame.set_length(psdu.len())?;
+                self.tx_frame.psdu_mut().copy_from_slice(psdu);
+                self.tx_metadata = metadata;
+                self.radio.transmit(self.tx_metadata.radio_frame(self.tx_frame.psdu(), self.mac_key))?;
+                self.tx_header = Some(header);
+                return Ok(false);
+            },
+            _ => return Err(OTError::NotImplemented),
+        }
+        Ok(true)
+    }
+
+    /// Insert or remove an entry of a list property
+    fn update_property_list(&mut self, property: Property, mut value: Decoder, insert: bool) -> Result<(), OTError<E>> {
+        match (property, insert) {
+            (Property::MacSrcMatchShortAddresses, true) => self.radio.add_src_match_short_entry(value.read_u16()?),
+            (Property::MacSrcMatchShortAddresses, false) => self.radio.clear_src_match_short_entry(value.read_u16()?),
+            (Property::MacSrcMatchExtendedAddresses, true) => self.radio.add_src_match_ext_entry(value.read_eui64()?),
+            (Property::MacSrcMatchExtendedAddresses, false) => self.radio.clear_src_match_ext_entry(value.read_eui64()?),
+            _ => Err(OTError::NotImplemented),
+        }
+    }
+
+    /// Start the energy scan of the next channel of the running scan
+    fn scan_next_channel(&mut self) -> Result<(), OTError<E>> {
+        if self.scan_remaining == 0 {
+            self.scan_done_pending = true;
+            return Ok(());
+        }
+
+        let channel = self.scan_remaining.trailing_zeros() as u8;
+        self.scan_remaining &= !(1 << channel);
+        self.scan_channel = channel;
+        self.radio.energy_scan(channel, self.scan_period as i16)
+    }
+
+    /// Drop the state set up by the host
+    fn reset(&mut self) {
+        self.events = RcpEvents::new();
+        self.tx_header = None;
+        self.raw_stream_enabled = false;
+        self.src_match_enabled = false;
+        self.scan_state = SCAN_STATE_IDLE;
+        self.scan_remaining = 0;
+        self.scan_done_pending = false;
+    }
+}
+
+/// Write a last status frame
+fn write_status<E>(buffer: &mut [u8], header: Header, status: Status) -> Result<usize, OTError<E>> {
+    let mut encoder = Encoder::new(buffer);
+    encoder.write_property_command(header, Command::PropValueIs, Property::LastStatus)?;
+    encoder.write_packed_uint(status.code())?;
+    Ok(encoder.len())
+}
+
+/// Write a property frame with an already encoded value
+fn write_property<E>(
+    buffer: &mut [u8],
+    header: Header,
+    command: Command,
+    property: Property,
+    value: &[u8],
+) -> Result<usize, OTError<E>> {
+    let mut encoder = Encoder::new(buffer);
+    encoder.write_property_command(header, command, property)?;
+    encoder.write_data(value)?;
+    Ok(encoder.len())
+}
+
+/// Write the channels of a channel mask as an array of channels
+fn write_channel_mask<E>(encoder: &mut Encoder, mask: u32) -> Result<(), OTError<E>> {
+    for channel in 0..u32::BITS as u8 {
+        if mask & (1 << channel) != 0 {
+            encoder.write_u8(channel)?;
+        }
+    }
+    Ok(())
+}
+
+/// Read a MAC key ('d')
+fn read_mac_key<E>(decoder: &mut Decoder) -> Result<OTMacKey, OTError<E>> {
+    decoder.read_data_with_len()?.try_into().map_err(|_| OTError::InvalidArgs)
+}
@@ -0,0 +1,277 @@
+//!
+//! Spinel SPI Framing
+//!
+//! Over SPI every transaction starts with a 5 byte header in both directions, followed by the Spinel frame (if any):
+//!
+//! ```text
+//! [flags (1 byte)][accept length (u16, little endian)][data length (u16, little endian)][frame]
+//! ```
+//!
+//! The accept length announces the largest frame the sender can receive in this transaction and the data length the
+//! size of the frame it sends. A frame is only taken by the receiver when it fits its accept length, otherwise the
+//! sender retries it in a later transaction.
+//!
+
+use embedded_hal::spi::SpiDevice;
+
+use crate::error::OTError;
+
+// Size of the SPI frame header (in bytes)
+pub const SPI_HEADER_SIZE: usize = 5;
+
+// Flag set by the first transaction after a reset
+const SPI_FLAG_RESET: u8 = 0x80;
+// Flag set when the frame is followed by a CRC
+const SPI_FLAG_CRC: u8 = 0x40;
+// Pattern distinguishing a valid header from an idle bus (0x00 or 0xff)
+const SPI_PATTERN_VALUE: u8 = 0x02;
+const SPI_PATTERN_MASK: u8 = 0x03;
+
+/// SPI frame header
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SpiFrameHeader {
+    // The sender was reset since its last transaction
+    pub reset: bool,
+    // The frame is followed by a CRC
+    pub crc: bool,
+    // Largest frame the sender can receive in this transaction (in bytes)
+    pub accept_length: u16,
+    // Size of the frame sent in this transaction (in bytes)
+    pub data_length: u16,
+}
+
+impl SpiFrameHeader {
+    /// Decode a header
+    ///
+    /// Returns:
+    ///     OTError::Parse if the buffer is too short or does not hold a valid header pattern
+    pub fn from_bytes<E>(bytes: &[u8]) -> Result<Self, OTError<E>> {
+        if bytes.len() < SPI_HEADER_SIZE || bytes[0] & SPI_PATTERN_MASK != SPI_PATTERN_VALUE {
+            return Err(OTError::Parse);
+        }
+        Ok(Self {
+            reset: bytes[0] & SPI_FLAG_RESET != 0,
+            crc: bytes[0] & SPI_FLAG_CRC != 0,
+            accept_length: u16::from_le_bytes([bytes[1], bytes[2]]),
+            data_length: u16::from_le_bytes([bytes[3], bytes[4]]),
+        })
+    }
+
+    /// Encode the header
+    pub fn to_bytes(&self) -> [u8; SPI_HEADER_SIZE] {
+        let mut flags = SPI_PATTERN_VALUE;
+        if self.reset {
+            flags |= SPI_FLAG_RESET;
+        }
+        if self.crc {
+            flags |= SPI_FLAG_CRC;
+        }
+        let accept_length = self.accept_length.to_le_bytes();
+        let data_length = self.data_length.to_le_bytes();
+        [flags, accept_length[0], accept_length[1], data_length[0], data_length[1]]
+    }
+}
+
+/// Write the header and frame of an SPI transaction
+///
+/// Params:
+///     buffer - buffer receiving the transaction bytes
+///     reset - whether this is the first transaction since a reset
+///     accept_length - largest frame that can be received in this transaction
+///     frame - the Spinel frame to send (may be empty)
+///
+/// Returns:
+///     The number of bytes to send
+///     OTError::NoBuffers if the buffer is too small
+pub fn encode_spi_frame<E>(
+    buffer: &mut [u8],
+    reset: bool,
+    accept_length: u16,
+    frame: &[u8],
+) -> Result<usize, OTError<E>> {
+    let length = SPI_HEADER_SIZE + frame.len();
+    if length > buffer.len() {
+        return Err(OTError::NoBuffers);
+    }
+
+    let header = SpiFrameHeader {
+        reset,
+        crc: false,
+        accept_length,
+        data_length: u16::try_from(frame.len()).map_err(|_| OTError::InvalidArgs)?,
+    };
+    buffer[..SPI_HEADER_SIZE].copy_from_slice(&header.to_bytes());
+    buffer[SPI_HEADER_SIZE..length].copy_from_slice(frame);
+    Ok(length)
+}
+
+/// Run an SPI transaction, sending an encoded transaction and receiving the peer's one
+///
+/// Params:
+///     spi - the SPI device
+///     transmit - the encoded transaction to send (see `encode_spi_frame`)
+///     receive - buffer receiving the peer's header and frame (its size bounds the accept length)
+///
+/// Returns:
+///     The peer's header and its frame (empty if it sent none or it did not fit `receive`)
+///     OTError::Parse if the peer did not answer with a valid header (e.g. it is not ready)
+pub fn spi_transfer<'r, S: SpiDevice>(
+    spi: &mut S,
+    transmit: &[u8],
+    receive: &'r mut [u8],
+) -> Result<(SpiFrameHeader, &'r [u8]), OTError<S::Error>> {
+    spi.transfer(receive, transmit).map_err(OTError::Platform)?;
+
+    let header = SpiFrameHeader::from_bytes(receive)?;
+    let end = SPI_HEADER_SIZE + header.data_length as usize;
+    let frame = if end <= receive.len() {
+        &receive[SPI_HEADER_SIZE..end]
+    } else {
+        &[]
+    };
+    Ok((header, frame))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use core::convert::Infallible;
+
+    use embedded_hal::spi::{ErrorType, Operation};
+
+    use super::*;
+
+    // Outcome of a transfer: the peer's header and frame
+    type Transfer<'a> = Result<(SpiFrameHeader, &'a [u8]), OTError<Infallible>>;
+
+    /// SPI device answering every transaction with the same bytes and recording what it was sent
+    struct MockSpi {
+        answer: Vec<u8>,
+        sent: Vec<u8>,
+    }
+
+    impl ErrorType for MockSpi {
+        type Error = Infallible;
+    }
+
+    impl SpiDevice for MockSpi {
+        fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), Self::Error> {
+            for operation in operations {
+                if let Operation::Transfer(read, write) = operation {
+                    self.sent.extend_from_slice(write);
+                    for (slot, byte) in read.iter_mut().zip(self.answer.iter().chain(core::iter::repeat(&0xff))) {
+                        *slot = *byte;
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn header_layout() {
+        let cases = [
+            ("idle", SpiFrameHeader::default(), [0x02, 0x00, 0x00, 0x00, 0x00]),
+            (
+                "reset with a frame",
+                SpiFrameHeader {
+                    reset: true,
+                    crc: false,
+                    accept_length: 0x0500,
+                    data_length: 0x0012,
+                },
+                [0x82, 0x00, 0x05, 0x12, 0x00],
+            ),
+            (
+                "crc",
+                SpiFrameHeader {
+                    reset: false,
+                    crc: true,
+                    accept_length: 0x1234,
+                    data_length: 0xabcd,
+                },
+                [0x42, 0x34, 0x12, 0xcd, 0xab],
+            ),
+        ];
+
+        for (name, header, bytes) in cases {
+            assert_eq!(header.to_bytes(), bytes, "{}", name);
+            assert_eq!(SpiFrameHeader::from_bytes::<Infallible>(&bytes), Ok(header), "{}", name);
+        }
+    }
+
+    #[test]
+    fn invalid_headers_are_rejected() {
+        let cases: [(&str, &[u8]); 3] = [
+            ("idle bus low", &[0x00; SPI_HEADER_SIZE]),
+            ("idle bus high", &[0xff; SPI_HEADER_SIZE]),
+            ("too short", &[0x02, 0x00, 0x00, 0x00]),
+        ];
+
+        for (name, bytes) in cases {
+            assert_eq!(SpiFrameHeader::from_bytes::<Infallible>(bytes), Err(OTError::Parse), "{}", name);
+        }
+    }
+
+    #[test]
+    fn encode_frame() {
+        let mut buffer = [0; 8];
+        assert_eq!(encode_spi_frame::<Infallible>(&mut buffer, true, 0x20, &[0x81, 0x02, 0x00]), Ok(8));
+        assert_eq!(buffer, [0x82, 0x20, 0x00, 0x03, 0x00, 0x81, 0x02, 0x00]);
+
+        assert_eq!(
+            encode_spi_frame::<Infallible>(&mut buffer, false, 0x20, &[0; 4]),
+            Err(OTError::NoBuffers)
+        );
+    }
+
+    #[test]
+    fn transfer_returns_the_peer_frame() {
+        let mut transmit = [0; 8];
+        let length = encode_spi_frame::<Infallible>(&mut transmit, false, 3, &[0x81, 0x02, 0x00]).unwrap();
+
+        let cases: [(&str, Vec<u8>, Transfer); 3] = [
+            (
+                "frame",
+                vec![0x02, 0x08, 0x00, 0x02, 0x00, 0x80, 0x06],
+                Ok((
+                    SpiFrameHeader {
+                        reset: false,
+                        crc: false,
+                        accept_length: 8,
+                        data_length: 2,
+                    },
+                    &[0x80, 0x06],
+                )),
+            ),
+            (
+                "frame larger than the receive buffer",
+                vec![0x02, 0x08, 0x00, 0x10, 0x00],
+                Ok((
+                    SpiFrameHeader {
+                        reset: false,
+                        crc: false,
+                        accept_length: 8,
+                        data_length: 16,
+                    },
+                    &[],
+                )),
+            ),
+            ("peer not ready", vec![0xff; SPI_HEADER_SIZE], Err(OTError::Parse)),
+        ];
+
+        for (name, answer, expected) in cases {
+            let mut spi = MockSpi {
+                answer,
+                sent: Vec::new(),
+            };
+            let mut receive = [0; 8];
+            let result = spi_transfer(&mut spi, &transmit[..length], &mut receive)
+                .map(|(header, frame)| (header, frame.to_vec()));
+            let expected = expected.map(|(header, frame)| (header, frame.to_vec()));
+            assert_eq!(result, expected, "{}", name);
+            assert_eq!(spi.sent, &transmit[..length], "{}", name);
+        }
+    }
+}